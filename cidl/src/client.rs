@@ -0,0 +1,396 @@
+use crate::generator::*;
+use crate::model::*;
+use crate::program::{AccountModel, MethodModel};
+use crate::validator::{has_errors, validate, Diagnostic};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClientOptions {
+    // Path of the codigolib crate, relative to the generated client
+    pub codigolib_path: String,
+}
+
+impl Default for ClientOptions {
+    fn default() -> ClientOptions {
+        ClientOptions {
+            codigolib_path: "../codigolib".to_string(),
+        }
+    }
+}
+
+// Renders a Rust client crate mirroring the TypeScript sdk: instruction builders, PDA helpers
+// and account decoders. Paths are relative to the client directory
+pub fn generate_client(
+    cidl: &Cidl,
+    options: &ClientOptions,
+) -> Result<Vec<GeneratedFile>, Vec<Diagnostic>> {
+    let diagnostics = validate(cidl);
+    if has_errors(&diagnostics) {
+        return Err(diagnostics);
+    }
+
+    let methods: Vec<_> = cidl
+        .methods
+        .iter()
+        .map(|method| MethodModel::new(cidl, method))
+        .collect();
+
+    Ok(vec![
+        GeneratedFile::generated("Cargo.toml", render_cargo_toml(cidl, options)),
+        GeneratedFile::generated("src/lib.rs", render_lib()),
+        GeneratedFile::generated("src/constants.rs", render_constants(&methods)),
+        GeneratedFile::generated("src/types.rs", render_types(cidl)),
+        GeneratedFile::generated("src/instructions.rs", render_instructions(cidl, &methods)),
+    ])
+}
+
+pub fn instruction_constant(method: &MethodModel) -> String {
+    method.name.to_uppercase()
+}
+
+fn render_cargo_toml(cidl: &Cidl, options: &ClientOptions) -> String {
+    let version = cidl
+        .info
+        .version
+        .as_ref()
+        .map_or("0.1.0", |version| &version.value);
+
+    format!(
+        r#"[package]
+name = "{}_sdk"
+version = "{}"
+edition = "2021"
+
+[dependencies]
+codigolib = {{ path = "{}" }}
+solana-program = "~1.14.17"
+{}"#,
+        cidl.info.name.value,
+        version,
+        options.codigolib_path,
+        library_dependencies(cidl)
+    )
+}
+
+fn render_lib() -> String {
+    format!(
+        "{}
+pub mod constants;
+pub mod instructions;
+pub mod types;
+",
+        GENERATED_HEADER
+    )
+}
+
+fn render_constants(methods: &[MethodModel]) -> String {
+    let mut out = String::from(GENERATED_HEADER);
+    out += "\n// First byte of the instruction data, the position of the method in the CIDL\n";
+    for (i, method) in methods.iter().enumerate() {
+        out += &format!("pub const {}: u8 = {};\n", instruction_constant(method), i);
+    }
+
+    out += "\n// Name and first byte of every instruction, in the order of the CIDL\n";
+    out += &format!(
+        "pub const INSTRUCTIONS: [(&str, u8); {}] = [\n",
+        methods.len()
+    );
+    for method in methods {
+        out += &format!(
+            "    (\"{}\", {}),\n",
+            method.name,
+            instruction_constant(method)
+        );
+    }
+    out += "];\n";
+    out
+}
+
+// Parameter type of a seed in `find_address`
+fn seed_param_type(ty: &TypeRef) -> String {
+    match ty {
+        TypeRef::Pubkey => "&Pubkey".to_string(),
+        TypeRef::String => "&str".to_string(),
+        _ => rust_type(ty),
+    }
+}
+
+fn seed_bytes(seed: &Seed, value: &str) -> String {
+    match seed.ty.as_ref().map(|ty| &ty.value) {
+        None => format!("b\"{}\"", seed.name.value),
+        Some(TypeRef::Pubkey) => format!("{}.as_ref()", value),
+        Some(TypeRef::String) => format!("{}.as_bytes()", value),
+        Some(_) => format!("&{}.to_le_bytes()", value),
+    }
+}
+
+fn render_types(cidl: &Cidl) -> String {
+    let accounts: Vec<_> = cidl
+        .types
+        .iter()
+        .filter_map(|ty| ty.solana.as_ref().map(|solana| (ty, solana)))
+        .filter(|(_, solana)| !solana.seeds.is_empty())
+        .collect();
+
+    let mut out = String::from(GENERATED_HEADER);
+    out += "\n";
+    if types_use_delegate(cidl) {
+        out += DELEGATE_USE;
+    }
+    out += "use codigolib::rust_utils::*;\nuse solana_program::program_error::ProgramError;\n";
+    if !accounts.is_empty() || types_use_pubkey(cidl) {
+        out += "use solana_program::pubkey::Pubkey;\n";
+    }
+
+    out += "\n";
+    out += STATE_USE;
+    out += "
+// Decodes the data of an account fetched from the cluster, failing with the CodecError of the
+// first field that can't be read
+pub fn decode<T: State>(data: &[u8]) -> Result<T, ProgramError> {
+    let (_, value) = T::try_unpack(data)?;
+    Ok(value)
+}
+";
+    out += &render_type_defs(cidl);
+
+    for (ty, solana) in accounts {
+        let mut params = vec!["program_id: &Pubkey".to_string()];
+        params.extend(solana.seeds.iter().filter_map(|seed| {
+            seed.ty
+                .as_ref()
+                .map(|ty| format!("{}: {}", seed.name.value, seed_param_type(&ty.value)))
+        }));
+        let seeds: Vec<_> = solana
+            .seeds
+            .iter()
+            .map(|seed| seed_bytes(seed, &seed.name.value))
+            .collect();
+
+        out += &format!(
+            "\nimpl {} {{\n    // Address of the account derived from its seeds\n",
+            ty.name.value
+        );
+        out += &signature("pub fn find_address", &params, "(Pubkey, u8)", 4);
+        out += &format!(
+            "        Pubkey::find_program_address(&[{}], program_id)\n    }}\n}}\n",
+            seeds.join(", ")
+        );
+    }
+
+    out
+}
+
+// Bytes taken by `value` in the instruction data, where strings and vectors are length-prefixed
+fn instruction_size(cidl: &Cidl, ty: &TypeRef, value: &str) -> String {
+    match ty {
+        TypeRef::String => format!("4 + {}.len()", value),
+        TypeRef::Option(inner) => format!(
+            "1 + {}.as_ref().map_or(0, |value| {})",
+            value,
+            instruction_size(cidl, inner, "value")
+        ),
+        TypeRef::Vec(inner) => format!("4 + {}.len() * {}", value, size(cidl, inner, None)),
+        _ => size(cidl, ty, None).to_string(),
+    }
+}
+
+// Mirrors `instructions_unpack_*`, options without a value only take their flag
+fn instruction_pack_call(cidl: &Cidl, ty: &TypeRef, buf: &str, value: &str) -> String {
+    match ty {
+        TypeRef::String => format!(
+            "pack_string({}, {}.clone(), 4 + {}.len())",
+            buf, value, value
+        ),
+        TypeRef::Option(inner) => format!(
+            "match {} {{ Some(value) => {}, None => pack_bool({}, false) }}",
+            value,
+            instruction_pack_call(cidl, inner, &format!("pack_bool({}, true)", buf), "value"),
+            buf
+        ),
+        TypeRef::Vec(inner) => format!(
+            "pack_vec({}, {}.len(), {}, {}, {}.clone())",
+            buf,
+            value,
+            size(cidl, inner, None),
+            pack_fn(cidl, inner, None),
+            value
+        ),
+        _ => pack_call(cidl, ty, None, buf, value),
+    }
+}
+
+fn render_instructions(cidl: &Cidl, methods: &[MethodModel]) -> String {
+    let mut out = String::from(GENERATED_HEADER);
+    out += "\nuse crate::constants::*;\n";
+    if methods.iter().any(|method| {
+        !method.accounts.is_empty() || method.args.iter().any(|arg| uses_own_type(cidl, &arg.ty))
+    }) {
+        out += "use crate::types::*;\n";
+    }
+    if methods
+        .iter()
+        .flat_map(|method| &method.args)
+        .any(|arg| uses_delegate(&arg.ty))
+    {
+        out += DELEGATE_USE;
+    }
+    out += "use codigolib::rust_utils::*;\n";
+    if methods.iter().any(MethodModel::uses_token_program) {
+        out += "use codigolib::token_utils::spl_token_program;\n";
+    }
+    out += "use solana_program::instruction::{AccountMeta, Instruction};
+use solana_program::pubkey::Pubkey;
+";
+    if methods.iter().any(MethodModel::pays_rent) {
+        out += "use solana_program::system_program;\n";
+    }
+
+    for method in methods {
+        out += &render_builder(cidl, method);
+    }
+
+    out
+}
+
+// Accounts created without seeds are keypairs, their address is passed by the caller
+fn is_keypair(account: &AccountModel) -> bool {
+    account.solana.seeds.is_empty()
+}
+
+fn render_builder(cidl: &Cidl, method: &MethodModel) -> String {
+    let mut out = String::from("\n");
+    if let Some(summary) = &method.method.summary {
+        out += &comment(&summary.value, "");
+    }
+
+    let mut params = vec!["program_id: &Pubkey".to_string()];
+    params.extend(
+        method
+            .inputs
+            .iter()
+            .map(|input| format!("{}: {}", input.name, rust_type(&input.ty))),
+    );
+    params.extend(
+        method
+            .accounts
+            .iter()
+            .filter(|account| is_keypair(account))
+            .map(|account| format!("{}: Pubkey", account.name())),
+    );
+    params.extend(
+        method
+            .args
+            .iter()
+            .skip(method.inputs.len())
+            .map(|arg| format!("{}: {}", arg.name, rust_type(&arg.ty))),
+    );
+    params.extend(
+        method
+            .authorities()
+            .map(|account| format!("{}: Pubkey", account.authority())),
+    );
+    if method.has_fee_payer() {
+        params.push("fee_payer: Pubkey".to_string());
+    }
+    out += &signature(
+        &format!("pub fn {}", method.name),
+        &params,
+        "Instruction",
+        0,
+    );
+
+    let derived: Vec<_> = method
+        .accounts
+        .iter()
+        .filter(|account| !is_keypair(account))
+        .collect();
+    for account in &derived {
+        let mut seeds = vec!["program_id".to_string()];
+        seeds.extend(account.solana.seeds.iter().filter_map(|seed| {
+            seed.ty.as_ref().map(|ty| match ty.value {
+                TypeRef::Pubkey | TypeRef::String => format!("&{}", account.seed_arg(seed)),
+                _ => account.seed_arg(seed),
+            })
+        }));
+        out += &format!(
+            "    let ({}, _) = {}::find_address({});\n",
+            account.name(),
+            account.ty.name.value,
+            seeds.join(", ")
+        );
+    }
+    if !derived.is_empty() {
+        out += "\n";
+    }
+
+    let mut sizes = vec!["1".to_string()];
+    sizes.extend(
+        method
+            .args
+            .iter()
+            .map(|arg| instruction_size(cidl, &arg.ty, &arg.name)),
+    );
+    out += &format!("    let mut data = vec![0; {}];\n", sizes.join(" + "));
+    let mut calls = vec![format!(
+        "pack_u8(&mut data, {})",
+        instruction_constant(method)
+    )];
+    calls.extend(
+        method
+            .args
+            .iter()
+            .map(|arg| instruction_pack_call(cidl, &arg.ty, "buf", &arg.name)),
+    );
+    // The rest of the buffer after the last argument is empty
+    if let Some((last, calls)) = calls.split_last() {
+        for call in calls {
+            out += &format!("    let buf = {};\n", call);
+        }
+        out += &format!("    {};\n", last);
+    }
+
+    let mut metas: Vec<_> = method
+        .accounts
+        .iter()
+        .map(|account| {
+            let signer = account.is_signer() || (account.is_init() && is_keypair(account));
+            account_meta(account.name(), account.is_mut(), signer)
+        })
+        .collect();
+    metas.extend(
+        method
+            .authorities()
+            .map(|account| account_meta(&account.authority(), false, true)),
+    );
+    if method.pays_rent() {
+        metas.push(account_meta("system_program::id()", false, false));
+    }
+    if method.uses_token_program() {
+        metas.push(account_meta("spl_token_program::id()", false, false));
+    }
+    if method.has_fee_payer() {
+        metas.push(account_meta("fee_payer", true, true));
+    }
+
+    out += "\n    Instruction {\n        program_id: *program_id,\n";
+    let line = format!("        accounts: vec![{}],\n", metas.join(", "));
+    if line.len() <= 100 {
+        out += &line;
+    } else {
+        out += "        accounts: vec![\n";
+        for meta in metas {
+            out += &format!("            {},\n", meta);
+        }
+        out += "        ],\n";
+    }
+    out += "        data,\n    }\n}\n";
+
+    out
+}
+
+fn account_meta(key: &str, writable: bool, signer: bool) -> String {
+    match writable {
+        true => format!("AccountMeta::new({}, {})", key, signer),
+        false => format!("AccountMeta::new_readonly({}, {})", key, signer),
+    }
+}
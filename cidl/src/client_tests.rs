@@ -0,0 +1,183 @@
+use crate::client::*;
+use crate::generator::*;
+use crate::parser::parse;
+use budget_tracker::instructions::BudgetTrackerInstruction;
+use budget_tracker_sdk::instructions as sdk;
+use budget_tracker_sdk::types::{decode, Record, State};
+use codigolib::errors::CodecError;
+use codigolib::token_utils::spl_token_program;
+use solana_program::instruction::AccountMeta;
+use solana_program::pubkey::Pubkey;
+use solana_program::system_program;
+use std::env;
+use std::fs;
+use std::path::Path;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BUDGET_TRACKER: &str = include_str!("../../cidl.yaml");
+
+    fn client_files() -> Vec<GeneratedFile> {
+        generate_client(&parse(BUDGET_TRACKER).unwrap(), &ClientOptions::default()).unwrap()
+    }
+
+    // The checked-in sdk-rs is the golden copy. Run with UPDATE_GOLDEN=1 to regenerate it after an
+    // intended change
+    #[test]
+    fn it_matches_the_checked_in_sdk() {
+        let sdk = Path::new(env!("CARGO_MANIFEST_DIR")).join("../sdk-rs");
+        let files = client_files();
+
+        if env::var_os("UPDATE_GOLDEN").is_some() {
+            write_files(&sdk, &files).unwrap();
+        }
+
+        for file in &files {
+            let checked_in = fs::read_to_string(sdk.join(&file.path))
+                .unwrap_or_else(|_| panic!("missing checked-in file {}", file.path.display()));
+            assert_eq!(file.contents, checked_in, "{}", file.path.display());
+        }
+    }
+
+    #[test]
+    fn it_builds_instructions_the_program_unpacks() {
+        let program_id = Pubkey::new_unique();
+        let signer = Pubkey::new_unique();
+        let fee_payer = Pubkey::new_unique();
+
+        let ix = sdk::create_user_record(
+            &program_id,
+            "John Doe".to_string(),
+            signer,
+            signer,
+            fee_payer,
+        );
+        match BudgetTrackerInstruction::unpack(&ix.data).unwrap() {
+            BudgetTrackerInstruction::CreateUserRecord(args) => {
+                assert_eq!(args.user_name, "John Doe");
+                assert_eq!(args.user_record_seed_signer, signer);
+            }
+            _ => panic!("expected CreateUserRecord"),
+        }
+
+        let ix = sdk::register_outcome(&program_id, 250, signer, signer);
+        match BudgetTrackerInstruction::unpack(&ix.data).unwrap() {
+            BudgetTrackerInstruction::RegisterOutcome(args) => {
+                assert_eq!(args.amount, 250);
+                assert_eq!(args.user_record_seed_signer, signer);
+            }
+            _ => panic!("expected RegisterOutcome"),
+        }
+    }
+
+    #[test]
+    fn it_encodes_data_like_the_ts_sdk() {
+        let program_id = Pubkey::new_unique();
+        let signer = Pubkey::new_unique();
+
+        // packUInt8(0), packString("John Doe"), packPubkey(signer)
+        let mut expected = vec![0, 8, 0, 0, 0];
+        expected.extend_from_slice(b"John Doe");
+        expected.extend_from_slice(signer.as_ref());
+        let ix = sdk::create_user_record(
+            &program_id,
+            "John Doe".to_string(),
+            signer,
+            signer,
+            Pubkey::new_unique(),
+        );
+        assert_eq!(ix.data, expected);
+
+        // packUInt8(1), packUInt32(1000), packPubkey(signer)
+        let mut expected = vec![1, 0xe8, 0x03, 0, 0];
+        expected.extend_from_slice(signer.as_ref());
+        assert_eq!(
+            sdk::register_income(&program_id, 1000, signer, signer).data,
+            expected
+        );
+    }
+
+    #[test]
+    fn it_uses_the_ts_sdk_account_keys() {
+        let program_id = Pubkey::new_unique();
+        let signer = Pubkey::new_unique();
+        let fee_payer = Pubkey::new_unique();
+        let (record, _) = Pubkey::find_program_address(&[b"record", signer.as_ref()], &program_id);
+
+        let ix = sdk::create_user_record(
+            &program_id,
+            "John Doe".to_string(),
+            signer,
+            signer,
+            fee_payer,
+        );
+        assert_eq!(ix.program_id, program_id);
+        assert_eq!(
+            ix.accounts,
+            vec![
+                AccountMeta::new(record, false),
+                AccountMeta::new_readonly(signer, true),
+                AccountMeta::new_readonly(system_program::id(), false),
+                AccountMeta::new(fee_payer, true),
+            ]
+        );
+
+        let ix = sdk::register_income(&program_id, 1000, signer, signer);
+        assert_eq!(
+            ix.accounts,
+            vec![
+                AccountMeta::new(record, false),
+                AccountMeta::new_readonly(signer, true),
+            ]
+        );
+        assert_eq!(Record::find_address(&program_id, &signer).0, record);
+
+        // The vault is derived from the record, the token program comes after the authority
+        let source = Pubkey::new_unique();
+        let (vault, _) = Pubkey::find_program_address(&[b"vault", record.as_ref()], &program_id);
+        let ix = sdk::register_token_income(&program_id, 1000, source, signer, record, signer);
+        assert_eq!(
+            ix.accounts,
+            vec![
+                AccountMeta::new(record, false),
+                AccountMeta::new(vault, false),
+                AccountMeta::new(source, false),
+                AccountMeta::new_readonly(signer, true),
+                AccountMeta::new_readonly(spl_token_program::id(), false),
+            ]
+        );
+    }
+
+    #[test]
+    fn it_decodes_accounts_stored_by_the_program() {
+        let record = budget_tracker::state::Record {
+            version: 2,
+            name: "John Doe".to_string(),
+            moves: 2,
+            outcome: 250,
+            income: 1000,
+            total_balance: 750,
+            delegates: vec![],
+            mint: Pubkey::new_unique(),
+            vault: Pubkey::new_unique(),
+        };
+        let mut data = vec![0; Record::LEN];
+        budget_tracker::state::State::pack(&record, &mut data);
+
+        let decoded: Record = decode(&data).unwrap();
+        assert_eq!(decoded.name, "John Doe");
+        assert_eq!(decoded.moves, 2);
+        assert_eq!(decoded.total_balance, 750);
+        assert_eq!(
+            decode::<Record>(&data[..Record::LEN - 1]),
+            Err(CodecError::BufferTooShort.into())
+        );
+        data[1] = 51;
+        assert_eq!(
+            decode::<Record>(&data),
+            Err(CodecError::StringTooLong.into())
+        );
+    }
+}
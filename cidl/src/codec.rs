@@ -0,0 +1,88 @@
+use crate::model::*;
+
+// solana_program's MAX_PERMITTED_DATA_LENGTH, the largest account. No capacity can be larger
+pub const MAX_ACCOUNT_DATA_LEN: usize = 10 * 1024 * 1024;
+
+// Bytes written by the codigolib::rust_utils pack function of a fixed size type
+pub fn primitive_size(ty: &TypeRef) -> Option<usize> {
+    match ty {
+        TypeRef::Bool | TypeRef::U8 | TypeRef::I8 => Some(1),
+        TypeRef::U16 | TypeRef::I16 => Some(2),
+        TypeRef::U32 | TypeRef::I32 | TypeRef::F32 => Some(4),
+        TypeRef::U64 | TypeRef::I64 | TypeRef::F64 => Some(8),
+        TypeRef::U128 | TypeRef::I128 => Some(16),
+        TypeRef::Pubkey => Some(32),
+        TypeRef::Delegate => Some(33),
+        TypeRef::String | TypeRef::Option(_) | TypeRef::Vec(_) | TypeRef::Named(_) => None,
+    }
+}
+
+// Suffix of the rust_utils pack_*/unpack_* functions for a fixed size type
+pub fn primitive_codec_name(ty: &TypeRef) -> Option<&'static str> {
+    match ty {
+        TypeRef::Bool => Some("bool"),
+        TypeRef::U8 => Some("u8"),
+        TypeRef::U16 => Some("u16"),
+        TypeRef::U32 => Some("u32"),
+        TypeRef::U64 => Some("u64"),
+        TypeRef::U128 => Some("u128"),
+        TypeRef::I8 => Some("i8"),
+        TypeRef::I16 => Some("i16"),
+        TypeRef::I32 => Some("i32"),
+        TypeRef::I64 => Some("i64"),
+        TypeRef::I128 => Some("i128"),
+        TypeRef::F32 => Some("f32"),
+        TypeRef::F64 => Some("f64"),
+        TypeRef::Pubkey => Some("pubkey"),
+        TypeRef::Delegate => Some("delegate"),
+        _ => None,
+    }
+}
+
+// Strings and vectors are the only types taking their size from the `cap` attribute
+pub fn is_capped(ty: &TypeRef) -> bool {
+    matches!(ty, TypeRef::String | TypeRef::Vec(_))
+}
+
+pub fn capped_types(ty: &TypeRef) -> Vec<&TypeRef> {
+    match ty {
+        TypeRef::Option(inner) => capped_types(inner),
+        TypeRef::Vec(inner) => {
+            let mut capped = vec![ty];
+            capped.extend(capped_types(inner));
+            capped
+        }
+        TypeRef::String => vec![ty],
+        _ => Vec::new(),
+    }
+}
+
+// Whether `pack_vec`/`instructions_unpack_vec` can step over elements of this type, they need
+// every element to take the same number of bytes
+pub fn is_fixed_size(ty: &TypeRef) -> bool {
+    match ty {
+        TypeRef::Option(inner) => is_fixed_size(inner),
+        TypeRef::String | TypeRef::Vec(_) => false,
+        _ => true,
+    }
+}
+
+// Bytes a value takes inside an account, `cap` is the attribute of the field the type belongs
+// to. Expects a validated CIDL, returns None for unknown types and sizes overflowing a usize
+pub fn state_size(cidl: &Cidl, ty: &TypeRef, cap: Option<usize>) -> Option<usize> {
+    match ty {
+        TypeRef::String => cap?.checked_add(4),
+        TypeRef::Option(inner) => state_size(cidl, inner, cap)?.checked_add(1),
+        TypeRef::Vec(inner) => cap?
+            .checked_mul(state_size(cidl, inner, None)?)?
+            .checked_add(4),
+        TypeRef::Named(name) => type_size(cidl, cidl.find_type(name)?),
+        _ => primitive_size(ty),
+    }
+}
+
+pub fn type_size(cidl: &Cidl, ty: &TypeDef) -> Option<usize> {
+    ty.fields.iter().try_fold(0usize, |size, field| {
+        size.checked_add(state_size(cidl, &field.ty.value, field.cap())?)
+    })
+}
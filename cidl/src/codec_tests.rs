@@ -0,0 +1,90 @@
+use crate::codec::*;
+use crate::model::*;
+use codigolib::rust_utils::*;
+use solana_program::pubkey::Pubkey;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_primitive_sizes_match_codigolib() {
+        let buf: &mut [u8] = &mut [0; 64];
+        let len = buf.len();
+
+        let sizes = [
+            (TypeRef::Bool, len - pack_bool(buf, true).len()),
+            (TypeRef::U8, len - pack_u8(buf, 0).len()),
+            (TypeRef::I8, len - pack_i8(buf, 0).len()),
+            (TypeRef::U16, len - pack_u16(buf, 0).len()),
+            (TypeRef::I16, len - pack_i16(buf, 0).len()),
+            (TypeRef::U32, len - pack_u32(buf, 0).len()),
+            (TypeRef::I32, len - pack_i32(buf, 0).len()),
+            (TypeRef::F32, len - pack_f32(buf, 0.0).len()),
+            (TypeRef::U64, len - pack_u64(buf, 0).len()),
+            (TypeRef::I64, len - pack_i64(buf, 0).len()),
+            (TypeRef::F64, len - pack_f64(buf, 0.0).len()),
+            (TypeRef::U128, len - pack_u128(buf, 0).len()),
+            (TypeRef::I128, len - pack_i128(buf, 0).len()),
+            (
+                TypeRef::Pubkey,
+                len - pack_pubkey(buf, Pubkey::default()).len(),
+            ),
+        ];
+
+        for (ty, size) in sizes {
+            assert_eq!(primitive_size(&ty), Some(size), "{}", ty);
+            assert!(primitive_codec_name(&ty).is_some(), "{}", ty);
+        }
+    }
+
+    #[test]
+    fn it_capped_sizes_match_codigolib() {
+        let buf: &mut [u8] = &mut [0; 64];
+        let len = buf.len();
+
+        // Strings and vectors take the 4 bytes length prefix plus their whole capacity
+        assert_eq!(
+            len - pack_string(buf, "abc".to_string(), 4 + 10).len(),
+            4 + 10
+        );
+        assert_eq!(
+            len - pack_vec(buf, 3, 2, pack_u16, vec![1, 2]).len(),
+            4 + 3 * 2
+        );
+    }
+
+    #[test]
+    fn it_capped_types() {
+        let nested = TypeRef::Option(Box::new(TypeRef::Vec(Box::new(TypeRef::String))));
+
+        assert_eq!(capped_types(&TypeRef::U8).len(), 0);
+        assert_eq!(capped_types(&TypeRef::String).len(), 1);
+        assert_eq!(capped_types(&nested).len(), 2);
+        assert!(is_fixed_size(&TypeRef::Option(Box::new(TypeRef::U64))));
+        assert!(!is_fixed_size(&nested));
+    }
+
+    #[test]
+    fn it_state_sizes() {
+        let cidl = crate::parse(include_str!("../../cidl.yaml")).unwrap();
+        let option_vec = TypeRef::Option(Box::new(TypeRef::Vec(Box::new(TypeRef::U16))));
+
+        // The version takes 1 byte, the string field 4 + 50, the delegates 4 + 5 * 33 and the mint and
+        // the vault 32 each, as decoded by `Record.decode` in the sdk
+        assert_eq!(
+            type_size(&cidl, cidl.find_type("Record").unwrap()),
+            Some(306)
+        );
+        assert_eq!(
+            state_size(&cidl, &TypeRef::Named("Record".to_string()), None),
+            Some(306)
+        );
+        assert_eq!(state_size(&cidl, &option_vec, Some(3)), Some(1 + 4 + 3 * 2));
+        assert_eq!(state_size(&cidl, &TypeRef::String, None), None);
+        assert_eq!(
+            state_size(&cidl, &TypeRef::Named("Unknown".to_string()), None),
+            None
+        );
+    }
+}
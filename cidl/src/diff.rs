@@ -0,0 +1,461 @@
+use crate::codec::*;
+use crate::model::*;
+use crate::program::MethodModel;
+use crate::yaml::Span;
+use std::fmt;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Impact {
+    Compatible,
+    // Deployed clients send instructions the new program no longer understands
+    ClientAbiBreaking,
+    // Accounts written by the old program can no longer be loaded by the new one
+    AccountLayoutBreaking,
+}
+
+impl fmt::Display for Impact {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Impact::Compatible => write!(f, "compatible"),
+            Impact::ClientAbiBreaking => write!(f, "client-ABI-breaking"),
+            Impact::AccountLayoutBreaking => write!(f, "account-layout-breaking"),
+        }
+    }
+}
+
+// Which of the two files the span of a change points to, removals only exist in the old one
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Side {
+    Old,
+    New,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Change {
+    pub impact: Impact,
+    pub side: Side,
+    pub span: Span,
+    pub message: String,
+}
+
+impl Change {
+    fn new(impact: Impact, side: Side, span: Span, message: impl Into<String>) -> Change {
+        Change {
+            impact,
+            side,
+            span,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}: {}", self.span, self.impact, self.message)
+    }
+}
+
+pub fn is_breaking(changes: &[Change]) -> bool {
+    changes
+        .iter()
+        .any(|change| change.impact != Impact::Compatible)
+}
+
+// Classifies the changes between two validated CIDLs by their effect on the codigolib byte
+// layouts of accounts and on the instructions built by deployed clients
+pub fn diff(old: &Cidl, new: &Cidl) -> Vec<Change> {
+    let mut changes = Vec::new();
+    let stored = stored_types(old);
+
+    for old_ty in &old.types {
+        if new.find_type(&old_ty.name.value).is_none() {
+            let impact = match old_ty.solana {
+                Some(_) => Impact::AccountLayoutBreaking,
+                None => Impact::ClientAbiBreaking,
+            };
+            changes.push(Change::new(
+                impact,
+                Side::Old,
+                old_ty.name.span,
+                format!("type `{}` was removed", old_ty.name.value),
+            ));
+        }
+    }
+    for new_ty in &new.types {
+        match old.find_type(&new_ty.name.value) {
+            Some(old_ty) => {
+                // Types stored in accounts break the accounts, the others only the instructions
+                let impact = match stored.contains(&old_ty.name.value.as_str()) {
+                    true => Impact::AccountLayoutBreaking,
+                    false => Impact::ClientAbiBreaking,
+                };
+                diff_fields(old, new, old_ty, new_ty, impact, &mut changes);
+                diff_solana(old_ty, new_ty, &mut changes);
+            }
+            None => changes.push(Change::new(
+                Impact::Compatible,
+                Side::New,
+                new_ty.name.span,
+                format!("type `{}` was added", new_ty.name.value),
+            )),
+        }
+    }
+
+    diff_methods(old, new, &mut changes);
+    changes
+}
+
+// Accounts and every type packed inside them
+fn stored_types(cidl: &Cidl) -> Vec<&str> {
+    let mut stored: Vec<&str> = cidl
+        .types
+        .iter()
+        .filter(|ty| ty.solana.is_some())
+        .map(|ty| ty.name.value.as_str())
+        .collect();
+
+    let mut i = 0;
+    while i < stored.len() {
+        if let Some(ty) = cidl.find_type(stored[i]) {
+            for field in &ty.fields {
+                if let Some(name) = named(&field.ty.value) {
+                    if !stored.contains(&name) {
+                        stored.push(name);
+                    }
+                }
+            }
+        }
+        i += 1;
+    }
+
+    stored
+}
+
+fn named(ty: &TypeRef) -> Option<&str> {
+    match ty {
+        TypeRef::Option(inner) | TypeRef::Vec(inner) => named(inner),
+        TypeRef::Named(name) => Some(name),
+        _ => None,
+    }
+}
+
+// Fields pack the same bytes when they have the same type and the same cap
+fn same_encoding(old: &Field, new: &Field) -> bool {
+    old.ty.value == new.ty.value && (!has_cap(&old.ty.value) || old.cap() == new.cap())
+}
+
+fn has_cap(ty: &TypeRef) -> bool {
+    !capped_types(ty).is_empty()
+}
+
+fn diff_fields(
+    old_cidl: &Cidl,
+    new_cidl: &Cidl,
+    old: &TypeDef,
+    new: &TypeDef,
+    impact: Impact,
+    changes: &mut Vec<Change>,
+) {
+    let name = &new.name.value;
+    let mut breaking = false;
+    let mut renamed = Vec::new();
+
+    for (i, field) in new.fields.iter().enumerate() {
+        let old_field = match old.find_field(&field.name.value) {
+            Some(old_field) => old_field,
+            None => {
+                // A field in the same position with the same encoding is a rename
+                match old.fields.get(i) {
+                    Some(old_field)
+                        if new.find_field(&old_field.name.value).is_none()
+                            && same_encoding(old_field, field) =>
+                    {
+                        renamed.push(old_field.name.value.as_str());
+                        changes.push(Change::new(
+                            Impact::Compatible,
+                            Side::New,
+                            field.name.span,
+                            format!(
+                                "field `{}.{}` was renamed to `{}`, the layout is unchanged",
+                                name, old_field.name.value, field.name.value
+                            ),
+                        ));
+                    }
+                    _ => {
+                        breaking = true;
+                        changes.push(Change::new(
+                            impact,
+                            Side::New,
+                            field.name.span,
+                            format!("field `{}.{}` was added", name, field.name.value),
+                        ));
+                    }
+                }
+                continue;
+            }
+        };
+
+        if old_field.ty.value != field.ty.value {
+            breaking = true;
+            changes.push(Change::new(
+                impact,
+                Side::New,
+                field.ty.span,
+                format!(
+                    "field `{}.{}` changed from {} to {}",
+                    name, field.name.value, old_field.ty.value, field.ty.value
+                ),
+            ));
+        } else if !same_encoding(old_field, field) {
+            breaking = true;
+            let (old_cap, new_cap) = (old_field.cap().unwrap_or(0), field.cap().unwrap_or(0));
+            let mut message = format!(
+                "cap of `{}.{}` changed from {} to {}",
+                name, field.name.value, old_cap, new_cap
+            );
+            if new_cap < old_cap {
+                message += ", stored values may no longer fit";
+            }
+            changes.push(Change::new(impact, Side::New, field.span, message));
+        }
+    }
+
+    for field in &old.fields {
+        if new.find_field(&field.name.value).is_none()
+            && !renamed.contains(&field.name.value.as_str())
+        {
+            breaking = true;
+            changes.push(Change::new(
+                impact,
+                Side::Old,
+                field.name.span,
+                format!("field `{}.{}` was removed", name, field.name.value),
+            ));
+        }
+    }
+
+    // Fields kept by both versions must still be packed in the same order
+    let kept = |from: &TypeDef, other: &TypeDef| -> Vec<String> {
+        from.fields
+            .iter()
+            .filter(|field| other.find_field(&field.name.value).is_some())
+            .map(|field| field.name.value.clone())
+            .collect()
+    };
+    if kept(old, new) != kept(new, old) {
+        breaking = true;
+        changes.push(Change::new(
+            impact,
+            Side::New,
+            new.name.span,
+            format!("fields of `{}` were reordered", name),
+        ));
+    }
+
+    if breaking && impact == Impact::AccountLayoutBreaking {
+        let old_size = type_size(old_cidl, old).unwrap_or(0);
+        let new_size = type_size(new_cidl, new).unwrap_or(0);
+        if old_size != new_size {
+            changes.push(Change::new(
+                impact,
+                Side::New,
+                new.name.span,
+                format!(
+                    "`{}` changed from {} to {} bytes, existing accounts fail `verify_len` until migrated",
+                    name, old_size, new_size
+                ),
+            ));
+        }
+    }
+}
+
+fn seeds(solana: &SolanaType) -> Vec<String> {
+    solana
+        .seeds
+        .iter()
+        .map(|seed| match &seed.ty {
+            Some(ty) => format!("{}: {}", seed.name.value, ty.value),
+            None => format!("\"{}\"", seed.name.value),
+        })
+        .collect()
+}
+
+fn owner(solana: &SolanaType) -> &str {
+    solana.owner.as_ref().map_or("self", |owner| &owner.value)
+}
+
+fn diff_solana(old: &TypeDef, new: &TypeDef, changes: &mut Vec<Change>) {
+    let name = &new.name.value;
+    let (old_solana, new_solana) = match (&old.solana, &new.solana) {
+        (Some(old_solana), Some(new_solana)) => (old_solana, new_solana),
+        (None, Some(new_solana)) => {
+            changes.push(Change::new(
+                Impact::Compatible,
+                Side::New,
+                new_solana.span,
+                format!("type `{}` became an account", name),
+            ));
+            return;
+        }
+        (Some(old_solana), None) => {
+            changes.push(Change::new(
+                Impact::AccountLayoutBreaking,
+                Side::Old,
+                old_solana.span,
+                format!("type `{}` is no longer an account", name),
+            ));
+            return;
+        }
+        (None, None) => return,
+    };
+
+    if seeds(old_solana) != seeds(new_solana) {
+        changes.push(Change::new(
+            Impact::AccountLayoutBreaking,
+            Side::New,
+            new_solana.span,
+            format!(
+                "seeds of `{}` changed from [{}] to [{}], existing accounts live at the old addresses",
+                name,
+                seeds(old_solana).join(", "),
+                seeds(new_solana).join(", ")
+            ),
+        ));
+    }
+    if owner(old_solana) != owner(new_solana) {
+        changes.push(Change::new(
+            Impact::AccountLayoutBreaking,
+            Side::New,
+            new_solana.span,
+            format!(
+                "owner of `{}` changed from {} to {}",
+                name,
+                owner(old_solana),
+                owner(new_solana)
+            ),
+        ));
+    }
+}
+
+// Account metas in the order the sdk passes them, accounts are named by their type since the
+// program only sees positions
+fn account_metas(method: &MethodModel) -> Vec<String> {
+    let mut metas: Vec<_> = method
+        .accounts
+        .iter()
+        .map(|account| {
+            let signer =
+                account.is_signer() || (account.is_init() && account.solana.seeds.is_empty());
+            meta(&account.ty.name.value, account.is_mut(), signer)
+        })
+        .collect();
+    if method.pays_rent() {
+        metas.push(meta("system_program", false, false));
+    }
+    if method.has_fee_payer() {
+        metas.push(meta("fee_payer", true, true));
+    }
+    metas
+}
+
+fn meta(name: &str, writable: bool, signer: bool) -> String {
+    match (writable, signer) {
+        (true, true) => format!("{} (writable, signer)", name),
+        (true, false) => format!("{} (writable)", name),
+        (false, true) => format!("{} (signer)", name),
+        (false, false) => name.to_string(),
+    }
+}
+
+fn diff_methods(old: &Cidl, new: &Cidl, changes: &mut Vec<Change>) {
+    for method in &old.methods {
+        if new.find_method(&method.name.value).is_none() {
+            changes.push(Change::new(
+                Impact::ClientAbiBreaking,
+                Side::Old,
+                method.name.span,
+                format!("method `{}` was removed", method.name.value),
+            ));
+        }
+    }
+
+    for (i, method) in new.methods.iter().enumerate() {
+        let name = &method.name.value;
+        let old_index = old.methods.iter().position(|old| old.name.value == *name);
+        let old_method = match old_index {
+            Some(old_index) => &old.methods[old_index],
+            None => {
+                changes.push(Change::new(
+                    Impact::Compatible,
+                    Side::New,
+                    method.name.span,
+                    format!("method `{}` was added as instruction {}", name, i),
+                ));
+                continue;
+            }
+        };
+
+        // The instruction tag is the position of the method
+        if old_index != Some(i) {
+            changes.push(Change::new(
+                Impact::ClientAbiBreaking,
+                Side::New,
+                method.name.span,
+                format!(
+                    "method `{}` moved from instruction {} to {}",
+                    name,
+                    old_index.unwrap_or(0),
+                    i
+                ),
+            ));
+        }
+
+        let old_model = MethodModel::new(old, old_method);
+        let new_model = MethodModel::new(new, method);
+
+        let types = |model: &MethodModel| -> Vec<String> {
+            model.args.iter().map(|arg| arg.ty.to_string()).collect()
+        };
+        let names = |model: &MethodModel| -> Vec<String> {
+            model.args.iter().map(|arg| arg.name.clone()).collect()
+        };
+        if types(&old_model) != types(&new_model) {
+            changes.push(Change::new(
+                Impact::ClientAbiBreaking,
+                Side::New,
+                method.name.span,
+                format!(
+                    "instruction data of `{}` changed from ({}) to ({})",
+                    name,
+                    types(&old_model).join(", "),
+                    types(&new_model).join(", ")
+                ),
+            ));
+        } else if names(&old_model) != names(&new_model) {
+            changes.push(Change::new(
+                Impact::Compatible,
+                Side::New,
+                method.name.span,
+                format!(
+                    "arguments of `{}` were renamed, the instruction data is unchanged",
+                    name
+                ),
+            ));
+        }
+
+        let (old_metas, new_metas) = (account_metas(&old_model), account_metas(&new_model));
+        if old_metas != new_metas {
+            changes.push(Change::new(
+                Impact::ClientAbiBreaking,
+                Side::New,
+                method.name.span,
+                format!(
+                    "accounts of `{}` changed from [{}] to [{}]",
+                    name,
+                    old_metas.join(", "),
+                    new_metas.join(", ")
+                ),
+            ));
+        }
+    }
+}
@@ -0,0 +1,258 @@
+use crate::diff::*;
+use crate::parser::parse;
+use crate::validator::{has_errors, validate};
+use crate::Cidl;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BUDGET_TRACKER: &str = include_str!("../../cidl.yaml");
+
+    const REGISTER_INCOME: &str = "  - name: register_income
+    summary: Register the given amount as an income for the given record account. The total total_balance of the account will be increased.
+    inputs:
+      - name: user_record
+        type: Record
+        description: The user record account
+        solana:
+          attributes: [ mut, role:contributor ]
+      - name: amount
+        type: u32
+        description: The amount to be registered as the income.
+";
+
+    fn cidl(source: &str) -> Cidl {
+        let cidl = parse(source).unwrap();
+        assert!(!has_errors(&validate(&cidl)));
+        cidl
+    }
+
+    // Adds a method after the last one of the budget tracker
+    fn append_method(source: &str, method: &str) -> String {
+        format!("{}{}", source, method)
+    }
+
+    fn changes(source: &str) -> Vec<Change> {
+        diff(&cidl(BUDGET_TRACKER), &cidl(source))
+    }
+
+    fn messages(changes: &[Change]) -> Vec<(Impact, &str)> {
+        changes
+            .iter()
+            .map(|change| (change.impact, change.message.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn it_finds_no_changes_between_equal_versions() {
+        assert!(changes(BUDGET_TRACKER).is_empty());
+    }
+
+    #[test]
+    fn it_breaks_accounts_when_a_field_type_changes() {
+        let changes = changes(&BUDGET_TRACKER.replace(
+            "      - name: outcome\n        type: u32",
+            "      - name: outcome\n        type: u64",
+        ));
+
+        assert_eq!(
+            messages(&changes),
+            vec![
+                (
+                    Impact::AccountLayoutBreaking,
+                    "field `Record.outcome` changed from u32 to u64"
+                ),
+                (
+                    Impact::AccountLayoutBreaking,
+                    "`Record` changed from 306 to 310 bytes, existing accounts fail `verify_len` until migrated"
+                ),
+            ]
+        );
+        assert!(is_breaking(&changes));
+    }
+
+    #[test]
+    fn it_breaks_accounts_when_a_cap_shrinks() {
+        let changes = changes(&BUDGET_TRACKER.replace("cap:50", "cap:20"));
+
+        assert_eq!(
+            messages(&changes)[0],
+            (
+                Impact::AccountLayoutBreaking,
+                "cap of `Record.name` changed from 50 to 20, stored values may no longer fit"
+            )
+        );
+        assert_eq!(changes[0].side, Side::New);
+    }
+
+    #[test]
+    fn it_breaks_accounts_when_fields_are_reordered() {
+        let changes = changes(&BUDGET_TRACKER.replace(
+            "      - name: outcome\n        type: u32\n        description: Sum of all outcomes.\n      - name: income\n        type: u32\n        description: Sum of all incomes.\n",
+            "      - name: income\n        type: u32\n        description: Sum of all incomes.\n      - name: outcome\n        type: u32\n        description: Sum of all outcomes.\n",
+        ));
+
+        // Same size, but income and outcome swap their bytes
+        assert_eq!(
+            messages(&changes),
+            vec![(
+                Impact::AccountLayoutBreaking,
+                "fields of `Record` were reordered"
+            )]
+        );
+    }
+
+    #[test]
+    fn it_breaks_accounts_when_a_field_is_removed() {
+        let changes = changes(&BUDGET_TRACKER.replace(
+            "      - name: moves\n        type: u16\n        description: Number incomes/outcomes registered.\n",
+            "",
+        ));
+
+        assert_eq!(changes[0].message, "field `Record.moves` was removed");
+        assert_eq!(changes[0].side, Side::Old);
+        assert!(changes[1].message.contains("from 306 to 304 bytes"));
+    }
+
+    #[test]
+    fn it_allows_renaming_a_field_in_place() {
+        let changes = changes(&BUDGET_TRACKER.replace("name: moves", "name: move_count"));
+
+        assert_eq!(
+            messages(&changes),
+            vec![(
+                Impact::Compatible,
+                "field `Record.moves` was renamed to `move_count`, the layout is unchanged"
+            )]
+        );
+        assert!(!is_breaking(&changes));
+    }
+
+    #[test]
+    fn it_breaks_clients_when_methods_are_renumbered() {
+        // register_income moves after register_token_outcome
+        let source = append_method(
+            &BUDGET_TRACKER.replace(REGISTER_INCOME, ""),
+            REGISTER_INCOME,
+        );
+        let changes = changes(&source);
+
+        assert_eq!(
+            messages(&changes),
+            vec![
+                (
+                    Impact::ClientAbiBreaking,
+                    "method `register_outcome` moved from instruction 2 to 1"
+                ),
+                (
+                    Impact::ClientAbiBreaking,
+                    "method `add_delegate` moved from instruction 3 to 2"
+                ),
+                (
+                    Impact::ClientAbiBreaking,
+                    "method `remove_delegate` moved from instruction 4 to 3"
+                ),
+                (
+                    Impact::ClientAbiBreaking,
+                    "method `update_user_name` moved from instruction 5 to 4"
+                ),
+                (
+                    Impact::ClientAbiBreaking,
+                    "method `migrate_record` moved from instruction 6 to 5"
+                ),
+                (
+                    Impact::ClientAbiBreaking,
+                    "method `close_record` moved from instruction 7 to 6"
+                ),
+                (
+                    Impact::ClientAbiBreaking,
+                    "method `bind_mint` moved from instruction 8 to 7"
+                ),
+                (
+                    Impact::ClientAbiBreaking,
+                    "method `register_token_income` moved from instruction 9 to 8"
+                ),
+                (
+                    Impact::ClientAbiBreaking,
+                    "method `register_token_outcome` moved from instruction 10 to 9"
+                ),
+                (
+                    Impact::ClientAbiBreaking,
+                    "method `register_income` moved from instruction 1 to 10"
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn it_allows_appending_methods() {
+        let source = append_method(
+            BUDGET_TRACKER,
+            &REGISTER_INCOME.replace("register_income", "register_refund"),
+        );
+        let changes = changes(&source);
+
+        assert_eq!(
+            messages(&changes),
+            vec![(
+                Impact::Compatible,
+                "method `register_refund` was added as instruction 11"
+            )]
+        );
+    }
+
+    #[test]
+    fn it_breaks_clients_when_instruction_data_or_accounts_change() {
+        let changes = changes(&BUDGET_TRACKER.replace(
+            "      - name: amount\n        type: u32\n        description: The amount to be registered as the income.",
+            "      - name: amount\n        type: u64\n        description: The amount to be registered as the income.",
+        ));
+        assert_eq!(
+            messages(&changes),
+            vec![(
+                Impact::ClientAbiBreaking,
+                "instruction data of `register_income` changed from (u32, sol:pubkey) to (u64, sol:pubkey)"
+            )]
+        );
+
+        let changes = changes_for_attributes("[ mut, init_if_needed ]", "[ mut ]");
+        assert_eq!(
+            messages(&changes),
+            vec![(
+                Impact::ClientAbiBreaking,
+                "accounts of `create_user_record` changed from [Record (writable), system_program, fee_payer (writable, signer)] to [Record (writable)]"
+            )]
+        );
+    }
+
+    fn changes_for_attributes(from: &str, to: &str) -> Vec<Change> {
+        changes(&BUDGET_TRACKER.replacen(from, to, 1))
+    }
+
+    #[test]
+    fn it_breaks_accounts_when_seeds_change() {
+        let changes = changes(&BUDGET_TRACKER.replacen(
+            "        - name: record\n",
+            "        - name: budget\n",
+            1,
+        ));
+
+        assert_eq!(
+            messages(&changes),
+            vec![(
+                Impact::AccountLayoutBreaking,
+                "seeds of `Record` changed from [\"record\", signer: sol:pubkey] to [\"budget\", signer: sol:pubkey], existing accounts live at the old addresses"
+            )]
+        );
+    }
+
+    #[test]
+    fn it_displays_changes_with_their_impact() {
+        let changes = changes(&BUDGET_TRACKER.replace("cap:50", "cap:60"));
+
+        assert!(changes[0]
+            .to_string()
+            .ends_with(": account-layout-breaking: cap of `Record.name` changed from 50 to 60"));
+    }
+}
@@ -0,0 +1,370 @@
+use crate::model::*;
+use crate::yaml::{comment_start, Span, Spanned};
+
+// Canonical layout of a cidl.yaml: two spaces per level, sequences indented below their key,
+// no trailing whitespace, at most one blank line in a row and a single final newline. Works on
+// lines so comments stay where they were, moved along with the block they belong to. The lines of
+// `|` and `>` scalars are part of their value, only their indentation follows the key
+
+struct Frame {
+    old: usize,
+    new: usize,
+    // Sequence written at the column of its key, only `- ` lines continue it
+    indentless: bool,
+}
+
+impl Frame {
+    fn new(old: usize, new: usize) -> Frame {
+        Frame {
+            old,
+            new,
+            indentless: false,
+        }
+    }
+}
+
+// Key owning the literal lines being copied, `base` is the column of the first one
+struct Scalar<'a> {
+    old: usize,
+    new: usize,
+    base: Option<usize>,
+    // `|+` and `>+` keep the blank lines ending the block in the value
+    keep: bool,
+    // Blank lines not known yet to be inside the block
+    blanks: Vec<&'a str>,
+}
+
+impl Scalar<'_> {
+    // The line moved along with the key, with the same characters after the block indentation
+    fn shift(&self, line: &str) -> String {
+        let indent = line.len() - line.trim_start_matches(' ').len();
+        match self.base {
+            Some(base) if indent >= base => {
+                format!(
+                    "{}{}",
+                    " ".repeat(self.new + 2 + indent - base),
+                    &line[indent..]
+                )
+            }
+            _ => line.to_string(),
+        }
+    }
+}
+
+enum Line<'a> {
+    Blank,
+    Comment { indent: usize, text: &'a str },
+    Content { indent: usize, text: &'a str },
+}
+
+fn classify(line: &str) -> Line<'_> {
+    let text = line.trim();
+    let indent = line.len() - line.trim_start().len();
+    if text.is_empty() {
+        Line::Blank
+    } else if text.starts_with('#') {
+        Line::Comment { indent, text }
+    } else {
+        Line::Content { indent, text }
+    }
+}
+
+// A key without an inline value opens a nested block
+fn opens_block(text: &str) -> bool {
+    let text = text.strip_prefix("- ").unwrap_or(text);
+    strip_comment(text).trim_end().ends_with(':')
+}
+
+// `key: |` and `key: >` are followed by literal lines
+fn opens_scalar(text: &str) -> bool {
+    let value = strip_comment(text).trim_end();
+    ["|", ">", "|-", ">-", "|+", ">+"]
+        .iter()
+        .any(|indicator| value.ends_with(&format!(": {}", indicator)))
+}
+
+fn strip_comment(text: &str) -> &str {
+    match comment_start(text) {
+        Some(i) => &text[..i],
+        None => text,
+    }
+}
+
+// Comments follow the innermost block starting at or before their column
+fn comment_indent(stack: &[Frame], indent: usize) -> usize {
+    match stack.iter().rev().find(|frame| frame.old <= indent) {
+        Some(frame) => frame.new + indent - frame.old,
+        None => indent,
+    }
+}
+
+fn shift(indent: usize, delta: isize) -> usize {
+    (indent as isize + delta).max(0) as usize
+}
+
+// Blank lines after the block are only part of the value with the keep indicator
+fn end_scalar(out: &mut Vec<String>, owner: &Scalar) {
+    if owner.keep {
+        out.extend(owner.blanks.iter().map(|blank| owner.shift(blank)));
+    } else if !owner.blanks.is_empty() && out.last().is_some_and(|last| !last.is_empty()) {
+        out.push(String::new());
+    }
+}
+
+pub fn format_cidl(source: &str) -> String {
+    let mut out: Vec<String> = Vec::new();
+    let mut stack: Vec<Frame> = Vec::new();
+    // Indentation change of the last content line, applied to plain scalars continuing it
+    let mut delta: isize = 0;
+    let mut previous_opens_block = false;
+    let mut scalar: Option<Scalar> = None;
+
+    for line in source.lines() {
+        if let Some(owner) = &mut scalar {
+            let indent = line.len() - line.trim_start_matches(' ').len();
+            if line.trim().is_empty() {
+                owner.blanks.push(line);
+                continue;
+            }
+            if indent > owner.old {
+                owner.base.get_or_insert(indent);
+                let blanks = std::mem::take(&mut owner.blanks);
+                out.extend(blanks.iter().map(|blank| owner.shift(blank)));
+                out.push(owner.shift(line));
+                continue;
+            }
+            end_scalar(&mut out, owner);
+            scalar = None;
+        }
+
+        let (indent, text, is_comment) = match classify(line) {
+            Line::Blank => {
+                if out.last().is_some_and(|last| !last.is_empty()) {
+                    out.push(String::new());
+                }
+                continue;
+            }
+            Line::Comment { indent, text } => (indent, text, true),
+            Line::Content { indent, text } => (indent, text, false),
+        };
+
+        if is_comment {
+            let new = comment_indent(&stack, indent);
+            out.push(format!("{}{}", " ".repeat(new), text));
+            continue;
+        }
+
+        let is_item = text == "-" || text.starts_with("- ");
+        while stack.last().is_some_and(|top| {
+            top.old > indent || (top.old == indent && top.indentless && !is_item)
+        }) {
+            stack.pop();
+        }
+
+        let new = match stack.last() {
+            Some(top) if previous_opens_block && (indent > top.old || is_item) => {
+                let mut frame = Frame::new(indent, top.new + 2);
+                frame.indentless = indent == top.old;
+                let new = frame.new;
+                stack.push(frame);
+                new
+            }
+            Some(top) if top.old == indent => top.new,
+            // Continuation of a multi-line plain scalar
+            Some(_) if !previous_opens_block => {
+                out.push(format!("{}{}", " ".repeat(shift(indent, delta)), text));
+                continue;
+            }
+            _ => {
+                stack.push(Frame::new(indent, 0));
+                0
+            }
+        };
+
+        // The mapping inside a sequence item continues two columns to the right of the dash
+        if is_item && text.len() > 2 {
+            stack.push(Frame::new(indent + 2, new + 2));
+        }
+
+        delta = new as isize - indent as isize;
+        previous_opens_block = opens_block(text);
+        if opens_scalar(text) {
+            scalar = Some(Scalar {
+                old: indent,
+                new,
+                base: None,
+                keep: strip_comment(text).trim_end().ends_with('+'),
+                blanks: Vec::new(),
+            });
+        }
+        out.push(format!("{}{}", " ".repeat(new), text));
+    }
+
+    let kept = match scalar {
+        Some(owner) if owner.keep => owner
+            .blanks
+            .iter()
+            .map(|blank| owner.shift(blank))
+            .collect(),
+        _ => Vec::new(),
+    };
+    while out.last().is_some_and(|last| last.is_empty()) {
+        out.pop();
+    }
+    out.extend(kept);
+    let mut formatted = out.join("\n");
+    formatted.push('\n');
+    formatted
+}
+
+// Formatting moves nodes to other lines and columns, everything else in the model must stay
+pub fn same_model(a: &Cidl, b: &Cidl) -> bool {
+    let (mut a, mut b) = (a.clone(), b.clone());
+    a.clear_spans();
+    b.clear_spans();
+    a == b
+}
+
+trait ClearSpans {
+    fn clear_spans(&mut self);
+}
+
+impl<T> ClearSpans for Spanned<T> {
+    fn clear_spans(&mut self) {
+        self.span = Span::default();
+    }
+}
+
+impl<T: ClearSpans> ClearSpans for Option<T> {
+    fn clear_spans(&mut self) {
+        if let Some(value) = self {
+            value.clear_spans();
+        }
+    }
+}
+
+impl<T: ClearSpans> ClearSpans for Vec<T> {
+    fn clear_spans(&mut self) {
+        self.iter_mut().for_each(ClearSpans::clear_spans);
+    }
+}
+
+impl ClearSpans for Cidl {
+    fn clear_spans(&mut self) {
+        self.cidl.clear_spans();
+        self.info.clear_spans();
+        self.imports.clear_spans();
+        self.types.clear_spans();
+        self.methods.clear_spans();
+        self.unknown_keys.clear_spans();
+    }
+}
+
+impl ClearSpans for UnknownKey {
+    fn clear_spans(&mut self) {
+        self.key.clear_spans();
+    }
+}
+
+impl ClearSpans for Import {
+    fn clear_spans(&mut self) {
+        self.path.clear_spans();
+        self.namespace.clear_spans();
+        self.span = Span::default();
+    }
+}
+
+impl ClearSpans for Info {
+    fn clear_spans(&mut self) {
+        self.name.clear_spans();
+        self.title.clear_spans();
+        self.version.clear_spans();
+        self.summary.clear_spans();
+        self.contact.clear_spans();
+        self.license.clear_spans();
+        self.span = Span::default();
+    }
+}
+
+impl ClearSpans for Contact {
+    fn clear_spans(&mut self) {
+        self.name.clear_spans();
+        self.web.clear_spans();
+        self.email.clear_spans();
+        self.git.clear_spans();
+        self.span = Span::default();
+    }
+}
+
+impl ClearSpans for License {
+    fn clear_spans(&mut self) {
+        self.name.clear_spans();
+        self.url.clear_spans();
+        self.span = Span::default();
+    }
+}
+
+impl ClearSpans for TypeDef {
+    fn clear_spans(&mut self) {
+        self.name.clear_spans();
+        self.summary.clear_spans();
+        self.description.clear_spans();
+        self.solana.clear_spans();
+        self.fields.clear_spans();
+        self.span = Span::default();
+    }
+}
+
+impl ClearSpans for SolanaType {
+    fn clear_spans(&mut self) {
+        self.owner.clear_spans();
+        self.seeds.clear_spans();
+        self.span = Span::default();
+    }
+}
+
+impl ClearSpans for Seed {
+    fn clear_spans(&mut self) {
+        self.name.clear_spans();
+        self.ty.clear_spans();
+        self.description.clear_spans();
+        self.span = Span::default();
+    }
+}
+
+impl ClearSpans for Field {
+    fn clear_spans(&mut self) {
+        self.name.clear_spans();
+        self.ty.clear_spans();
+        self.description.clear_spans();
+        self.solana.clear_spans();
+        self.span = Span::default();
+    }
+}
+
+impl ClearSpans for Method {
+    fn clear_spans(&mut self) {
+        self.name.clear_spans();
+        self.summary.clear_spans();
+        self.description.clear_spans();
+        self.inputs.clear_spans();
+        self.span = Span::default();
+    }
+}
+
+impl ClearSpans for Input {
+    fn clear_spans(&mut self) {
+        self.name.clear_spans();
+        self.ty.clear_spans();
+        self.description.clear_spans();
+        self.solana.clear_spans();
+        self.span = Span::default();
+    }
+}
+
+impl ClearSpans for SolanaAttributes {
+    fn clear_spans(&mut self) {
+        self.attributes.clear_spans();
+        self.span = Span::default();
+    }
+}
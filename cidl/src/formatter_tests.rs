@@ -0,0 +1,94 @@
+use crate::formatter::*;
+use crate::parser::parse;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BUDGET_TRACKER: &str = include_str!("../../cidl.yaml");
+
+    // The checked-in spec is kept as `cidl fmt` writes it
+    #[test]
+    fn it_keeps_the_budget_tracker_formatted() {
+        assert_eq!(format_cidl(BUDGET_TRACKER), BUDGET_TRACKER);
+    }
+
+    #[test]
+    fn it_indents_sequences_below_their_key() {
+        // The inputs are listed at the same column as `inputs:`
+        let source = "cidl: \"0.8\"\ninfo:\n  name: counter\nmethods:\n  - name: increment\n    inputs:\n    - name: counter\n      type: Counter\n      solana:\n        attributes: [ mut ]\n    - name: amount\n      type: u64\n";
+        let formatted = format_cidl(source);
+
+        assert_eq!(
+            formatted,
+            "cidl: \"0.8\"\ninfo:\n  name: counter\nmethods:\n  - name: increment\n    inputs:\n      - name: counter\n        type: Counter\n        solana:\n          attributes: [ mut ]\n      - name: amount\n        type: u64\n"
+        );
+        assert!(same_model(
+            &parse(&formatted).unwrap(),
+            &parse(source).unwrap()
+        ));
+        assert_eq!(format_cidl(&formatted), formatted);
+    }
+
+    #[test]
+    fn it_keeps_comments_with_their_block() {
+        let source = "cidl: \"0.8\"\ninfo:\n  name: counter\ntypes:\n  Counter:\n    fields:\n    - name: count\n      type: u64\n\n    # TODO: add the owner\n    # of the counter\nmethods: []\n\n\n# TODO: define increment\n#  - counter of type Counter\n";
+
+        assert_eq!(
+            format_cidl(source),
+            "cidl: \"0.8\"\ninfo:\n  name: counter\ntypes:\n  Counter:\n    fields:\n      - name: count\n        type: u64\n\n      # TODO: add the owner\n      # of the counter\nmethods: []\n\n# TODO: define increment\n#  - counter of type Counter\n"
+        );
+    }
+
+    #[test]
+    fn it_normalizes_whitespace() {
+        let source = "cidl: \"0.8\"   \n\n\n\ninfo:\n    name: counter\n    version: 0.0.1\ntypes:\n- name: Counter\n  fields:\n     - name: count   # Current value\n       type: u64\nmethods: []";
+
+        assert_eq!(
+            format_cidl(source),
+            "cidl: \"0.8\"\n\ninfo:\n  name: counter\n  version: 0.0.1\ntypes:\n  - name: Counter\n    fields:\n      - name: count   # Current value\n        type: u64\nmethods: []\n"
+        );
+    }
+
+    #[test]
+    fn it_shifts_literal_blocks_with_their_key() {
+        let source =
+            "info:\n    summary: |\n        First line\n          indented\n    name: counter\n";
+
+        assert_eq!(
+            format_cidl(source),
+            "info:\n  summary: |\n    First line\n      indented\n  name: counter\n"
+        );
+    }
+
+    #[test]
+    fn it_keeps_the_lines_of_literal_blocks() {
+        let source = "cidl: \"0.8\"\ninfo:\n    name: counter\n    summary: |\n        Counts things.  \n\n\n        # Not a comment\n          indented \n    version: 0.0.1\n";
+        let formatted = format_cidl(source);
+
+        assert_eq!(
+            formatted,
+            "cidl: \"0.8\"\ninfo:\n  name: counter\n  summary: |\n    Counts things.  \n\n\n    # Not a comment\n      indented \n  version: 0.0.1\n"
+        );
+        assert_eq!(
+            parse(&formatted).unwrap().info.summary.unwrap().value,
+            "Counts things.  \n\n\n# Not a comment\n  indented \n"
+        );
+        assert!(same_model(
+            &parse(source).unwrap(),
+            &parse(&formatted).unwrap()
+        ));
+        assert_eq!(format_cidl(&formatted), formatted);
+    }
+
+    #[test]
+    fn it_keeps_the_trailing_lines_of_kept_blocks() {
+        let source = "info:\n  name: counter\n  summary: |+\n    Counts things.\n\n\nmethods: []\n";
+
+        assert_eq!(format_cidl(source), source);
+        assert!(!same_model(
+            &parse("cidl: \"0.8\"\ninfo:\n  name: counter\n").unwrap(),
+            &parse("cidl: \"0.8\"\ninfo:\n  name: other\n").unwrap()
+        ));
+    }
+}
@@ -0,0 +1,542 @@
+use crate::codec::*;
+use crate::imports::libraries;
+use crate::merge::{merge_stub, stub_name};
+use crate::model::*;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+pub const GENERATED_HEADER: &str =
+    "// Generated from the CIDL by `cidl generate`, changes to this file will be overwritten\n";
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum FileKind {
+    // Owned by the generator, rewritten on every run
+    Generated,
+    // Written once, from then on owned by the developer
+    Stub,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GeneratedFile {
+    pub path: PathBuf,
+    pub contents: String,
+    pub kind: FileKind,
+}
+
+impl GeneratedFile {
+    pub fn generated(path: impl Into<PathBuf>, contents: String) -> GeneratedFile {
+        GeneratedFile {
+            path: path.into(),
+            contents,
+            kind: FileKind::Generated,
+        }
+    }
+
+    pub fn stub(path: impl Into<PathBuf>, contents: String) -> GeneratedFile {
+        GeneratedFile {
+            path: path.into(),
+            contents,
+            kind: FileKind::Stub,
+        }
+    }
+}
+
+// Writes the files below `root` and returns the ones that changed. Existing stubs keep their body,
+// only the generated part above it is updated
+pub fn write_files(root: &Path, files: &[GeneratedFile]) -> io::Result<Vec<PathBuf>> {
+    let mut written = Vec::new();
+
+    for file in files {
+        let path = root.join(&file.path);
+        let contents = match (fs::read_to_string(&path).ok(), file.kind) {
+            (Some(current), FileKind::Stub) => {
+                match merge_stub(&current, &file.contents, stub_name(&file.path)) {
+                    Some(merged) if merged != current => merged,
+                    _ => continue,
+                }
+            }
+            (Some(current), FileKind::Generated) if current == file.contents => continue,
+            _ => file.contents.clone(),
+        };
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&path, contents)?;
+        written.push(file.path.clone());
+    }
+
+    Ok(written)
+}
+
+pub fn pascal_case(name: &str) -> String {
+    name.split('_')
+        .map(|word| {
+            let mut chars = word.chars();
+            match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars).collect(),
+                None => String::new(),
+            }
+        })
+        .collect()
+}
+
+pub fn rust_type(ty: &TypeRef) -> String {
+    match ty {
+        TypeRef::String => "String".to_string(),
+        TypeRef::Pubkey => "Pubkey".to_string(),
+        TypeRef::Delegate => "Delegate".to_string(),
+        TypeRef::Option(inner) => format!("Option<{}>", rust_type(inner)),
+        TypeRef::Vec(inner) => format!("Vec<{}>", rust_type(inner)),
+        TypeRef::Named(name) => name.clone(),
+        _ => ty.to_string(),
+    }
+}
+
+pub fn is_copy(ty: &TypeRef) -> bool {
+    match ty {
+        TypeRef::Option(inner) => is_copy(inner),
+        TypeRef::String | TypeRef::Vec(_) | TypeRef::Named(_) => false,
+        _ => true,
+    }
+}
+
+pub fn uses_pubkey(ty: &TypeRef) -> bool {
+    match ty {
+        TypeRef::Pubkey => true,
+        TypeRef::Option(inner) | TypeRef::Vec(inner) => uses_pubkey(inner),
+        _ => false,
+    }
+}
+
+pub fn uses_delegate(ty: &TypeRef) -> bool {
+    match ty {
+        TypeRef::Delegate => true,
+        TypeRef::Option(inner) | TypeRef::Vec(inner) => uses_delegate(inner),
+        _ => false,
+    }
+}
+
+// How a value is laid out, see `Context` in the validator
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Layout {
+    State,
+    Instruction,
+}
+
+// The generated code calls the codigolib::rust_utils functions with sizes computed here, so the
+// CIDL must have been validated before rendering anything
+pub fn size(cidl: &Cidl, ty: &TypeRef, cap: Option<usize>) -> usize {
+    state_size(cidl, ty, cap).unwrap_or_else(|| panic!("`{}` has no packed size", ty))
+}
+
+fn inner_cap(ty: &TypeRef, cap: Option<usize>) -> Option<usize> {
+    match ty {
+        TypeRef::Vec(_) => None,
+        _ => cap,
+    }
+}
+
+// Expression packing `value` into `buf`, evaluates to the rest of the buffer
+pub fn pack_call(cidl: &Cidl, ty: &TypeRef, cap: Option<usize>, buf: &str, value: &str) -> String {
+    match ty {
+        TypeRef::String => format!("pack_string({}, {}, {})", buf, value, size(cidl, ty, cap)),
+        TypeRef::Option(inner) => format!(
+            "pack_option({}, {}, {}, Default::default())",
+            buf,
+            pack_fn(cidl, inner, cap),
+            value
+        ),
+        TypeRef::Vec(inner) => format!(
+            "pack_vec({}, {}, {}, {}, {})",
+            buf,
+            cap.expect("vectors have a cap"),
+            size(cidl, inner, None),
+            pack_fn(cidl, inner, inner_cap(ty, cap)),
+            value
+        ),
+        TypeRef::Named(_) => format!("{}.pack({})", value, buf),
+        _ => format!(
+            "pack_{}({}, {})",
+            primitive_codec_name(ty).unwrap(),
+            buf,
+            value
+        ),
+    }
+}
+
+// Function or closure usable where codigolib expects `Fn(&mut [u8], T) -> &mut [u8]`
+pub fn pack_fn(cidl: &Cidl, ty: &TypeRef, cap: Option<usize>) -> String {
+    match ty {
+        TypeRef::Named(name) => format!("|buf, data: {}| data.pack(buf)", name),
+        _ if ty.is_primitive() && *ty != TypeRef::String => {
+            format!("pack_{}", primitive_codec_name(ty).unwrap())
+        }
+        _ => format!("|buf, data| {}", pack_call(cidl, ty, cap, "buf", "data")),
+    }
+}
+
+// Expression unpacking a value from `buf`, evaluates to `(rest, value)`
+pub fn unpack_call(
+    cidl: &Cidl,
+    ty: &TypeRef,
+    cap: Option<usize>,
+    layout: Layout,
+    buf: &str,
+) -> String {
+    match (ty, layout) {
+        (TypeRef::String, Layout::State) => {
+            format!("state_unpack_string({}, {})", buf, size(cidl, ty, cap))
+        }
+        (TypeRef::String, Layout::Instruction) => format!("instructions_unpack_string({})", buf),
+        (TypeRef::Option(inner), Layout::State) => format!(
+            "state_unpack_option({}, {}, {})",
+            buf,
+            unpack_fn(cidl, inner, cap, layout),
+            size(cidl, inner, cap)
+        ),
+        (TypeRef::Option(inner), Layout::Instruction) => format!(
+            "instructions_unpack_option({}, {})",
+            buf,
+            unpack_fn(cidl, inner, cap, layout)
+        ),
+        (TypeRef::Vec(inner), Layout::State) => format!(
+            "state_unpack_vec({}, {}, {}, {})",
+            buf,
+            cap.expect("vectors have a cap"),
+            size(cidl, inner, None),
+            unpack_fn(cidl, inner, None, layout)
+        ),
+        // Elements keep their account layout, `instructions_unpack_vec` skips them by size
+        (TypeRef::Vec(inner), Layout::Instruction) => format!(
+            "instructions_unpack_vec({}, {}, {})",
+            buf,
+            size(cidl, inner, None),
+            unpack_fn(cidl, inner, None, Layout::State)
+        ),
+        // Named types always use their account layout, even inside instructions
+        (TypeRef::Named(name), _) => format!("{}::unpack({})", name, buf),
+        _ => format!("unpack_{}({})", primitive_codec_name(ty).unwrap(), buf),
+    }
+}
+
+// Function or closure usable where codigolib expects `Fn(&[u8]) -> (&[u8], T)`
+pub fn unpack_fn(cidl: &Cidl, ty: &TypeRef, cap: Option<usize>, layout: Layout) -> String {
+    match ty {
+        TypeRef::Named(name) => format!("{}::unpack", name),
+        TypeRef::String if layout == Layout::Instruction => {
+            "instructions_unpack_string".to_string()
+        }
+        _ if ty.is_primitive() && *ty != TypeRef::String => {
+            format!("unpack_{}", primitive_codec_name(ty).unwrap())
+        }
+        _ => format!("|buf| {}", unpack_call(cidl, ty, cap, layout, "buf")),
+    }
+}
+
+// Checked counterpart of `unpack_call` for bytes that may not come from the codec, evaluates to
+// `Result<(rest, value), ProgramError>`
+pub fn try_unpack_call(
+    cidl: &Cidl,
+    ty: &TypeRef,
+    cap: Option<usize>,
+    layout: Layout,
+    buf: &str,
+) -> String {
+    match (ty, layout) {
+        (TypeRef::String, Layout::State) => {
+            format!("try_state_unpack_string({}, {})", buf, size(cidl, ty, cap))
+        }
+        (TypeRef::String, Layout::Instruction) => {
+            format!("try_instructions_unpack_string({})", buf)
+        }
+        (TypeRef::Option(inner), Layout::State) => format!(
+            "try_state_unpack_option({}, {}, {})",
+            buf,
+            try_unpack_fn(cidl, inner, cap, layout),
+            size(cidl, inner, cap)
+        ),
+        (TypeRef::Option(inner), Layout::Instruction) => format!(
+            "try_instructions_unpack_option({}, {})",
+            buf,
+            try_unpack_fn(cidl, inner, cap, layout)
+        ),
+        (TypeRef::Vec(inner), Layout::State) => format!(
+            "try_state_unpack_vec({}, {}, {}, {})",
+            buf,
+            cap.expect("vectors have a cap"),
+            size(cidl, inner, None),
+            try_unpack_fn(cidl, inner, None, layout)
+        ),
+        (TypeRef::Vec(inner), Layout::Instruction) => format!(
+            "try_instructions_unpack_vec({}, {}, {})",
+            buf,
+            size(cidl, inner, None),
+            try_unpack_fn(cidl, inner, None, Layout::State)
+        ),
+        (TypeRef::Named(name), _) => format!("{}::try_unpack({})", name, buf),
+        _ => format!("try_unpack_{}({})", primitive_codec_name(ty).unwrap(), buf),
+    }
+}
+
+// Function or closure usable where codigolib expects
+// `Fn(&[u8]) -> Result<(&[u8], T), ProgramError>`
+pub fn try_unpack_fn(cidl: &Cidl, ty: &TypeRef, cap: Option<usize>, layout: Layout) -> String {
+    match ty {
+        TypeRef::Named(name) => format!("{}::try_unpack", name),
+        TypeRef::String if layout == Layout::Instruction => {
+            "try_instructions_unpack_string".to_string()
+        }
+        _ if ty.is_primitive() && *ty != TypeRef::String => {
+            format!("try_unpack_{}", primitive_codec_name(ty).unwrap())
+        }
+        _ => format!("|buf| {}", try_unpack_call(cidl, ty, cap, layout, "buf")),
+    }
+}
+
+// Each line of `text` as a `//` comment, indented by `indent`
+pub fn comment(text: &str, indent: &str) -> String {
+    text.lines()
+        .map(|line| match line.trim_end() {
+            "" => format!("{}//\n", indent),
+            line => format!("{}// {}\n", indent, line),
+        })
+        .collect()
+}
+
+// The trait lives in codigolib so crates generated from different CIDL files share it
+pub const STATE_USE: &str = "pub use codigolib::rust_utils::State;\n";
+
+// Types of the CIDL itself, imported ones are defined in the crate of their library
+pub fn own_types(cidl: &Cidl) -> impl Iterator<Item = &TypeDef> {
+    cidl.types.iter().filter(|ty| ty.library.is_none())
+}
+
+// `[dependencies]` lines of the crates generated from the imported CIDL files, expected next to
+// the generated crate like codigolib
+pub fn library_dependencies(cidl: &Cidl) -> String {
+    libraries(cidl)
+        .iter()
+        .map(|library| format!("{} = {{ path = \"../{}\" }}\n", library, library))
+        .collect()
+}
+
+// Whether `ty` refers to a type defined by the CIDL itself, which has to be imported from the
+// module rendering the type definitions
+pub fn uses_own_type(cidl: &Cidl, ty: &TypeRef) -> bool {
+    match ty {
+        TypeRef::Option(inner) | TypeRef::Vec(inner) => uses_own_type(cidl, inner),
+        TypeRef::Named(name) => cidl.find_type(name).is_some_and(|ty| ty.library.is_none()),
+        _ => false,
+    }
+}
+
+pub fn types_use_pubkey(cidl: &Cidl) -> bool {
+    own_types(cidl)
+        .flat_map(|ty| &ty.fields)
+        .any(|field| uses_pubkey(&field.ty.value))
+}
+
+pub fn types_use_delegate(cidl: &Cidl) -> bool {
+    own_types(cidl)
+        .flat_map(|ty| &ty.fields)
+        .any(|field| uses_delegate(&field.ty.value))
+}
+
+// The Delegate type and its pack/unpack functions
+pub const DELEGATE_USE: &str = "use codigolib::delegate_utils::*;\n";
+
+// A struct per CIDL type implementing `State` with the codigolib account layout
+pub fn render_type_defs(cidl: &Cidl) -> String {
+    let mut out = String::new();
+
+    for ty in own_types(cidl) {
+        out += "\n";
+        if let Some(summary) = &ty.summary {
+            out += &comment(&summary.value, "");
+        }
+        // The token program defines the layout, the type only names the accounts
+        if ty.is_token_program() {
+            out += &format!("pub struct {};\n", ty.name.value);
+            continue;
+        }
+        out += "#[derive(Clone, Debug, Default, PartialEq)]\n";
+        out += &format!("pub struct {} {{\n", ty.name.value);
+        for field in &ty.fields {
+            if let Some(description) = &field.description {
+                out += &comment(&description.value, "    ");
+            }
+            out += &format!(
+                "    pub {}: {},\n",
+                field.name.value,
+                rust_type(&field.ty.value)
+            );
+        }
+        out += "}\n";
+
+        out += &format!(
+            "\nimpl State for {} {{\n    const LEN: usize = {};\n",
+            ty.name.value,
+            type_size(cidl, ty).expect("validated CIDL")
+        );
+        out += "    const FIELDS: &'static [(&'static str, usize)] = &[\n";
+        for field in &ty.fields {
+            out += &format!(
+                "        (\"{}\", {}),\n",
+                field.name.value,
+                state_size(cidl, &field.ty.value, field.cap()).expect("validated CIDL")
+            );
+        }
+        out += "    ];\n";
+
+        out += "\n    fn pack<'a>(&self, buf: &'a mut [u8]) -> &'a mut [u8] {\n";
+        let calls: Vec<_> = ty
+            .fields
+            .iter()
+            .map(|field| {
+                let ty = &field.ty.value;
+                let value = match ty {
+                    TypeRef::Named(_) => format!("self.{}", field.name.value),
+                    _ if is_copy(ty) => format!("self.{}", field.name.value),
+                    _ => format!("self.{}.clone()", field.name.value),
+                };
+                pack_call(cidl, ty, field.cap(), "buf", &value)
+            })
+            .collect();
+        out += &chain_calls(&calls, "buf", 8);
+        out += "    }\n";
+
+        out += "\n    fn unpack(buf: &[u8]) -> (&[u8], Self) {\n";
+        for field in &ty.fields {
+            out += &format!(
+                "        let (buf, {}) = {};\n",
+                field.name.value,
+                unpack_call(cidl, &field.ty.value, field.cap(), Layout::State, "buf")
+            );
+        }
+        let names: Vec<_> = ty.fields.iter().map(|f| f.name.value.as_str()).collect();
+        let value = struct_literal("Self", &names, 12);
+        if value.contains('\n') {
+            out += &format!(
+                "        (\n            buf,\n            {},\n        )\n",
+                value
+            );
+        } else {
+            out += &format!("        (buf, {})\n", value);
+        }
+        out += "    }\n";
+
+        out += "\n    fn try_unpack(buf: &[u8]) -> Result<(&[u8], Self), ProgramError> {\n";
+        for field in &ty.fields {
+            out += &format!(
+                "        let (buf, {}) = {}?;\n",
+                field.name.value,
+                try_unpack_call(cidl, &field.ty.value, field.cap(), Layout::State, "buf")
+            );
+        }
+        if value.contains('\n') {
+            out += &format!(
+                "        Ok((\n            buf,\n            {},\n        ))\n",
+                value
+            );
+        } else {
+            out += &format!("        Ok((buf, {}))\n", value);
+        }
+        out += "    }\n}\n";
+    }
+
+    out
+}
+
+// `let buf = ..` for every call but the last one, which is the value of the block
+pub fn chain_calls(calls: &[String], buf: &str, indent: usize) -> String {
+    let pad = " ".repeat(indent);
+    match calls.split_last() {
+        Some((last, calls)) => {
+            let mut out: String = calls
+                .iter()
+                .map(|call| format!("{}let {} = {};\n", pad, buf, call))
+                .collect();
+            out += &format!("{}{}\n", pad, last);
+            out
+        }
+        None => format!("{}{}\n", pad, buf),
+    }
+}
+
+// Formatted the way rustfmt does, on one line when the fields are short enough
+pub fn struct_literal(name: &str, fields: &[&str], indent: usize) -> String {
+    let joined = fields.join(", ");
+    if fields.is_empty() {
+        return format!("{} {{}}", name);
+    }
+    if joined.len() <= 18 {
+        return format!("{} {{ {} }}", name, joined);
+    }
+
+    let pad = " ".repeat(indent);
+    let mut out = format!("{} {{\n", name);
+    for field in fields {
+        out += &format!("{}    {},\n", pad, field);
+    }
+    out += &format!("{}}}", pad);
+    out
+}
+
+// `{prefix}&[items];` on one line if it fits, one item per line otherwise
+pub fn array_statement(prefix: &str, items: &[String], indent: usize) -> String {
+    let pad = " ".repeat(indent);
+    let line = format!("{}{}&[{}];", pad, prefix, items.join(", "));
+    if line.len() <= 100 {
+        return line + "\n";
+    }
+
+    let mut out = format!("{}{}&[\n", pad, prefix);
+    for item in items {
+        out += &format!("{}    {},\n", pad, item);
+    }
+    out += &format!("{}];\n", pad);
+    out
+}
+
+pub fn indent(text: &str, indent: usize) -> String {
+    let pad = " ".repeat(indent);
+    text.lines()
+        .map(|line| format!("{}{}\n", pad, line))
+        .collect()
+}
+
+// `{prefix}(args){suffix}` on one line if it fits, one argument per line otherwise, as rustfmt
+// does past 60 characters of arguments
+pub fn call_statement(prefix: &str, args: &[String], suffix: &str, indent: usize) -> String {
+    let pad = " ".repeat(indent);
+    let joined = args.join(", ");
+    let line = format!("{}{}({}){}", pad, prefix, joined, suffix);
+    if joined.len() <= 60 && line.len() <= 100 {
+        return line + "\n";
+    }
+
+    let mut out = format!("{}{}(\n", pad, prefix);
+    for arg in args {
+        out += &format!("{}    {},\n", pad, arg);
+    }
+    out += &format!("{}){}\n", pad, suffix);
+    out
+}
+
+// `{prefix}(params) -> {ret} {` on one line if it fits, one parameter per line otherwise
+pub fn signature(prefix: &str, params: &[String], ret: &str, indent: usize) -> String {
+    let pad = " ".repeat(indent);
+    let line = format!("{}{}({}) -> {} {{", pad, prefix, params.join(", "), ret);
+    if line.len() <= 100 {
+        return line + "\n";
+    }
+
+    let mut out = format!("{}{}(\n", pad, prefix);
+    for param in params {
+        out += &format!("{}    {},\n", pad, param);
+    }
+    out += &format!("{}) -> {} {{\n", pad, ret);
+    out
+}
@@ -0,0 +1,231 @@
+use crate::codec::*;
+use crate::generator::pascal_case;
+use crate::model::*;
+use crate::program::{AccountModel, MethodModel};
+use crate::validator::{has_errors, validate, Diagnostic};
+use crate::yaml::Spanned;
+use serde_json::{json, Map, Value};
+
+// Describes the program as an Anchor IDL so explorers and indexers can display it. Instructions
+// are tagged by a single u8 discriminant instead of Anchor's 8 byte hash, and the account fields
+// packed differently than borsh carry a `codigolib` annotation
+pub fn export_idl(cidl: &Cidl) -> Result<Value, Vec<Diagnostic>> {
+    let diagnostics = validate(cidl);
+    if has_errors(&diagnostics) {
+        return Err(diagnostics);
+    }
+
+    let instructions: Vec<_> = cidl
+        .methods
+        .iter()
+        .enumerate()
+        .map(|(i, method)| idl_instruction(&MethodModel::new(cidl, method), i))
+        .collect();
+    let accounts: Vec<_> = cidl
+        .types
+        .iter()
+        .filter(|ty| ty.solana.is_some() && !ty.is_token_program())
+        .map(|ty| idl_type_def(cidl, ty))
+        .collect();
+    let types: Vec<_> = cidl
+        .types
+        .iter()
+        .filter(|ty| ty.solana.is_none())
+        .map(|ty| idl_type_def(cidl, ty))
+        .collect();
+
+    Ok(json!({
+        "version": cidl.info.version.as_ref().map_or("0.1.0", |version| &version.value),
+        "name": cidl.info.name.value,
+        "instructions": instructions,
+        "accounts": accounts,
+        "types": types,
+        "metadata": {
+            "origin": "cidl",
+            "cidl": cidl.cidl.value,
+            "encoding": "codigolib",
+        },
+    }))
+}
+
+pub fn camel_case(name: &str) -> String {
+    let pascal = pascal_case(name);
+    let mut chars = pascal.chars();
+    match chars.next() {
+        Some(first) => first.to_lowercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+pub fn idl_type(ty: &TypeRef) -> Value {
+    match ty {
+        TypeRef::Pubkey => json!("publicKey"),
+        TypeRef::Option(inner) => json!({ "option": idl_type(inner) }),
+        TypeRef::Vec(inner) => json!({ "vec": idl_type(inner) }),
+        TypeRef::Named(name) => json!({ "defined": name }),
+        _ => json!(ty.to_string()),
+    }
+}
+
+fn docs(lines: &[Option<&Spanned<String>>]) -> Option<Value> {
+    let docs: Vec<_> = lines
+        .iter()
+        .flatten()
+        .map(|line| line.value.as_str())
+        .collect();
+    (!docs.is_empty()).then(|| json!(docs))
+}
+
+// Adds the entries of `extra` that are set
+fn object(entries: Value, extra: Vec<(&str, Option<Value>)>) -> Value {
+    let mut object: Map<String, Value> = match entries {
+        Value::Object(object) => object,
+        _ => Map::new(),
+    };
+    for (key, value) in extra {
+        if let Some(value) = value {
+            object.insert(key.to_string(), value);
+        }
+    }
+    Value::Object(object)
+}
+
+// Layout of a field inside an account when it differs from borsh: strings and vectors take their
+// whole capacity and options are padded with the default value of their type when empty
+fn codigolib_encoding(cidl: &Cidl, field: &Field) -> Option<Value> {
+    let ty = &field.ty.value;
+    let size = state_size(cidl, ty, field.cap())?;
+    let encoding = match ty {
+        TypeRef::String => "fixedCapString",
+        TypeRef::Vec(_) => "fixedCapVec",
+        TypeRef::Option(_) => "paddedOption",
+        _ => return None,
+    };
+
+    Some(object(
+        json!({ "encoding": encoding, "size": size }),
+        vec![("cap", field.cap().map(|cap| json!(cap)))],
+    ))
+}
+
+fn idl_type_def(cidl: &Cidl, ty: &TypeDef) -> Value {
+    let fields: Vec<_> = ty
+        .fields
+        .iter()
+        .map(|field| {
+            object(
+                json!({
+                    "name": camel_case(&field.name.value),
+                    "type": idl_type(&field.ty.value),
+                }),
+                vec![
+                    ("docs", docs(&[field.description.as_ref()])),
+                    ("codigolib", codigolib_encoding(cidl, field)),
+                ],
+            )
+        })
+        .collect();
+
+    object(
+        json!({
+            "name": ty.name.value,
+            "type": { "kind": "struct", "fields": fields },
+        }),
+        vec![
+            (
+                "docs",
+                docs(&[ty.summary.as_ref(), ty.description.as_ref()]),
+            ),
+            (
+                "codigolib",
+                type_size(cidl, ty).map(|size| json!({ "size": size })),
+            ),
+        ],
+    )
+}
+
+fn idl_seeds(account: &AccountModel) -> Value {
+    let seeds: Vec<_> = account
+        .solana
+        .seeds
+        .iter()
+        .map(|seed| match &seed.ty {
+            Some(ty) => json!({
+                "kind": "arg",
+                "type": idl_type(&ty.value),
+                "path": camel_case(&account.seed_arg(seed)),
+            }),
+            None => json!({
+                "kind": "const",
+                "type": "string",
+                "value": seed.name.value,
+            }),
+        })
+        .collect();
+    json!({ "seeds": seeds })
+}
+
+fn idl_account(name: &str, is_mut: bool, is_signer: bool) -> Value {
+    json!({
+        "name": camel_case(name),
+        "isMut": is_mut,
+        "isSigner": is_signer,
+    })
+}
+
+fn idl_instruction(method: &MethodModel, discriminant: usize) -> Value {
+    let mut accounts: Vec<_> = method
+        .accounts
+        .iter()
+        .map(|account| {
+            let keypair = account.solana.seeds.is_empty();
+            object(
+                idl_account(
+                    account.name(),
+                    account.is_mut(),
+                    account.is_signer() || (account.is_init() && keypair),
+                ),
+                vec![
+                    ("docs", docs(&[account.input.description.as_ref()])),
+                    ("pda", (!keypair).then(|| idl_seeds(account))),
+                ],
+            )
+        })
+        .collect();
+    if method.pays_rent() {
+        accounts.push(idl_account("system_program", false, false));
+    }
+    if method.uses_token_program() {
+        accounts.push(idl_account("token_program", false, false));
+    }
+    if method.has_fee_payer() {
+        accounts.push(idl_account("fee_payer", true, true));
+    }
+
+    let args: Vec<_> = method
+        .args
+        .iter()
+        .map(|arg| {
+            object(
+                json!({ "name": camel_case(&arg.name), "type": idl_type(&arg.ty) }),
+                vec![("docs", arg.description.as_ref().map(|d| json!([d])))],
+            )
+        })
+        .collect();
+
+    object(
+        json!({
+            "name": camel_case(method.name),
+            "accounts": accounts,
+            "args": args,
+            "discriminant": { "type": "u8", "value": discriminant },
+        }),
+        vec![(
+            "docs",
+            docs(&[
+                method.method.summary.as_ref(),
+                method.method.description.as_ref(),
+            ]),
+        )],
+    )
+}
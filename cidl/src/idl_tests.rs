@@ -0,0 +1,144 @@
+use crate::idl::*;
+use crate::model::TypeRef;
+use crate::parser::parse;
+use serde_json::{json, Value};
+use std::env;
+use std::fs;
+use std::path::Path;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BUDGET_TRACKER: &str = include_str!("../../cidl.yaml");
+
+    fn budget_tracker_idl() -> Value {
+        export_idl(&parse(BUDGET_TRACKER).unwrap()).unwrap()
+    }
+
+    // Run with UPDATE_GOLDEN=1 to rewrite tests/golden after an intended change
+    #[test]
+    fn it_matches_budget_tracker_golden_idl() {
+        let golden =
+            Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/golden/budget_tracker.idl.json");
+        let json = serde_json::to_string_pretty(&budget_tracker_idl()).unwrap() + "\n";
+
+        if env::var_os("UPDATE_GOLDEN").is_some() {
+            fs::write(&golden, &json).unwrap();
+        }
+
+        assert_eq!(json, fs::read_to_string(golden).unwrap());
+    }
+
+    #[test]
+    fn it_describes_instructions() {
+        let idl = budget_tracker_idl();
+        let create = &idl["instructions"][0];
+
+        assert_eq!(idl["name"], "budget_tracker");
+        assert_eq!(create["name"], "createUserRecord");
+        assert_eq!(create["discriminant"], json!({ "type": "u8", "value": 0 }));
+        assert_eq!(
+            create["args"],
+            json!([
+                {
+                    "name": "userName",
+                    "type": "string",
+                    "docs": ["The username to be assigned to the Record.name property"],
+                },
+                { "name": "userRecordSeedSigner", "type": "publicKey" },
+            ])
+        );
+        assert_eq!(idl["instructions"][2]["discriminant"]["value"], 2);
+    }
+
+    #[test]
+    fn it_derives_account_metas_from_attributes() {
+        let idl = budget_tracker_idl();
+        let accounts = &idl["instructions"][0]["accounts"];
+
+        // init_if_needed adds the system program and the fee payer, like the sdk does
+        let metas: Vec<_> = accounts
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|account| {
+                (
+                    account["name"].as_str().unwrap(),
+                    account["isMut"].as_bool().unwrap(),
+                    account["isSigner"].as_bool().unwrap(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            metas,
+            vec![
+                ("userRecord", true, false),
+                ("systemProgram", false, false),
+                ("feePayer", true, true),
+            ]
+        );
+        assert_eq!(
+            accounts[0]["pda"]["seeds"],
+            json!([
+                { "kind": "const", "type": "string", "value": "record" },
+                { "kind": "arg", "type": "publicKey", "path": "userRecordSeedSigner" },
+            ])
+        );
+        assert_eq!(
+            idl["instructions"][1]["accounts"].as_array().unwrap().len(),
+            1
+        );
+    }
+
+    #[test]
+    fn it_annotates_codigolib_encodings() {
+        let idl = budget_tracker_idl();
+        let record = &idl["accounts"][0];
+        let fields = &record["type"]["fields"];
+
+        assert_eq!(record["name"], "Record");
+        assert_eq!(record["codigolib"]["size"], 306);
+        assert_eq!(
+            fields[1]["codigolib"],
+            json!({ "encoding": "fixedCapString", "size": 54, "cap": 50 })
+        );
+        assert!(fields[0].get("codigolib").is_none());
+        assert_eq!(fields[5]["name"], "totalBalance");
+        assert_eq!(idl["types"], json!([]));
+    }
+
+    #[test]
+    fn it_annotates_padded_options() {
+        let source = BUDGET_TRACKER.replace(
+            "      - name: total_balance\n        type: i64",
+            "      - name: total_balance\n        type: option<i64>",
+        );
+        let idl = export_idl(&parse(&source).unwrap()).unwrap();
+        let field = &idl["accounts"][0]["type"]["fields"][5];
+
+        assert_eq!(field["type"], json!({ "option": "i64" }));
+        assert_eq!(
+            field["codigolib"],
+            json!({ "encoding": "paddedOption", "size": 9 })
+        );
+    }
+
+    #[test]
+    fn it_maps_types() {
+        let nested = TypeRef::Option(Box::new(TypeRef::Vec(Box::new(TypeRef::Named(
+            "Entry".to_string(),
+        )))));
+
+        assert_eq!(idl_type(&TypeRef::U64), json!("u64"));
+        assert_eq!(idl_type(&TypeRef::Pubkey), json!("publicKey"));
+        assert_eq!(
+            idl_type(&nested),
+            json!({ "option": { "vec": { "defined": "Entry" } } })
+        );
+        assert_eq!(
+            camel_case("user_record_seed_signer"),
+            "userRecordSeedSigner"
+        );
+    }
+}
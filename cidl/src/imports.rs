@@ -0,0 +1,320 @@
+use crate::lint::is_snake_case;
+use crate::model::*;
+use crate::parser::parse;
+use crate::validator::{validate, Diagnostic, Severity};
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+
+// A problem found while resolving the imports, in the file it was found in
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImportError {
+    pub path: PathBuf,
+    pub diagnostic: Diagnostic,
+}
+
+impl fmt::Display for ImportError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}", self.path.display(), self.diagnostic)
+    }
+}
+
+pub fn resolve(path: &Path, source: &str) -> Result<Cidl, Vec<ImportError>> {
+    resolve_with(path, source, |path| fs::read_to_string(path))
+}
+
+// Parses the CIDL at `path` and every file it imports. Imported types are added to `types` under
+// the name of the crate generated from their file, `library::Type`, and the references to them are
+// rewritten to that name. Account types lose their `solana` extension, only the program declaring
+// them creates and owns those accounts
+pub fn resolve_with<F>(path: &Path, source: &str, read: F) -> Result<Cidl, Vec<ImportError>>
+where
+    F: Fn(&Path) -> io::Result<String>,
+{
+    let mut resolver = Resolver {
+        read: &read,
+        files: HashMap::new(),
+        loading: Vec::new(),
+        libraries: HashMap::new(),
+        errors: Vec::new(),
+    };
+
+    match resolver.resolve_file(&normalize(path), source) {
+        Some(cidl) if resolver.errors.is_empty() => Ok(cidl),
+        _ => Err(resolver.errors),
+    }
+}
+
+struct Resolver<'a> {
+    read: &'a dyn Fn(&Path) -> io::Result<String>,
+    // Files imported more than once are read and checked once
+    files: HashMap<PathBuf, Option<Cidl>>,
+    // Files whose imports are being resolved, from the root down
+    loading: Vec<PathBuf>,
+    // Library name to the file defining it
+    libraries: HashMap<String, PathBuf>,
+    errors: Vec<ImportError>,
+}
+
+impl<'a> Resolver<'a> {
+    fn error(&mut self, path: &Path, diagnostic: Diagnostic) {
+        self.errors.push(ImportError {
+            path: path.to_path_buf(),
+            diagnostic,
+        });
+    }
+
+    fn resolve_file(&mut self, path: &Path, source: &str) -> Option<Cidl> {
+        let mut cidl = match parse(source) {
+            Ok(cidl) => cidl,
+            Err(e) => {
+                self.error(path, Diagnostic::error(e.span(), e.message));
+                return None;
+            }
+        };
+
+        // Name a type is referenced by in this file, to its resolved name and the file defining it
+        let mut scope: HashMap<String, (String, PathBuf)> = HashMap::new();
+        let mut imported: Vec<TypeDef> = Vec::new();
+        // The errors of a file importing a broken one would only repeat them
+        let mut failed = false;
+
+        self.loading.push(path.to_path_buf());
+        for import in &cidl.imports {
+            let target = normalize(
+                &path
+                    .parent()
+                    .unwrap_or(Path::new(""))
+                    .join(&import.path.value),
+            );
+            let library = match self.import(path, import, &target) {
+                Some(library) => library,
+                None => {
+                    failed = true;
+                    continue;
+                }
+            };
+
+            for ty in exported_types(&library) {
+                let local = ty.name.value.split("::").nth(1).unwrap_or_default();
+                let is_own = ty.library.as_ref() == Some(&library.info.name.value);
+                if is_own {
+                    let visible = match &import.namespace {
+                        Some(namespace) => format!("{}.{}", namespace.value, local),
+                        None => local.to_string(),
+                    };
+                    match scope.get(&visible) {
+                        Some((resolved, from)) if *resolved != ty.name.value => {
+                            let message = format!(
+                                "type `{}` is imported from both `{}` and `{}`",
+                                visible,
+                                from.display(),
+                                target.display()
+                            );
+                            self.error(
+                                path,
+                                Diagnostic::error(import.span, message)
+                                    .with_help("import one of them with `as: <namespace>`"),
+                            );
+                        }
+                        _ => {
+                            scope.insert(visible, (ty.name.value.clone(), target.clone()));
+                        }
+                    }
+                }
+                if !imported.iter().any(|other| other.name == ty.name) {
+                    imported.push(ty);
+                }
+            }
+        }
+        self.loading.pop();
+
+        for ty in &cidl.types {
+            if let Some((_, from)) = scope.get(&ty.name.value) {
+                let message = format!(
+                    "type `{}` is also imported from `{}`",
+                    ty.name.value,
+                    from.display()
+                );
+                self.error(
+                    path,
+                    Diagnostic::error(ty.name.span, message)
+                        .with_help("import the file with `as: <namespace>`"),
+                );
+            }
+        }
+
+        let resolve = |name: &str| scope.get(name).map(|(resolved, _)| resolved.clone());
+        for field in cidl.types.iter_mut().flat_map(|ty| &mut ty.fields) {
+            rename_type(&mut field.ty.value, &resolve);
+        }
+        for input in cidl
+            .methods
+            .iter_mut()
+            .flat_map(|method| &mut method.inputs)
+        {
+            rename_type(&mut input.ty.value, &resolve);
+        }
+        cidl.types.extend(imported);
+
+        (!failed).then_some(cidl)
+    }
+
+    fn import(&mut self, path: &Path, import: &Import, target: &Path) -> Option<Cidl> {
+        if let Some(start) = self.loading.iter().position(|loading| loading == target) {
+            let cycle: Vec<_> = self.loading[start..]
+                .iter()
+                .map(|path| path.as_path())
+                .chain([target])
+                .map(|path| format!("`{}`", path.display()))
+                .collect();
+            self.error(
+                path,
+                Diagnostic::error(
+                    import.path.span,
+                    format!("import cycle {}", cycle.join(" -> ")),
+                ),
+            );
+            return None;
+        }
+        if let Some(library) = self.files.get(target) {
+            return library.clone();
+        }
+
+        let library = match (self.read)(target) {
+            Ok(source) => self
+                .resolve_file(target, &source)
+                .filter(|library| self.check_library(target, library)),
+            Err(e) => {
+                self.error(
+                    path,
+                    Diagnostic::error(
+                        import.path.span,
+                        format!("cannot read `{}`: {}", target.display(), e),
+                    ),
+                );
+                None
+            }
+        };
+        self.files.insert(target.to_path_buf(), library.clone());
+        library
+    }
+
+    // An imported file is checked on its own, its name becomes the name of the generated crate
+    fn check_library(&mut self, path: &Path, library: &Cidl) -> bool {
+        let mut ok = true;
+        for diagnostic in validate(library) {
+            if diagnostic.severity == Severity::Error {
+                self.error(path, diagnostic);
+                ok = false;
+            }
+        }
+
+        let name = &library.info.name;
+        if !is_snake_case(&name.value) {
+            self.error(
+                path,
+                Diagnostic::error(
+                    name.span,
+                    format!(
+                        "imported file is named `{}`, its name must be a snake_case crate name",
+                        name.value
+                    ),
+                ),
+            );
+            ok = false;
+        }
+        match self.libraries.get(&name.value) {
+            Some(other) if other != path => {
+                let message = format!(
+                    "`{}` is also the name of `{}`, imported files need different names",
+                    name.value,
+                    other.display()
+                );
+                self.error(path, Diagnostic::error(name.span, message));
+                ok = false;
+            }
+            _ => {
+                self.libraries
+                    .insert(name.value.clone(), path.to_path_buf());
+            }
+        }
+
+        ok
+    }
+}
+
+// The types of a resolved file as seen by the files importing it: its own types are renamed to
+// `library::Type` and the types it imports are passed along
+fn exported_types(library: &Cidl) -> Vec<TypeDef> {
+    let name = &library.info.name.value;
+    let own: Vec<&str> = library
+        .types
+        .iter()
+        .filter(|ty| ty.library.is_none())
+        .map(|ty| ty.name.value.as_str())
+        .collect();
+    let resolve = |ty: &str| own.contains(&ty).then(|| format!("{}::{}", name, ty));
+
+    library
+        .types
+        .iter()
+        .cloned()
+        .map(|mut ty| {
+            if ty.library.is_none() {
+                for field in &mut ty.fields {
+                    rename_type(&mut field.ty.value, &resolve);
+                }
+                ty.name.value = format!("{}::{}", name, ty.name.value);
+                ty.library = Some(name.clone());
+                ty.solana = None;
+            }
+            ty
+        })
+        .collect()
+}
+
+fn rename_type(ty: &mut TypeRef, resolve: &dyn Fn(&str) -> Option<String>) {
+    match ty {
+        TypeRef::Option(inner) | TypeRef::Vec(inner) => rename_type(inner, resolve),
+        TypeRef::Named(name) => {
+            if let Some(resolved) = resolve(name) {
+                *name = resolved;
+            }
+        }
+        _ => {}
+    }
+}
+
+// Imports are relative to the importing file, `..` is resolved without touching the file system
+pub fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir
+                if matches!(
+                    normalized.components().next_back(),
+                    Some(Component::Normal(_))
+                ) =>
+            {
+                normalized.pop();
+            }
+            other => normalized.push(other),
+        }
+    }
+    normalized
+}
+
+// Name of the types crate generated from each imported file used by `cidl`
+pub fn libraries(cidl: &Cidl) -> Vec<&str> {
+    let mut libraries: Vec<&str> = Vec::new();
+    for library in cidl.types.iter().filter_map(|ty| ty.library.as_deref()) {
+        if !libraries.contains(&library) {
+            libraries.push(library);
+        }
+    }
+    libraries
+}
@@ -0,0 +1,353 @@
+use crate::imports::*;
+use crate::library::{generate_library, LibraryOptions};
+use crate::model::*;
+use crate::program::{generate_program, ProgramOptions};
+use crate::validator::{has_errors, validate, Diagnostic};
+use std::collections::HashMap;
+use std::io;
+use std::path::{Path, PathBuf};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const UNITS: &str = "cidl: \"0.8\"
+info:
+  name: units
+types:
+  Amount:
+    fields:
+      - name: value
+        type: u64
+      - name: currency
+        type: string
+        solana:
+          attributes: [ cap:3 ]
+";
+
+    const COMMON: &str = "cidl: \"0.8\"
+info:
+  name: common_types
+imports:
+  - path: units.yaml
+    as: units
+types:
+  Money:
+    summary: An amount owned by someone
+    fields:
+      - name: owner
+        type: sol:pubkey
+      - name: amount
+        type: units.Amount
+";
+
+    const PROGRAM: &str = "cidl: \"0.8\"
+info:
+  name: wallet
+imports:
+  - path: shared/common.yaml
+    as: common
+types:
+  Wallet:
+    solana:
+      seeds:
+        - name: wallet
+        - name: owner
+          type: sol:pubkey
+    fields:
+      - name: balance
+        type: common.Money
+methods:
+  - name: deposit
+    inputs:
+      - name: wallet
+        type: Wallet
+        solana:
+          attributes: [ mut ]
+      - name: money
+        type: common.Money
+";
+
+    fn sources(entries: &[(&str, &str)]) -> HashMap<PathBuf, String> {
+        entries
+            .iter()
+            .map(|(path, source)| (PathBuf::from(path), source.to_string()))
+            .collect()
+    }
+
+    fn resolve_in(files: &HashMap<PathBuf, String>, path: &str) -> Result<Cidl, Vec<ImportError>> {
+        let read = |path: &Path| {
+            files
+                .get(path)
+                .cloned()
+                .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "not found"))
+        };
+        resolve_with(Path::new(path), &files[Path::new(path)], read)
+    }
+
+    fn wallet_files() -> HashMap<PathBuf, String> {
+        sources(&[
+            ("wallet.yaml", PROGRAM),
+            ("shared/common.yaml", COMMON),
+            ("shared/units.yaml", UNITS),
+        ])
+    }
+
+    fn messages(errors: &[ImportError]) -> Vec<String> {
+        errors.iter().map(ToString::to_string).collect()
+    }
+
+    fn diagnostic_messages(diagnostics: &[Diagnostic]) -> Vec<String> {
+        diagnostics.iter().map(|d| d.message.clone()).collect()
+    }
+
+    #[test]
+    fn it_resolves_namespaced_imports() {
+        let cidl = resolve_in(&wallet_files(), "wallet.yaml").unwrap();
+
+        let names: Vec<_> = cidl.types.iter().map(|ty| ty.name.value.as_str()).collect();
+        assert_eq!(
+            names,
+            vec!["Wallet", "common_types::Money", "units::Amount"]
+        );
+        assert_eq!(
+            cidl.types[0].fields[0].ty.value,
+            TypeRef::Named("common_types::Money".to_string())
+        );
+        assert_eq!(
+            cidl.methods[0].inputs[1].ty.value,
+            TypeRef::Named("common_types::Money".to_string())
+        );
+
+        let money = cidl.find_type("common_types::Money").unwrap();
+        assert_eq!(money.library.as_deref(), Some("common_types"));
+        assert_eq!(
+            money.fields[1].ty.value,
+            TypeRef::Named("units::Amount".to_string())
+        );
+        assert_eq!(libraries(&cidl), vec!["common_types", "units"]);
+        assert!(!has_errors(&validate(&cidl)));
+    }
+
+    #[test]
+    fn it_imports_types_without_namespace() {
+        let source = PROGRAM
+            .replace("    as: common\n", "")
+            .replace("common.Money", "Money");
+        let files = sources(&[
+            ("wallet.yaml", &source),
+            ("shared/common.yaml", COMMON),
+            ("shared/units.yaml", UNITS),
+        ]);
+        let cidl = resolve_in(&files, "wallet.yaml").unwrap();
+
+        assert_eq!(
+            cidl.methods[0].inputs[1].ty.value,
+            TypeRef::Named("common_types::Money".to_string())
+        );
+        // Types imported by an imported file are not visible without importing them
+        let source = source.replace("type: Money\n", "type: Amount\n");
+        let files = sources(&[
+            ("wallet.yaml", &source),
+            ("shared/common.yaml", COMMON),
+            ("shared/units.yaml", UNITS),
+        ]);
+        let cidl = resolve_in(&files, "wallet.yaml").unwrap();
+        assert!(
+            diagnostic_messages(&validate(&cidl)).contains(&"unknown type `Amount`".to_string())
+        );
+    }
+
+    #[test]
+    fn it_shares_types_imported_twice() {
+        let source = PROGRAM.replace(
+            "    as: common\n",
+            "    as: common\n  - path: shared/units.yaml\n    as: units\n",
+        );
+        let source = source.replace(
+            "        type: common.Money\nmethods:",
+            "        type: common.Money\n      - name: fee\n        type: units.Amount\nmethods:",
+        );
+        let files = sources(&[
+            ("wallet.yaml", &source),
+            ("shared/common.yaml", COMMON),
+            ("shared/units.yaml", UNITS),
+        ]);
+        let cidl = resolve_in(&files, "wallet.yaml").unwrap();
+
+        let names: Vec<_> = cidl.types.iter().map(|ty| ty.name.value.as_str()).collect();
+        assert_eq!(
+            names,
+            vec!["Wallet", "common_types::Money", "units::Amount"]
+        );
+        assert_eq!(
+            cidl.types[0].fields[1].ty.value,
+            TypeRef::Named("units::Amount".to_string())
+        );
+    }
+
+    #[test]
+    fn it_drops_the_solana_extension_of_imported_accounts() {
+        let common = COMMON.replace(
+            "  Money:\n",
+            "  Money:\n    solana:\n      seeds:\n        - name: money\n",
+        );
+        let files = sources(&[
+            ("wallet.yaml", PROGRAM),
+            ("shared/common.yaml", &common),
+            ("shared/units.yaml", UNITS),
+        ]);
+        let cidl = resolve_in(&files, "wallet.yaml").unwrap();
+
+        assert!(cidl
+            .find_type("common_types::Money")
+            .unwrap()
+            .solana
+            .is_none());
+    }
+
+    #[test]
+    fn it_reports_import_cycles() {
+        let units = UNITS.replace(
+            "types:\n",
+            "imports:\n  - path: common.yaml\n    as: common\ntypes:\n",
+        );
+        let files = sources(&[
+            ("wallet.yaml", PROGRAM),
+            ("shared/common.yaml", COMMON),
+            ("shared/units.yaml", &units),
+        ]);
+        let errors = resolve_in(&files, "wallet.yaml").unwrap_err();
+
+        assert_eq!(
+            messages(&errors)[0],
+            "shared/units.yaml:5:11: error: import cycle `shared/common.yaml` -> `shared/units.yaml` -> `shared/common.yaml`"
+        );
+    }
+
+    #[test]
+    fn it_reports_conflicting_imports() {
+        let other = UNITS.replace("name: units", "name: other_units");
+        let common = COMMON.replace(
+            "  - path: units.yaml\n    as: units\n",
+            "  - units.yaml\n  - other.yaml\n",
+        );
+        let common = common.replace("units.Amount", "Amount");
+        let files = sources(&[
+            ("wallet.yaml", PROGRAM),
+            ("shared/common.yaml", &common),
+            ("shared/units.yaml", UNITS),
+            ("shared/other.yaml", &other),
+        ]);
+        let errors = resolve_in(&files, "wallet.yaml").unwrap_err();
+
+        assert_eq!(
+            messages(&errors),
+            vec!["shared/common.yaml:6:5: error: type `Amount` is imported from both `shared/units.yaml` and `shared/other.yaml`\n  help: import one of them with `as: <namespace>`"]
+        );
+    }
+
+    #[test]
+    fn it_reports_types_shadowing_imports() {
+        let common = COMMON.replace("    as: units\n", "");
+        let common = common.replace("units.Amount", "Amount")
+            + "  Amount:\n    fields:\n      - name: cents\n        type: u64\n";
+        let files = sources(&[
+            ("wallet.yaml", PROGRAM),
+            ("shared/common.yaml", &common),
+            ("shared/units.yaml", UNITS),
+        ]);
+        let errors = resolve_in(&files, "wallet.yaml").unwrap_err();
+
+        assert_eq!(
+            messages(&errors)[0],
+            "shared/common.yaml:14:3: error: type `Amount` is also imported from `shared/units.yaml`\n  help: import the file with `as: <namespace>`"
+        );
+    }
+
+    #[test]
+    fn it_reports_errors_in_the_imported_file() {
+        let units = UNITS.replace("          attributes: [ cap:3 ]\n", "");
+        let files = sources(&[
+            ("wallet.yaml", PROGRAM),
+            ("shared/common.yaml", COMMON),
+            ("shared/units.yaml", &units),
+        ]);
+        let errors = resolve_in(&files, "wallet.yaml").unwrap_err();
+
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].path, PathBuf::from("shared/units.yaml"));
+        assert_eq!(
+            errors[0].diagnostic.message,
+            "`string` field `currency` has no `cap` attribute"
+        );
+    }
+
+    #[test]
+    fn it_reports_missing_and_clashing_libraries() {
+        let files = sources(&[("wallet.yaml", PROGRAM)]);
+        let errors = resolve_in(&files, "wallet.yaml").unwrap_err();
+        assert_eq!(
+            messages(&errors),
+            vec!["wallet.yaml:5:11: error: cannot read `shared/common.yaml`: not found"]
+        );
+
+        let units = UNITS.replace("name: units", "name: common_types");
+        let files = sources(&[
+            ("wallet.yaml", PROGRAM),
+            ("shared/common.yaml", COMMON),
+            ("shared/units.yaml", &units),
+        ]);
+        let errors = resolve_in(&files, "wallet.yaml").unwrap_err();
+        assert_eq!(
+            messages(&errors),
+            vec!["shared/common.yaml:3:9: error: `common_types` is also the name of `shared/units.yaml`, imported files need different names"]
+        );
+    }
+
+    #[test]
+    fn it_normalizes_relative_paths() {
+        assert_eq!(
+            normalize(Path::new("programs/./wallet/../shared/common.yaml")),
+            PathBuf::from("programs/shared/common.yaml")
+        );
+        assert_eq!(
+            normalize(Path::new("../shared/common.yaml")),
+            PathBuf::from("../shared/common.yaml")
+        );
+    }
+
+    #[test]
+    fn it_generates_imported_types_once() {
+        let files = wallet_files();
+        let cidl = resolve_in(&files, "wallet.yaml").unwrap();
+        let program = generate_program(&cidl, &ProgramOptions::default()).unwrap();
+        let file = |path: &str| {
+            &program
+                .iter()
+                .find(|file| file.path == Path::new(path))
+                .unwrap()
+                .contents
+        };
+
+        assert!(file("Cargo.toml").contains(
+            "common_types = { path = \"../common_types\" }\nunits = { path = \"../units\" }\n"
+        ));
+        assert!(file("rendered/state.rs").contains("pub struct Wallet {"));
+        assert!(!file("rendered/state.rs").contains("pub struct Money"));
+        assert!(file("rendered/state.rs").contains("pub balance: common_types::Money,"));
+        assert!(file("rendered/instructions.rs").contains("common_types::Money::try_unpack(buf)?"));
+
+        let common = resolve_in(&files, "shared/common.yaml").unwrap();
+        let library = generate_library(&common, &LibraryOptions::default()).unwrap();
+        assert!(library[0]
+            .contents
+            .starts_with("[package]\nname = \"common_types\"\n"));
+        assert!(library[0]
+            .contents
+            .ends_with("units = { path = \"../units\" }\n"));
+        assert!(library[1].contents.contains("pub struct Money {"));
+        assert!(library[1].contents.contains("pub amount: units::Amount,"));
+        assert!(!library[1].contents.contains("pub struct Amount"));
+    }
+}
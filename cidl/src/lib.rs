@@ -0,0 +1,58 @@
+pub mod client;
+pub mod codec;
+pub mod diff;
+pub mod errors;
+pub mod formatter;
+pub mod generator;
+pub mod idl;
+pub mod imports;
+pub mod library;
+pub mod lint;
+pub mod merge;
+pub mod model;
+pub mod parser;
+pub mod program;
+pub mod sizes;
+pub mod validator;
+pub mod yaml;
+
+pub use errors::ParseError;
+pub use model::*;
+pub use parser::parse;
+pub use validator::{validate, Diagnostic, Severity};
+
+#[cfg(test)]
+pub mod codec_tests;
+
+#[cfg(test)]
+pub mod diff_tests;
+
+#[cfg(test)]
+pub mod formatter_tests;
+
+#[cfg(test)]
+pub mod idl_tests;
+
+#[cfg(test)]
+pub mod imports_tests;
+
+#[cfg(test)]
+pub mod lint_tests;
+
+#[cfg(test)]
+pub mod merge_tests;
+
+#[cfg(test)]
+pub mod parser_tests;
+
+#[cfg(test)]
+pub mod validator_tests;
+
+#[cfg(test)]
+pub mod program_tests;
+
+#[cfg(test)]
+pub mod client_tests;
+
+#[cfg(test)]
+pub mod sizes_tests;
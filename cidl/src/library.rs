@@ -0,0 +1,80 @@
+use crate::generator::*;
+use crate::model::*;
+use crate::validator::{has_errors, validate, Diagnostic};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LibraryOptions {
+    // Path of the codigolib crate, relative to the generated library
+    pub codigolib_path: String,
+}
+
+impl Default for LibraryOptions {
+    fn default() -> LibraryOptions {
+        LibraryOptions {
+            codigolib_path: "../codigolib".to_string(),
+        }
+    }
+}
+
+// Renders the crate holding the types of a CIDL file imported by others, the programs and clients
+// importing it depend on this crate instead of defining the types again. Methods are not part of
+// it. Paths are relative to the library directory
+pub fn generate_library(
+    cidl: &Cidl,
+    options: &LibraryOptions,
+) -> Result<Vec<GeneratedFile>, Vec<Diagnostic>> {
+    let diagnostics = validate(cidl);
+    if has_errors(&diagnostics) {
+        return Err(diagnostics);
+    }
+
+    Ok(vec![
+        GeneratedFile::generated("Cargo.toml", render_cargo_toml(cidl, options)),
+        GeneratedFile::generated("src/lib.rs", render_lib(cidl)),
+    ])
+}
+
+fn render_cargo_toml(cidl: &Cidl, options: &LibraryOptions) -> String {
+    let version = cidl
+        .info
+        .version
+        .as_ref()
+        .map_or("0.1.0", |version| &version.value);
+
+    format!(
+        r#"[package]
+name = "{}"
+version = "{}"
+edition = "2021"
+
+[dependencies]
+codigolib = {{ path = "{}" }}
+solana-program = "~1.14.17"
+{}"#,
+        cidl.info.name.value,
+        version,
+        options.codigolib_path,
+        library_dependencies(cidl)
+    )
+}
+
+fn render_lib(cidl: &Cidl) -> String {
+    let mut out = String::from(GENERATED_HEADER);
+    out += "\n";
+    if types_use_delegate(cidl) {
+        out += DELEGATE_USE;
+    }
+    out += "use codigolib::rust_utils::*;\n";
+    if own_types(cidl).next().is_some() {
+        out += "use solana_program::program_error::ProgramError;\n";
+    }
+    if types_use_pubkey(cidl) {
+        out += "use solana_program::pubkey::Pubkey;\n";
+    }
+
+    out += "\n";
+    out += STATE_USE;
+    out += &render_type_defs(cidl);
+
+    out
+}
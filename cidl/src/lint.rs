@@ -0,0 +1,347 @@
+use crate::codec::capped_types;
+use crate::generator::own_types;
+use crate::model::*;
+use crate::validator::Diagnostic;
+use crate::yaml::{comment_start, Span, Spanned};
+use std::fmt;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Rule {
+    MissingDocs,
+    UnusedTypes,
+    Todo,
+    StringWithoutCap,
+    SeedsWithoutSigner,
+    Naming,
+}
+
+impl Rule {
+    pub const ALL: [Rule; 6] = [
+        Rule::MissingDocs,
+        Rule::UnusedTypes,
+        Rule::Todo,
+        Rule::StringWithoutCap,
+        Rule::SeedsWithoutSigner,
+        Rule::Naming,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Rule::MissingDocs => "missing-docs",
+            Rule::UnusedTypes => "unused-types",
+            Rule::Todo => "todo",
+            Rule::StringWithoutCap => "string-without-cap",
+            Rule::SeedsWithoutSigner => "seeds-without-signer",
+            Rule::Naming => "naming",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Rule> {
+        Rule::ALL.into_iter().find(|rule| rule.name() == name)
+    }
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Level {
+    Allow,
+    Warn,
+    Deny,
+}
+
+// Every rule warns unless configured otherwise
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct LintConfig {
+    pub levels: Vec<(Rule, Level)>,
+}
+
+impl LintConfig {
+    pub fn set(&mut self, rule: Rule, level: Level) {
+        self.levels.retain(|(other, _)| *other != rule);
+        self.levels.push((rule, level));
+    }
+
+    pub fn level(&self, rule: Rule) -> Level {
+        self.levels
+            .iter()
+            .find(|(other, _)| *other == rule)
+            .map_or(Level::Warn, |(_, level)| *level)
+    }
+}
+
+struct Linter<'a> {
+    cidl: &'a Cidl,
+    config: &'a LintConfig,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl<'a> Linter<'a> {
+    fn push(&mut self, rule: Rule, span: Span, message: String, help: Option<&str>) {
+        let message = format!("{} [{}]", message, rule);
+        let mut diagnostic = match self.config.level(rule) {
+            Level::Allow => return,
+            Level::Warn => Diagnostic::warning(span, message),
+            Level::Deny => Diagnostic::error(span, message),
+        };
+        if let Some(help) = help {
+            diagnostic = diagnostic.with_help(help);
+        }
+        self.diagnostics.push(diagnostic);
+    }
+
+    fn missing_docs(&mut self) {
+        let cidl = self.cidl;
+        for ty in own_types(cidl) {
+            if ty.summary.is_none() && ty.description.is_none() {
+                self.push(
+                    Rule::MissingDocs,
+                    ty.name.span,
+                    format!("type `{}` has no summary or description", ty.name.value),
+                    None,
+                );
+            }
+            for field in &ty.fields {
+                if field.description.is_none() {
+                    self.push(
+                        Rule::MissingDocs,
+                        field.name.span,
+                        format!(
+                            "field `{}.{}` has no description",
+                            ty.name.value, field.name.value
+                        ),
+                        None,
+                    );
+                }
+            }
+        }
+
+        for method in &cidl.methods {
+            if method.summary.is_none() && method.description.is_none() {
+                self.push(
+                    Rule::MissingDocs,
+                    method.name.span,
+                    format!(
+                        "method `{}` has no summary or description",
+                        method.name.value
+                    ),
+                    None,
+                );
+            }
+            for input in &method.inputs {
+                if input.description.is_none() {
+                    self.push(
+                        Rule::MissingDocs,
+                        input.name.span,
+                        format!(
+                            "input `{}` of `{}` has no description",
+                            input.name.value, method.name.value
+                        ),
+                        None,
+                    );
+                }
+            }
+        }
+    }
+
+    fn unused_types(&mut self) {
+        let cidl = self.cidl;
+        let mut used: Vec<&str> = cidl
+            .methods
+            .iter()
+            .flat_map(|method| &method.inputs)
+            .filter_map(|input| named(&input.ty.value))
+            .collect();
+        let mut i = 0;
+        while i < used.len() {
+            if let Some(ty) = cidl.find_type(used[i]) {
+                for field in &ty.fields {
+                    if let Some(name) = named(&field.ty.value) {
+                        if !used.contains(&name) {
+                            used.push(name);
+                        }
+                    }
+                }
+            }
+            i += 1;
+        }
+
+        for ty in own_types(cidl) {
+            if !used.contains(&ty.name.value.as_str()) {
+                self.push(
+                    Rule::UnusedTypes,
+                    ty.name.span,
+                    format!("type `{}` is not used by any method", ty.name.value),
+                    None,
+                );
+            }
+        }
+    }
+
+    fn todo(&mut self, source: &str) {
+        for (i, line) in source.lines().enumerate() {
+            let comment = match comment_start(line) {
+                Some(col) => col,
+                None => continue,
+            };
+            let text = &line[comment..];
+            if let Some(marker) = ["TODO", "FIXME"].iter().find(|m| text.contains(*m)) {
+                self.push(
+                    Rule::Todo,
+                    Span {
+                        line: i + 1,
+                        col: comment + 1,
+                    },
+                    format!("leftover `{}` comment", marker),
+                    None,
+                );
+            }
+        }
+    }
+
+    fn string_without_cap(&mut self) {
+        let cidl = self.cidl;
+        for method in &cidl.methods {
+            for input in &method.inputs {
+                if !capped_types(&input.ty.value).is_empty() && input.cap().is_none() {
+                    self.push(
+                        Rule::StringWithoutCap,
+                        input.ty.span,
+                        format!(
+                            "input `{}` of `{}` has no cap, clients can send any length",
+                            input.name.value, method.name.value
+                        ),
+                        Some(
+                            "add `solana: { attributes: [ cap:N ] }` to bound the instruction size",
+                        ),
+                    );
+                }
+            }
+        }
+    }
+
+    // Accounts created by users are derived per user, otherwise every user gets the same address
+    fn seeds_without_signer(&mut self) {
+        let cidl = self.cidl;
+        for ty in own_types(cidl) {
+            let solana = match &ty.solana {
+                Some(solana) if !solana.seeds.is_empty() => solana,
+                _ => continue,
+            };
+            let created = cidl
+                .methods
+                .iter()
+                .flat_map(|method| &method.inputs)
+                .any(|input| {
+                    named(&input.ty.value) == Some(ty.name.value.as_str())
+                        && (input.has_attribute(&Attribute::Init)
+                            || input.has_attribute(&Attribute::InitIfNeeded))
+                });
+            let has_signer = solana.seeds.iter().any(|seed| {
+                seed.ty
+                    .as_ref()
+                    .is_some_and(|ty| ty.value == TypeRef::Pubkey)
+            });
+
+            if created && !has_signer {
+                self.push(
+                    Rule::SeedsWithoutSigner,
+                    solana.span,
+                    format!(
+                        "seeds of `{}` have no `sol:pubkey` component, every user derives the same account",
+                        ty.name.value
+                    ),
+                    Some("add a seed with `type: sol:pubkey` for the owner of the account"),
+                );
+            }
+        }
+    }
+
+    fn naming(&mut self) {
+        let cidl = self.cidl;
+        let mut snake: Vec<(&Spanned<String>, &str)> = Vec::new();
+
+        for ty in own_types(cidl) {
+            if !is_pascal_case(&ty.name.value) {
+                self.push(
+                    Rule::Naming,
+                    ty.name.span,
+                    format!("type `{}` should be PascalCase", ty.name.value),
+                    None,
+                );
+            }
+            snake.extend(ty.fields.iter().map(|field| (&field.name, "field")));
+            if let Some(solana) = &ty.solana {
+                snake.extend(
+                    solana
+                        .seeds
+                        .iter()
+                        .filter(|seed| seed.ty.is_some())
+                        .map(|seed| (&seed.name, "seed")),
+                );
+            }
+        }
+        for method in &cidl.methods {
+            snake.push((&method.name, "method"));
+            snake.extend(method.inputs.iter().map(|input| (&input.name, "input")));
+        }
+
+        for (name, what) in snake {
+            if !is_snake_case(&name.value) {
+                self.push(
+                    Rule::Naming,
+                    name.span,
+                    format!("{} `{}` should be snake_case", what, name.value),
+                    None,
+                );
+            }
+        }
+    }
+}
+
+fn named(ty: &TypeRef) -> Option<&str> {
+    match ty {
+        TypeRef::Option(inner) | TypeRef::Vec(inner) => named(inner),
+        TypeRef::Named(name) => Some(name),
+        _ => None,
+    }
+}
+
+pub fn is_snake_case(name: &str) -> bool {
+    name.starts_with(|c: char| c.is_ascii_lowercase())
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+        && !name.contains("__")
+        && !name.ends_with('_')
+}
+
+pub fn is_pascal_case(name: &str) -> bool {
+    name.starts_with(|c: char| c.is_ascii_uppercase())
+        && name.chars().all(|c| c.is_ascii_alphanumeric())
+}
+
+// Style checks on top of the validator, `source` is the text the CIDL was parsed from since
+// comments are not part of the model
+pub fn lint(source: &str, cidl: &Cidl, config: &LintConfig) -> Vec<Diagnostic> {
+    let mut linter = Linter {
+        cidl,
+        config,
+        diagnostics: Vec::new(),
+    };
+
+    linter.missing_docs();
+    linter.unused_types();
+    linter.todo(source);
+    linter.string_without_cap();
+    linter.seeds_without_signer();
+    linter.naming();
+
+    let mut diagnostics = linter.diagnostics;
+    diagnostics.sort_by_key(|diagnostic| (diagnostic.span.line, diagnostic.span.col));
+    diagnostics
+}
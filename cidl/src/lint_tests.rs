@@ -0,0 +1,146 @@
+use crate::lint::*;
+use crate::parser::parse;
+use crate::validator::{Diagnostic, Severity};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BUDGET_TRACKER: &str = include_str!("../../cidl.yaml");
+
+    fn lint_source(source: &str, config: &LintConfig) -> Vec<Diagnostic> {
+        lint(source, &parse(source).unwrap(), config)
+    }
+
+    fn messages(diagnostics: &[Diagnostic]) -> Vec<&str> {
+        diagnostics
+            .iter()
+            .map(|diagnostic| diagnostic.message.as_str())
+            .collect()
+    }
+
+    fn only(rule: Rule) -> LintConfig {
+        let mut config = LintConfig::default();
+        for other in Rule::ALL {
+            if other != rule {
+                config.set(other, Level::Allow);
+            }
+        }
+        config
+    }
+
+    // The checked-in spec stays lint clean. The user names are left uncapped, their stubs cap them
+    // once the surrounding whitespace is trimmed
+    #[test]
+    fn it_lints_the_budget_tracker() {
+        let diagnostics = lint_source(BUDGET_TRACKER, &LintConfig::default());
+
+        assert_eq!(
+            messages(&diagnostics),
+            vec![
+                "input `user_name` of `create_user_record` has no cap, clients can send any length [string-without-cap]",
+                "input `user_name` of `update_user_name` has no cap, clients can send any length [string-without-cap]",
+            ]
+        );
+        assert_eq!(diagnostics[0].span.line, 95);
+        assert_eq!(diagnostics[0].span.col, 15);
+        assert!(diagnostics
+            .iter()
+            .all(|diagnostic| diagnostic.severity == Severity::Warning));
+
+        let mut config = LintConfig::default();
+        config.set(Rule::StringWithoutCap, Level::Allow);
+        assert!(lint_source(BUDGET_TRACKER, &config).is_empty());
+    }
+
+    #[test]
+    fn it_configures_rule_levels() {
+        let source = format!(
+            "{}\n  # TODO: define register_refund\n  # FIXME: and its event\n",
+            BUDGET_TRACKER
+        );
+        let mut config = only(Rule::Todo);
+        config.set(Rule::Todo, Level::Deny);
+        let diagnostics = lint_source(&source, &config);
+
+        assert_eq!(diagnostics.len(), 2);
+        assert_eq!(diagnostics[0].severity, Severity::Error);
+
+        config.set(Rule::Todo, Level::Allow);
+        assert!(lint_source(&source, &config).is_empty());
+        assert_eq!(
+            Rule::from_name("seeds-without-signer"),
+            Some(Rule::SeedsWithoutSigner)
+        );
+        assert_eq!(Rule::from_name("unknown"), None);
+    }
+
+    #[test]
+    fn it_ignores_todo_inside_quoted_values() {
+        let source = "cidl: \"0.8\"\ninfo:\n  name: test\n  title: \"Budget # TODO\"\n  summary: 'It''s # FIXME' # TODO: reword\n  version: v1#TODO\n";
+        let diagnostics = lint_source(source, &only(Rule::Todo));
+
+        assert_eq!(
+            messages(&diagnostics),
+            vec!["leftover `TODO` comment [todo]"]
+        );
+        assert_eq!((diagnostics[0].span.line, diagnostics[0].span.col), (5, 28));
+    }
+
+    #[test]
+    fn it_reports_unused_types() {
+        let source = BUDGET_TRACKER.replace(
+            "methods:\n",
+            "  Unused:\n    summary: Never referenced\n    fields:\n      - name: value\n        type: u8\n        description: A value\nmethods:\n",
+        );
+
+        assert_eq!(
+            messages(&lint_source(&source, &only(Rule::UnusedTypes))),
+            vec!["type `Unused` is not used by any method [unused-types]"]
+        );
+    }
+
+    #[test]
+    fn it_reports_seeds_without_signer() {
+        let source = BUDGET_TRACKER.replace(
+            "        - name: signer\n          type: sol:pubkey\n",
+            "        - name: id\n          type: u64\n",
+        );
+        let diagnostics = lint_source(&source, &only(Rule::SeedsWithoutSigner));
+
+        assert_eq!(
+            messages(&diagnostics),
+            vec!["seeds of `Record` have no `sol:pubkey` component, every user derives the same account [seeds-without-signer]"]
+        );
+        assert!(diagnostics[0].help.is_some());
+    }
+
+    #[test]
+    fn it_reports_naming_violations() {
+        let source = BUDGET_TRACKER
+            .replace("name: total_balance", "name: totalBalance")
+            .replace("name: register_outcome", "name: RegisterOutcome")
+            .replace("  Record:\n", "  record_account:\n")
+            .replace("type: Record", "type: record_account");
+
+        assert_eq!(
+            messages(&lint_source(&source, &only(Rule::Naming))),
+            vec![
+                "type `record_account` should be PascalCase [naming]",
+                "field `totalBalance` should be snake_case [naming]",
+                "method `RegisterOutcome` should be snake_case [naming]",
+            ]
+        );
+        assert!(is_snake_case("user_record_2"));
+        assert!(!is_snake_case("user__record"));
+        assert!(is_pascal_case("Record2"));
+    }
+
+    #[test]
+    fn it_reports_missing_docs() {
+        let source = BUDGET_TRACKER.replace("        description: Sum of all incomes.\n", "");
+
+        assert!(messages(&lint_source(&source, &only(Rule::MissingDocs)))
+            .contains(&"field `Record.income` has no description [missing-docs]"));
+    }
+}
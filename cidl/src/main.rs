@@ -0,0 +1,374 @@
+use cidl::client::{generate_client, ClientOptions};
+use cidl::diff::{diff, is_breaking, Side};
+use cidl::formatter::{format_cidl, same_model};
+use cidl::generator::write_files;
+use cidl::idl::export_idl;
+use cidl::imports::resolve;
+use cidl::library::{generate_library, LibraryOptions};
+use cidl::lint::{lint, Level, LintConfig, Rule};
+use cidl::merge::check_stubs;
+use cidl::program::{generate_program, ProgramOptions};
+use cidl::sizes::{size_report, SizeOptions};
+use cidl::validator::{has_errors, validate, Diagnostic};
+use cidl::Cidl;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+const USAGE: &str = "usage:
+  cidl check <cidl.yaml>
+  cidl generate <cidl.yaml> [--out <dir>] [--codigolib <path>]
+  cidl generate-client <cidl.yaml> [--out <dir>] [--codigolib <path>]
+  cidl generate-library <types.yaml> [--out <dir>] [--codigolib <path>]
+  cidl size <cidl.yaml> [--lamports-per-byte-year <lamports>]
+  cidl diff <old.yaml> <new.yaml>
+  cidl idl <cidl.yaml> [--out <file>]
+  cidl fmt <cidl.yaml> [--check]
+  cidl lint <cidl.yaml> [--allow <rule>] [--deny <rule>]
+
+`fmt` rewrites the file in place, `--check` only reports whether it is formatted";
+
+fn main() -> ExitCode {
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    let result = match args.first().map(String::as_str) {
+        Some("check") => check(&args[1..]),
+        Some("generate") => generate(&args[1..]),
+        Some("generate-client") => generate_client_sdk(&args[1..]),
+        Some("generate-library") => generate_types_library(&args[1..]),
+        Some("size") => size(&args[1..]),
+        Some("diff") => diff_versions(&args[1..]),
+        Some("idl") => idl(&args[1..]),
+        Some("fmt") => fmt(&args[1..]),
+        Some("lint") => lint_file(&args[1..]),
+        _ => Err(USAGE.to_string()),
+    };
+
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(message) => {
+            eprintln!("{}", message);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+struct Args<'a> {
+    positional: Vec<&'a str>,
+    // `--name value` pairs
+    options: Vec<(&'a str, &'a str)>,
+    // `--name` switches without a value
+    flags: Vec<&'a str>,
+}
+
+fn parse_args<'a>(
+    args: &'a [String],
+    options: &[&str],
+    flags: &[&str],
+) -> Result<Args<'a>, String> {
+    let mut parsed = Args {
+        positional: Vec::new(),
+        options: Vec::new(),
+        flags: Vec::new(),
+    };
+    let mut iter = args.iter();
+
+    while let Some(arg) = iter.next() {
+        match arg.strip_prefix("--") {
+            Some(name) if options.contains(&name) => {
+                let value = iter
+                    .next()
+                    .ok_or_else(|| format!("missing value for `--{}`", name))?;
+                parsed.options.push((name, value.as_str()));
+            }
+            Some(name) if flags.contains(&name) => parsed.flags.push(name),
+            Some(_) => return Err(format!("unknown option `{}`\n{}", arg, USAGE)),
+            None => parsed.positional.push(arg.as_str()),
+        }
+    }
+
+    Ok(parsed)
+}
+
+// Parses the CIDL together with the files it imports
+fn load(path: &str) -> Result<Cidl, String> {
+    let source = fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+    resolve(Path::new(path), &source).map_err(|errors| {
+        let errors: Vec<_> = errors.iter().map(ToString::to_string).collect();
+        errors.join("\n")
+    })
+}
+
+fn report(path: &str, diagnostics: &[Diagnostic]) {
+    for diagnostic in diagnostics {
+        eprintln!("{}:{}", path, diagnostic);
+    }
+}
+
+fn check(args: &[String]) -> Result<(), String> {
+    let args = parse_args(args, &[], &[])?;
+    let path = match args.positional.as_slice() {
+        [path] => *path,
+        _ => return Err(USAGE.to_string()),
+    };
+
+    let diagnostics = validate(&load(path)?);
+    report(path, &diagnostics);
+    if has_errors(&diagnostics) {
+        return Err(format!("{}: validation failed", path));
+    }
+
+    Ok(())
+}
+
+fn generate(args: &[String]) -> Result<(), String> {
+    let args = parse_args(args, &["out", "codigolib"], &[])?;
+    let path = match args.positional.as_slice() {
+        [path] => *path,
+        _ => return Err(USAGE.to_string()),
+    };
+
+    let mut out = PathBuf::from("generated");
+    let mut options = ProgramOptions::default();
+    for (name, value) in args.options {
+        match name {
+            "out" => out = PathBuf::from(value),
+            _ => options.codigolib_path = value.to_string(),
+        }
+    }
+
+    let cidl = load(path)?;
+    let files = generate_program(&cidl, &options).map_err(|diagnostics| {
+        report(path, &diagnostics);
+        format!("{}: validation failed", path)
+    })?;
+    report(path, &validate(&cidl));
+
+    let written = write_files(&out, &files).map_err(|e| format!("{}: {}", out.display(), e))?;
+    for file in written {
+        println!("{}", Path::new(&out).join(file).display());
+    }
+    for warning in check_stubs(&out, &files) {
+        eprintln!(
+            "{}: warning: {}",
+            out.join(&warning.path).display(),
+            warning.message
+        );
+    }
+
+    Ok(())
+}
+
+fn generate_client_sdk(args: &[String]) -> Result<(), String> {
+    let args = parse_args(args, &["out", "codigolib"], &[])?;
+    let path = match args.positional.as_slice() {
+        [path] => *path,
+        _ => return Err(USAGE.to_string()),
+    };
+
+    let mut out = PathBuf::from("sdk-rs");
+    let mut options = ClientOptions::default();
+    for (name, value) in args.options {
+        match name {
+            "out" => out = PathBuf::from(value),
+            _ => options.codigolib_path = value.to_string(),
+        }
+    }
+
+    let cidl = load(path)?;
+    let files = generate_client(&cidl, &options).map_err(|diagnostics| {
+        report(path, &diagnostics);
+        format!("{}: validation failed", path)
+    })?;
+    report(path, &validate(&cidl));
+
+    let written = write_files(&out, &files).map_err(|e| format!("{}: {}", out.display(), e))?;
+    for file in written {
+        println!("{}", Path::new(&out).join(file).display());
+    }
+
+    Ok(())
+}
+
+// Imported files get a crate of their own, named after the file, that importing programs depend on
+fn generate_types_library(args: &[String]) -> Result<(), String> {
+    let args = parse_args(args, &["out", "codigolib"], &[])?;
+    let path = match args.positional.as_slice() {
+        [path] => *path,
+        _ => return Err(USAGE.to_string()),
+    };
+
+    let cidl = load(path)?;
+    let mut out = PathBuf::from(&cidl.info.name.value);
+    let mut options = LibraryOptions::default();
+    for (name, value) in args.options {
+        match name {
+            "out" => out = PathBuf::from(value),
+            _ => options.codigolib_path = value.to_string(),
+        }
+    }
+
+    let files = generate_library(&cidl, &options).map_err(|diagnostics| {
+        report(path, &diagnostics);
+        format!("{}: validation failed", path)
+    })?;
+    report(path, &validate(&cidl));
+
+    let written = write_files(&out, &files).map_err(|e| format!("{}: {}", out.display(), e))?;
+    for file in written {
+        println!("{}", Path::new(&out).join(file).display());
+    }
+
+    Ok(())
+}
+
+fn size(args: &[String]) -> Result<(), String> {
+    let args = parse_args(args, &["lamports-per-byte-year"], &[])?;
+    let path = match args.positional.as_slice() {
+        [path] => *path,
+        _ => return Err(USAGE.to_string()),
+    };
+
+    let mut options = SizeOptions::default();
+    for (_, value) in args.options {
+        options.lamports_per_byte_year = value
+            .parse()
+            .map_err(|_| format!("invalid lamports per byte-year `{}`", value))?;
+    }
+
+    let cidl = load(path)?;
+    let sizes = size_report(&cidl, &options).map_err(|diagnostics| {
+        report(path, &diagnostics);
+        format!("{}: validation failed", path)
+    })?;
+    print!("{}", sizes);
+    report(path, &sizes.warnings);
+
+    Ok(())
+}
+
+// Exits with an error when a change breaks existing accounts or deployed clients
+fn diff_versions(args: &[String]) -> Result<(), String> {
+    let args = parse_args(args, &[], &[])?;
+    let (old_path, new_path) = match args.positional.as_slice() {
+        [old_path, new_path] => (*old_path, *new_path),
+        _ => return Err(USAGE.to_string()),
+    };
+
+    let mut versions = Vec::new();
+    for path in [old_path, new_path] {
+        let cidl = load(path)?;
+        let diagnostics = validate(&cidl);
+        if has_errors(&diagnostics) {
+            report(path, &diagnostics);
+            return Err(format!("{}: validation failed", path));
+        }
+        versions.push(cidl);
+    }
+
+    let changes = diff(&versions[0], &versions[1]);
+    for change in &changes {
+        let path = match change.side {
+            Side::Old => old_path,
+            Side::New => new_path,
+        };
+        println!("{}:{}", path, change);
+    }
+
+    if is_breaking(&changes) {
+        return Err(format!("{}: breaking changes from {}", new_path, old_path));
+    }
+
+    Ok(())
+}
+
+fn idl(args: &[String]) -> Result<(), String> {
+    let args = parse_args(args, &["out"], &[])?;
+    let path = match args.positional.as_slice() {
+        [path] => *path,
+        _ => return Err(USAGE.to_string()),
+    };
+
+    let cidl = load(path)?;
+    let idl = export_idl(&cidl).map_err(|diagnostics| {
+        report(path, &diagnostics);
+        format!("{}: validation failed", path)
+    })?;
+    report(path, &validate(&cidl));
+
+    let json = serde_json::to_string_pretty(&idl).map_err(|e| e.to_string())? + "\n";
+    match args.options.first() {
+        Some((_, out)) => fs::write(out, json).map_err(|e| format!("{}: {}", out, e))?,
+        None => print!("{}", json),
+    }
+
+    Ok(())
+}
+
+fn fmt(args: &[String]) -> Result<(), String> {
+    let args = parse_args(args, &[], &["check"])?;
+    let path = match args.positional.as_slice() {
+        [path] => *path,
+        _ => return Err(USAGE.to_string()),
+    };
+
+    let source = fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+    let formatted = format_cidl(&source);
+    if formatted == source {
+        return Ok(());
+    }
+    let before = cidl::parse(&source).map_err(|e| format!("{}:{}", path, e))?;
+    let after = cidl::parse(&formatted)
+        .map_err(|e| format!("{}: formatting breaks the file at {}", path, e))?;
+    if !same_model(&before, &after) {
+        return Err(format!(
+            "{}: formatting changes the meaning of the file",
+            path
+        ));
+    }
+
+    if args.flags.contains(&"check") {
+        return Err(format!("{}: not formatted", path));
+    }
+    fs::write(path, formatted).map_err(|e| format!("{}: {}", path, e))?;
+    println!("{}", path);
+
+    Ok(())
+}
+
+fn lint_file(args: &[String]) -> Result<(), String> {
+    let args = parse_args(args, &["allow", "deny"], &[])?;
+    let path = match args.positional.as_slice() {
+        [path] => *path,
+        _ => return Err(USAGE.to_string()),
+    };
+
+    let mut config = LintConfig::default();
+    for (name, value) in args.options {
+        let rule = Rule::from_name(value).ok_or_else(|| {
+            let rules: Vec<_> = Rule::ALL.iter().map(Rule::name).collect();
+            format!(
+                "unknown rule `{}`, expected one of {}",
+                value,
+                rules.join(", ")
+            )
+        })?;
+        let level = match name {
+            "allow" => Level::Allow,
+            _ => Level::Deny,
+        };
+        config.set(rule, level);
+    }
+
+    let source = fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+    let cidl = load(path)?;
+    let mut diagnostics = validate(&cidl);
+    diagnostics.extend(lint(&source, &cidl, &config));
+    report(path, &diagnostics);
+    if has_errors(&diagnostics) {
+        return Err(format!("{}: lint failed", path));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,164 @@
+use crate::generator::{FileKind, GeneratedFile};
+use std::collections::HashSet;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// A stub keeps everything the developer wrote except the summary comment and the signature of
+// its function, which are re-rendered from the CIDL. Code above the summary is kept as it is,
+// imports the rendered stub needs and the file lacks are added after its last import. Imports are
+// never removed, the developer may still use the ones a previous version of the CIDL needed
+pub fn merge_stub(current: &str, rendered: &str, name: &str) -> Option<String> {
+    let (start, end) = stub_signature(current, name)?;
+    let (rendered_start, rendered_end) = stub_signature(rendered, name)?;
+
+    let (header, _) = split_summary(&current[..start]);
+    let (rendered_header, rendered_summary) = split_summary(&rendered[..rendered_start]);
+
+    let user_uses = use_statements(header);
+    let missing: Vec<_> = use_statements(rendered_header)
+        .into_iter()
+        .filter(|(_, statement)| !user_uses.iter().any(|(_, user)| user == statement))
+        .collect();
+
+    let split = match user_uses.last() {
+        Some((end, _)) if header[*end..].starts_with('\n') => end + 1,
+        Some((end, _)) => *end,
+        None => 0,
+    };
+    let mut out = header[..split].to_string();
+    for (_, statement) in &missing {
+        out += statement;
+        out += "\n";
+    }
+    if split == 0 && !missing.is_empty() {
+        out += "\n";
+    }
+    out += &header[split..];
+    out += rendered_summary;
+    out += &rendered[rendered_start..rendered_end];
+    out += &current[end..];
+
+    Some(out)
+}
+
+// From `pub fn name(`, or `pub fn name<'a>(` for stubs migrating accounts, to the brace opening
+// the body
+fn stub_signature(source: &str, name: &str) -> Option<(usize, usize)> {
+    let prefix = format!("pub fn {}", name);
+    let start = source
+        .match_indices(&prefix)
+        .map(|(start, _)| start)
+        .find(|start| {
+            matches!(
+                source[start + prefix.len()..].chars().next(),
+                Some('(' | '<')
+            )
+        })?;
+    let arrow = start + source[start..].find(") -> ")?;
+    let end = arrow + source[arrow..].find('{')? + 1;
+    Some((start, end))
+}
+
+// Splits the `//` lines right above the signature, the rendered summary, from the rest
+fn split_summary(header: &str) -> (&str, &str) {
+    let mut split = header.len();
+    while let Some(rest) = header[..split].strip_suffix('\n') {
+        let line = rest.rfind('\n').map_or(0, |newline| newline + 1);
+        if !header[line..split].starts_with("//") {
+            break;
+        }
+        split = line;
+    }
+    (&header[..split], &header[split..])
+}
+
+// Every `use` statement starting a line, with the offset right after its `;`
+fn use_statements(header: &str) -> Vec<(usize, &str)> {
+    let mut statements = Vec::new();
+    let mut offset = 0;
+
+    while let Some(start) = header[offset..].find("use ").map(|start| offset + start) {
+        let at_line_start = start == 0 || header[..start].ends_with('\n');
+        let end = match header[start..].find(';') {
+            Some(end) => start + end + 1,
+            None => break,
+        };
+        if at_line_start {
+            statements.push((end, &header[start..end]));
+        }
+        offset = end;
+    }
+
+    statements
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StubWarning {
+    pub path: PathBuf,
+    pub message: String,
+}
+
+impl fmt::Display for StubWarning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: warning: {}", self.path.display(), self.message)
+    }
+}
+
+pub fn stub_name(path: &Path) -> &str {
+    path.file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or("")
+}
+
+// Stubs the generator could not update and stubs left behind by methods removed from the CIDL.
+// Neither is touched, the developer decides what to do with the code in them
+pub fn check_stubs(root: &Path, files: &[GeneratedFile]) -> Vec<StubWarning> {
+    let mut warnings = Vec::new();
+    let stubs: Vec<_> = files
+        .iter()
+        .filter(|file| file.kind == FileKind::Stub)
+        .collect();
+
+    for stub in &stubs {
+        if let Ok(current) = fs::read_to_string(root.join(&stub.path)) {
+            if stub_signature(&current, stub_name(&stub.path)).is_none() {
+                warnings.push(StubWarning {
+                    path: stub.path.clone(),
+                    message: format!(
+                        "`pub fn {}` was not found, its signature was not updated",
+                        stub_name(&stub.path)
+                    ),
+                });
+            }
+        }
+    }
+
+    let known: HashSet<_> = stubs.iter().map(|stub| stub.path.as_path()).collect();
+    let dirs: HashSet<_> = stubs.iter().filter_map(|stub| stub.path.parent()).collect();
+    let mut removed = Vec::new();
+    for dir in dirs {
+        let entries = match fs::read_dir(root.join(dir)) {
+            Ok(entries) => entries,
+            Err(_) => continue,
+        };
+        for entry in entries.flatten() {
+            let path = dir.join(entry.file_name());
+            let is_stub = path.extension().is_some_and(|extension| extension == "rs")
+                && stub_name(&path) != "mod";
+            if is_stub && !known.contains(path.as_path()) {
+                removed.push(path);
+            }
+        }
+    }
+    removed.sort();
+    warnings.extend(removed.into_iter().map(|path| StubWarning {
+        message: format!(
+            "method `{}` is no longer in the CIDL, the stub is kept but not compiled",
+            stub_name(&path)
+        ),
+        path,
+    }));
+
+    warnings
+}
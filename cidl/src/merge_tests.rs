@@ -0,0 +1,178 @@
+use crate::generator::*;
+use crate::merge::*;
+use crate::parser::parse;
+use crate::program::*;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BUDGET_TRACKER: &str = include_str!("../../cidl.yaml");
+    const REGISTER_INCOME_STUB: &str =
+        include_str!("../../generated/rendered/stubs/register_income.rs");
+    const AMOUNT: &str = "        description: The amount to be registered as the income.\n";
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let nanos = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        let dir = env::temp_dir().join(format!("cidl-{}-{}", name, nanos));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn program(source: &str) -> Vec<GeneratedFile> {
+        generate_program(&parse(source).unwrap(), &ProgramOptions::default()).unwrap()
+    }
+
+    // The budget tracker with the business logic of register_income filled in
+    fn generated_with_user_code(name: &str) -> PathBuf {
+        let dir = temp_dir(name);
+        write_files(&dir, &program(BUDGET_TRACKER)).unwrap();
+        fs::write(
+            dir.join("rendered/stubs/register_income.rs"),
+            REGISTER_INCOME_STUB,
+        )
+        .unwrap();
+        dir
+    }
+
+    fn with_category_input(source: &str) -> String {
+        source.replace(
+            AMOUNT,
+            &format!("{}      - name: category\n        type: u8\n", AMOUNT),
+        )
+    }
+
+    #[test]
+    fn it_keeps_the_body_when_an_input_is_added() {
+        let dir = generated_with_user_code("merge-input");
+        let files = program(&with_category_input(BUDGET_TRACKER));
+
+        let written = write_files(&dir, &files).unwrap();
+        let stub = fs::read_to_string(dir.join("rendered/stubs/register_income.rs")).unwrap();
+
+        assert!(written.contains(&PathBuf::from("rendered/stubs/register_income.rs")));
+        assert!(written.contains(&PathBuf::from("rendered/processor.rs")));
+        assert!(stub.contains(
+            "pub fn register_income(\n    user_record: &mut AccountPDA<Record>,\n    amount: u32,\n    category: u8,\n) -> ProgramResult {\n"
+        ));
+        let body =
+            &REGISTER_INCOME_STUB[REGISTER_INCOME_STUB.find("    if is_token_backed").unwrap()..];
+        assert!(stub.ends_with(body));
+        assert!(stub.contains("use codigolib::errors::{MathError, SecurityError};\n"));
+        assert!(!stub.contains(STUB_PLACEHOLDER));
+        assert!(check_stubs(&dir, &files).is_empty());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn it_keeps_the_body_of_stubs_with_a_lifetime() {
+        let files = program(BUDGET_TRACKER);
+        let rendered = &files
+            .iter()
+            .find(|file| file.path == Path::new("rendered/stubs/migrate_record.rs"))
+            .unwrap()
+            .contents;
+        let current = rendered
+            .replace("program_id: &Pubkey,\n", "")
+            .replace(STUB_PLACEHOLDER, "migrate(user_record)?;");
+
+        let merged = merge_stub(&current, rendered, "migrate_record").unwrap();
+
+        assert!(merged.contains("pub fn migrate_record<'a>(\n    program_id: &Pubkey,\n"));
+        assert!(merged.contains("    migrate(user_record)?;\n"));
+        assert_eq!(merge_stub(&current, rendered, "migrate"), None);
+    }
+
+    #[test]
+    fn it_leaves_up_to_date_stubs_untouched() {
+        let dir = generated_with_user_code("merge-unchanged");
+
+        let written = write_files(&dir, &program(BUDGET_TRACKER)).unwrap();
+
+        assert!(written.is_empty());
+        assert_eq!(
+            fs::read_to_string(dir.join("rendered/stubs/register_income.rs")).unwrap(),
+            REGISTER_INCOME_STUB
+        );
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn it_flags_stubs_of_removed_methods() {
+        let dir = generated_with_user_code("merge-removed");
+        let start = BUDGET_TRACKER.find("  - name: register_income").unwrap();
+        let end = BUDGET_TRACKER.find("  - name: register_outcome").unwrap();
+        let source = format!("{}{}", &BUDGET_TRACKER[..start], &BUDGET_TRACKER[end..]);
+        let files = program(&source);
+
+        write_files(&dir, &files).unwrap();
+        let warnings = check_stubs(&dir, &files);
+
+        assert_eq!(
+            warnings,
+            vec![StubWarning {
+                path: PathBuf::from("rendered/stubs/register_income.rs"),
+                message:
+                    "method `register_income` is no longer in the CIDL, the stub is kept but not compiled"
+                        .to_string(),
+            }]
+        );
+        assert!(dir.join("rendered/stubs/register_income.rs").exists());
+        assert!(!fs::read_to_string(dir.join("rendered/stubs/mod.rs"))
+            .unwrap()
+            .contains("register_income"));
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn it_flags_stubs_without_their_signature() {
+        let dir = generated_with_user_code("merge-renamed");
+        let path = dir.join("rendered/stubs/register_income.rs");
+        let renamed = REGISTER_INCOME_STUB.replace("pub fn register_income(", "pub fn income(");
+        fs::write(&path, &renamed).unwrap();
+        let files = program(&with_category_input(BUDGET_TRACKER));
+
+        write_files(&dir, &files).unwrap();
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), renamed);
+        assert_eq!(
+            check_stubs(&dir, &files)[0].to_string(),
+            "rendered/stubs/register_income.rs: warning: `pub fn register_income` was not found, its signature was not updated"
+        );
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn it_merges_user_imports() {
+        let current = "use crate::state::*;\nuse solana_program::msg;\nuse solana_program::entrypoint::ProgramResult;\n\n// Old summary\npub fn run(a: u8) -> ProgramResult {\n    msg!(\"{}\", a);\n    Ok(())\n}\n";
+        let rendered = "use crate::state::*;\nuse solana_program::entrypoint::ProgramResult;\n\n// New summary\npub fn run(a: u8, b: u16) -> ProgramResult {\n    // Place your custom code here...\n\n    Ok(())\n}\n";
+
+        assert_eq!(
+            merge_stub(current, rendered, "run").unwrap(),
+            "use crate::state::*;\nuse solana_program::msg;\nuse solana_program::entrypoint::ProgramResult;\n\n// New summary\npub fn run(a: u8, b: u16) -> ProgramResult {\n    msg!(\"{}\", a);\n    Ok(())\n}\n"
+        );
+        assert_eq!(merge_stub("fn other() {}", rendered, "run"), None);
+        assert_eq!(stub_name(Path::new("rendered/stubs/run.rs")), "run");
+    }
+
+    #[test]
+    fn it_keeps_user_items_above_the_stub() {
+        let current = "use solana_program::entrypoint::ProgramResult;\nuse solana_program::msg;\n\nconst LIMIT: u8 = 10;\n\n// Logs the value\nfn log(a: u8) {\n    msg!(\"{}\", a);\n}\n\n// Old summary\n// on two lines\npub fn run(a: u8) -> ProgramResult {\n    log(a.min(LIMIT));\n    Ok(())\n}\n";
+        let rendered = "use solana_program::entrypoint::ProgramResult;\nuse solana_program::pubkey::Pubkey;\n\n// New summary\npub fn run(a: u8, key: Pubkey) -> ProgramResult {\n    // Place your custom code here...\n\n    Ok(())\n}\n";
+
+        assert_eq!(
+            merge_stub(current, rendered, "run").unwrap(),
+            "use solana_program::entrypoint::ProgramResult;\nuse solana_program::msg;\nuse solana_program::pubkey::Pubkey;\n\nconst LIMIT: u8 = 10;\n\n// Logs the value\nfn log(a: u8) {\n    msg!(\"{}\", a);\n}\n\n// New summary\npub fn run(a: u8, key: Pubkey) -> ProgramResult {\n    log(a.min(LIMIT));\n    Ok(())\n}\n"
+        );
+        assert_eq!(
+            merge_stub("const LIMIT: u8 = 10;\n\npub fn run(a: u8) -> ProgramResult {\n    Ok(())\n}\n", rendered, "run").unwrap(),
+            "use solana_program::entrypoint::ProgramResult;\nuse solana_program::pubkey::Pubkey;\n\nconst LIMIT: u8 = 10;\n\n// New summary\npub fn run(a: u8, key: Pubkey) -> ProgramResult {\n    Ok(())\n}\n"
+        );
+    }
+}
@@ -0,0 +1,332 @@
+use crate::yaml::{Span, Spanned};
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cidl {
+    pub cidl: Spanned<String>,
+    pub info: Info,
+    pub imports: Vec<Import>,
+    pub types: Vec<TypeDef>,
+    pub methods: Vec<Method>,
+    // Keys the parser skipped, most likely misspelled
+    pub unknown_keys: Vec<UnknownKey>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownKey {
+    pub key: Spanned<String>,
+    // The keys of the mapping it was found in
+    pub expected: &'static [&'static str],
+}
+
+impl Cidl {
+    pub fn find_type(&self, name: &str) -> Option<&TypeDef> {
+        self.types.iter().find(|ty| ty.name.value == name)
+    }
+
+    pub fn find_method(&self, name: &str) -> Option<&Method> {
+        self.methods.iter().find(|method| method.name.value == name)
+    }
+}
+
+// Another CIDL file whose types can be used here, under `namespace.` when one is given
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Import {
+    pub path: Spanned<String>,
+    pub namespace: Option<Spanned<String>>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Info {
+    pub name: Spanned<String>,
+    pub title: Option<Spanned<String>>,
+    pub version: Option<Spanned<String>>,
+    pub summary: Option<Spanned<String>>,
+    pub contact: Option<Contact>,
+    pub license: Option<License>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Contact {
+    pub name: Option<Spanned<String>>,
+    pub web: Option<Spanned<String>>,
+    pub email: Option<Spanned<String>>,
+    pub git: Option<Spanned<String>>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct License {
+    pub name: Option<Spanned<String>>,
+    pub url: Option<Spanned<String>>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TypeDef {
+    pub name: Spanned<String>,
+    pub summary: Option<Spanned<String>>,
+    pub description: Option<Spanned<String>>,
+    pub solana: Option<SolanaType>,
+    pub fields: Vec<Field>,
+    // Crate generated from the CIDL file that defines an imported type, set by the import resolver
+    pub library: Option<String>,
+    pub span: Span,
+}
+
+impl TypeDef {
+    pub fn is_token_program(&self) -> bool {
+        self.solana
+            .as_ref()
+            .is_some_and(SolanaType::is_token_program)
+    }
+
+    pub fn find_field(&self, name: &str) -> Option<&Field> {
+        self.fields.iter().find(|field| field.name.value == name)
+    }
+}
+
+// `solana` extension of a type, turns the type into an account
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SolanaType {
+    pub owner: Option<Spanned<String>>,
+    pub seeds: Vec<Seed>,
+    pub span: Span,
+}
+
+// Owners of the accounts of the SPL token program, whose layout the token program defines
+pub const TOKEN_ACCOUNT_OWNER: &str = "spl-token-account";
+pub const MINT_OWNER: &str = "spl-token-mint";
+
+impl SolanaType {
+    fn has_owner(&self, owner: &str) -> bool {
+        self.owner.as_ref().is_some_and(|o| o.value == owner)
+    }
+
+    pub fn is_token_account(&self) -> bool {
+        self.has_owner(TOKEN_ACCOUNT_OWNER)
+    }
+
+    pub fn is_mint(&self) -> bool {
+        self.has_owner(MINT_OWNER)
+    }
+
+    // Token accounts and mints have no fields, they are not stored by the program
+    pub fn is_token_program(&self) -> bool {
+        self.is_token_account() || self.is_mint()
+    }
+}
+
+// A seed without type is the constant string `name`, otherwise it is provided by the client
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Seed {
+    pub name: Spanned<String>,
+    pub ty: Option<Spanned<TypeRef>>,
+    pub description: Option<Spanned<String>>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Field {
+    pub name: Spanned<String>,
+    pub ty: Spanned<TypeRef>,
+    pub description: Option<Spanned<String>>,
+    pub solana: Option<SolanaAttributes>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Method {
+    pub name: Spanned<String>,
+    pub summary: Option<Spanned<String>>,
+    pub description: Option<Spanned<String>>,
+    pub inputs: Vec<Input>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Input {
+    pub name: Spanned<String>,
+    pub ty: Spanned<TypeRef>,
+    pub description: Option<Spanned<String>>,
+    pub solana: Option<SolanaAttributes>,
+    pub span: Span,
+}
+
+// `solana` extension of fields and inputs
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SolanaAttributes {
+    pub attributes: Vec<Spanned<Attribute>>,
+    pub span: Span,
+}
+
+impl SolanaAttributes {
+    pub fn has(&self, attribute: &Attribute) -> bool {
+        self.attributes.iter().any(|a| a.value == *attribute)
+    }
+
+    pub fn cap(&self) -> Option<usize> {
+        self.attributes.iter().find_map(|a| match a.value {
+            Attribute::Cap(cap) => Some(cap),
+            _ => None,
+        })
+    }
+
+    pub fn role(&self) -> Option<Role> {
+        self.attributes.iter().find_map(|a| match a.value {
+            Attribute::Role(role) => Some(role),
+            _ => None,
+        })
+    }
+}
+
+// Shared by fields and inputs, so both can be queried the same way
+pub trait HasAttributes {
+    fn solana(&self) -> Option<&SolanaAttributes>;
+
+    fn has_attribute(&self, attribute: &Attribute) -> bool {
+        self.solana().is_some_and(|solana| solana.has(attribute))
+    }
+
+    fn cap(&self) -> Option<usize> {
+        self.solana().and_then(SolanaAttributes::cap)
+    }
+
+    fn role(&self) -> Option<Role> {
+        self.solana().and_then(SolanaAttributes::role)
+    }
+}
+
+impl HasAttributes for Field {
+    fn solana(&self) -> Option<&SolanaAttributes> {
+        self.solana.as_ref()
+    }
+}
+
+impl HasAttributes for Input {
+    fn solana(&self) -> Option<&SolanaAttributes> {
+        self.solana.as_ref()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Attribute {
+    Mut,
+    Init,
+    InitIfNeeded,
+    Signer,
+    Cap(usize),
+    // Delegates with at least this role may sign for the account instead of its owner
+    Role(Role),
+    // The stub rewrites the account from an older layout, it is neither checked nor loaded
+    Migrate,
+    // The account is deleted once the stub returns, its lamports go to the fee payer
+    Close,
+    Other(String),
+}
+
+// The codigolib DelegateRole names, from the least to the most privileged
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Role {
+    Viewer,
+    Contributor,
+    Admin,
+}
+
+impl Role {
+    pub fn variant(&self) -> &'static str {
+        match self {
+            Role::Viewer => "Viewer",
+            Role::Contributor => "Contributor",
+            Role::Admin => "Admin",
+        }
+    }
+}
+
+impl fmt::Display for Role {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Role::Viewer => write!(f, "viewer"),
+            Role::Contributor => write!(f, "contributor"),
+            Role::Admin => write!(f, "admin"),
+        }
+    }
+}
+
+impl fmt::Display for Attribute {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Attribute::Mut => write!(f, "mut"),
+            Attribute::Init => write!(f, "init"),
+            Attribute::InitIfNeeded => write!(f, "init_if_needed"),
+            Attribute::Signer => write!(f, "signer"),
+            Attribute::Cap(cap) => write!(f, "cap:{}", cap),
+            Attribute::Role(role) => write!(f, "role:{}", role),
+            Attribute::Migrate => write!(f, "migrate"),
+            Attribute::Close => write!(f, "close"),
+            Attribute::Other(other) => write!(f, "{}", other),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TypeRef {
+    Bool,
+    U8,
+    U16,
+    U32,
+    U64,
+    U128,
+    I8,
+    I16,
+    I32,
+    I64,
+    I128,
+    F32,
+    F64,
+    String,
+    Pubkey,
+    // codigolib Delegate, a pubkey and its DelegateRole
+    Delegate,
+    Option(Box<TypeRef>),
+    Vec(Box<TypeRef>),
+    // A type defined in `types`, or an extension type the parser does not know about
+    Named(String),
+}
+
+impl TypeRef {
+    pub fn is_primitive(&self) -> bool {
+        !matches!(
+            self,
+            TypeRef::Option(_) | TypeRef::Vec(_) | TypeRef::Named(_)
+        )
+    }
+}
+
+impl fmt::Display for TypeRef {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TypeRef::Bool => write!(f, "bool"),
+            TypeRef::U8 => write!(f, "u8"),
+            TypeRef::U16 => write!(f, "u16"),
+            TypeRef::U32 => write!(f, "u32"),
+            TypeRef::U64 => write!(f, "u64"),
+            TypeRef::U128 => write!(f, "u128"),
+            TypeRef::I8 => write!(f, "i8"),
+            TypeRef::I16 => write!(f, "i16"),
+            TypeRef::I32 => write!(f, "i32"),
+            TypeRef::I64 => write!(f, "i64"),
+            TypeRef::I128 => write!(f, "i128"),
+            TypeRef::F32 => write!(f, "f32"),
+            TypeRef::F64 => write!(f, "f64"),
+            TypeRef::String => write!(f, "string"),
+            TypeRef::Pubkey => write!(f, "sol:pubkey"),
+            TypeRef::Delegate => write!(f, "sol:delegate"),
+            TypeRef::Option(inner) => write!(f, "option<{}>", inner),
+            TypeRef::Vec(inner) => write!(f, "vec<{}>", inner),
+            TypeRef::Named(name) => write!(f, "{}", name),
+        }
+    }
+}
@@ -0,0 +1,459 @@
+use crate::errors::ParseError;
+use crate::model::*;
+use crate::yaml::{self, Entry, Node, Span, Spanned};
+
+pub fn parse(source: &str) -> Result<Cidl, ParseError> {
+    let root = yaml::load(source)?;
+    parse_cidl(&root)
+}
+
+pub fn parse_cidl(root: &Spanned<Node>) -> Result<Cidl, ParseError> {
+    let entries = expect_map(root, "the CIDL document")?;
+
+    let types = match get(entries, "types") {
+        Some(types) => expect_map(types, "`types`")?
+            .iter()
+            .map(|(name, node)| parse_type_def(name, node))
+            .collect::<Result<_, _>>()?,
+        None => Vec::new(),
+    };
+    let imports = match get(entries, "imports") {
+        Some(imports) => expect_seq(imports, "`imports`")?
+            .iter()
+            .map(parse_import)
+            .collect::<Result<_, _>>()?,
+        None => Vec::new(),
+    };
+    let methods = match get(entries, "methods") {
+        Some(methods) => expect_seq(methods, "`methods`")?
+            .iter()
+            .map(parse_method)
+            .collect::<Result<_, _>>()?,
+        None => Vec::new(),
+    };
+
+    Ok(Cidl {
+        cidl: required_str(entries, root.span, "cidl", "the CIDL document")?,
+        info: parse_info(required(entries, root.span, "info", "the CIDL document")?)?,
+        imports,
+        types,
+        methods,
+        unknown_keys: unknown_keys(root),
+    })
+}
+
+// Keys of each mapping of the document
+const DOCUMENT_KEYS: &[&str] = &["cidl", "info", "imports", "types", "methods"];
+const INFO_KEYS: &[&str] = &["name", "title", "version", "summary", "contact", "license"];
+const CONTACT_KEYS: &[&str] = &["name", "web", "email", "git"];
+const LICENSE_KEYS: &[&str] = &["name", "url"];
+const IMPORT_KEYS: &[&str] = &["path", "as"];
+const TYPE_KEYS: &[&str] = &["summary", "description", "solana", "fields"];
+const SOLANA_TYPE_KEYS: &[&str] = &["owner", "seeds"];
+const SEED_KEYS: &[&str] = &["name", "type", "description"];
+const FIELD_KEYS: &[&str] = &["name", "type", "description", "solana"];
+const METHOD_KEYS: &[&str] = &["name", "summary", "description", "inputs"];
+const SOLANA_ATTRIBUTES_KEYS: &[&str] = &["attributes"];
+
+// Walks the document the parser accepted, collecting the keys none of the parse functions read
+fn unknown_keys(root: &Spanned<Node>) -> Vec<UnknownKey> {
+    let mut unknown = Vec::new();
+    let document = check_keys(Some(root), DOCUMENT_KEYS, &mut unknown);
+
+    let info = check_keys(get(document, "info"), INFO_KEYS, &mut unknown);
+    check_keys(get(info, "contact"), CONTACT_KEYS, &mut unknown);
+    check_keys(get(info, "license"), LICENSE_KEYS, &mut unknown);
+
+    for import in items(get(document, "imports")) {
+        check_keys(Some(import), IMPORT_KEYS, &mut unknown);
+    }
+
+    let types = match get(document, "types").map(|types| &types.value) {
+        Some(Node::Map(types)) => types.as_slice(),
+        _ => &[],
+    };
+    for (_, ty) in types {
+        let ty = check_keys(Some(ty), TYPE_KEYS, &mut unknown);
+        let solana = check_keys(get(ty, "solana"), SOLANA_TYPE_KEYS, &mut unknown);
+        for seed in items(get(solana, "seeds")) {
+            check_keys(Some(seed), SEED_KEYS, &mut unknown);
+        }
+        for field in items(get(ty, "fields")) {
+            let field = check_keys(Some(field), FIELD_KEYS, &mut unknown);
+            check_keys(get(field, "solana"), SOLANA_ATTRIBUTES_KEYS, &mut unknown);
+        }
+    }
+
+    for method in items(get(document, "methods")) {
+        let method = check_keys(Some(method), METHOD_KEYS, &mut unknown);
+        for input in items(get(method, "inputs")) {
+            // Inputs have the keys of a field
+            let input = check_keys(Some(input), FIELD_KEYS, &mut unknown);
+            check_keys(get(input, "solana"), SOLANA_ATTRIBUTES_KEYS, &mut unknown);
+        }
+    }
+
+    unknown
+}
+
+fn check_keys<'a>(
+    node: Option<&'a Spanned<Node>>,
+    expected: &'static [&'static str],
+    unknown: &mut Vec<UnknownKey>,
+) -> &'a [Entry] {
+    let entries = match node.map(|node| &node.value) {
+        Some(Node::Map(entries)) => entries.as_slice(),
+        _ => return &[],
+    };
+    for (key, _) in entries {
+        if !expected.contains(&key.value.as_str()) {
+            unknown.push(UnknownKey {
+                key: key.clone(),
+                expected,
+            });
+        }
+    }
+    entries
+}
+
+fn items(node: Option<&Spanned<Node>>) -> &[Spanned<Node>] {
+    match node.map(|node| &node.value) {
+        Some(Node::Seq(items)) => items,
+        _ => &[],
+    }
+}
+
+// `- common.yaml` or `- { path: common.yaml, as: common }`
+fn parse_import(node: &Spanned<Node>) -> Result<Import, ParseError> {
+    if let Node::Scalar(..) = node.value {
+        return Ok(Import {
+            path: expect_str(node, "an import")?,
+            namespace: None,
+            span: node.span,
+        });
+    }
+    let entries = expect_map(node, "an import")?;
+
+    Ok(Import {
+        path: required_str(entries, node.span, "path", "an import")?,
+        namespace: optional_str(entries, "as")?,
+        span: node.span,
+    })
+}
+
+fn parse_info(node: &Spanned<Node>) -> Result<Info, ParseError> {
+    let entries = expect_map(node, "`info`")?;
+
+    Ok(Info {
+        name: required_str(entries, node.span, "name", "`info`")?,
+        title: optional_str(entries, "title")?,
+        version: optional_str(entries, "version")?,
+        summary: optional_str(entries, "summary")?,
+        contact: get(entries, "contact").map(parse_contact).transpose()?,
+        license: get(entries, "license").map(parse_license).transpose()?,
+        span: node.span,
+    })
+}
+
+fn parse_contact(node: &Spanned<Node>) -> Result<Contact, ParseError> {
+    let entries = expect_map(node, "`contact`")?;
+
+    Ok(Contact {
+        name: optional_str(entries, "name")?,
+        web: optional_str(entries, "web")?,
+        email: optional_str(entries, "email")?,
+        git: optional_str(entries, "git")?,
+        span: node.span,
+    })
+}
+
+fn parse_license(node: &Spanned<Node>) -> Result<License, ParseError> {
+    let entries = expect_map(node, "`license`")?;
+
+    Ok(License {
+        name: optional_str(entries, "name")?,
+        url: optional_str(entries, "url")?,
+        span: node.span,
+    })
+}
+
+fn parse_type_def(name: &Spanned<String>, node: &Spanned<Node>) -> Result<TypeDef, ParseError> {
+    let what = format!("type `{}`", name.value);
+    let entries = expect_map(node, &what)?;
+
+    let fields = match get(entries, "fields") {
+        Some(fields) => expect_seq(fields, "`fields`")?
+            .iter()
+            .map(parse_field)
+            .collect::<Result<_, _>>()?,
+        None => Vec::new(),
+    };
+
+    Ok(TypeDef {
+        name: name.clone(),
+        summary: optional_str(entries, "summary")?,
+        description: optional_str(entries, "description")?,
+        solana: get(entries, "solana").map(parse_solana_type).transpose()?,
+        fields,
+        library: None,
+        span: name.span,
+    })
+}
+
+fn parse_solana_type(node: &Spanned<Node>) -> Result<SolanaType, ParseError> {
+    let entries = expect_map(node, "`solana`")?;
+
+    let seeds = match get(entries, "seeds") {
+        Some(seeds) => expect_seq(seeds, "`seeds`")?
+            .iter()
+            .map(parse_seed)
+            .collect::<Result<_, _>>()?,
+        None => Vec::new(),
+    };
+
+    Ok(SolanaType {
+        owner: optional_str(entries, "owner")?,
+        seeds,
+        span: node.span,
+    })
+}
+
+fn parse_seed(node: &Spanned<Node>) -> Result<Seed, ParseError> {
+    let entries = expect_map(node, "a seed")?;
+
+    Ok(Seed {
+        name: required_str(entries, node.span, "name", "a seed")?,
+        ty: optional_type(entries)?,
+        description: optional_str(entries, "description")?,
+        span: node.span,
+    })
+}
+
+fn parse_field(node: &Spanned<Node>) -> Result<Field, ParseError> {
+    let entries = expect_map(node, "a field")?;
+
+    Ok(Field {
+        name: required_str(entries, node.span, "name", "a field")?,
+        ty: required_type(entries, node.span, "a field")?,
+        description: optional_str(entries, "description")?,
+        solana: get(entries, "solana")
+            .map(parse_solana_attributes)
+            .transpose()?,
+        span: node.span,
+    })
+}
+
+fn parse_method(node: &Spanned<Node>) -> Result<Method, ParseError> {
+    let entries = expect_map(node, "a method")?;
+
+    let inputs = match get(entries, "inputs") {
+        Some(inputs) => expect_seq(inputs, "`inputs`")?
+            .iter()
+            .map(parse_input)
+            .collect::<Result<_, _>>()?,
+        None => Vec::new(),
+    };
+
+    Ok(Method {
+        name: required_str(entries, node.span, "name", "a method")?,
+        summary: optional_str(entries, "summary")?,
+        description: optional_str(entries, "description")?,
+        inputs,
+        span: node.span,
+    })
+}
+
+fn parse_input(node: &Spanned<Node>) -> Result<Input, ParseError> {
+    let entries = expect_map(node, "an input")?;
+
+    Ok(Input {
+        name: required_str(entries, node.span, "name", "an input")?,
+        ty: required_type(entries, node.span, "an input")?,
+        description: optional_str(entries, "description")?,
+        solana: get(entries, "solana")
+            .map(parse_solana_attributes)
+            .transpose()?,
+        span: node.span,
+    })
+}
+
+fn parse_solana_attributes(node: &Spanned<Node>) -> Result<SolanaAttributes, ParseError> {
+    let entries = expect_map(node, "`solana`")?;
+
+    let attributes = match get(entries, "attributes") {
+        Some(attributes) => expect_seq(attributes, "`attributes`")?
+            .iter()
+            .map(|attribute| {
+                let attribute = expect_str(attribute, "an attribute")?;
+                parse_attribute(&attribute.value)
+                    .map(|value| Spanned::new(value, attribute.span))
+                    .map_err(|message| ParseError::new(attribute.span, message))
+            })
+            .collect::<Result<_, _>>()?,
+        None => Vec::new(),
+    };
+
+    Ok(SolanaAttributes {
+        attributes,
+        span: node.span,
+    })
+}
+
+// Attributes are plain words (`mut`) or `key:value` pairs (`cap:50`)
+pub fn parse_attribute(source: &str) -> Result<Attribute, String> {
+    let source = source.trim();
+    let (key, value) = match source.split_once(':') {
+        Some((key, value)) => (key.trim(), Some(value.trim())),
+        None => (source, None),
+    };
+
+    match (key, value) {
+        ("mut", None) => Ok(Attribute::Mut),
+        ("init", None) => Ok(Attribute::Init),
+        ("init_if_needed", None) => Ok(Attribute::InitIfNeeded),
+        ("signer", None) => Ok(Attribute::Signer),
+        ("migrate", None) => Ok(Attribute::Migrate),
+        ("close", None) => Ok(Attribute::Close),
+        ("cap", value) => match value.and_then(|value| value.parse::<usize>().ok()) {
+            Some(cap) if cap > 0 => Ok(Attribute::Cap(cap)),
+            _ => Err(format!(
+                "`cap` expects a positive integer, found `{}`",
+                source
+            )),
+        },
+        ("role", Some("viewer")) => Ok(Attribute::Role(Role::Viewer)),
+        ("role", Some("contributor")) => Ok(Attribute::Role(Role::Contributor)),
+        ("role", Some("admin")) => Ok(Attribute::Role(Role::Admin)),
+        ("role", _) => Err(format!(
+            "`role` expects `viewer`, `contributor` or `admin`, found `{}`",
+            source
+        )),
+        ("", _) => Err("empty attribute".to_string()),
+        _ => Ok(Attribute::Other(source.to_string())),
+    }
+}
+
+pub fn parse_type_ref(source: &str) -> Result<TypeRef, String> {
+    let source = source.trim();
+
+    if let Some(inner) = strip_generic(source, "option") {
+        return Ok(TypeRef::Option(Box::new(parse_type_ref(inner)?)));
+    }
+    if let Some(inner) = strip_generic(source, "vec").or_else(|| strip_generic(source, "array")) {
+        return Ok(TypeRef::Vec(Box::new(parse_type_ref(inner)?)));
+    }
+
+    match source {
+        "bool" => Ok(TypeRef::Bool),
+        "u8" => Ok(TypeRef::U8),
+        "u16" => Ok(TypeRef::U16),
+        "u32" => Ok(TypeRef::U32),
+        "u64" => Ok(TypeRef::U64),
+        "u128" => Ok(TypeRef::U128),
+        "i8" => Ok(TypeRef::I8),
+        "i16" => Ok(TypeRef::I16),
+        "i32" => Ok(TypeRef::I32),
+        "i64" => Ok(TypeRef::I64),
+        "i128" => Ok(TypeRef::I128),
+        "f32" => Ok(TypeRef::F32),
+        "f64" => Ok(TypeRef::F64),
+        "string" => Ok(TypeRef::String),
+        "sol:pubkey" => Ok(TypeRef::Pubkey),
+        "sol:delegate" => Ok(TypeRef::Delegate),
+        "" => Err("empty type".to_string()),
+        _ if source.contains(|c: char| c.is_whitespace() || c == '<' || c == '>') => {
+            Err(format!("invalid type `{}`", source))
+        }
+        _ => Ok(TypeRef::Named(source.to_string())),
+    }
+}
+
+fn strip_generic<'a>(source: &'a str, name: &str) -> Option<&'a str> {
+    source
+        .strip_prefix(name)?
+        .strip_prefix('<')?
+        .strip_suffix('>')
+}
+
+fn required_type(
+    entries: &[Entry],
+    span: Span,
+    what: &str,
+) -> Result<Spanned<TypeRef>, ParseError> {
+    let ty = required_str(entries, span, "type", what)?;
+    parse_type_ref(&ty.value)
+        .map(|value| Spanned::new(value, ty.span))
+        .map_err(|message| ParseError::new(ty.span, message))
+}
+
+fn optional_type(entries: &[Entry]) -> Result<Option<Spanned<TypeRef>>, ParseError> {
+    match optional_str(entries, "type")? {
+        Some(ty) => parse_type_ref(&ty.value)
+            .map(|value| Some(Spanned::new(value, ty.span)))
+            .map_err(|message| ParseError::new(ty.span, message)),
+        None => Ok(None),
+    }
+}
+
+// Null values are treated as absent keys
+fn get<'a>(entries: &'a [Entry], key: &str) -> Option<&'a Spanned<Node>> {
+    entries
+        .iter()
+        .find(|(k, _)| k.value == key)
+        .map(|(_, v)| v)
+        .filter(|v| v.value != Node::Null)
+}
+
+fn required<'a>(
+    entries: &'a [Entry],
+    span: Span,
+    key: &str,
+    what: &str,
+) -> Result<&'a Spanned<Node>, ParseError> {
+    get(entries, key).ok_or_else(|| ParseError::new(span, format!("missing `{}` in {}", key, what)))
+}
+
+fn required_str(
+    entries: &[Entry],
+    span: Span,
+    key: &str,
+    what: &str,
+) -> Result<Spanned<String>, ParseError> {
+    expect_str(required(entries, span, key, what)?, &format!("`{}`", key))
+}
+
+fn optional_str(entries: &[Entry], key: &str) -> Result<Option<Spanned<String>>, ParseError> {
+    get(entries, key)
+        .map(|node| expect_str(node, &format!("`{}`", key)))
+        .transpose()
+}
+
+fn expect_map<'a>(node: &'a Spanned<Node>, what: &str) -> Result<&'a [Entry], ParseError> {
+    match &node.value {
+        Node::Map(entries) => Ok(entries),
+        other => Err(ParseError::new(
+            node.span,
+            format!("expected a mapping for {}, found a {}", what, other.kind()),
+        )),
+    }
+}
+
+fn expect_seq<'a>(node: &'a Spanned<Node>, what: &str) -> Result<&'a [Spanned<Node>], ParseError> {
+    match &node.value {
+        Node::Seq(items) => Ok(items),
+        other => Err(ParseError::new(
+            node.span,
+            format!("expected a sequence for {}, found a {}", what, other.kind()),
+        )),
+    }
+}
+
+fn expect_str(node: &Spanned<Node>, what: &str) -> Result<Spanned<String>, ParseError> {
+    match &node.value {
+        Node::Scalar(value, _) => Ok(Spanned::new(value.clone(), node.span)),
+        other => Err(ParseError::new(
+            node.span,
+            format!("expected a scalar for {}, found a {}", what, other.kind()),
+        )),
+    }
+}
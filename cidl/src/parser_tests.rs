@@ -0,0 +1,361 @@
+use crate::model::*;
+use crate::parser::*;
+use crate::yaml::{Span, MAX_NODES};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BUDGET_TRACKER: &str = include_str!("../../cidl.yaml");
+
+    fn parse_err(source: &str) -> (usize, usize, String) {
+        let error = parse(source).unwrap_err();
+        (error.line, error.col, error.message)
+    }
+
+    #[test]
+    fn it_parses_budget_tracker_info() {
+        let cidl = parse(BUDGET_TRACKER).unwrap();
+
+        assert_eq!(cidl.cidl.value, "0.8");
+        assert_eq!(cidl.info.name.value, "budget_tracker");
+        assert_eq!(cidl.info.title.unwrap().value, "Budget Tracker");
+        assert_eq!(cidl.info.version.unwrap().value, "0.0.1");
+        assert_eq!(
+            cidl.info.contact.unwrap().email.unwrap().value,
+            "support@codigo.ai"
+        );
+        assert_eq!(cidl.info.license.unwrap().name.unwrap().value, "MIT");
+    }
+
+    #[test]
+    fn it_parses_budget_tracker_types() {
+        let cidl = parse(BUDGET_TRACKER).unwrap();
+
+        assert_eq!(cidl.types.len(), 4);
+        let record = cidl.find_type("Record").unwrap();
+        assert_eq!(record.name.span, Span { line: 29, col: 3 });
+
+        let solana = record.solana.as_ref().unwrap();
+        assert_eq!(solana.owner.as_ref().unwrap().value, "self");
+        assert_eq!(solana.seeds.len(), 2);
+        assert_eq!(solana.seeds[0].name.value, "record");
+        assert_eq!(solana.seeds[0].ty, None);
+        assert_eq!(solana.seeds[1].name.value, "signer");
+        assert_eq!(solana.seeds[1].ty.as_ref().unwrap().value, TypeRef::Pubkey);
+        assert_eq!(
+            solana.seeds[1].ty.as_ref().unwrap().span,
+            Span { line: 36, col: 17 }
+        );
+
+        let fields: Vec<(&str, TypeRef)> = record
+            .fields
+            .iter()
+            .map(|field| (field.name.value.as_str(), field.ty.value.clone()))
+            .collect();
+        assert_eq!(
+            fields,
+            vec![
+                ("version", TypeRef::U8),
+                ("name", TypeRef::String),
+                ("moves", TypeRef::U16),
+                ("outcome", TypeRef::U32),
+                ("income", TypeRef::U32),
+                ("total_balance", TypeRef::I64),
+                ("delegates", TypeRef::Vec(Box::new(TypeRef::Delegate))),
+                ("mint", TypeRef::Pubkey),
+                ("vault", TypeRef::Pubkey),
+            ]
+        );
+
+        let name = record.find_field("name").unwrap();
+        assert_eq!(name.span, Span { line: 41, col: 9 });
+        assert_eq!(name.cap(), Some(50));
+        assert_eq!(
+            name.solana.as_ref().unwrap().attributes[0].span,
+            Span { line: 44, col: 25 }
+        );
+        assert_eq!(record.find_field("moves").unwrap().cap(), None);
+        assert_eq!(record.find_field("delegates").unwrap().cap(), Some(5));
+
+        let vault = cidl.find_type("Vault").unwrap().solana.as_ref().unwrap();
+        assert_eq!(vault.owner.as_ref().unwrap().value, "spl-token-account");
+        assert!(vault.is_token_account());
+        assert!(cidl.find_type("Mint").unwrap().is_token_program());
+    }
+
+    #[test]
+    fn it_parses_budget_tracker_methods() {
+        let cidl = parse(BUDGET_TRACKER).unwrap();
+
+        let names: Vec<&str> = cidl
+            .methods
+            .iter()
+            .map(|method| method.name.value.as_str())
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                "create_user_record",
+                "register_income",
+                "register_outcome",
+                "add_delegate",
+                "remove_delegate",
+                "update_user_name",
+                "migrate_record",
+                "close_record",
+                "bind_mint",
+                "register_token_income",
+                "register_token_outcome"
+            ]
+        );
+
+        let create = cidl.find_method("create_user_record").unwrap();
+        assert_eq!(create.span, Span { line: 86, col: 5 });
+        assert_eq!(create.inputs.len(), 2);
+        assert_eq!(
+            create.inputs[0].ty.value,
+            TypeRef::Named("Record".to_string())
+        );
+        assert!(create.inputs[0].has_attribute(&Attribute::Mut));
+        assert!(create.inputs[0].has_attribute(&Attribute::InitIfNeeded));
+        assert_eq!(create.inputs[1].ty.value, TypeRef::String);
+        assert!(create.inputs[1].solana.is_none());
+
+        let income = cidl.find_method("register_income").unwrap();
+        assert!(income.inputs[0].has_attribute(&Attribute::Mut));
+        assert!(!income.inputs[0].has_attribute(&Attribute::InitIfNeeded));
+        assert!(income.inputs[0].has_attribute(&Attribute::Role(Role::Contributor)));
+        assert_eq!(income.inputs[1].ty.value, TypeRef::U32);
+        assert_eq!(
+            income.inputs[1].description.as_ref().unwrap().value,
+            "The amount to be registered as the income."
+        );
+
+        let add_delegate = cidl.find_method("add_delegate").unwrap();
+        assert!(add_delegate.inputs[0].has_attribute(&Attribute::Role(Role::Admin)));
+        assert_eq!(add_delegate.inputs[1].ty.value, TypeRef::Delegate);
+    }
+
+    #[test]
+    fn it_parses_type_refs() {
+        assert_eq!(parse_type_ref("u128"), Ok(TypeRef::U128));
+        assert_eq!(parse_type_ref("sol:pubkey"), Ok(TypeRef::Pubkey));
+        assert_eq!(parse_type_ref("sol:delegate"), Ok(TypeRef::Delegate));
+        assert_eq!(
+            parse_type_ref("option<vec<string>>"),
+            Ok(TypeRef::Option(Box::new(TypeRef::Vec(Box::new(
+                TypeRef::String
+            )))))
+        );
+        assert_eq!(
+            parse_type_ref("array<Record>"),
+            Ok(TypeRef::Vec(Box::new(TypeRef::Named("Record".to_string()))))
+        );
+        assert!(parse_type_ref("vec<u8").is_err());
+        assert!(parse_type_ref("option<>").is_err());
+        assert!(parse_type_ref("u 8").is_err());
+    }
+
+    #[test]
+    fn it_type_refs_round_trip_through_display() {
+        for source in [
+            "bool",
+            "i128",
+            "f64",
+            "string",
+            "sol:pubkey",
+            "option<u8>",
+            "vec<option<sol:pubkey>>",
+            "vec<sol:delegate>",
+            "Record",
+        ] {
+            assert_eq!(parse_type_ref(source).unwrap().to_string(), source);
+        }
+    }
+
+    #[test]
+    fn it_parses_attributes() {
+        assert_eq!(parse_attribute("mut"), Ok(Attribute::Mut));
+        assert_eq!(
+            parse_attribute(" init_if_needed "),
+            Ok(Attribute::InitIfNeeded)
+        );
+        assert_eq!(parse_attribute("migrate"), Ok(Attribute::Migrate));
+        assert_eq!(parse_attribute("close"), Ok(Attribute::Close));
+        assert_eq!(parse_attribute("cap:50"), Ok(Attribute::Cap(50)));
+        assert_eq!(parse_attribute("cap: 7"), Ok(Attribute::Cap(7)));
+        assert_eq!(
+            parse_attribute("role:contributor"),
+            Ok(Attribute::Role(Role::Contributor))
+        );
+        assert_eq!(
+            parse_attribute("space:10"),
+            Ok(Attribute::Other("space:10".to_string()))
+        );
+        assert!(parse_attribute("cap").is_err());
+        assert!(parse_attribute("cap:0").is_err());
+        assert!(parse_attribute("cap:-1").is_err());
+        assert!(parse_attribute("cap:ten").is_err());
+        assert_eq!(
+            parse_attribute("role:owner"),
+            Err(
+                "`role` expects `viewer`, `contributor` or `admin`, found `role:owner`".to_string()
+            )
+        );
+        assert!(parse_attribute("").is_err());
+    }
+
+    #[test]
+    fn it_reports_invalid_cap_location() {
+        let source = "cidl: \"0.8\"
+info:
+  name: test
+types:
+  Note:
+    fields:
+      - name: text
+        type: string
+        solana:
+          attributes: [ mut, cap:abc ]
+";
+        let (line, col, message) = parse_err(source);
+        assert_eq!((line, col), (10, 30));
+        assert!(message.contains("cap:abc"), "{}", message);
+    }
+
+    #[test]
+    fn it_reports_yaml_syntax_errors() {
+        let source = "cidl: \"0.8\"
+info:
+  name: test
+methods:
+  - name: broken
+    inputs: [ a, b
+";
+        let (line, _, message) = parse_err(source);
+        assert_eq!(line, 7);
+        assert!(!message.is_empty());
+    }
+
+    #[test]
+    fn it_reports_missing_keys() {
+        let (line, col, message) = parse_err("cidl: \"0.8\"\ninfo:\n  title: test\n");
+        assert_eq!((line, col), (3, 3));
+        assert_eq!(message, "missing `name` in `info`");
+
+        let (line, col, message) = parse_err("info:\n  name: test\n");
+        assert_eq!((line, col), (1, 1));
+        assert_eq!(message, "missing `cidl` in the CIDL document");
+
+        let source = "cidl: \"0.8\"
+info:
+  name: test
+methods:
+  - name: run
+    inputs:
+      - name: amount
+";
+        let (line, col, message) = parse_err(source);
+        assert_eq!((line, col), (7, 9));
+        assert_eq!(message, "missing `type` in an input");
+    }
+
+    #[test]
+    fn it_reports_wrong_node_kinds() {
+        let source = "cidl: \"0.8\"
+info:
+  name: test
+types:
+  - Record
+";
+        let (line, col, message) = parse_err(source);
+        assert_eq!((line, col), (5, 3));
+        assert_eq!(message, "expected a mapping for `types`, found a sequence");
+    }
+
+    #[test]
+    fn it_reports_duplicate_keys() {
+        let source = "cidl: \"0.8\"
+info:
+  name: test
+  name: again
+";
+        let (line, col, message) = parse_err(source);
+        assert_eq!((line, col), (4, 3));
+        assert_eq!(message, "duplicate key `name`");
+    }
+
+    #[test]
+    fn it_accepts_empty_sections() {
+        let cidl = parse("cidl: \"0.8\"\ninfo:\n  name: test\ntypes:\nmethods:\n").unwrap();
+
+        assert!(cidl.types.is_empty());
+        assert!(cidl.methods.is_empty());
+        assert!(cidl.info.title.is_none());
+    }
+
+    #[test]
+    fn it_resolves_aliases() {
+        let source = "cidl: \"0.8\"
+info:
+  name: test
+types:
+  Pair:
+    fields:
+      - &amount
+        name: amount
+        type: u64
+methods:
+  - name: run
+    inputs:
+      - *amount
+";
+        let cidl = parse(source).unwrap();
+
+        assert_eq!(cidl.methods[0].inputs[0].name.value, "amount");
+        assert_eq!(cidl.methods[0].inputs[0].ty.value, TypeRef::U64);
+        assert_eq!(cidl.methods[0].inputs[0].span.line, 13);
+    }
+
+    #[test]
+    fn it_limits_the_expansion_of_aliases() {
+        // Each level holds ten copies of the previous one, 10^9 nodes at the last one
+        let mut source = String::from("a0: &a0 [x]\n");
+        for level in 1..10 {
+            let copies = vec![format!("*a{}", level - 1); 10].join(", ");
+            source += &format!("a{}: &a{} [{}]\n", level, level, copies);
+        }
+
+        let (line, _, message) = parse_err(&source);
+        assert_eq!(line, 6);
+        assert_eq!(
+            message,
+            format!(
+                "the document has more than {} nodes once its aliases are expanded",
+                MAX_NODES
+            )
+        );
+    }
+
+    #[test]
+    fn it_parses_imports() {
+        let source = "cidl: \"0.8\"
+info:
+  name: test
+imports:
+  - units.yaml
+  - path: ../shared/common.yaml
+    as: common
+";
+        let cidl = parse(source).unwrap();
+
+        assert_eq!(cidl.imports.len(), 2);
+        assert_eq!(cidl.imports[0].path.value, "units.yaml");
+        assert!(cidl.imports[0].namespace.is_none());
+        assert_eq!(cidl.imports[1].path.value, "../shared/common.yaml");
+        assert_eq!(cidl.imports[1].namespace.as_ref().unwrap().value, "common");
+        assert_eq!(cidl.imports[1].span, Span { line: 6, col: 5 });
+        assert!(parse("cidl: \"0.8\"\ninfo:\n  name: test\nimports:\n  - as: common\n").is_err());
+    }
+}
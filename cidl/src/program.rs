@@ -0,0 +1,1199 @@
+use crate::generator::*;
+use crate::model::*;
+use crate::validator::{has_errors, validate, Diagnostic};
+
+pub const STUB_PLACEHOLDER: &str = "// Place your custom code here...";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProgramOptions {
+    // Path of the codigolib crate, relative to the generated program
+    pub codigolib_path: String,
+}
+
+impl Default for ProgramOptions {
+    fn default() -> ProgramOptions {
+        ProgramOptions {
+            codigolib_path: "../codigolib".to_string(),
+        }
+    }
+}
+
+// Renders the native program crate: a Cargo.toml plus rendered/ with the state, instructions,
+// processor and one stub per method. Paths are relative to the program directory
+pub fn generate_program(
+    cidl: &Cidl,
+    options: &ProgramOptions,
+) -> Result<Vec<GeneratedFile>, Vec<Diagnostic>> {
+    let diagnostics = validate(cidl);
+    if has_errors(&diagnostics) {
+        return Err(diagnostics);
+    }
+
+    let methods: Vec<_> = cidl
+        .methods
+        .iter()
+        .map(|method| MethodModel::new(cidl, method))
+        .collect();
+
+    let mut files = vec![
+        GeneratedFile::generated("Cargo.toml", render_cargo_toml(cidl, options)),
+        GeneratedFile::generated("rendered/lib.rs", render_lib()),
+        GeneratedFile::generated("rendered/entrypoint.rs", render_entrypoint()),
+        GeneratedFile::generated("rendered/state.rs", render_state(cidl)),
+        GeneratedFile::generated(
+            "rendered/instructions.rs",
+            render_instructions(cidl, &methods),
+        ),
+        GeneratedFile::generated("rendered/processor.rs", render_processor(cidl, &methods)),
+        GeneratedFile::generated("rendered/stubs/mod.rs", render_stubs_mod(&methods)),
+    ];
+    for method in &methods {
+        files.push(GeneratedFile::stub(
+            format!("rendered/stubs/{}.rs", method.name),
+            render_stub(method),
+        ));
+    }
+
+    Ok(files)
+}
+
+pub fn instruction_enum_name(cidl: &Cidl) -> String {
+    format!("{}Instruction", pascal_case(&cidl.info.name.value))
+}
+
+// An input whose type has a `solana` extension, it is passed as an account instead of data
+pub struct AccountModel<'a> {
+    pub input: &'a Input,
+    pub ty: &'a TypeDef,
+    pub solana: &'a SolanaType,
+}
+
+impl<'a> AccountModel<'a> {
+    pub fn name(&self) -> &str {
+        &self.input.name.value
+    }
+
+    pub fn is_mut(&self) -> bool {
+        self.input.has_attribute(&Attribute::Mut)
+    }
+
+    pub fn is_signer(&self) -> bool {
+        self.input.has_attribute(&Attribute::Signer)
+    }
+
+    // Accounts without an explicit owner belong to the program
+    pub fn is_owned(&self) -> bool {
+        self.solana
+            .owner
+            .as_ref()
+            .is_none_or(|owner| owner.value == "self")
+    }
+
+    pub fn is_init(&self) -> bool {
+        self.input.has_attribute(&Attribute::Init)
+            || self.input.has_attribute(&Attribute::InitIfNeeded)
+    }
+
+    pub fn is_init_if_needed(&self) -> bool {
+        self.input.has_attribute(&Attribute::InitIfNeeded)
+    }
+
+    // The stub receives the AccountInfo to rewrite it, its current layout can't be loaded
+    pub fn is_migrate(&self) -> bool {
+        self.input.has_attribute(&Attribute::Migrate)
+    }
+
+    // The stub reads the account one last time, it is not stored back but closed
+    pub fn is_close(&self) -> bool {
+        self.input.has_attribute(&Attribute::Close)
+    }
+
+    pub fn is_token_account(&self) -> bool {
+        self.solana.is_token_account()
+    }
+
+    pub fn is_mint(&self) -> bool {
+        self.solana.is_mint()
+    }
+
+    // The token program owns the data, the stub receives the AccountInfo and nothing is stored
+    pub fn is_token_program(&self) -> bool {
+        self.solana.is_token_program()
+    }
+
+    // A token account at a PDA of the program, its own authority. The stub receives it with the
+    // seeds signing the transfers out of it
+    pub fn is_token_pda(&self) -> bool {
+        self.is_token_account() && !self.solana.seeds.is_empty()
+    }
+
+    pub fn seed_arg(&self, seed: &Seed) -> String {
+        format!("{}_seed_{}", self.name(), seed.name.value)
+    }
+
+    // The first `sol:pubkey` seed, the wallet the account belongs to
+    pub fn owner_seed(&self) -> Option<&'a Seed> {
+        self.solana.seeds.iter().find(|seed| {
+            seed.ty
+                .as_ref()
+                .is_some_and(|ty| ty.value == TypeRef::Pubkey)
+        })
+    }
+
+    // Writes to an account with an owner must be signed by an authority, passed after the
+    // accounts of the method. The token program checks who moves the tokens of its accounts
+    pub fn has_authority(&self) -> bool {
+        self.is_mut() && self.owner_seed().is_some() && !self.is_token_program()
+    }
+
+    pub fn authority(&self) -> String {
+        format!("{}_authority", self.name())
+    }
+
+    // Delegates with this role may sign instead of the owner, looked up in `delegates_field`
+    pub fn role(&self) -> Option<Role> {
+        self.input.role()
+    }
+
+    pub fn delegates_field(&self) -> Option<&'a Field> {
+        let delegates = TypeRef::Vec(Box::new(TypeRef::Delegate));
+        self.ty
+            .fields
+            .iter()
+            .find(|field| field.ty.value == delegates)
+    }
+}
+
+// Instruction data, method inputs first and then the seeds of every account, as packed by the sdk
+pub struct ArgModel {
+    pub name: String,
+    pub ty: TypeRef,
+    pub cap: Option<usize>,
+    pub description: Option<String>,
+}
+
+pub struct MethodModel<'a> {
+    pub name: &'a str,
+    pub method: &'a Method,
+    pub accounts: Vec<AccountModel<'a>>,
+    pub inputs: Vec<ArgModel>,
+    pub args: Vec<ArgModel>,
+}
+
+impl<'a> MethodModel<'a> {
+    pub fn new(cidl: &'a Cidl, method: &'a Method) -> MethodModel<'a> {
+        let mut accounts = Vec::new();
+        let mut inputs = Vec::new();
+
+        for input in &method.inputs {
+            let account = match &input.ty.value {
+                TypeRef::Named(name) => cidl.find_type(name).and_then(|ty| {
+                    ty.solana
+                        .as_ref()
+                        .map(|solana| AccountModel { input, ty, solana })
+                }),
+                _ => None,
+            };
+            match account {
+                Some(account) => accounts.push(account),
+                None => inputs.push(ArgModel {
+                    name: input.name.value.clone(),
+                    ty: input.ty.value.clone(),
+                    cap: input.cap(),
+                    description: input.description.as_ref().map(|d| d.value.clone()),
+                }),
+            }
+        }
+
+        let mut args: Vec<_> = inputs
+            .iter()
+            .map(|input| ArgModel {
+                name: input.name.clone(),
+                ty: input.ty.clone(),
+                cap: input.cap,
+                description: input.description.clone(),
+            })
+            .collect();
+        for account in &accounts {
+            for seed in &account.solana.seeds {
+                if let Some(ty) = &seed.ty {
+                    args.push(ArgModel {
+                        name: account.seed_arg(seed),
+                        ty: ty.value.clone(),
+                        cap: None,
+                        description: seed.description.as_ref().map(|d| d.value.clone()),
+                    });
+                }
+            }
+        }
+
+        MethodModel {
+            name: &method.name.value,
+            method,
+            accounts,
+            inputs,
+            args,
+        }
+    }
+
+    pub fn variant(&self) -> String {
+        pascal_case(self.name)
+    }
+
+    pub fn args_struct(&self) -> String {
+        format!("{}Args", self.variant())
+    }
+
+    pub fn authorities(&self) -> impl Iterator<Item = &AccountModel<'a>> {
+        self.accounts
+            .iter()
+            .filter(|account| account.has_authority())
+    }
+
+    // Accounts created by the method are paid by a signing fee payer through the system program
+    pub fn creates_accounts(&self) -> bool {
+        self.accounts.iter().any(AccountModel::is_init)
+    }
+
+    pub fn migrates_accounts(&self) -> bool {
+        self.accounts.iter().any(AccountModel::is_migrate)
+    }
+
+    pub fn closes_accounts(&self) -> bool {
+        self.accounts.iter().any(AccountModel::is_close)
+    }
+
+    // Created accounts and the rent of migrated ones are paid by the fee payer, passed with the
+    // system program after the authorities
+    pub fn pays_rent(&self) -> bool {
+        self.creates_accounts() || self.migrates_accounts()
+    }
+
+    // The fee payer also receives the lamports of closed accounts, without the system program
+    pub fn has_fee_payer(&self) -> bool {
+        self.pays_rent() || self.closes_accounts()
+    }
+
+    // The token program is passed after the system program, to create token accounts or for the
+    // stub to move tokens
+    pub fn uses_token_program(&self) -> bool {
+        self.accounts.iter().any(AccountModel::is_token_program)
+    }
+
+    // The stub moves tokens out of existing token accounts, signed by the authorities of the
+    // method or with the seeds of the token PDAs, so it receives them and the token program
+    pub fn moves_tokens(&self) -> bool {
+        self.accounts
+            .iter()
+            .any(|account| account.is_token_account() && account.is_mut() && !account.is_init())
+    }
+
+    // The mint of the token accounts the method creates, there is one when it creates any
+    pub fn mint(&self) -> Option<&AccountModel<'a>> {
+        self.accounts.iter().find(|account| account.is_mint())
+    }
+}
+
+fn render_cargo_toml(cidl: &Cidl, options: &ProgramOptions) -> String {
+    let version = cidl
+        .info
+        .version
+        .as_ref()
+        .map_or("0.1.0", |version| &version.value);
+
+    format!(
+        r#"[package]
+name = "{}"
+version = "{}"
+edition = "2021"
+
+[lib]
+crate-type = ["cdylib", "lib"]
+path = "rendered/lib.rs"
+
+[features]
+no-entrypoint = []
+
+[dependencies]
+codigolib = {{ path = "{}" }}
+solana-program = "~1.14.17"
+{}
+# Used by solana_program's entrypoint! macro
+[lints.rust]
+unexpected_cfgs = {{ level = "warn", check-cfg = ['cfg(target_os, values("solana"))', 'cfg(feature, values("custom-heap", "custom-panic"))'] }}
+"#,
+        cidl.info.name.value,
+        version,
+        options.codigolib_path,
+        library_dependencies(cidl)
+    )
+}
+
+fn render_lib() -> String {
+    format!(
+        "{}
+#[cfg(not(feature = \"no-entrypoint\"))]
+mod entrypoint;
+pub mod instructions;
+pub mod processor;
+pub mod state;
+pub mod stubs;
+",
+        GENERATED_HEADER
+    )
+}
+
+fn render_entrypoint() -> String {
+    format!(
+        "{}
+use crate::processor::process_instruction;
+use solana_program::entrypoint;
+
+entrypoint!(process_instruction);
+",
+        GENERATED_HEADER
+    )
+}
+
+fn render_state(cidl: &Cidl) -> String {
+    let mut out = String::from(GENERATED_HEADER);
+    out += "\n";
+    if types_use_delegate(cidl) {
+        out += DELEGATE_USE;
+    }
+    out += "use codigolib::rust_utils::*;\nuse solana_program::account_info::AccountInfo;\n";
+    out += "use solana_program::program_error::ProgramError;\n";
+    if types_use_pubkey(cidl) {
+        out += "use solana_program::pubkey::Pubkey;\n";
+    }
+
+    out += "\n";
+    out += STATE_USE;
+    out += "
+// An account owned by the program together with its unpacked data, stubs update `data` and the
+// processor stores it back once they return
+pub struct AccountPDA<'a, 'b, T: State> {
+    pub info: &'a AccountInfo<'b>,
+    pub data: T,
+}
+
+impl<'a, 'b, T: State> AccountPDA<'a, 'b, T> {
+    pub fn new(info: &'a AccountInfo<'b>, data: T) -> Self {
+        AccountPDA { info, data }
+    }
+
+    // The data was checked to have the length of T, the value of every field is checked here
+    pub fn load(info: &'a AccountInfo<'b>) -> Result<Self, ProgramError> {
+        let (_, data) = T::try_unpack(&info.data.borrow())?;
+        Ok(AccountPDA { info, data })
+    }
+
+    pub fn store(&self) {
+        self.data.pack(&mut self.info.data.borrow_mut());
+    }
+}
+";
+
+    out += &render_type_defs(cidl);
+
+    out
+}
+
+fn render_instructions(cidl: &Cidl, methods: &[MethodModel]) -> String {
+    let enum_name = instruction_enum_name(cidl);
+    let mut out = String::from(GENERATED_HEADER);
+    out += "\n";
+    if methods
+        .iter()
+        .flat_map(|method| &method.args)
+        .any(|arg| uses_own_type(cidl, &arg.ty))
+    {
+        out += "use crate::state::*;\n";
+    }
+    if methods
+        .iter()
+        .flat_map(|method| &method.args)
+        .any(|arg| uses_delegate(&arg.ty))
+    {
+        out += DELEGATE_USE;
+    }
+    out += "use codigolib::rust_utils::*;\n";
+    out += "use solana_program::program_error::ProgramError;\n";
+    if methods
+        .iter()
+        .flat_map(|method| &method.args)
+        .any(|arg| uses_pubkey(&arg.ty))
+    {
+        out += "use solana_program::pubkey::Pubkey;\n";
+    }
+
+    out += &format!("\npub enum {} {{\n", enum_name);
+    for method in methods {
+        out += &format!("    {}({}),\n", method.variant(), method.args_struct());
+    }
+    out += "}\n";
+
+    for method in methods {
+        out += "\n#[derive(Clone, Debug, Default, PartialEq)]\n";
+        if method.args.is_empty() {
+            out += &format!("pub struct {} {{}}\n", method.args_struct());
+            continue;
+        }
+        out += &format!("pub struct {} {{\n", method.args_struct());
+        for arg in &method.args {
+            out += &format!("    pub {}: {},\n", arg.name, rust_type(&arg.ty));
+        }
+        out += "}\n";
+    }
+
+    let buf = if methods.iter().any(|method| !method.args.is_empty()) {
+        "buf"
+    } else {
+        "_buf"
+    };
+    out += &format!(
+        "
+impl {} {{
+    // The first byte is the position of the method in the CIDL, followed by the method inputs and
+    // the seeds of its accounts
+    pub fn unpack(data: &[u8]) -> Result<Self, ProgramError> {{
+        let (tag, {}) = data
+            .split_first()
+            .ok_or(ProgramError::InvalidInstructionData)?;
+
+        match tag {{
+",
+        enum_name, buf
+    );
+    for (i, method) in methods.iter().enumerate() {
+        let names: Vec<_> = method.args.iter().map(|arg| arg.name.as_str()).collect();
+        let value = format!(
+            "Self::{}({})",
+            method.variant(),
+            struct_literal(&method.args_struct(), &names, 16)
+        );
+        if method.args.is_empty() {
+            out += &format!("            {} => Ok({}),\n", i, value);
+            continue;
+        }
+
+        out += &format!("            {} => {{\n", i);
+        for (j, arg) in method.args.iter().enumerate() {
+            let rest = if j + 1 == method.args.len() {
+                "_"
+            } else {
+                "buf"
+            };
+            out += &format!(
+                "                let ({}, {}) = {}?;\n",
+                rest,
+                arg.name,
+                try_unpack_call(cidl, &arg.ty, arg.cap, Layout::Instruction, "buf")
+            );
+        }
+        out += &format!("                Ok({})\n            }}\n", value);
+    }
+    out += "            _ => Err(ProgramError::InvalidInstructionData),
+        }
+    }
+}
+";
+
+    out
+}
+
+fn render_processor(cidl: &Cidl, methods: &[MethodModel]) -> String {
+    let enum_name = instruction_enum_name(cidl);
+    let creates_accounts = methods.iter().any(MethodModel::creates_accounts);
+    let pays_rent = methods.iter().any(MethodModel::pays_rent);
+    let closes_accounts = methods.iter().any(MethodModel::closes_accounts);
+    let derives_addresses = methods
+        .iter()
+        .flat_map(|method| &method.accounts)
+        .any(|account| {
+            (account.is_init() || account.is_token_pda()) && !account.solana.seeds.is_empty()
+        });
+    let uses_token_program = methods.iter().any(MethodModel::uses_token_program);
+
+    let checks_roles = methods
+        .iter()
+        .flat_map(|method| &method.accounts)
+        .any(|account| account.role().is_some());
+
+    let mut out = String::from(GENERATED_HEADER);
+    out += "\nuse crate::instructions::*;\nuse crate::state::*;\nuse crate::stubs;\n";
+    if checks_roles {
+        out += "use codigolib::delegate_utils::DelegateRole;\n";
+    }
+    match (closes_accounts, derives_addresses) {
+        (true, true) => out += "use codigolib::errors::{MathError, SecurityError};\n",
+        (true, false) => out += "use codigolib::errors::MathError;\n",
+        (false, true) => out += "use codigolib::errors::SecurityError;\n",
+        (false, false) => {}
+    }
+    out += "use codigolib::sec_utils::*;\n";
+    if uses_token_program {
+        out += "use codigolib::token_utils::*;\n";
+    }
+    out += "use solana_program::account_info::{next_account_info, AccountInfo};
+use solana_program::entrypoint::ProgramResult;
+";
+    if creates_accounts {
+        out += "use solana_program::program::{invoke, invoke_signed};\n";
+    }
+    if pays_rent {
+        out += "use solana_program::program_error::ProgramError;\n";
+    }
+    out += "use solana_program::pubkey::Pubkey;\n";
+    if creates_accounts {
+        out += "use solana_program::rent::Rent;\nuse solana_program::system_instruction;\n";
+    }
+    if pays_rent || closes_accounts {
+        out += "use solana_program::system_program;\n";
+    }
+    if creates_accounts {
+        out += "use solana_program::sysvar::Sysvar;\n";
+    }
+
+    out += "
+pub fn process_instruction(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+";
+    out += &format!("    match {}::unpack(data)? {{\n", enum_name);
+    for method in methods {
+        let arm = format!("        {}::{}(args) =>", enum_name, method.variant());
+        let call = format!("process_{}(program_id, accounts, args)", method.name);
+        if arm.len() + call.len() + 2 <= 100 {
+            out += &format!("{} {},\n", arm, call);
+        } else {
+            out += &format!("{} {{\n            {}\n        }}\n", arm, call);
+        }
+    }
+    out += "    }\n}\n";
+
+    for method in methods {
+        out += &render_process_method(method);
+    }
+
+    if creates_accounts {
+        out += "
+// Creates `account` for `owner` with enough lamports to be rent exempt, PDAs sign with their
+// seeds. Anyone can send lamports to the address beforehand, which makes the system program refuse
+// to create it, so a funded account is topped up, allocated and assigned instead
+fn create_account<'a>(
+    owner: &Pubkey,
+    payer: &AccountInfo<'a>,
+    account: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>,
+    len: usize,
+    signer_seeds: &[&[&[u8]]],
+) -> ProgramResult {
+    let lamports = Rent::get()?.minimum_balance(len);
+
+    if account.lamports() == 0 {
+        return invoke_signed(
+            &system_instruction::create_account(
+                payer.key,
+                account.key,
+                lamports,
+                len as u64,
+                owner,
+            ),
+            &[payer.clone(), account.clone(), system_program.clone()],
+            signer_seeds,
+        );
+    }
+
+    let missing = lamports.saturating_sub(account.lamports());
+    if missing > 0 {
+        invoke(
+            &system_instruction::transfer(payer.key, account.key, missing),
+            &[payer.clone(), account.clone(), system_program.clone()],
+        )?;
+    }
+    invoke_signed(
+        &system_instruction::allocate(account.key, len as u64),
+        &[account.clone(), system_program.clone()],
+        signer_seeds,
+    )?;
+    invoke_signed(
+        &system_instruction::assign(account.key, owner),
+        &[account.clone(), system_program.clone()],
+        signer_seeds,
+    )
+}
+";
+    }
+
+    if closes_accounts {
+        out += "
+// Moves the lamports of `account` to `recipient` and gives the emptied account back to the
+// system program, the runtime deletes it at the end of the transaction
+fn close_account(account: &AccountInfo, recipient: &AccountInfo) -> ProgramResult {
+    let lamports = recipient
+        .lamports()
+        .checked_add(account.lamports())
+        .ok_or(MathError::Overflow)?;
+    **recipient.lamports.borrow_mut() = lamports;
+    **account.lamports.borrow_mut() = 0;
+
+    account.realloc(0, false)?;
+    account.assign(&system_program::id());
+
+    Ok(())
+}
+";
+    }
+
+    out
+}
+
+fn seed_expr(account: &AccountModel, seed: &Seed) -> String {
+    let arg = format!("args.{}", account.seed_arg(seed));
+    match seed.ty.as_ref().map(|ty| &ty.value) {
+        None => format!("b\"{}\"", seed.name.value),
+        Some(TypeRef::Pubkey) => format!("{}.as_ref()", arg),
+        Some(TypeRef::String) => format!("{}.as_bytes()", arg),
+        Some(_) => format!("&{}.to_le_bytes()", arg),
+    }
+}
+
+fn render_process_method(method: &MethodModel) -> String {
+    let has_accounts = !method.accounts.is_empty();
+    let uses_program_id = method
+        .accounts
+        .iter()
+        .any(|account| account.is_owned() || account.is_init() || !account.solana.seeds.is_empty());
+    let params = [
+        format!(
+            "{}program_id: &Pubkey",
+            if uses_program_id { "" } else { "_" }
+        ),
+        format!(
+            "{}accounts: &[AccountInfo]",
+            if has_accounts { "" } else { "_" }
+        ),
+        format!(
+            "{}args: {}",
+            if method.args.is_empty() { "_" } else { "" },
+            method.args_struct()
+        ),
+    ];
+
+    let mut out = String::from("\n");
+    out += &signature(
+        &format!("fn process_{}", method.name),
+        &params,
+        "ProgramResult",
+        0,
+    );
+    if has_accounts {
+        out += "    let account_info_iter = &mut accounts.iter();\n";
+    }
+    for account in &method.accounts {
+        out += &format!(
+            "    let {}_info = next_account_info(account_info_iter)?;\n",
+            account.name()
+        );
+    }
+    for account in method.authorities() {
+        out += &format!(
+            "    let {}_info = next_account_info(account_info_iter)?;\n",
+            account.authority()
+        );
+    }
+    if method.pays_rent() {
+        out += "    let system_program_info = next_account_info(account_info_iter)?;\n";
+    }
+    if method.uses_token_program() {
+        out += "    let token_program_info = next_account_info(account_info_iter)?;\n";
+    }
+    if method.has_fee_payer() {
+        out += "    let fee_payer_info = next_account_info(account_info_iter)?;\n";
+    }
+
+    // Accounts created without seeds are keypairs, they have to sign their own creation
+    let mut signers: Vec<_> = method
+        .accounts
+        .iter()
+        .filter(|account| {
+            account.is_signer() || (account.is_init() && account.solana.seeds.is_empty())
+        })
+        .map(|account| format!("{}_info", account.name()))
+        .collect();
+    signers.extend(
+        method
+            .authorities()
+            .map(|account| format!("{}_info", account.authority())),
+    );
+    if method.has_fee_payer() {
+        signers.push("fee_payer_info".to_string());
+    }
+    if !signers.is_empty() || method.pays_rent() || method.uses_token_program() {
+        out += "\n";
+    }
+    if !signers.is_empty() {
+        out += &format!("    verify_signers(&[{}])?;\n", signers.join(", "));
+    }
+    if method.pays_rent() {
+        out += "    if *system_program_info.key != system_program::id() {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+";
+    }
+    if method.uses_token_program() {
+        out += "    verify_token_program(token_program_info)?;\n";
+    }
+
+    for account in &method.accounts {
+        if account.is_token_program() {
+            out += &render_token_account(method, account);
+        } else {
+            out += &render_account(account);
+        }
+    }
+
+    let mut stub_args = Vec::new();
+    if method.migrates_accounts() {
+        stub_args.push("program_id".to_string());
+    }
+    stub_args.extend(method.accounts.iter().map(|account| {
+        if account.is_migrate() || (account.is_token_program() && !account.is_token_pda()) {
+            format!("{}_info", account.name())
+        } else if account.is_token_pda() {
+            format!("&{}", account.name())
+        } else if account.is_mut() && !account.is_close() {
+            format!("&mut {}", account.name())
+        } else {
+            format!("&{}", account.name())
+        }
+    }));
+    if method.migrates_accounts() {
+        stub_args.push("fee_payer_info".to_string());
+        stub_args.push("system_program_info".to_string());
+    }
+    if method.moves_tokens() {
+        stub_args.extend(
+            method
+                .authorities()
+                .map(|account| format!("{}_info", account.authority())),
+        );
+        stub_args.push("token_program_info".to_string());
+    }
+    stub_args.extend(
+        method
+            .inputs
+            .iter()
+            .map(|input| format!("args.{}", input.name)),
+    );
+    // The stub initializes the accounts, it is not run again once they all exist
+    let existing: Vec<_> = method
+        .accounts
+        .iter()
+        .filter(|account| account.is_init_if_needed())
+        .map(|account| format!("{}_exists", account.name()))
+        .collect();
+    if !existing.is_empty() {
+        out += &format!(
+            "\n    if {} {{\n        return Ok(());\n    }}\n",
+            existing.join(" && ")
+        );
+    }
+
+    let call = format!(
+        "stubs::{}::{}({})?;",
+        method.name,
+        method.name,
+        stub_args.join(", ")
+    );
+    if has_accounts {
+        out += "\n";
+    }
+    if call.len() + 4 <= 100 {
+        out += &format!("    {}\n", call);
+    } else {
+        out += &format!("    stubs::{}::{}(\n", method.name, method.name);
+        for arg in &stub_args {
+            out += &format!("        {},\n", arg);
+        }
+        out += "    )?;\n";
+    }
+
+    let stores: Vec<_> = method
+        .accounts
+        .iter()
+        .filter(|account| {
+            account.is_mut()
+                && !account.is_migrate()
+                && !account.is_close()
+                && !account.is_token_program()
+        })
+        .collect();
+    let closes: Vec<_> = method
+        .accounts
+        .iter()
+        .filter(|account| account.is_close())
+        .collect();
+    if !stores.is_empty() || !closes.is_empty() {
+        out += "\n";
+    }
+    for account in stores {
+        out += &format!("    {}.store();\n", account.name());
+    }
+    for account in closes {
+        out += &format!(
+            "    close_account({}_info, fee_payer_info)?;\n",
+            account.name()
+        );
+    }
+    out += "\n    Ok(())\n}\n";
+
+    out
+}
+
+fn render_account(account: &AccountModel) -> String {
+    let name = account.name();
+    let ty = &account.ty.name.value;
+    let owned = account.is_owned();
+    let mut out = String::from("\n");
+
+    let seeds: Vec<_> = account
+        .solana
+        .seeds
+        .iter()
+        .map(|seed| seed_expr(account, seed))
+        .collect();
+    if !seeds.is_empty() {
+        out += &array_statement(&format!("let {}_seeds: &[&[u8]] = ", name), &seeds, 4);
+    }
+    let owner = account
+        .owner_seed()
+        .filter(|_| account.has_authority())
+        .map(|seed| format!("&args.{}", account.seed_arg(seed)));
+    if let (Some(owner), None) = (&owner, account.role()) {
+        out += &call_statement(
+            "verify_signer_address",
+            &[format!("{}_info.key", account.authority()), owner.clone()],
+            "?;",
+            4,
+        );
+    }
+
+    let mut checks = String::new();
+    if owned {
+        checks += &format!("verify_ownership(program_id, &[{}_info])?;\n", name);
+    }
+    checks += &format!("verify_len({}::LEN, &[{}_info])?;\n", ty, name);
+
+    if account.is_migrate() {
+        out += &format!(
+            "    verify_pda({}_info, {}_seeds, program_id)?;\n",
+            name, name
+        );
+        if owned {
+            out += &format!("    verify_ownership(program_id, &[{}_info])?;\n", name);
+        }
+        return out;
+    }
+
+    if !account.is_init() {
+        if !seeds.is_empty() {
+            out += &format!(
+                "    verify_pda({}_info, {}_seeds, program_id)?;\n",
+                name, name
+            );
+        }
+        for check in checks.lines() {
+            out += &format!("    {}\n", check);
+        }
+        let binding = if account.is_mut() && !account.is_close() {
+            "let mut"
+        } else {
+            "let"
+        };
+        out += &format!(
+            "    {} {} = AccountPDA::<{}>::load({}_info)?;\n",
+            binding, name, ty, name
+        );
+        if let (Some(owner), Some(role), Some(field)) =
+            (owner, account.role(), account.delegates_field())
+        {
+            out += &call_statement(
+                "verify_delegate_role",
+                &[
+                    format!("{}_info", account.authority()),
+                    owner,
+                    format!("&{}.data.{}", name, field.name.value),
+                    format!("DelegateRole::{}", role.variant()),
+                ],
+                "?;",
+                4,
+            );
+        }
+        return out;
+    }
+
+    let signer_seeds = if seeds.is_empty() {
+        "&[]".to_string()
+    } else {
+        let binding = format!("    let ({}_address, {}_bump) =", name, name);
+        let call = format!("Pubkey::find_program_address({}_seeds, program_id);", name);
+        if binding.len() + call.len() < 100 {
+            out += &format!("{} {}\n", binding, call);
+        } else {
+            out += &format!("{}\n        {}\n", binding, call);
+        }
+        out += &format!(
+            "    if *{}_info.key != {}_address {{
+        return Err(SecurityError::NotExpectedAddress.into());
+    }}
+",
+            name, name
+        );
+
+        let mut signer_seeds = seeds.clone();
+        signer_seeds.push(format!("&[{}_bump]", name));
+        out += &array_statement(
+            &format!("let {}_signer_seeds: &[&[u8]] = ", name),
+            &signer_seeds,
+            4,
+        );
+        format!("&[{}_signer_seeds]", name)
+    };
+    let create = format!(
+        "create_account(
+    program_id,
+    fee_payer_info,
+    {}_info,
+    system_program_info,
+    {}::LEN,
+    {},
+)?;
+",
+        name, ty, signer_seeds
+    );
+    let init = format!("AccountPDA::new({}_info, {}::default())", name, ty);
+
+    if account.is_init_if_needed() {
+        out += &format!(
+            "    let {}_exists = !{}_info.data_is_empty();\n",
+            name, name
+        );
+        out += &format!("    let mut {} = if {}_exists {{\n", name, name);
+        out += &indent(&checks, 8);
+        out += &format!("        AccountPDA::load({}_info)?\n    }} else {{\n", name);
+        out += &indent(&create, 8);
+        out += &format!("        {}\n    }};\n", init);
+    } else {
+        out += &format!(
+            "    if !{}_info.data_is_empty() {{
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }}
+",
+            name
+        );
+        out += &indent(&create, 4);
+        out += &format!("    let mut {} = {};\n", name, init);
+    }
+
+    out
+}
+
+// The token program checks the data of its accounts when it moves tokens, the processor checks
+// they belong to it and derives the token PDAs. The stub checks their mint and owner
+fn render_token_account(method: &MethodModel, account: &AccountModel) -> String {
+    let name = account.name();
+    let len = if account.is_mint() {
+        "MINT_LEN"
+    } else {
+        "TOKEN_ACCOUNT_LEN"
+    };
+    let mut out = String::from("\n");
+
+    if account.is_token_pda() {
+        let seeds: Vec<_> = account
+            .solana
+            .seeds
+            .iter()
+            .map(|seed| seed_expr(account, seed))
+            .collect();
+        out += &array_statement(&format!("let {}_seeds: &[&[u8]] = ", name), &seeds, 4);
+        let binding = format!("    let ({}_address, {}_bump) =", name, name);
+        let call = format!("Pubkey::find_program_address({}_seeds, program_id);", name);
+        if binding.len() + call.len() < 100 {
+            out += &format!("{} {}\n", binding, call);
+        } else {
+            out += &format!("{}\n        {}\n", binding, call);
+        }
+        out += &format!(
+            "    if *{}_info.key != {}_address {{
+        return Err(SecurityError::NotExpectedAddress.into());
+    }}
+",
+            name, name
+        );
+        let mut signer_seeds = seeds;
+        signer_seeds.push(format!("&[{}_bump]", name));
+        out += &array_statement(
+            &format!("let {}_signer_seeds: &[&[u8]] = ", name),
+            &signer_seeds,
+            4,
+        );
+    }
+
+    if account.is_init() {
+        let mint = method.mint().expect("validated CIDL").name();
+        out += &format!(
+            "    if !{}_info.data_is_empty() {{
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }}
+",
+            name
+        );
+        out += &indent(
+            &format!(
+                "create_account(
+    &spl_token_program::id(),
+    fee_payer_info,
+    {}_info,
+    system_program_info,
+    TOKEN_ACCOUNT_LEN,
+    &[{}_signer_seeds],
+)?;
+",
+                name, name
+            ),
+            4,
+        );
+        out += &call_statement(
+            "initialize_token_account",
+            &[
+                "token_program_info".to_string(),
+                format!("{}_info", name),
+                format!("{}_info", mint),
+                format!("{}_info.key", name),
+            ],
+            "?;",
+            4,
+        );
+    } else {
+        out += &format!(
+            "    verify_ownership(&spl_token_program::id(), &[{}_info])?;\n",
+            name
+        );
+        out += &format!("    verify_len({}, &[{}_info])?;\n", len, name);
+    }
+
+    if account.is_token_pda() {
+        out += &call_statement(
+            &format!("let {} = TokenAccountPDA::new", name),
+            &[format!("{}_info", name), format!("{}_signer_seeds", name)],
+            ";",
+            4,
+        );
+    }
+
+    out
+}
+
+// Sorted as rustfmt reorders them
+fn render_stubs_mod(methods: &[MethodModel]) -> String {
+    let mut names: Vec<_> = methods.iter().map(|method| &method.name).collect();
+    names.sort();
+
+    let mut out = String::from(GENERATED_HEADER);
+    out += "\n";
+    for name in names {
+        out += &format!("pub mod {};\n", name);
+    }
+    out
+}
+
+fn render_stub(method: &MethodModel) -> String {
+    let migrates = method.migrates_accounts();
+    // Accounts passed as AccountInfos share the lifetime of the entrypoint accounts
+    let infos = migrates || method.uses_token_program();
+    let mut out = String::new();
+    if method
+        .accounts
+        .iter()
+        .any(|account| !account.is_migrate() && !account.is_token_program())
+    {
+        out += "use crate::state::*;\n";
+    }
+    if method.inputs.iter().any(|input| uses_delegate(&input.ty)) {
+        out += DELEGATE_USE;
+    }
+    if method.accounts.iter().any(AccountModel::is_token_pda) {
+        out += "use codigolib::token_utils::TokenAccountPDA;\n";
+    }
+    if infos {
+        out += "use solana_program::account_info::AccountInfo;\n";
+    }
+    out += "use solana_program::entrypoint::ProgramResult;\n";
+    if migrates || method.inputs.iter().any(|input| uses_pubkey(&input.ty)) {
+        out += "use solana_program::pubkey::Pubkey;\n";
+    }
+
+    out += "\n";
+    if let Some(summary) = &method.method.summary {
+        out += &comment(&summary.value, "");
+    }
+    // Migrated accounts are passed to codigolib's migrate_account with the fee payer and the
+    // system program, which all share the lifetime of the entrypoint accounts
+    let mut params = Vec::new();
+    if migrates {
+        params.push("program_id: &Pubkey".to_string());
+    }
+    params.extend(method.accounts.iter().map(|account| {
+        if account.is_token_pda() {
+            return format!("{}: &TokenAccountPDA<'_, 'a>", account.name());
+        }
+        if account.is_migrate() || account.is_token_program() {
+            return format!("{}: &AccountInfo<'a>", account.name());
+        }
+        let reference = if account.is_mut() && !account.is_close() {
+            "&mut "
+        } else {
+            "&"
+        };
+        format!(
+            "{}: {}AccountPDA<{}>",
+            account.name(),
+            reference,
+            account.ty.name.value
+        )
+    }));
+    if migrates {
+        params.push("fee_payer: &AccountInfo<'a>".to_string());
+        params.push("system_program: &AccountInfo<'a>".to_string());
+    }
+    if method.moves_tokens() {
+        params.extend(
+            method
+                .authorities()
+                .map(|account| format!("{}: &AccountInfo<'a>", account.authority())),
+        );
+        params.push("token_program: &AccountInfo<'a>".to_string());
+    }
+    params.extend(
+        method
+            .inputs
+            .iter()
+            .map(|input| format!("{}: {}", input.name, rust_type(&input.ty))),
+    );
+
+    let lifetime = if infos { "<'a>" } else { "" };
+    out += &signature(
+        &format!("pub fn {}{}", method.name, lifetime),
+        &params,
+        "ProgramResult",
+        0,
+    );
+    out += &format!("    {}\n\n    Ok(())\n}}\n", STUB_PLACEHOLDER);
+
+    out
+}
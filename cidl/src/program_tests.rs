@@ -0,0 +1,312 @@
+use crate::generator::*;
+use crate::merge::*;
+use crate::parser::parse;
+use crate::program::*;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BUDGET_TRACKER: &str = include_str!("../../cidl.yaml");
+    const CONSTANTS_TS: &str = include_str!("../../sdk/constants.ts");
+
+    fn budget_tracker_files() -> Vec<GeneratedFile> {
+        generate_program(&parse(BUDGET_TRACKER).unwrap(), &ProgramOptions::default()).unwrap()
+    }
+
+    fn file<'a>(files: &'a [GeneratedFile], path: &str) -> &'a GeneratedFile {
+        files
+            .iter()
+            .find(|file| file.path == Path::new(path))
+            .unwrap()
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("cidl-{}-{}", name, unique_suffix()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn unique_suffix() -> u128 {
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_nanos()
+    }
+
+    // The checked-in program is the golden copy. Run with UPDATE_GOLDEN=1 to regenerate it after
+    // an intended change, stubs keep their business logic
+    #[test]
+    fn it_matches_the_checked_in_program() {
+        let generated = Path::new(env!("CARGO_MANIFEST_DIR")).join("../generated");
+        let files = budget_tracker_files();
+
+        if env::var_os("UPDATE_GOLDEN").is_some() {
+            write_files(&generated, &files).unwrap();
+        }
+
+        for file in &files {
+            let checked_in = fs::read_to_string(generated.join(&file.path))
+                .unwrap_or_else(|_| panic!("missing checked-in file {}", file.path.display()));
+            match file.kind {
+                FileKind::Generated => {
+                    assert_eq!(file.contents, checked_in, "{}", file.path.display())
+                }
+                // Stubs hold the business logic from the README instead of the placeholder
+                FileKind::Stub => {
+                    let merged = merge_stub(&checked_in, &file.contents, stub_name(&file.path));
+                    assert_eq!(
+                        merged.as_ref(),
+                        Some(&checked_in),
+                        "{}",
+                        file.path.display()
+                    );
+                    assert!(!checked_in.contains(STUB_PLACEHOLDER));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn it_numbers_instructions_like_the_sdk() {
+        let files = budget_tracker_files();
+        let instructions = &file(&files, "rendered/instructions.rs").contents;
+
+        // `createUserRecord: 0,` in constants.ts must be unpacked as `0 => ... CreateUserRecord`
+        let mut numbered = 0;
+        for line in CONSTANTS_TS.lines() {
+            if let Some((name, number)) = line.trim().trim_end_matches(',').split_once(": ") {
+                if let Ok(number) = number.parse::<u8>() {
+                    let variant = format!("{}{}", name[..1].to_uppercase(), &name[1..]);
+                    let arm = format!("{} => {{\n                let", number);
+                    let start = instructions.find(&arm).unwrap();
+                    let end = start + instructions[start..].find("            }").unwrap();
+                    assert!(
+                        instructions[start..end].contains(&format!("Self::{}(", variant)),
+                        "{}",
+                        variant
+                    );
+                    numbered += 1;
+                }
+            }
+        }
+        assert_eq!(numbered, 11);
+    }
+
+    #[test]
+    fn it_renders_state_with_codigolib_layout() {
+        let files = budget_tracker_files();
+        let state = &file(&files, "rendered/state.rs").contents;
+
+        assert!(state.contains("const LEN: usize = 306;"));
+        assert!(state.contains("        (\"version\", 1),\n        (\"name\", 54),\n"));
+        assert!(state.contains("pack_string(buf, self.name.clone(), 54)"));
+        assert!(state.contains("state_unpack_string(buf, 54)"));
+        assert!(state.contains("pack_i64(buf, self.total_balance);\n"));
+        assert!(state.contains("pack_vec(buf, 5, 33, pack_delegate, self.delegates.clone())"));
+        assert!(state.contains("try_state_unpack_vec(buf, 5, 33, try_unpack_delegate)?"));
+        assert!(state.contains("use codigolib::delegate_utils::*;\n"));
+    }
+
+    #[test]
+    fn it_wires_security_checks_from_attributes() {
+        let files = budget_tracker_files();
+        let processor = &file(&files, "rendered/processor.rs").contents;
+        let create = &processor[processor.find("fn process_create_user_record").unwrap()
+            ..processor.find("fn process_register_income").unwrap()];
+        let register = &processor[processor.find("fn process_register_income").unwrap()
+            ..processor.find("fn process_register_outcome").unwrap()];
+
+        // init_if_needed: owner and fee payer sign, address derived from the seeds, checked once
+        // it exists
+        assert!(create.contains("verify_signers(&[user_record_authority_info, fee_payer_info])?;"));
+        assert!(create.contains(
+            "verify_signer_address(\n        user_record_authority_info.key,\n        &args.user_record_seed_signer,\n    )?;"
+        ));
+        assert!(create.contains("system_program::id()"));
+        assert!(create.contains(
+            "let user_record_seeds: &[&[u8]] = &[b\"record\", args.user_record_seed_signer.as_ref()];"
+        ));
+        assert!(create.contains("Err(SecurityError::NotExpectedAddress.into())"));
+        assert!(create.contains("verify_ownership(program_id, &[user_record_info])?;"));
+        assert!(create.contains("verify_len(Record::LEN, &[user_record_info])?;"));
+        assert!(create.contains(
+            "    if user_record_exists {\n        return Ok(());\n    }\n\n    stubs::create_user_record::"
+        ));
+        assert!(processor.contains("system_instruction::allocate(account.key, len as u64)"));
+        assert!(processor.contains("system_instruction::assign(account.key, owner)"));
+
+        // mut: existing PDA owned by the program, stored back after the stub
+        assert!(register.contains("verify_pda(user_record_info, user_record_seeds, program_id)?;"));
+        assert!(register.contains("verify_ownership(program_id, &[user_record_info])?;"));
+        assert!(register.contains("verify_len(Record::LEN, &[user_record_info])?;"));
+        assert!(register.contains("AccountPDA::<Record>::load(user_record_info)?;"));
+        assert!(register.contains("user_record.store();"));
+        assert!(register.contains("verify_signers(&[user_record_authority_info])?;"));
+
+        // role: the owner or a delegate with at least that role signs, checked once loaded
+        assert!(register.contains(
+            "verify_delegate_role(\n        user_record_authority_info,\n        &args.user_record_seed_signer,\n        &user_record.data.delegates,\n        DelegateRole::Contributor,\n    )?;"
+        ));
+        assert!(!register.contains("verify_signer_address"));
+        assert!(
+            register.find("AccountPDA::<Record>::load").unwrap()
+                < register.find("verify_delegate_role").unwrap()
+        );
+        assert!(processor.contains("use codigolib::delegate_utils::DelegateRole;\n"));
+    }
+
+    #[test]
+    fn it_wires_migrated_accounts_without_loading_them() {
+        let files = budget_tracker_files();
+        let processor = &file(&files, "rendered/processor.rs").contents;
+        let migrate = &processor[processor.find("fn process_migrate_record").unwrap()
+            ..processor.find("fn process_close_record").unwrap()];
+
+        // migrate: the owner and the fee payer sign, the raw account goes to the stub
+        assert!(migrate.contains("verify_signers(&[user_record_authority_info, fee_payer_info])?;"));
+        assert!(migrate.contains("system_program::id()"));
+        assert!(migrate.contains("verify_pda(user_record_info, user_record_seeds, program_id)?;"));
+        assert!(migrate.contains("verify_ownership(program_id, &[user_record_info])?;"));
+        assert!(!migrate.contains("verify_len"));
+        assert!(!migrate.contains("load"));
+        assert!(migrate.contains(
+            "stubs::migrate_record::migrate_record(\n        program_id,\n        user_record_info,\n        fee_payer_info,\n        system_program_info,\n    )?;"
+        ));
+
+        let stub = file(&files, "rendered/stubs/migrate_record.rs");
+        assert!(stub.contents.contains(
+            "pub fn migrate_record<'a>(\n    program_id: &Pubkey,\n    user_record: &AccountInfo<'a>,\n    fee_payer: &AccountInfo<'a>,\n    system_program: &AccountInfo<'a>,\n) -> ProgramResult {"
+        ));
+        assert!(!stub.contents.contains("use crate::state::*;"));
+    }
+
+    #[test]
+    fn it_closes_accounts_after_the_stub() {
+        let files = budget_tracker_files();
+        let processor = &file(&files, "rendered/processor.rs").contents;
+        let close = &processor[processor.find("fn process_close_record").unwrap()
+            ..processor.find("fn process_bind_mint").unwrap()];
+
+        // close: the owner and the fee payer sign, the lamports go to the fee payer
+        assert!(close.contains("verify_signers(&[user_record_authority_info, fee_payer_info])?;"));
+        assert!(!close.contains("system_program"));
+        assert!(close.contains("verify_len(Record::LEN, &[user_record_info])?;"));
+        assert!(!close.contains("store"));
+        assert!(close.contains(
+            "stubs::close_record::close_record(&user_record)?;\n\n    close_account(user_record_info, fee_payer_info)?;\n\n    Ok(())\n}"
+        ));
+        assert!(
+            processor.contains("fn close_account(account: &AccountInfo, recipient: &AccountInfo)")
+        );
+
+        let stub = file(&files, "rendered/stubs/close_record.rs");
+        assert!(stub
+            .contents
+            .contains("pub fn close_record(user_record: &AccountPDA<Record>) -> ProgramResult {"));
+    }
+
+    #[test]
+    fn it_wires_token_accounts_to_the_token_program() {
+        let files = budget_tracker_files();
+        let processor = &file(&files, "rendered/processor.rs").contents;
+        let bind = &processor[processor.find("fn process_bind_mint").unwrap()
+            ..processor.find("fn process_register_token_income").unwrap()];
+        let income = &processor[processor.find("fn process_register_token_income").unwrap()
+            ..processor.find("fn process_register_token_outcome").unwrap()];
+
+        // init: the vault is created for the token program at its PDA, and owns itself
+        assert!(bind.contains("let token_program_info = next_account_info(account_info_iter)?;\n    let fee_payer_info"));
+        assert!(bind.contains("verify_token_program(token_program_info)?;"));
+        assert!(bind.contains("verify_ownership(&spl_token_program::id(), &[mint_info])?;"));
+        assert!(bind.contains("verify_len(MINT_LEN, &[mint_info])?;"));
+        assert!(bind.contains("if *vault_info.key != vault_address {"));
+        assert!(bind.contains("    create_account(\n        &spl_token_program::id(),"));
+        assert!(bind.contains(
+            "initialize_token_account(token_program_info, vault_info, mint_info, vault_info.key)?;"
+        ));
+        assert!(!bind.contains("vault.store()"));
+
+        // mut: the token program checks the data when it moves tokens, the stub receives it
+        assert!(income.contains("verify_ownership(&spl_token_program::id(), &[source_info])?;"));
+        assert!(income.contains("verify_len(TOKEN_ACCOUNT_LEN, &[vault_info])?;"));
+        assert!(
+            income.contains("let vault = TokenAccountPDA::new(vault_info, vault_signer_seeds);")
+        );
+        assert!(income.contains(
+            "        &vault,\n        source_info,\n        user_record_authority_info,\n        token_program_info,\n        args.amount,"
+        ));
+
+        let stub = file(&files, "rendered/stubs/register_token_income.rs");
+        assert!(stub.contents.contains(
+            "pub fn register_token_income<'a>(\n    user_record: &mut AccountPDA<Record>,\n    vault: &TokenAccountPDA<'_, 'a>,\n    source: &AccountInfo<'a>,\n"
+        ));
+        let state = &file(&files, "rendered/state.rs").contents;
+        assert!(state.contains("pub struct Vault;\n"));
+        assert!(!state.contains("impl State for Vault"));
+    }
+
+    #[test]
+    fn it_renders_stubs_with_placeholder() {
+        let files = budget_tracker_files();
+        let stub = file(&files, "rendered/stubs/create_user_record.rs");
+
+        assert_eq!(stub.kind, FileKind::Stub);
+        assert!(stub.contents.contains(
+            "pub fn create_user_record(\n    user_record: &mut AccountPDA<Record>,\n    user_name: String,\n) -> ProgramResult {\n    // Place your custom code here...\n\n    Ok(())\n}\n"
+        ));
+    }
+
+    #[test]
+    fn it_rejects_invalid_cidl() {
+        let source = BUDGET_TRACKER.replace("type: u16", "type: u61");
+        let diagnostics =
+            generate_program(&parse(&source).unwrap(), &ProgramOptions::default()).unwrap_err();
+
+        assert_eq!(diagnostics[0].message, "unknown type `u61`");
+    }
+
+    #[test]
+    fn it_write_files_keeps_existing_stubs() {
+        let dir = temp_dir("write");
+        let files = vec![
+            GeneratedFile::generated("rendered/state.rs", "state v2".to_string()),
+            GeneratedFile::stub("rendered/stubs/run.rs", "stub v2".to_string()),
+            GeneratedFile::stub("rendered/stubs/new.rs", "new".to_string()),
+            GeneratedFile::generated("Cargo.toml", "same".to_string()),
+        ];
+        fs::create_dir_all(dir.join("rendered/stubs")).unwrap();
+        fs::write(dir.join("rendered/state.rs"), "state v1").unwrap();
+        fs::write(dir.join("rendered/stubs/run.rs"), "user code").unwrap();
+        fs::write(dir.join("Cargo.toml"), "same").unwrap();
+
+        let written = write_files(&dir, &files).unwrap();
+
+        assert_eq!(
+            written,
+            vec![
+                PathBuf::from("rendered/state.rs"),
+                PathBuf::from("rendered/stubs/new.rs")
+            ]
+        );
+        assert_eq!(
+            fs::read_to_string(dir.join("rendered/state.rs")).unwrap(),
+            "state v2"
+        );
+        assert_eq!(
+            fs::read_to_string(dir.join("rendered/stubs/run.rs")).unwrap(),
+            "user code"
+        );
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn it_pascal_cases_names() {
+        assert_eq!(pascal_case("budget_tracker"), "BudgetTracker");
+        assert_eq!(pascal_case("create_user_record"), "CreateUserRecord");
+        assert_eq!(pascal_case("Record"), "Record");
+    }
+}
@@ -0,0 +1,351 @@
+use crate::codec::*;
+use crate::model::*;
+use crate::program::MethodModel;
+use crate::validator::{has_errors, validate, Diagnostic};
+use crate::yaml::Span;
+use std::fmt;
+
+// Defaults of solana_program::rent::Rent, rent exemption needs two years of rent
+pub const DEFAULT_LAMPORTS_PER_BYTE_YEAR: u64 = 3480;
+pub const EXEMPTION_THRESHOLD_YEARS: u64 = 2;
+// Metadata bytes the runtime charges on top of the account data
+pub const ACCOUNT_STORAGE_OVERHEAD: usize = 128;
+// solana_program's MAX_PERMITTED_DATA_INCREASE, the most a program can allocate in a CPI
+pub const MAX_CPI_ALLOCATION: usize = 10 * 1024;
+// Accounts over this percentage of MAX_CPI_ALLOCATION are reported as lacking headroom
+pub const HEADROOM_WARNING_PERCENT: usize = 90;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SizeOptions {
+    pub lamports_per_byte_year: u64,
+}
+
+impl Default for SizeOptions {
+    fn default() -> SizeOptions {
+        SizeOptions {
+            lamports_per_byte_year: DEFAULT_LAMPORTS_PER_BYTE_YEAR,
+        }
+    }
+}
+
+// Bytes a field or argument takes, `max` is None when the client picks the length
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldSize {
+    pub name: String,
+    pub ty: TypeRef,
+    pub min: usize,
+    pub max: Option<usize>,
+    // How the size adds up, e.g. `4 + 50` for a string with cap:50
+    pub breakdown: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TypeSize {
+    pub name: String,
+    pub is_account: bool,
+    pub size: usize,
+    pub rent_exempt_minimum: u64,
+    pub fields: Vec<FieldSize>,
+}
+
+// Instruction data, including the tag byte
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InstructionSize {
+    pub name: String,
+    pub min: usize,
+    pub max: Option<usize>,
+    pub fields: Vec<FieldSize>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SizeReport {
+    pub types: Vec<TypeSize>,
+    pub instructions: Vec<InstructionSize>,
+    pub warnings: Vec<Diagnostic>,
+}
+
+// None when the rent does not fit in a u64
+pub fn rent_exempt_minimum(size: usize, lamports_per_byte_year: u64) -> Option<u64> {
+    (ACCOUNT_STORAGE_OVERHEAD as u64)
+        .checked_add(size as u64)?
+        .checked_mul(lamports_per_byte_year)?
+        .checked_mul(EXEMPTION_THRESHOLD_YEARS)
+}
+
+// Sizes of every type and instruction payload with codigolib's layout rules
+pub fn size_report(cidl: &Cidl, options: &SizeOptions) -> Result<SizeReport, Vec<Diagnostic>> {
+    let diagnostics = validate(cidl);
+    if has_errors(&diagnostics) {
+        return Err(diagnostics);
+    }
+
+    let mut warnings = Vec::new();
+    let mut errors = Vec::new();
+    // The token program defines the size of its accounts
+    let types: Vec<_> = cidl
+        .types
+        .iter()
+        .filter(|ty| !ty.is_token_program())
+        .map(|ty| {
+            let size = type_size(cidl, ty).expect("validated types have a size");
+            if size > MAX_ACCOUNT_DATA_LEN {
+                errors.push(
+                    Diagnostic::error(
+                        ty.name.span,
+                        format!(
+                            "type `{}` takes {} bytes, over the {} bytes of the largest account",
+                            ty.name.value, size, MAX_ACCOUNT_DATA_LEN
+                        ),
+                    )
+                    .with_help("reduce the `cap` of its fields"),
+                );
+            } else if ty.solana.is_some() {
+                warnings.extend(headroom_warning(&ty.name.value, size, ty.name.span));
+            }
+
+            let rent = rent_exempt_minimum(size, options.lamports_per_byte_year);
+            if rent.is_none() {
+                errors.push(Diagnostic::error(
+                    ty.name.span,
+                    format!(
+                        "the rent-exempt minimum of type `{}` overflows at {} lamports per byte-year",
+                        ty.name.value, options.lamports_per_byte_year
+                    ),
+                ));
+            }
+
+            TypeSize {
+                name: ty.name.value.clone(),
+                is_account: ty.solana.is_some(),
+                size,
+                rent_exempt_minimum: rent.unwrap_or(u64::MAX),
+                fields: ty
+                    .fields
+                    .iter()
+                    .map(|field| state_field(cidl, &field.name.value, &field.ty.value, field.cap()))
+                    .collect(),
+            }
+        })
+        .collect();
+
+    let instructions = cidl
+        .methods
+        .iter()
+        .map(|method| {
+            let model = MethodModel::new(cidl, method);
+            let mut fields = vec![FieldSize {
+                name: "tag".to_string(),
+                ty: TypeRef::U8,
+                min: 1,
+                max: Some(1),
+                breakdown: None,
+            }];
+            fields.extend(
+                model
+                    .args
+                    .iter()
+                    .map(|arg| instruction_field(cidl, &arg.name, &arg.ty, arg.cap)),
+            );
+
+            let min = fields
+                .iter()
+                .try_fold(0usize, |min, field| min.checked_add(field.min));
+            let max = fields
+                .iter()
+                .try_fold(Some(0usize), |max, field| match (max, field.max) {
+                    (Some(max), Some(field)) => max.checked_add(field).map(Some),
+                    _ => Some(None),
+                });
+            if min.is_none() || max.is_none() {
+                errors.push(Diagnostic::error(
+                    method.name.span,
+                    format!(
+                        "the instruction data of method `{}` overflows",
+                        method.name.value
+                    ),
+                ));
+            }
+
+            InstructionSize {
+                name: method.name.value.clone(),
+                min: min.unwrap_or(usize::MAX),
+                max: max.unwrap_or(Some(usize::MAX)),
+                fields,
+            }
+        })
+        .collect();
+
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+
+    Ok(SizeReport {
+        types,
+        instructions,
+        warnings,
+    })
+}
+
+fn headroom_warning(name: &str, size: usize, span: Span) -> Option<Diagnostic> {
+    if size > MAX_CPI_ALLOCATION {
+        Some(
+            Diagnostic::warning(
+                span,
+                format!(
+                    "account `{}` takes {} bytes, over the {} bytes a program can allocate in a CPI",
+                    name, size, MAX_CPI_ALLOCATION
+                ),
+            )
+            .with_help("create the account from the client or reduce the `cap` of its fields"),
+        )
+    } else if size * 100 > MAX_CPI_ALLOCATION * HEADROOM_WARNING_PERCENT {
+        // Only reached for sizes up to MAX_CPI_ALLOCATION
+        Some(Diagnostic::warning(
+            span,
+            format!(
+                "account `{}` takes {} bytes, only {} bytes below the CPI allocation limit",
+                name,
+                size,
+                MAX_CPI_ALLOCATION - size
+            ),
+        ))
+    } else {
+        None
+    }
+}
+
+fn state_field(cidl: &Cidl, name: &str, ty: &TypeRef, cap: Option<usize>) -> FieldSize {
+    let size = state_size(cidl, ty, cap).expect("validated fields have a size");
+    FieldSize {
+        name: name.to_string(),
+        ty: ty.clone(),
+        min: size,
+        max: Some(size),
+        breakdown: state_breakdown(cidl, ty, cap),
+    }
+}
+
+fn state_breakdown(cidl: &Cidl, ty: &TypeRef, cap: Option<usize>) -> Option<String> {
+    let inner_size = |inner: &TypeRef, cap| state_size(cidl, inner, cap).unwrap_or(0);
+    match ty {
+        TypeRef::String => Some(format!("4 + {}", cap.unwrap_or(0))),
+        // pack_option always writes the inner value, None is padded with its default
+        TypeRef::Option(inner) => Some(format!("1 + {}", inner_size(inner, cap))),
+        TypeRef::Vec(inner) => Some(format!(
+            "4 + {} * {}",
+            cap.unwrap_or(0),
+            inner_size(inner, None)
+        )),
+        _ => None,
+    }
+}
+
+// Strings and vectors are length-prefixed in instructions, options without a value only take
+// their flag. A maximum that overflows saturates, adding the tag to it reports the overflow
+fn instruction_field(cidl: &Cidl, name: &str, ty: &TypeRef, cap: Option<usize>) -> FieldSize {
+    let (min, max, breakdown) = instruction_size(cidl, ty, cap);
+    FieldSize {
+        name: name.to_string(),
+        ty: ty.clone(),
+        min,
+        max,
+        breakdown,
+    }
+}
+
+fn instruction_size(
+    cidl: &Cidl,
+    ty: &TypeRef,
+    cap: Option<usize>,
+) -> (usize, Option<usize>, Option<String>) {
+    match ty {
+        TypeRef::String => (
+            4,
+            cap.map(|cap| cap.saturating_add(4)),
+            Some("4 + len".to_string()),
+        ),
+        TypeRef::Option(inner) => {
+            let (_, max, breakdown) = instruction_size(cidl, inner, cap);
+            let inner = breakdown.unwrap_or_else(|| inner_size(cidl, inner).to_string());
+            (
+                1,
+                max.map(|max| max.saturating_add(1)),
+                Some(format!("1 + {} if set", inner)),
+            )
+        }
+        TypeRef::Vec(inner) => {
+            let element = inner_size(cidl, inner);
+            (
+                4,
+                cap.map(|cap| cap.saturating_mul(element).saturating_add(4)),
+                Some(format!("4 + len * {}", element)),
+            )
+        }
+        _ => {
+            let size = inner_size(cidl, ty);
+            (size, Some(size), None)
+        }
+    }
+}
+
+fn inner_size(cidl: &Cidl, ty: &TypeRef) -> usize {
+    state_size(cidl, ty, None).unwrap_or(0)
+}
+
+fn bytes(min: usize, max: Option<usize>) -> String {
+    match max {
+        Some(max) if max == min && min == 1 => "1 byte".to_string(),
+        Some(max) if max == min => format!("{} bytes", min),
+        Some(max) => format!("{} to {} bytes", min, max),
+        None => format!("{} bytes or more", min),
+    }
+}
+
+fn write_fields(f: &mut fmt::Formatter, fields: &[FieldSize]) -> fmt::Result {
+    for field in fields {
+        write!(
+            f,
+            "  {}: {}, {}",
+            field.name,
+            field.ty,
+            bytes(field.min, field.max)
+        )?;
+        match &field.breakdown {
+            Some(breakdown) => writeln!(f, " ({})", breakdown)?,
+            None => writeln!(f)?,
+        }
+    }
+    Ok(())
+}
+
+impl fmt::Display for SizeReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for ty in &self.types {
+            writeln!(
+                f,
+                "type {}{}: {}, rent-exempt minimum {} lamports",
+                ty.name,
+                if ty.is_account { " (account)" } else { "" },
+                bytes(ty.size, Some(ty.size)),
+                ty.rent_exempt_minimum
+            )?;
+            write_fields(f, &ty.fields)?;
+            writeln!(f)?;
+        }
+
+        for (i, instruction) in self.instructions.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            writeln!(
+                f,
+                "method {}: {}",
+                instruction.name,
+                bytes(instruction.min, instruction.max)
+            )?;
+            write_fields(f, &instruction.fields)?;
+        }
+
+        Ok(())
+    }
+}
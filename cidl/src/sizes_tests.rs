@@ -0,0 +1,181 @@
+use crate::model::*;
+use crate::parser::parse;
+use crate::sizes::*;
+use crate::validator::Severity;
+use solana_program::entrypoint::MAX_PERMITTED_DATA_INCREASE;
+use solana_program::rent::Rent;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BUDGET_TRACKER: &str = include_str!("../../cidl.yaml");
+
+    fn budget_tracker_report() -> SizeReport {
+        size_report(&parse(BUDGET_TRACKER).unwrap(), &SizeOptions::default()).unwrap()
+    }
+
+    fn report_with_record_cap(cap: usize) -> SizeReport {
+        let source = BUDGET_TRACKER.replace("cap:50", &format!("cap:{}", cap));
+        size_report(&parse(&source).unwrap(), &SizeOptions::default()).unwrap()
+    }
+
+    #[test]
+    fn it_sizes_record_fields() {
+        let report = budget_tracker_report();
+        let record = &report.types[0];
+
+        assert_eq!(record.name, "Record");
+        assert!(record.is_account);
+        assert_eq!(record.size, 306);
+        let sizes: Vec<_> = record.fields.iter().map(|field| field.min).collect();
+        assert_eq!(sizes, vec![1, 54, 2, 4, 4, 8, 169, 32, 32]);
+        assert_eq!(record.fields[1].breakdown.as_deref(), Some("4 + 50"));
+        assert_eq!(record.fields[6].breakdown.as_deref(), Some("4 + 5 * 33"));
+        assert!(report.warnings.is_empty());
+    }
+
+    #[test]
+    fn it_matches_solana_rent() {
+        let report = budget_tracker_report();
+
+        assert_eq!(
+            report.types[0].rent_exempt_minimum,
+            Rent::default().minimum_balance(306)
+        );
+        assert_eq!(rent_exempt_minimum(0, 1000), Some(256_000));
+        assert_eq!(rent_exempt_minimum(0, u64::MAX), None);
+        assert_eq!(MAX_CPI_ALLOCATION, MAX_PERMITTED_DATA_INCREASE);
+    }
+
+    #[test]
+    fn it_uses_configured_lamports_per_byte_year() {
+        let options = SizeOptions {
+            lamports_per_byte_year: 1,
+        };
+        let report = size_report(&parse(BUDGET_TRACKER).unwrap(), &options).unwrap();
+
+        assert_eq!(report.types[0].rent_exempt_minimum, (128 + 306) * 2);
+    }
+
+    #[test]
+    fn it_sizes_instruction_payloads() {
+        let report = budget_tracker_report();
+        let create = &report.instructions[0];
+        let register = &report.instructions[1];
+
+        // The user name has no cap, the client decides its length
+        assert_eq!(create.name, "create_user_record");
+        assert_eq!((create.min, create.max), (1 + 4 + 32, None));
+        assert_eq!(create.fields[1].breakdown.as_deref(), Some("4 + len"));
+        assert_eq!((register.min, register.max), (1 + 4 + 32, Some(37)));
+    }
+
+    #[test]
+    fn it_sizes_options_and_vectors() {
+        let source = BUDGET_TRACKER.replace(
+            "        description: Token account of the program holding the tokens of the record, bound with the mint.\n",
+            "        description: Token account of the program holding the tokens of the record, bound with the mint.\n      - name: history\n        type: option<vec<u32>>\n        solana:\n          attributes: [ cap:3 ]\n",
+        );
+        let report = size_report(&parse(&source).unwrap(), &SizeOptions::default()).unwrap();
+        let history = report.types[0].fields.last().unwrap();
+
+        assert_eq!(
+            history.ty,
+            TypeRef::Option(Box::new(TypeRef::Vec(Box::new(TypeRef::U32))))
+        );
+        assert_eq!(history.min, 1 + 4 + 3 * 4);
+        assert_eq!(history.breakdown.as_deref(), Some("1 + 16"));
+        assert_eq!(report.types[0].size, 306 + 17);
+    }
+
+    #[test]
+    fn it_warns_near_the_cpi_allocation_limit() {
+        // 252 bytes of fixed fields plus the 4 bytes string prefix
+        assert!(report_with_record_cap(8900).warnings.is_empty());
+
+        let near = report_with_record_cap(9500).warnings;
+        assert_eq!(near.len(), 1);
+        assert_eq!(
+            near[0].message,
+            "account `Record` takes 9756 bytes, only 484 bytes below the CPI allocation limit"
+        );
+
+        let over = report_with_record_cap(10240).warnings;
+        assert_eq!(
+            over[0].message,
+            "account `Record` takes 10496 bytes, over the 10240 bytes a program can allocate in a CPI"
+        );
+    }
+
+    #[test]
+    fn it_reports_accounts_larger_than_the_largest_account() {
+        let source = BUDGET_TRACKER.replace("cap:50", "cap:10485760");
+        let errors = size_report(&parse(&source).unwrap(), &SizeOptions::default()).unwrap_err();
+
+        assert_eq!(errors.len(), 1);
+        assert_eq!(
+            errors[0].message,
+            "type `Record` takes 10486016 bytes, over the 10485760 bytes of the largest account"
+        );
+        assert_eq!(errors[0].severity, Severity::Error);
+    }
+
+    #[test]
+    fn it_reports_sizes_that_overflow() {
+        let options = SizeOptions {
+            lamports_per_byte_year: u64::MAX / 2,
+        };
+        let errors = size_report(&parse(BUDGET_TRACKER).unwrap(), &options).unwrap_err();
+        assert_eq!(
+            errors[0].message,
+            format!(
+                "the rent-exempt minimum of type `Record` overflows at {} lamports per byte-year",
+                u64::MAX / 2
+            )
+        );
+
+        // Each level multiplies the size by the largest capacity
+        let source = "cidl: \"0.8\"
+info:
+  name: test
+types:
+  Values:
+    fields:
+      - name: values
+        type: vec<u128>
+        solana:
+          attributes: [ cap:10485760 ]
+  Big:
+    fields:
+      - name: values
+        type: vec<Values>
+        solana:
+          attributes: [ cap:10485760 ]
+methods:
+  - name: run
+    inputs:
+      - name: bigs
+        type: vec<Big>
+        solana:
+          attributes: [ cap:10485760 ]
+";
+        let errors = size_report(&parse(source).unwrap(), &SizeOptions::default()).unwrap_err();
+        assert_eq!(
+            errors.last().unwrap().message,
+            "the instruction data of method `run` overflows"
+        );
+    }
+
+    #[test]
+    fn it_displays_a_breakdown() {
+        let output = budget_tracker_report().to_string();
+
+        assert!(output.starts_with(
+            "type Record (account): 306 bytes, rent-exempt minimum 3020640 lamports\n  version: u8, 1 byte\n  name: string, 54 bytes (4 + 50)\n"
+        ));
+        assert!(output.contains(
+            "method create_user_record: 37 bytes or more\n  tag: u8, 1 byte\n  user_name: string, 4 bytes or more (4 + len)\n  user_record_seed_signer: sol:pubkey, 32 bytes\n"
+        ));
+    }
+}
@@ -0,0 +1,860 @@
+use crate::codec::*;
+use crate::lint::is_snake_case;
+use crate::model::*;
+use crate::yaml::Span;
+use std::collections::HashMap;
+use std::fmt;
+
+pub const SUPPORTED_CIDL_VERSION: &str = "0.8";
+
+// Solana rejects PDA seeds longer than 32 bytes
+pub const MAX_SEED_LEN: usize = 32;
+
+const PRIMITIVE_TYPES: &[&str] = &[
+    "bool",
+    "u8",
+    "u16",
+    "u32",
+    "u64",
+    "u128",
+    "i8",
+    "i16",
+    "i32",
+    "i64",
+    "i128",
+    "f32",
+    "f64",
+    "string",
+    "sol:pubkey",
+    "sol:delegate",
+];
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Severity::Error => write!(f, "error"),
+            Severity::Warning => write!(f, "warning"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub span: Span,
+    pub message: String,
+    pub help: Option<String>,
+}
+
+impl Diagnostic {
+    pub fn error(span: Span, message: impl Into<String>) -> Diagnostic {
+        Diagnostic {
+            severity: Severity::Error,
+            span,
+            message: message.into(),
+            help: None,
+        }
+    }
+
+    pub fn warning(span: Span, message: impl Into<String>) -> Diagnostic {
+        Diagnostic {
+            severity: Severity::Warning,
+            span,
+            message: message.into(),
+            help: None,
+        }
+    }
+
+    pub fn with_help(mut self, help: impl Into<String>) -> Diagnostic {
+        self.help = Some(help.into());
+        self
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}: {}", self.span, self.severity, self.message)?;
+        if let Some(help) = &self.help {
+            write!(f, "\n  help: {}", help)?;
+        }
+        Ok(())
+    }
+}
+
+pub fn has_errors(diagnostics: &[Diagnostic]) -> bool {
+    diagnostics
+        .iter()
+        .any(|diagnostic| diagnostic.severity == Severity::Error)
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Context {
+    // Packed into an account, every string and vector needs a fixed capacity
+    State,
+    // Packed into the instruction data, strings and vectors are length-prefixed
+    Instruction,
+}
+
+// Returns every problem found in the CIDL, sorted by location
+pub fn validate(cidl: &Cidl) -> Vec<Diagnostic> {
+    let mut validator = Validator {
+        cidl,
+        diagnostics: Vec::new(),
+    };
+
+    validator.check_version();
+    validator.check_unknown_keys();
+    validator.check_imports();
+    // Imported types are checked against the file defining them when the imports are resolved
+    for ty in cidl.types.iter().filter(|ty| ty.library.is_none()) {
+        validator.check_type_def(ty);
+    }
+    validator.check_recursive_types();
+    validator.check_methods();
+    validator.check_sizes();
+
+    let mut diagnostics = validator.diagnostics;
+    diagnostics.sort_by_key(|d| (d.span, d.severity));
+    diagnostics
+}
+
+struct Validator<'a> {
+    cidl: &'a Cidl,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl<'a> Validator<'a> {
+    fn push(&mut self, diagnostic: Diagnostic) {
+        self.diagnostics.push(diagnostic);
+    }
+
+    fn check_version(&mut self) {
+        let version = &self.cidl.cidl;
+        if version.value != SUPPORTED_CIDL_VERSION {
+            self.push(
+                Diagnostic::warning(
+                    version.span,
+                    format!("unsupported CIDL version `{}`", version.value),
+                )
+                .with_help(format!("set `cidl: \"{}\"`", SUPPORTED_CIDL_VERSION)),
+            );
+        }
+    }
+
+    fn check_imports(&mut self) {
+        let imports = &self.cidl.imports;
+        self.check_duplicates(
+            imports
+                .iter()
+                .map(|import| (&import.path.value, import.path.span)),
+            "import",
+        );
+        self.check_duplicates(
+            imports
+                .iter()
+                .filter_map(|import| import.namespace.as_ref())
+                .map(|namespace| (&namespace.value, namespace.span)),
+            "namespace",
+        );
+
+        for namespace in imports
+            .iter()
+            .filter_map(|import| import.namespace.as_ref())
+        {
+            if !is_snake_case(&namespace.value) {
+                self.push(
+                    Diagnostic::error(
+                        namespace.span,
+                        format!("namespace `{}` is not a snake_case name", namespace.value),
+                    )
+                    .with_help("types are referenced as `namespace.Type`"),
+                );
+            }
+        }
+    }
+
+    fn check_type_def(&mut self, ty: &TypeDef) {
+        if PRIMITIVE_TYPES.contains(&ty.name.value.as_str()) {
+            self.push(Diagnostic::error(
+                ty.name.span,
+                format!("type `{}` shadows a built-in type", ty.name.value),
+            ));
+        }
+
+        self.check_duplicates(
+            ty.fields
+                .iter()
+                .map(|field| (&field.name.value, field.name.span)),
+            &format!("field in type `{}`", ty.name.value),
+        );
+
+        for field in &ty.fields {
+            let what = format!("field `{}`", field.name.value);
+            self.check_type_ref(&field.ty.value, field.ty.span, Context::State, field, &what);
+            self.check_cap(&field.ty.value, field, &what);
+
+            for attribute in field.solana.iter().flat_map(|solana| &solana.attributes) {
+                match &attribute.value {
+                    Attribute::Cap(_) => {}
+                    Attribute::Other(other) => self.unknown_attribute(other, attribute.span),
+                    other => self.push(Diagnostic::warning(
+                        attribute.span,
+                        format!("attribute `{}` has no effect on fields", other),
+                    )),
+                }
+            }
+        }
+
+        if let Some(solana) = &ty.solana {
+            self.check_seeds(ty, solana);
+            self.check_token_program_type(ty, solana);
+        }
+    }
+
+    // The token program lays out its accounts, and mints are created outside of the program
+    fn check_token_program_type(&mut self, ty: &TypeDef, solana: &SolanaType) {
+        if solana.is_token_program() && !ty.fields.is_empty() {
+            self.push(
+                Diagnostic::error(
+                    ty.name.span,
+                    format!(
+                        "type `{}` is an account of the SPL token program and can't have fields",
+                        ty.name.value
+                    ),
+                )
+                .with_help("the token program defines its layout, remove `fields`"),
+            );
+        }
+        if solana.is_mint() && !solana.seeds.is_empty() {
+            self.push(
+                Diagnostic::error(
+                    solana.span,
+                    format!("mint `{}` can't have seeds", ty.name.value),
+                )
+                .with_help("mints are created outside of the program, remove `seeds`"),
+            );
+        }
+    }
+
+    fn check_seeds(&mut self, ty: &TypeDef, solana: &SolanaType) {
+        self.check_duplicates(
+            solana
+                .seeds
+                .iter()
+                .map(|seed| (&seed.name.value, seed.name.span)),
+            &format!("seed in type `{}`", ty.name.value),
+        );
+
+        for seed in &solana.seeds {
+            let ty = match &seed.ty {
+                Some(ty) => ty,
+                None => {
+                    if seed.name.value.len() > MAX_SEED_LEN {
+                        self.push(Diagnostic::error(
+                            seed.name.span,
+                            format!(
+                                "constant seed `{}` is longer than {} bytes",
+                                seed.name.value, MAX_SEED_LEN
+                            ),
+                        ));
+                    }
+                    continue;
+                }
+            };
+
+            match &ty.value {
+                TypeRef::Named(name) if self.cidl.find_type(name).is_none() => {
+                    self.unknown_type(name, ty.span)
+                }
+                TypeRef::Pubkey
+                | TypeRef::String
+                | TypeRef::U8
+                | TypeRef::U16
+                | TypeRef::U32
+                | TypeRef::U64
+                | TypeRef::U128
+                | TypeRef::I8
+                | TypeRef::I16
+                | TypeRef::I32
+                | TypeRef::I64
+                | TypeRef::I128 => {}
+                other => self.push(
+                    Diagnostic::error(
+                        ty.span,
+                        format!(
+                            "type `{}` cannot be used as seed `{}`",
+                            other, seed.name.value
+                        ),
+                    )
+                    .with_help("seeds can be `sol:pubkey`, `string` or an integer type"),
+                ),
+            }
+        }
+    }
+
+    fn check_recursive_types(&mut self) {
+        for ty in self.cidl.types.iter().filter(|ty| ty.library.is_none()) {
+            if self.contains_type(ty, &ty.name.value, &mut Vec::new()) {
+                self.push(Diagnostic::error(
+                    ty.name.span,
+                    format!(
+                        "type `{}` contains itself and has no fixed packed size",
+                        ty.name.value
+                    ),
+                ));
+            }
+        }
+    }
+
+    fn contains_type(&self, ty: &TypeDef, target: &str, visited: &mut Vec<String>) -> bool {
+        for field in &ty.fields {
+            let name = match named_type(&field.ty.value) {
+                Some(name) => name,
+                None => continue,
+            };
+            if name == target {
+                return true;
+            }
+            if visited.iter().any(|v| v == name) {
+                continue;
+            }
+            visited.push(name.to_string());
+            if let Some(inner) = self.cidl.find_type(name) {
+                if self.contains_type(inner, target, visited) {
+                    return true;
+                }
+            }
+        }
+        false
+    }
+
+    fn check_methods(&mut self) {
+        self.check_duplicates(
+            self.cidl
+                .methods
+                .iter()
+                .map(|method| (&method.name.value, method.name.span)),
+            "method",
+        );
+
+        for method in &self.cidl.methods {
+            self.check_duplicates(
+                method
+                    .inputs
+                    .iter()
+                    .map(|input| (&input.name.value, input.name.span)),
+                &format!("input in method `{}`", method.name.value),
+            );
+
+            for input in &method.inputs {
+                self.check_input(input);
+            }
+            self.check_token_mint(method);
+        }
+    }
+
+    // A token account is created for the mint passed to the same method
+    fn check_token_mint(&mut self, method: &Method) {
+        let solana = |input: &Input| match &input.ty.value {
+            TypeRef::Named(name) => self.cidl.find_type(name).and_then(|ty| ty.solana.as_ref()),
+            _ => None,
+        };
+        let created = method.inputs.iter().find(|input| {
+            solana(input).is_some_and(SolanaType::is_token_account)
+                && input.has_attribute(&Attribute::Init)
+        });
+        let mints = method
+            .inputs
+            .iter()
+            .filter(|input| solana(input).is_some_and(SolanaType::is_mint))
+            .count();
+
+        if let Some(created) = created {
+            if mints != 1 {
+                self.push(
+                    Diagnostic::error(
+                        created.name.span,
+                        format!(
+                            "`init` on input `{}` needs exactly one mint input in method `{}`, found {}",
+                            created.name.value, method.name.value, mints
+                        ),
+                    )
+                    .with_help(format!(
+                        "add an input whose type has `owner: {}`",
+                        MINT_OWNER
+                    )),
+                );
+            }
+        }
+    }
+
+    fn check_input(&mut self, input: &Input) {
+        let what = format!("input `{}`", input.name.value);
+        self.check_type_ref(
+            &input.ty.value,
+            input.ty.span,
+            Context::Instruction,
+            input,
+            &what,
+        );
+        self.check_cap(&input.ty.value, input, &what);
+
+        // Unknown types are already reported, don't pile account warnings on top
+        let (is_known, is_account) = match &input.ty.value {
+            TypeRef::Named(name) => match self.cidl.find_type(name) {
+                Some(ty) => (true, ty.solana.is_some()),
+                None => (false, false),
+            },
+            _ => (true, false),
+        };
+        let solana = match &input.solana {
+            Some(solana) => solana,
+            None => return,
+        };
+
+        for attribute in &solana.attributes {
+            match &attribute.value {
+                Attribute::Cap(_) => {}
+                Attribute::Other(other) => self.unknown_attribute(other, attribute.span),
+                other if is_known && !is_account => self.push(Diagnostic::warning(
+                    attribute.span,
+                    format!(
+                        "attribute `{}` has no effect on {}, it is not an account",
+                        other, what
+                    ),
+                )),
+                _ => {}
+            }
+        }
+
+        if !is_account {
+            return;
+        }
+
+        let has_init = solana.has(&Attribute::Init);
+        let has_init_if_needed = solana.has(&Attribute::InitIfNeeded);
+        if (has_init || has_init_if_needed) && !solana.has(&Attribute::Mut) {
+            let init = if has_init { "init" } else { "init_if_needed" };
+            self.push(
+                Diagnostic::error(
+                    solana.span,
+                    format!("`{}` on {} requires `mut`", init, what),
+                )
+                .with_help(format!("use `attributes: [ mut, {} ]`", init)),
+            );
+        }
+        if has_init && has_init_if_needed {
+            self.push(
+                Diagnostic::error(
+                    solana.span,
+                    format!("{} has both `init` and `init_if_needed`", what),
+                )
+                .with_help("keep only one of them"),
+            );
+        }
+
+        if let TypeRef::Named(name) = &input.ty.value {
+            if let Some(ty) = self.cidl.find_type(name).filter(|ty| ty.solana.is_some()) {
+                if ty.is_token_program() {
+                    self.check_token_program_input(ty, solana, &what);
+                } else {
+                    self.check_role(ty, solana, &what);
+                    self.check_owner_only(ty, solana, &what, Attribute::Migrate, "migrated");
+                    self.check_owner_only(ty, solana, &what, Attribute::Close, "closed");
+                }
+            }
+        }
+    }
+
+    // The program only reads and moves the tokens of these accounts, and creates token accounts
+    // at its own PDAs
+    fn check_token_program_input(&mut self, ty: &TypeDef, solana: &SolanaAttributes, what: &str) {
+        let creatable = ty
+            .solana
+            .as_ref()
+            .is_some_and(|solana| solana.is_token_account() && !solana.seeds.is_empty());
+
+        for attribute in &solana.attributes {
+            let supported = match attribute.value {
+                Attribute::Mut | Attribute::Cap(_) | Attribute::Other(_) => true,
+                Attribute::Init => creatable,
+                _ => false,
+            };
+            if !supported {
+                self.push(
+                    Diagnostic::error(
+                        attribute.span,
+                        format!(
+                            "`{}` is not supported on {}, an account of the SPL token program",
+                            attribute.value, what
+                        ),
+                    )
+                    .with_help("token accounts with seeds take `init`, the others only `mut`"),
+                );
+            }
+        }
+    }
+
+    // Delegates are looked up in the account before it is written, so it must exist, belong to a
+    // wallet and hold them
+    fn check_role(&mut self, ty: &TypeDef, solana: &SolanaAttributes, what: &str) {
+        let (role, span) = match solana.attributes.iter().find_map(|a| match a.value {
+            Attribute::Role(role) => Some((role, a.span)),
+            _ => None,
+        }) {
+            Some(found) => found,
+            None => return,
+        };
+
+        if !solana.has(&Attribute::Mut) {
+            self.push(
+                Diagnostic::error(span, format!("`role:{}` on {} requires `mut`", role, what))
+                    .with_help(format!("use `attributes: [ mut, role:{} ]`", role)),
+            );
+        }
+        if solana.has(&Attribute::Init) || solana.has(&Attribute::InitIfNeeded) {
+            self.push(
+                Diagnostic::error(
+                    span,
+                    format!(
+                        "`role:{}` on {} is not allowed on accounts it creates",
+                        role, what
+                    ),
+                )
+                .with_help("only the owner can create the account, remove `role`"),
+            );
+        }
+
+        let has_owner = ty
+            .solana
+            .iter()
+            .flat_map(|solana| &solana.seeds)
+            .any(|seed| {
+                seed.ty
+                    .as_ref()
+                    .is_some_and(|ty| ty.value == TypeRef::Pubkey)
+            });
+        if !has_owner {
+            self.push(
+                Diagnostic::error(
+                    span,
+                    format!(
+                        "`role:{}` on {} needs a `sol:pubkey` seed in `{}`, the owner of the account",
+                        role, what, ty.name.value
+                    ),
+                )
+                .with_help("add a seed like `- name: owner\n  type: sol:pubkey`"),
+            );
+        }
+
+        let delegates = TypeRef::Vec(Box::new(TypeRef::Delegate));
+        let fields = ty
+            .fields
+            .iter()
+            .filter(|field| field.ty.value == delegates)
+            .count();
+        if fields != 1 {
+            self.push(
+                Diagnostic::error(
+                    span,
+                    format!(
+                        "`role:{}` on {} needs exactly one `vec<sol:delegate>` field in `{}`, found {}",
+                        role, what, ty.name.value, fields
+                    ),
+                )
+                .with_help("the delegates allowed to sign for the owner are read from it"),
+            );
+        }
+    }
+
+    // Old layouts can't be loaded and closed accounts are gone, so for `migrate` and `close`
+    // only the owner may sign and the account must already exist. `done` names the accounts
+    // in the help, "migrated" or "closed"
+    fn check_owner_only(
+        &mut self,
+        ty: &TypeDef,
+        solana: &SolanaAttributes,
+        what: &str,
+        attribute: Attribute,
+        done: &str,
+    ) {
+        let span = match solana.attributes.iter().find(|a| a.value == attribute) {
+            Some(attribute) => attribute.span,
+            None => return,
+        };
+
+        if !solana.has(&Attribute::Mut) {
+            self.push(
+                Diagnostic::error(span, format!("`{}` on {} requires `mut`", attribute, what))
+                    .with_help(format!("use `attributes: [ mut, {} ]`", attribute)),
+            );
+        }
+        let conflicting = solana.attributes.iter().find(|a| {
+            matches!(
+                a.value,
+                Attribute::Init
+                    | Attribute::InitIfNeeded
+                    | Attribute::Role(_)
+                    | Attribute::Migrate
+                    | Attribute::Close
+            ) && a.value != attribute
+        });
+        if let Some(conflicting) = conflicting {
+            self.push(
+                Diagnostic::error(
+                    span,
+                    format!(
+                        "`{}` on {} can't be combined with `{}`",
+                        attribute, what, conflicting.value
+                    ),
+                )
+                .with_help(format!(
+                    "{} accounts already exist and only their owner signs",
+                    done
+                )),
+            );
+        }
+
+        let has_owner = ty
+            .solana
+            .iter()
+            .flat_map(|solana| &solana.seeds)
+            .any(|seed| {
+                seed.ty
+                    .as_ref()
+                    .is_some_and(|ty| ty.value == TypeRef::Pubkey)
+            });
+        if !has_owner {
+            self.push(
+                Diagnostic::error(
+                    span,
+                    format!(
+                        "`{}` on {} needs a `sol:pubkey` seed in `{}`, the owner of the account",
+                        attribute, what, ty.name.value
+                    ),
+                )
+                .with_help("add a seed like `- name: owner\n  type: sol:pubkey`"),
+            );
+        }
+    }
+
+    fn check_type_ref<T: HasAttributes>(
+        &mut self,
+        ty: &TypeRef,
+        span: Span,
+        context: Context,
+        owner: &T,
+        what: &str,
+    ) {
+        if !self.check_type_tree(ty, span, what) {
+            return;
+        }
+
+        if context == Context::State && needs_cap(ty, owner) {
+            self.push(
+                Diagnostic::error(span, format!("`{}` {} has no `cap` attribute", ty, what))
+                    .with_help("add `solana: { attributes: [ cap:N ] }` with the maximum length"),
+            );
+        }
+    }
+
+    // Checks every type in the tree is defined and packable, returns false if it is not
+    fn check_type_tree(&mut self, ty: &TypeRef, span: Span, what: &str) -> bool {
+        match ty {
+            TypeRef::Named(name) => {
+                if self.cidl.find_type(name).is_none() {
+                    self.unknown_type(name, span);
+                    return false;
+                }
+                true
+            }
+            TypeRef::Option(inner) => self.check_type_tree(inner, span, what),
+            TypeRef::Vec(inner) => {
+                if !is_fixed_size(inner) {
+                    self.push(
+                        Diagnostic::error(
+                            span,
+                            format!(
+                                "codigolib cannot pack `{}` of {}, vector elements must have a fixed size",
+                                ty, what
+                            ),
+                        )
+                        .with_help("use a custom type with capped fields as the element type"),
+                    );
+                    return false;
+                }
+                self.check_type_tree(inner, span, what)
+            }
+            _ => true,
+        }
+    }
+
+    fn check_cap<T: HasAttributes>(&mut self, ty: &TypeRef, owner: &T, what: &str) {
+        let cap = match owner.cap() {
+            Some(cap) => cap,
+            None => return,
+        };
+        if cap > MAX_ACCOUNT_DATA_LEN {
+            self.push(
+                Diagnostic::error(
+                    cap_span(owner),
+                    format!(
+                        "`cap:{}` of {} is larger than the largest account, {} bytes",
+                        cap, what, MAX_ACCOUNT_DATA_LEN
+                    ),
+                )
+                .with_help("use a capacity that fits in the account data"),
+            );
+        }
+        if !capped_types(ty).is_empty() {
+            return;
+        }
+
+        self.push(
+            Diagnostic::warning(
+                cap_span(owner),
+                format!("`cap` has no effect on `{}` {}", ty, what),
+            )
+            .with_help("only `string` and vector types use a capacity"),
+        );
+    }
+
+    // Types of a valid CIDL have a size, unless adding up their fields overflows
+    fn check_sizes(&mut self) {
+        if has_errors(&self.diagnostics) {
+            return;
+        }
+
+        for ty in self.cidl.types.iter().filter(|ty| ty.library.is_none()) {
+            if type_size(self.cidl, ty).is_none() {
+                self.push(Diagnostic::error(
+                    ty.name.span,
+                    format!("the packed size of type `{}` overflows", ty.name.value),
+                ));
+            }
+        }
+    }
+
+    fn check_duplicates<'b, I>(&mut self, names: I, what: &str)
+    where
+        I: Iterator<Item = (&'b String, Span)>,
+    {
+        let mut seen: HashMap<&String, Span> = HashMap::new();
+        for (name, span) in names {
+            match seen.get(name) {
+                Some(first) => {
+                    let first = *first;
+                    self.push(
+                        Diagnostic::error(span, format!("duplicate {} `{}`", what, name))
+                            .with_help(format!("first defined at {}", first)),
+                    )
+                }
+                None => {
+                    seen.insert(name, span);
+                }
+            }
+        }
+    }
+
+    fn unknown_type(&mut self, name: &str, span: Span) {
+        let candidates = self
+            .cidl
+            .types
+            .iter()
+            .filter(|ty| ty.library.is_none())
+            .map(|ty| ty.name.value.as_str())
+            .chain(PRIMITIVE_TYPES.iter().copied());
+
+        let help = match closest(name, candidates) {
+            Some(candidate) => format!("did you mean `{}`?", candidate),
+            None => format!("define `{}` under `types`", name),
+        };
+        self.push(Diagnostic::error(span, format!("unknown type `{}`", name)).with_help(help));
+    }
+
+    fn check_unknown_keys(&mut self) {
+        for unknown in &self.cidl.unknown_keys {
+            let key = &unknown.key;
+            let help = match closest(&key.value, unknown.expected.iter().copied()) {
+                Some(candidate) => format!("did you mean `{}`?", candidate),
+                None => format!("expected one of `{}`", unknown.expected.join("`, `")),
+            };
+            self.push(
+                Diagnostic::warning(key.span, format!("unknown key `{}`", key.value))
+                    .with_help(help),
+            );
+        }
+    }
+
+    fn unknown_attribute(&mut self, attribute: &str, span: Span) {
+        let candidates = ["mut", "init", "init_if_needed", "signer", "cap", "role"];
+
+        let mut diagnostic =
+            Diagnostic::warning(span, format!("unknown attribute `{}`", attribute));
+        if let Some(candidate) = closest(attribute, candidates.into_iter()) {
+            diagnostic = diagnostic.with_help(format!("did you mean `{}`?", candidate));
+        }
+        self.push(diagnostic);
+    }
+}
+
+fn cap_span<T: HasAttributes>(owner: &T) -> Span {
+    owner
+        .solana()
+        .and_then(|solana| {
+            solana
+                .attributes
+                .iter()
+                .find(|a| matches!(a.value, Attribute::Cap(_)))
+        })
+        .map(|attribute| attribute.span)
+        .unwrap_or_default()
+}
+
+// A string or vector (maybe optional) stored in an account takes its size from `cap`
+fn needs_cap<T: HasAttributes>(ty: &TypeRef, owner: &T) -> bool {
+    let root = match ty {
+        TypeRef::Option(inner) => inner,
+        ty => ty,
+    };
+    is_capped(root) && owner.cap().is_none()
+}
+
+fn named_type(ty: &TypeRef) -> Option<&str> {
+    match ty {
+        TypeRef::Named(name) => Some(name),
+        TypeRef::Option(inner) | TypeRef::Vec(inner) => named_type(inner),
+        _ => None,
+    }
+}
+
+fn closest<'b, I: Iterator<Item = &'b str>>(name: &str, candidates: I) -> Option<&'b str> {
+    candidates
+        .map(|candidate| (edit_distance(name, candidate), candidate))
+        .filter(|(distance, candidate)| *distance <= 2.max(candidate.len() / 3))
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate)
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.to_lowercase().chars().collect();
+    let b: Vec<char> = b.to_lowercase().chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, ca) in a.iter().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != cb);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+
+    previous[b.len()]
+}
@@ -0,0 +1,778 @@
+use crate::parser::parse;
+use crate::validator::*;
+use crate::yaml::Span;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BUDGET_TRACKER: &str = include_str!("../../cidl.yaml");
+
+    fn diagnostics(body: &str) -> Vec<Diagnostic> {
+        let source = format!("cidl: \"0.8\"\ninfo:\n  name: test\n{}", body);
+        validate(&parse(&source).unwrap())
+    }
+
+    fn messages(diagnostics: &[Diagnostic]) -> Vec<(Severity, String)> {
+        diagnostics
+            .iter()
+            .map(|d| (d.severity, d.message.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn it_accepts_budget_tracker() {
+        let diagnostics = validate(&parse(BUDGET_TRACKER).unwrap());
+
+        assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+    }
+
+    #[test]
+    fn it_reports_string_field_without_cap() {
+        let diagnostics = diagnostics(
+            "types:
+  Record:
+    fields:
+      - name: name
+        type: string
+      - name: tags
+        type: option<vec<u8>>
+",
+        );
+
+        assert_eq!(
+            messages(&diagnostics),
+            vec![
+                (
+                    Severity::Error,
+                    "`string` field `name` has no `cap` attribute".to_string()
+                ),
+                (
+                    Severity::Error,
+                    "`option<vec<u8>>` field `tags` has no `cap` attribute".to_string()
+                ),
+            ]
+        );
+        assert_eq!(diagnostics[0].span, Span { line: 8, col: 15 });
+        assert!(diagnostics[0].help.as_ref().unwrap().contains("cap:N"));
+        assert!(has_errors(&diagnostics));
+    }
+
+    #[test]
+    fn it_accepts_uncapped_strings_in_instructions() {
+        let diagnostics = diagnostics(
+            "methods:
+  - name: rename
+    inputs:
+      - name: name
+        type: string
+      - name: amounts
+        type: vec<u32>
+",
+        );
+
+        assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+    }
+
+    #[test]
+    fn it_reports_unknown_seed_type() {
+        let diagnostics = diagnostics(
+            "types:
+  Record:
+    solana:
+      owner: self
+      seeds:
+        - name: record
+        - name: signer
+          type: sol:pubky
+        - name: ratio
+          type: f32
+",
+        );
+
+        assert_eq!(
+            messages(&diagnostics),
+            vec![
+                (Severity::Error, "unknown type `sol:pubky`".to_string()),
+                (
+                    Severity::Error,
+                    "type `f32` cannot be used as seed `ratio`".to_string()
+                ),
+            ]
+        );
+        assert_eq!(diagnostics[0].span, Span { line: 11, col: 17 });
+        assert_eq!(
+            diagnostics[0].help,
+            Some("did you mean `sol:pubkey`?".to_string())
+        );
+    }
+
+    #[test]
+    fn it_reports_long_and_duplicate_seeds() {
+        let diagnostics = diagnostics(
+            "types:
+  Record:
+    solana:
+      seeds:
+        - name: a_constant_seed_longer_than_32_bytes
+        - name: signer
+          type: sol:pubkey
+        - name: signer
+          type: sol:pubkey
+",
+        );
+
+        assert_eq!(
+            messages(&diagnostics),
+            vec![
+                (
+                    Severity::Error,
+                    "constant seed `a_constant_seed_longer_than_32_bytes` is longer than 32 bytes"
+                        .to_string()
+                ),
+                (
+                    Severity::Error,
+                    "duplicate seed in type `Record` `signer`".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn it_reports_undefined_input_type() {
+        let diagnostics = diagnostics(
+            "types:
+  Record:
+    solana:
+      owner: self
+methods:
+  - name: register_income
+    inputs:
+      - name: user_record
+        type: Recrod
+        solana:
+          attributes: [ mut ]
+      - name: amount
+        type: Amount
+",
+        );
+
+        assert_eq!(
+            messages(&diagnostics),
+            vec![
+                (Severity::Error, "unknown type `Recrod`".to_string()),
+                (Severity::Error, "unknown type `Amount`".to_string()),
+            ]
+        );
+        assert_eq!(
+            diagnostics[0].help,
+            Some("did you mean `Record`?".to_string())
+        );
+        assert_eq!(
+            diagnostics[1].help,
+            Some("define `Amount` under `types`".to_string())
+        );
+    }
+
+    #[test]
+    fn it_reports_duplicate_names() {
+        let diagnostics = diagnostics(
+            "types:
+  Record:
+    fields:
+      - name: income
+        type: u32
+      - name: income
+        type: u64
+methods:
+  - name: run
+    inputs:
+      - name: amount
+        type: u32
+      - name: amount
+        type: u32
+  - name: run
+",
+        );
+
+        assert_eq!(
+            messages(&diagnostics),
+            vec![
+                (
+                    Severity::Error,
+                    "duplicate field in type `Record` `income`".to_string()
+                ),
+                (
+                    Severity::Error,
+                    "duplicate input in method `run` `amount`".to_string()
+                ),
+                (Severity::Error, "duplicate method `run`".to_string()),
+            ]
+        );
+        assert_eq!(
+            diagnostics[0].help,
+            Some("first defined at 7:15".to_string())
+        );
+    }
+
+    #[test]
+    fn it_reports_init_if_needed_without_mut() {
+        let diagnostics = diagnostics(
+            "types:
+  Record:
+    solana:
+      owner: self
+methods:
+  - name: create
+    inputs:
+      - name: user_record
+        type: Record
+        solana:
+          attributes: [ init_if_needed ]
+  - name: create_twice
+    inputs:
+      - name: user_record
+        type: Record
+        solana:
+          attributes: [ mut, init, init_if_needed ]
+",
+        );
+
+        assert_eq!(
+            messages(&diagnostics),
+            vec![
+                (
+                    Severity::Error,
+                    "`init_if_needed` on input `user_record` requires `mut`".to_string()
+                ),
+                (
+                    Severity::Error,
+                    "input `user_record` has both `init` and `init_if_needed`".to_string()
+                ),
+            ]
+        );
+        assert_eq!(
+            diagnostics[0].help,
+            Some("use `attributes: [ mut, init_if_needed ]`".to_string())
+        );
+    }
+
+    #[test]
+    fn it_reports_roles_without_delegates() {
+        let diagnostics = diagnostics(
+            "types:
+  Record:
+    solana:
+      owner: self
+      seeds:
+        - name: record
+    fields:
+      - name: moves
+        type: u16
+methods:
+  - name: register
+    inputs:
+      - name: user_record
+        type: Record
+        solana:
+          attributes: [ init_if_needed, role:contributor ]
+",
+        );
+
+        assert_eq!(
+            messages(&diagnostics),
+            vec![
+                (
+                    Severity::Error,
+                    "`init_if_needed` on input `user_record` requires `mut`".to_string()
+                ),
+                (
+                    Severity::Error,
+                    "`role:contributor` on input `user_record` requires `mut`".to_string()
+                ),
+                (
+                    Severity::Error,
+                    "`role:contributor` on input `user_record` is not allowed on accounts it creates"
+                        .to_string()
+                ),
+                (
+                    Severity::Error,
+                    "`role:contributor` on input `user_record` needs a `sol:pubkey` seed in `Record`, the owner of the account"
+                        .to_string()
+                ),
+                (
+                    Severity::Error,
+                    "`role:contributor` on input `user_record` needs exactly one `vec<sol:delegate>` field in `Record`, found 0"
+                        .to_string()
+                ),
+            ]
+        );
+        assert_eq!(
+            diagnostics[1].help,
+            Some("use `attributes: [ mut, role:contributor ]`".to_string())
+        );
+    }
+
+    #[test]
+    fn it_reports_migrate_misuse() {
+        let diagnostics = diagnostics(
+            "types:
+  Record:
+    solana:
+      owner: self
+      seeds:
+        - name: record
+    fields:
+      - name: moves
+        type: u16
+methods:
+  - name: migrate
+    inputs:
+      - name: user_record
+        type: Record
+        solana:
+          attributes: [ migrate, init ]
+",
+        );
+
+        assert_eq!(
+            messages(&diagnostics),
+            vec![
+                (
+                    Severity::Error,
+                    "`init` on input `user_record` requires `mut`".to_string()
+                ),
+                (
+                    Severity::Error,
+                    "`migrate` on input `user_record` requires `mut`".to_string()
+                ),
+                (
+                    Severity::Error,
+                    "`migrate` on input `user_record` can't be combined with `init`".to_string()
+                ),
+                (
+                    Severity::Error,
+                    "`migrate` on input `user_record` needs a `sol:pubkey` seed in `Record`, the owner of the account"
+                        .to_string()
+                ),
+            ]
+        );
+        assert_eq!(
+            diagnostics[1].help,
+            Some("use `attributes: [ mut, migrate ]`".to_string())
+        );
+    }
+
+    #[test]
+    fn it_reports_close_misuse() {
+        let diagnostics = diagnostics(
+            "types:
+  Record:
+    solana:
+      owner: self
+      seeds:
+        - name: record
+        - name: signer
+          type: sol:pubkey
+    fields:
+      - name: moves
+        type: u16
+methods:
+  - name: close
+    inputs:
+      - name: user_record
+        type: Record
+        solana:
+          attributes: [ mut, close, init_if_needed ]
+",
+        );
+
+        assert_eq!(
+            messages(&diagnostics),
+            vec![(
+                Severity::Error,
+                "`close` on input `user_record` can't be combined with `init_if_needed`"
+                    .to_string()
+            )]
+        );
+        assert_eq!(
+            diagnostics[0].help,
+            Some("closed accounts already exist and only their owner signs".to_string())
+        );
+    }
+
+    #[test]
+    fn it_reports_token_account_misuse() {
+        let diagnostics = diagnostics(
+            "types:
+  Vault:
+    solana:
+      owner: spl-token-account
+      seeds:
+        - name: vault
+    fields:
+      - name: amount
+        type: u64
+  Wallet:
+    solana:
+      owner: spl-token-account
+  Mint:
+    solana:
+      owner: spl-token-mint
+      seeds:
+        - name: mint
+methods:
+  - name: bind
+    inputs:
+      - name: vault
+        type: Vault
+        solana:
+          attributes: [ mut, init ]
+      - name: wallet
+        type: Wallet
+        solana:
+          attributes: [ mut, init ]
+",
+        );
+
+        assert_eq!(
+            messages(&diagnostics),
+            vec![
+                (
+                    Severity::Error,
+                    "type `Vault` is an account of the SPL token program and can't have fields"
+                        .to_string()
+                ),
+                (Severity::Error, "mint `Mint` can't have seeds".to_string()),
+                (
+                    Severity::Error,
+                    "`init` on input `vault` needs exactly one mint input in method `bind`, found 0"
+                        .to_string()
+                ),
+                (
+                    Severity::Error,
+                    "`init` is not supported on input `wallet`, an account of the SPL token program"
+                        .to_string()
+                ),
+            ]
+        );
+        assert_eq!(
+            diagnostics[2].help,
+            Some("add an input whose type has `owner: spl-token-mint`".to_string())
+        );
+    }
+
+    #[test]
+    fn it_accepts_roles_on_accounts_with_delegates() {
+        let diagnostics = diagnostics(
+            "types:
+  Record:
+    solana:
+      owner: self
+      seeds:
+        - name: owner
+          type: sol:pubkey
+    fields:
+      - name: delegates
+        type: vec<sol:delegate>
+        solana:
+          attributes: [ cap:3 ]
+methods:
+  - name: register
+    inputs:
+      - name: user_record
+        type: Record
+        solana:
+          attributes: [ mut, role:viewer ]
+      - name: delegate
+        type: sol:delegate
+",
+        );
+
+        assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+    }
+
+    #[test]
+    fn it_warns_about_ineffective_attributes() {
+        let diagnostics = diagnostics(
+            "types:
+  Record:
+    fields:
+      - name: moves
+        type: u16
+        solana:
+          attributes: [ cap:10, mut ]
+methods:
+  - name: run
+    inputs:
+      - name: amount
+        type: u32
+        solana:
+          attributes: [ signer, imut ]
+",
+        );
+
+        assert_eq!(
+            messages(&diagnostics),
+            vec![
+                (
+                    Severity::Warning,
+                    "`cap` has no effect on `u16` field `moves`".to_string()
+                ),
+                (
+                    Severity::Warning,
+                    "attribute `mut` has no effect on fields".to_string()
+                ),
+                (
+                    Severity::Warning,
+                    "attribute `signer` has no effect on input `amount`, it is not an account"
+                        .to_string()
+                ),
+                (Severity::Warning, "unknown attribute `imut`".to_string()),
+            ]
+        );
+        assert_eq!(diagnostics[3].help, Some("did you mean `mut`?".to_string()));
+        assert!(!has_errors(&diagnostics));
+    }
+
+    #[test]
+    fn it_reports_types_codigolib_cannot_pack() {
+        let diagnostics = diagnostics(
+            "types:
+  Record:
+    fields:
+      - name: names
+        type: vec<string>
+        solana:
+          attributes: [ cap:5 ]
+methods:
+  - name: run
+    inputs:
+      - name: batches
+        type: vec<vec<u8>>
+",
+        );
+
+        assert_eq!(
+            messages(&diagnostics),
+            vec![
+                (
+                    Severity::Error,
+                    "codigolib cannot pack `vec<string>` of field `names`, vector elements must have a fixed size"
+                        .to_string()
+                ),
+                (
+                    Severity::Error,
+                    "codigolib cannot pack `vec<vec<u8>>` of input `batches`, vector elements must have a fixed size"
+                        .to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn it_reports_recursive_types() {
+        let diagnostics = diagnostics(
+            "types:
+  Node:
+    fields:
+      - name: next
+        type: option<Link>
+  Link:
+    fields:
+      - name: node
+        type: Node
+  Leaf:
+    fields:
+      - name: node
+        type: Node
+",
+        );
+
+        assert_eq!(
+            messages(&diagnostics),
+            vec![
+                (
+                    Severity::Error,
+                    "type `Node` contains itself and has no fixed packed size".to_string()
+                ),
+                (
+                    Severity::Error,
+                    "type `Link` contains itself and has no fixed packed size".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn it_reports_caps_larger_than_an_account() {
+        let diagnostics = diagnostics(
+            "types:
+  Record:
+    fields:
+      - name: name
+        type: string
+        solana:
+          attributes: [ cap:18446744073709551615 ]
+      - name: tags
+        type: vec<u8>
+        solana:
+          attributes: [ cap:10485760 ]
+",
+        );
+
+        assert_eq!(
+            messages(&diagnostics),
+            vec![(
+                Severity::Error,
+                "`cap:18446744073709551615` of field `name` is larger than the largest account, 10485760 bytes"
+                    .to_string()
+            )]
+        );
+        assert_eq!(diagnostics[0].span, Span { line: 10, col: 25 });
+    }
+
+    #[test]
+    fn it_reports_sizes_that_overflow() {
+        let diagnostics = diagnostics(
+            "types:
+  Inner:
+    fields:
+      - name: values
+        type: vec<u128>
+        solana:
+          attributes: [ cap:10485760 ]
+  Outer:
+    fields:
+      - name: inners
+        type: vec<Inner>
+        solana:
+          attributes: [ cap:10485760 ]
+  Record:
+    fields:
+      - name: outers
+        type: vec<Outer>
+        solana:
+          attributes: [ cap:10485760 ]
+",
+        );
+
+        assert_eq!(
+            messages(&diagnostics),
+            vec![(
+                Severity::Error,
+                "the packed size of type `Record` overflows".to_string()
+            )]
+        );
+    }
+
+    #[test]
+    fn it_warns_about_unknown_keys() {
+        let diagnostics = diagnostics(
+            "types:
+  Record:
+    fields:
+      - name: name
+        type: string
+        solana:
+          atributes: [ cap:50 ]
+        default: none
+methods:
+  - name: run
+    input: []
+",
+        );
+
+        assert_eq!(
+            messages(&diagnostics),
+            vec![
+                (
+                    Severity::Error,
+                    "`string` field `name` has no `cap` attribute".to_string()
+                ),
+                (Severity::Warning, "unknown key `atributes`".to_string()),
+                (Severity::Warning, "unknown key `default`".to_string()),
+                (Severity::Warning, "unknown key `input`".to_string()),
+            ]
+        );
+        assert_eq!(diagnostics[1].span, Span { line: 10, col: 11 });
+        assert_eq!(
+            diagnostics[1].help.as_deref(),
+            Some("did you mean `attributes`?")
+        );
+        assert_eq!(
+            diagnostics[2].help.as_deref(),
+            Some("expected one of `name`, `type`, `description`, `solana`")
+        );
+        assert_eq!(
+            diagnostics[3].help.as_deref(),
+            Some("did you mean `inputs`?")
+        );
+    }
+
+    #[test]
+    fn it_reports_unsupported_version_and_shadowed_types() {
+        let source = "cidl: \"0.9\"
+info:
+  name: test
+types:
+  u8:
+    fields: []
+";
+        let diagnostics = validate(&parse(source).unwrap());
+
+        assert_eq!(
+            messages(&diagnostics),
+            vec![
+                (
+                    Severity::Warning,
+                    "unsupported CIDL version `0.9`".to_string()
+                ),
+                (
+                    Severity::Error,
+                    "type `u8` shadows a built-in type".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn it_reports_invalid_imports() {
+        let diagnostics = diagnostics(
+            "imports:
+  - path: common.yaml
+    as: Common
+  - path: common.yaml
+    as: shared
+  - path: units.yaml
+    as: shared
+",
+        );
+
+        assert_eq!(
+            messages(&diagnostics),
+            vec![
+                (
+                    Severity::Error,
+                    "namespace `Common` is not a snake_case name".to_string()
+                ),
+                (
+                    Severity::Error,
+                    "duplicate import `common.yaml`".to_string()
+                ),
+                (Severity::Error, "duplicate namespace `shared`".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn it_formats_diagnostics() {
+        let diagnostic = Diagnostic::error(Span { line: 3, col: 7 }, "unknown type `Recrod`")
+            .with_help("did you mean `Record`?");
+
+        assert_eq!(
+            diagnostic.to_string(),
+            "3:7: error: unknown type `Recrod`\n  help: did you mean `Record`?"
+        );
+    }
+}
@@ -0,0 +1,320 @@
+use crate::errors::ParseError;
+use std::collections::HashMap;
+use std::fmt;
+use yaml_rust::parser::{Event, MarkedEventReceiver, Parser};
+use yaml_rust::scanner::{Marker, TScalarStyle};
+
+// Position in the source document, both line and column start at 1
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Span {
+    pub line: usize,
+    pub col: usize,
+}
+
+impl From<Marker> for Span {
+    fn from(mark: Marker) -> Self {
+        Span {
+            line: mark.line(),
+            col: mark.col() + 1,
+        }
+    }
+}
+
+impl fmt::Display for Span {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}", self.line, self.col)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Spanned<T> {
+    pub value: T,
+    pub span: Span,
+}
+
+impl<T> Spanned<T> {
+    pub fn new(value: T, span: Span) -> Spanned<T> {
+        Spanned { value, span }
+    }
+
+    pub fn map<U, F: FnOnce(T) -> U>(self, f: F) -> Spanned<U> {
+        Spanned {
+            value: f(self.value),
+            span: self.span,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ScalarStyle {
+    Plain,
+    SingleQuoted,
+    DoubleQuoted,
+    Literal,
+    Folded,
+}
+
+impl From<TScalarStyle> for ScalarStyle {
+    fn from(style: TScalarStyle) -> Self {
+        match style {
+            TScalarStyle::SingleQuoted => ScalarStyle::SingleQuoted,
+            TScalarStyle::DoubleQuoted => ScalarStyle::DoubleQuoted,
+            TScalarStyle::Literal => ScalarStyle::Literal,
+            TScalarStyle::Foled => ScalarStyle::Folded,
+            TScalarStyle::Any | TScalarStyle::Plain => ScalarStyle::Plain,
+        }
+    }
+}
+
+pub type Entry = (Spanned<String>, Spanned<Node>);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Node {
+    Null,
+    Scalar(String, ScalarStyle),
+    Seq(Vec<Spanned<Node>>),
+    // Entries keep the order of the source document
+    Map(Vec<Entry>),
+}
+
+impl Node {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Node::Null => "null",
+            Node::Scalar(..) => "scalar",
+            Node::Seq(_) => "sequence",
+            Node::Map(_) => "mapping",
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Node::Scalar(value, _) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn get(&self, key: &str) -> Option<&Spanned<Node>> {
+        match self {
+            Node::Map(entries) => entries.iter().find(|(k, _)| k.value == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    // Number of nodes in the tree, mapping keys included
+    fn size(&self) -> usize {
+        match self {
+            Node::Null | Node::Scalar(..) => 1,
+            Node::Seq(items) => 1 + items.iter().map(|item| item.value.size()).sum::<usize>(),
+            Node::Map(entries) => {
+                1 + entries
+                    .iter()
+                    .map(|(_, value)| 1 + value.value.size())
+                    .sum::<usize>()
+            }
+        }
+    }
+}
+
+// Aliases copy the node they refer to, nested ones grow the document exponentially. A CIDL file
+// is far below this limit, a document expanding past it is rejected before it exhausts the memory
+pub const MAX_NODES: usize = 100_000;
+
+struct Frame {
+    node: Spanned<Node>,
+    anchor: usize,
+    pending_key: Option<Spanned<String>>,
+}
+
+#[derive(Default)]
+struct Loader {
+    documents: Vec<Spanned<Node>>,
+    stack: Vec<Frame>,
+    anchors: HashMap<usize, Spanned<Node>>,
+    // Nodes loaded so far, the ones copied by aliases included
+    nodes: usize,
+    error: Option<ParseError>,
+}
+
+impl Loader {
+    fn register_anchor(&mut self, anchor: usize, node: &Spanned<Node>) {
+        if anchor > 0 {
+            self.anchors.insert(anchor, node.clone());
+        }
+    }
+
+    fn insert(&mut self, node: Spanned<Node>) -> Result<(), ParseError> {
+        let frame = match self.stack.last_mut() {
+            Some(frame) => frame,
+            None => {
+                self.documents.push(node);
+                return Ok(());
+            }
+        };
+
+        match (&mut frame.node.value, frame.pending_key.take()) {
+            (Node::Seq(items), _) => items.push(node),
+            (Node::Map(entries), Some(key)) => entries.push((key, node)),
+            (Node::Map(entries), None) => {
+                let key = match node.value {
+                    Node::Scalar(key, _) => key,
+                    other => {
+                        return Err(ParseError::new(
+                            node.span,
+                            format!("mapping keys must be scalars, found a {}", other.kind()),
+                        ))
+                    }
+                };
+                if entries.iter().any(|(k, _)| k.value == key) {
+                    return Err(ParseError::new(
+                        node.span,
+                        format!("duplicate key `{}`", key),
+                    ));
+                }
+                // The mapping start marker points past the first key, use the key position instead
+                if entries.is_empty() {
+                    frame.node.span = node.span;
+                }
+                frame.pending_key = Some(Spanned::new(key, node.span));
+            }
+            _ => unreachable!("only collections are pushed to the stack"),
+        }
+
+        Ok(())
+    }
+
+    fn count(&mut self, nodes: usize, span: Span) -> Result<(), ParseError> {
+        self.nodes += nodes;
+        if self.nodes > MAX_NODES {
+            return Err(ParseError::new(
+                span,
+                format!(
+                    "the document has more than {} nodes once its aliases are expanded",
+                    MAX_NODES
+                ),
+            ));
+        }
+        Ok(())
+    }
+
+    fn handle(&mut self, event: Event, span: Span) -> Result<(), ParseError> {
+        match event {
+            Event::SequenceStart(_) | Event::MappingStart(_) | Event::Scalar(..) => {
+                self.count(1, span)?
+            }
+            Event::Alias(anchor) => {
+                let size = self
+                    .anchors
+                    .get(&anchor)
+                    .map_or(0, |node| node.value.size());
+                self.count(size, span)?
+            }
+            _ => {}
+        }
+
+        match event {
+            Event::SequenceStart(anchor) => self.stack.push(Frame {
+                node: Spanned::new(Node::Seq(Vec::new()), span),
+                anchor,
+                pending_key: None,
+            }),
+            Event::MappingStart(anchor) => self.stack.push(Frame {
+                node: Spanned::new(Node::Map(Vec::new()), span),
+                anchor,
+                pending_key: None,
+            }),
+            Event::SequenceEnd | Event::MappingEnd => {
+                let frame = self.stack.pop().expect("unbalanced collection events");
+                self.register_anchor(frame.anchor, &frame.node);
+                self.insert(frame.node)?;
+            }
+            Event::Scalar(value, style, anchor, _) => {
+                let style = ScalarStyle::from(style);
+                let node = if style == ScalarStyle::Plain && is_null(&value) {
+                    Spanned::new(Node::Null, span)
+                } else {
+                    Spanned::new(Node::Scalar(value, style), span)
+                };
+                self.register_anchor(anchor, &node);
+                self.insert(node)?;
+            }
+            Event::Alias(anchor) => {
+                let node = match self.anchors.get(&anchor) {
+                    Some(node) => Spanned::new(node.value.clone(), span),
+                    None => return Err(ParseError::new(span, "unknown alias")),
+                };
+                self.insert(node)?;
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+}
+
+impl MarkedEventReceiver for Loader {
+    fn on_event(&mut self, event: Event, mark: Marker) {
+        if self.error.is_none() {
+            if let Err(error) = self.handle(event, Span::from(mark)) {
+                self.error = Some(error);
+            }
+        }
+    }
+}
+
+fn is_null(value: &str) -> bool {
+    matches!(value, "" | "~" | "null" | "Null" | "NULL")
+}
+
+// Loads the first document of the source keeping the position of every node
+// Byte offset of the `#` opening the comment of a line. A `#` inside a quoted scalar or right
+// after a non-blank character belongs to the value
+pub fn comment_start(line: &str) -> Option<usize> {
+    let mut quote = None;
+    let mut previous = ' ';
+    let mut chars = line.char_indices().peekable();
+
+    while let Some((i, c)) = chars.next() {
+        match quote {
+            Some('"') if c == '\\' => {
+                chars.next();
+            }
+            // `''` is a quote inside a single-quoted scalar
+            Some('\'') if c == '\'' && chars.peek().is_some_and(|(_, next)| *next == '\'') => {
+                chars.next();
+            }
+            Some(open) if c == open => quote = None,
+            Some(_) => {}
+            None if c == '#' && previous.is_whitespace() => return Some(i),
+            None if (c == '"' || c == '\'')
+                && (previous.is_whitespace() || "[{,".contains(previous)) =>
+            {
+                quote = Some(c)
+            }
+            None => {}
+        }
+        previous = c;
+    }
+
+    None
+}
+
+pub fn load(source: &str) -> Result<Spanned<Node>, ParseError> {
+    let mut loader = Loader::default();
+    let mut parser = Parser::new(source.chars());
+
+    if let Err(error) = parser.load(&mut loader, false) {
+        let message = error.to_string();
+        let message = message.split(" at line ").next().unwrap_or_default();
+        return Err(ParseError::new(Span::from(*error.marker()), message));
+    }
+    if let Some(error) = loader.error {
+        return Err(error);
+    }
+
+    loader
+        .documents
+        .into_iter()
+        .next()
+        .ok_or_else(|| ParseError::new(Span { line: 1, col: 1 }, "empty document"))
+}
@@ -0,0 +1,297 @@
+use crate::mock::*;
+use crate::*;
+use budget_tracker::stubs::migrate_record::RECORD_UNVERSIONED_LEN;
+use codigolib::delegate_utils::{Delegate, DelegateRole};
+use codigolib::errors::{CodecError, SecurityError};
+use codigolib::testing::TestAccount;
+use solana_program::system_program;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn client() -> Client<MockTransport> {
+        let mut transport = MockTransport::budget_tracker();
+        let program_id = transport.program_id();
+        let fee_payer = transport.runtime.add_payer(10_000_000_000);
+
+        Client::new(transport, program_id, fee_payer)
+    }
+
+    fn record(name: &str, moves: u16, income: u32, outcome: u32, total_balance: i64) -> Record {
+        Record {
+            version: 2,
+            name: name.to_string(),
+            moves,
+            outcome,
+            income,
+            total_balance,
+            delegates: vec![],
+            mint: Pubkey::default(),
+            vault: Pubkey::default(),
+        }
+    }
+
+    // The steps of app.ts in the README
+    #[test]
+    fn it_tracks_incomes_and_outcomes() {
+        let mut client = client();
+        let fee_payer = client.fee_payer;
+
+        client.create_user_record("John Doe").unwrap();
+        assert_eq!(
+            client.get_record(&fee_payer),
+            Ok(record("John Doe", 0, 0, 0, 0))
+        );
+
+        client.register_income(100, &fee_payer).unwrap();
+        assert_eq!(
+            client.get_record(&fee_payer),
+            Ok(record("John Doe", 1, 100, 0, 100))
+        );
+
+        client.register_outcome(50, &fee_payer).unwrap();
+        assert_eq!(
+            client.get_record(&fee_payer),
+            Ok(record("John Doe", 2, 100, 50, 50))
+        );
+    }
+
+    #[test]
+    fn it_derives_the_record_address() {
+        let mut client = client();
+        let fee_payer = client.fee_payer;
+        client.create_user_record("John Doe").unwrap();
+
+        let address = client.record_address(&fee_payer);
+        let (expected, _) = Record::find_address(&client.program_id, &fee_payer);
+        assert_eq!(address, expected);
+
+        let account = client.transport.get_account(&address).unwrap().unwrap();
+        assert_eq!(account.owner, client.program_id);
+        assert_eq!(account.data.len(), Record::LEN);
+        assert!(!account.executable);
+    }
+
+    #[test]
+    fn it_returns_the_error_of_failed_transactions() {
+        let mut client = client();
+        let fee_payer = client.fee_payer;
+
+        assert_eq!(
+            client.register_income(100, &fee_payer),
+            Err(ClientError::Program(
+                SecurityError::WrongAccountOwner.into()
+            ))
+        );
+    }
+
+    #[test]
+    fn it_commits_transactions_all_or_nothing() {
+        let mut client = client();
+        let fee_payer = client.fee_payer;
+        client.create_user_record("John Doe").unwrap();
+
+        let program_id = client.program_id;
+        // `unknown` has no record
+        let unknown = Pubkey::new_unique();
+        assert_eq!(
+            client.send(&[
+                instructions::register_income(&program_id, 100, fee_payer, fee_payer),
+                instructions::register_income(&program_id, 100, unknown, fee_payer),
+            ]),
+            Err(ClientError::Program(
+                SecurityError::WrongAccountOwner.into()
+            ))
+        );
+        assert_eq!(
+            client.get_record(&fee_payer),
+            Ok(record("John Doe", 0, 0, 0, 0))
+        );
+
+        client
+            .send(&[
+                instructions::register_income(&program_id, 100, fee_payer, fee_payer),
+                instructions::register_outcome(&program_id, 30, fee_payer, fee_payer),
+            ])
+            .unwrap();
+        assert_eq!(
+            client.get_record(&fee_payer),
+            Ok(record("John Doe", 2, 100, 30, 70))
+        );
+    }
+
+    // The fee payer registers moves on the record of another wallet once it is its delegate
+    #[test]
+    fn it_registers_moves_as_a_delegate() {
+        let mut client = client();
+        let john = client.fee_payer;
+        let jane = client.transport.runtime.add_payer(10_000_000_000);
+        client.create_user_record("John Doe").unwrap();
+
+        client.fee_payer = jane;
+        assert_eq!(
+            client.register_income(100, &john),
+            Err(ClientError::Program(
+                SecurityError::UnrecognizedSignerAddress.into()
+            ))
+        );
+
+        client.fee_payer = john;
+        client
+            .add_delegate(Delegate {
+                key: jane,
+                role: DelegateRole::Contributor,
+            })
+            .unwrap();
+        client.fee_payer = jane;
+        client.register_income(100, &john).unwrap();
+        assert_eq!(client.get_record(&john).unwrap().total_balance, 100);
+
+        client.fee_payer = john;
+        client.remove_delegate(&jane).unwrap();
+        client.fee_payer = jane;
+        assert_eq!(
+            client.register_outcome(50, &john),
+            Err(ClientError::Program(
+                SecurityError::UnrecognizedSignerAddress.into()
+            ))
+        );
+        assert_eq!(
+            client.get_record(&john),
+            Ok(record("John Doe", 1, 100, 0, 100))
+        );
+    }
+
+    #[test]
+    fn it_fails_to_get_missing_records() {
+        let client = client();
+        let fee_payer = client.fee_payer;
+
+        assert_eq!(
+            client.get_record(&fee_payer),
+            Err(ClientError::AccountNotFound(
+                client.record_address(&fee_payer)
+            ))
+        );
+    }
+
+    #[test]
+    fn it_fails_to_get_records_of_other_owners() {
+        let mut client = client();
+        let signer = Pubkey::new_unique();
+        let address = client.record_address(&signer);
+        client.transport.runtime.add_account(
+            TestAccount::new()
+                .key(address)
+                .owner(system_program::id())
+                .lamports(1_000),
+        );
+
+        assert_eq!(
+            client.get_record(&signer),
+            Err(ClientError::InvalidAccount(address))
+        );
+    }
+
+    #[test]
+    fn it_fails_to_get_records_it_cannot_decode() {
+        let mut client = client();
+        let fee_payer = client.fee_payer;
+        client.create_user_record("John Doe").unwrap();
+        let address = client.record_address(&fee_payer);
+        let mut account = client.transport.runtime.account(&address).unwrap().clone();
+        account.data[1] = 51;
+        client.transport.runtime.add_account(account);
+
+        assert_eq!(
+            client.get_record(&fee_payer),
+            Err(ClientError::InvalidRecord(
+                address,
+                CodecError::StringTooLong.into()
+            ))
+        );
+    }
+
+    #[test]
+    fn it_migrates_records_created_before_the_version_byte() {
+        let mut client = client();
+        let fee_payer = client.fee_payer;
+        client.create_user_record("John Doe").unwrap();
+        client.register_income(100, &fee_payer).unwrap();
+        let address = client.record_address(&fee_payer);
+        let mut account = client.transport.runtime.account(&address).unwrap().clone();
+        account.data = account.data[1..1 + RECORD_UNVERSIONED_LEN].to_vec();
+        client.transport.runtime.add_account(account);
+        assert!(client.get_record(&fee_payer).is_err());
+
+        client.migrate_record().unwrap();
+
+        assert_eq!(
+            client.get_record(&fee_payer),
+            Ok(record("John Doe", 1, 100, 0, 100))
+        );
+    }
+
+    #[test]
+    fn it_rejects_user_names_longer_than_the_record() {
+        let mut client = client();
+        let fee_payer = client.fee_payer;
+        let name = "a".repeat(USER_NAME_CAP + 1);
+
+        assert_eq!(
+            client.create_user_record(&name),
+            Err(ClientError::UserNameTooLong(USER_NAME_CAP + 1))
+        );
+        assert_eq!(
+            client.get_record(&fee_payer),
+            Err(ClientError::AccountNotFound(
+                client.record_address(&fee_payer)
+            ))
+        );
+
+        client
+            .create_user_record(&format!(" {} ", "a".repeat(USER_NAME_CAP)))
+            .unwrap();
+        assert_eq!(
+            client.get_record(&fee_payer).unwrap().name,
+            "a".repeat(USER_NAME_CAP)
+        );
+
+        assert_eq!(
+            client.update_user_name(&"é".repeat(26), &fee_payer),
+            Err(ClientError::UserNameTooLong(52))
+        );
+        client
+            .update_user_name(&"é".repeat(25), &fee_payer)
+            .unwrap();
+        assert_eq!(client.get_record(&fee_payer).unwrap().name, "é".repeat(25));
+    }
+
+    // A transport for a cluster, failing as an unreachable RPC node does
+    struct Offline;
+
+    impl Transport for Offline {
+        fn send_and_confirm(
+            &mut self,
+            _instructions: &[Instruction],
+            _signers: &[Pubkey],
+        ) -> Result<(), ClientError> {
+            Err(ClientError::Transport("connection refused".to_string()))
+        }
+
+        fn get_account(&self, _address: &Pubkey) -> Result<Option<Account>, ClientError> {
+            Err(ClientError::Transport("connection refused".to_string()))
+        }
+    }
+
+    #[test]
+    fn it_returns_transport_errors() {
+        let mut client = Client::new(Offline, Pubkey::new_unique(), Pubkey::new_unique());
+        let fee_payer = client.fee_payer;
+
+        let error = ClientError::Transport("connection refused".to_string());
+        assert_eq!(client.create_user_record("John Doe"), Err(error.clone()));
+        assert_eq!(client.get_record(&fee_payer), Err(error));
+    }
+}
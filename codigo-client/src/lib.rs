@@ -0,0 +1,188 @@
+use budget_tracker_sdk::instructions;
+use budget_tracker_sdk::types::*;
+use codigolib::delegate_utils::Delegate;
+use solana_program::instruction::Instruction;
+use solana_program::program_error::ProgramError;
+use solana_program::pubkey::Pubkey;
+use thiserror::Error;
+
+#[cfg(any(test, feature = "mock"))]
+pub mod mock;
+
+#[cfg(test)]
+pub mod client_tests;
+
+// `cap:50` of Record.name in the CIDL, longer names can't be stored by the program
+pub const USER_NAME_CAP: usize = 50;
+
+// The program trims the name before checking it fits the record
+fn check_user_name(user_name: &str) -> Result<(), ClientError> {
+    let len = user_name.trim().len();
+    if len > USER_NAME_CAP {
+        return Err(ClientError::UserNameTooLong(len));
+    }
+    Ok(())
+}
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum ClientError {
+    #[error("transaction failed: {0}")]
+    Program(#[from] ProgramError),
+
+    #[error("account {0} not found")]
+    AccountNotFound(Pubkey),
+
+    #[error("account {0} is not a Record of the program")]
+    InvalidAccount(Pubkey),
+
+    #[error("account {0} holds an invalid Record: {1}")]
+    InvalidRecord(Pubkey, ProgramError),
+
+    #[error("user name of {0} bytes is longer than the {USER_NAME_CAP} bytes of a Record")]
+    UserNameTooLong(usize),
+
+    #[error("transport error: {0}")]
+    Transport(String),
+}
+
+// An account as the RPC returns it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Account {
+    pub lamports: u64,
+    pub owner: Pubkey,
+    pub data: Vec<u8>,
+    pub executable: bool,
+}
+
+// The RPC calls of the client. A transport for a cluster wraps an RPC client and holds the
+// keypairs of the signers it is given
+pub trait Transport {
+    // Sends `instructions` in one transaction signed by `signers` and waits until it is
+    // processed. A failed instruction fails the transaction with its error
+    fn send_and_confirm(
+        &mut self,
+        instructions: &[Instruction],
+        signers: &[Pubkey],
+    ) -> Result<(), ClientError>;
+
+    // None when there is no account at `address`
+    fn get_account(&self, address: &Pubkey) -> Result<Option<Account>, ClientError>;
+}
+
+// The budget tracker called as sdk/core.ts does, with transactions paid and signed by the fee
+// payer
+pub struct Client<T: Transport> {
+    pub transport: T,
+    pub program_id: Pubkey,
+    pub fee_payer: Pubkey,
+}
+
+impl<T: Transport> Client<T> {
+    pub fn new(transport: T, program_id: Pubkey, fee_payer: Pubkey) -> Self {
+        Client {
+            transport,
+            program_id,
+            fee_payer,
+        }
+    }
+
+    // Address of the Record of `signer`
+    pub fn record_address(&self, signer: &Pubkey) -> Pubkey {
+        let (address, _) = Record::find_address(&self.program_id, signer);
+        address
+    }
+
+    pub fn send(&mut self, instructions: &[Instruction]) -> Result<(), ClientError> {
+        let signers = [self.fee_payer];
+        self.transport.send_and_confirm(instructions, &signers)
+    }
+
+    // Creates the Record of the fee payer
+    pub fn create_user_record(&mut self, user_name: &str) -> Result<(), ClientError> {
+        check_user_name(user_name)?;
+        self.send(&[instructions::create_user_record(
+            &self.program_id,
+            user_name.to_string(),
+            self.fee_payer,
+            self.fee_payer,
+            self.fee_payer,
+        )])
+    }
+
+    pub fn register_income(&mut self, amount: u32, signer: &Pubkey) -> Result<(), ClientError> {
+        self.send(&[instructions::register_income(
+            &self.program_id,
+            amount,
+            *signer,
+            self.fee_payer,
+        )])
+    }
+
+    pub fn register_outcome(&mut self, amount: u32, signer: &Pubkey) -> Result<(), ClientError> {
+        self.send(&[instructions::register_outcome(
+            &self.program_id,
+            amount,
+            *signer,
+            self.fee_payer,
+        )])
+    }
+
+    // Lets `delegate` act on the Record of the fee payer, or changes the role it has
+    pub fn add_delegate(&mut self, delegate: Delegate) -> Result<(), ClientError> {
+        self.send(&[instructions::add_delegate(
+            &self.program_id,
+            delegate,
+            self.fee_payer,
+            self.fee_payer,
+        )])
+    }
+
+    pub fn remove_delegate(&mut self, delegate: &Pubkey) -> Result<(), ClientError> {
+        self.send(&[instructions::remove_delegate(
+            &self.program_id,
+            *delegate,
+            self.fee_payer,
+            self.fee_payer,
+        )])
+    }
+
+    // Renames the user of the Record of `signer`
+    pub fn update_user_name(
+        &mut self,
+        user_name: &str,
+        signer: &Pubkey,
+    ) -> Result<(), ClientError> {
+        check_user_name(user_name)?;
+        self.send(&[instructions::update_user_name(
+            &self.program_id,
+            user_name.to_string(),
+            *signer,
+            self.fee_payer,
+        )])
+    }
+
+    // Rewrites the Record of the fee payer created before the version byte, nothing changes for
+    // a Record already in the latest layout
+    pub fn migrate_record(&mut self) -> Result<(), ClientError> {
+        self.send(&[instructions::migrate_record(
+            &self.program_id,
+            self.fee_payer,
+            self.fee_payer,
+            self.fee_payer,
+        )])
+    }
+
+    // As getRecord, the Record of `signer` decoded from its account
+    pub fn get_record(&self, signer: &Pubkey) -> Result<Record, ClientError> {
+        let address = self.record_address(signer);
+        let account = self
+            .transport
+            .get_account(&address)?
+            .ok_or(ClientError::AccountNotFound(address))?;
+        if account.owner != self.program_id {
+            return Err(ClientError::InvalidAccount(address));
+        }
+
+        decode(&account.data).map_err(|e| ClientError::InvalidRecord(address, e))
+    }
+}
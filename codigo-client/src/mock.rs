@@ -0,0 +1,50 @@
+use crate::*;
+use budget_tracker::processor::process_instruction;
+use codigolib::testing::TestRuntime;
+
+// A transport without a cluster: transactions run the program processor in a TestRuntime,
+// which keeps the accounts between them
+pub struct MockTransport {
+    pub runtime: TestRuntime,
+}
+
+impl MockTransport {
+    pub fn new(runtime: TestRuntime) -> Self {
+        MockTransport { runtime }
+    }
+
+    // The budget tracker deployed at a new address
+    pub fn budget_tracker() -> Self {
+        MockTransport::new(TestRuntime::new(Pubkey::new_unique(), process_instruction))
+    }
+
+    pub fn program_id(&self) -> Pubkey {
+        self.runtime.program_id
+    }
+}
+
+impl Transport for MockTransport {
+    fn send_and_confirm(
+        &mut self,
+        instructions: &[Instruction],
+        signers: &[Pubkey],
+    ) -> Result<(), ClientError> {
+        // The instructions of a transaction are committed together or not at all
+        let mut runtime = self.runtime.clone();
+        for instruction in instructions {
+            runtime.process(instruction, signers)?;
+        }
+        self.runtime = runtime;
+
+        Ok(())
+    }
+
+    fn get_account(&self, address: &Pubkey) -> Result<Option<Account>, ClientError> {
+        Ok(self.runtime.account(address).map(|account| Account {
+            lamports: account.lamports,
+            owner: account.owner,
+            data: account.data.clone(),
+            executable: account.executable,
+        }))
+    }
+}
@@ -0,0 +1,562 @@
+use codigolib::delegate_utils::*;
+use codigolib::rust_utils::*;
+use codigolib::sec_utils::*;
+use codigolib::string_utils::*;
+use codigolib::token_utils::*;
+use serde_json::{json, Value};
+use solana_program::account_info::AccountInfo;
+use solana_program::pubkey::Pubkey;
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::hint::black_box;
+use std::path::Path;
+use std::process::exit;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+use std::{env, fs};
+
+// There is no SBF interpreter to count compute units outside of a validator, so every codec
+// function and security check is measured natively: the heap it allocates in one call, which
+// the bump allocator of a program never gives back, and its median time per call.
+//
+// `cargo bench --bench costs` writes the report to target/bench/costs.json and fails when a
+// function allocates more than in benches/costs.json, `UPDATE_GOLDEN=1` rewrites the latter
+
+// ==== ALLOCATIONS =======
+
+static ALLOCATIONS: AtomicU64 = AtomicU64::new(0);
+static HEAP_BYTES: AtomicU64 = AtomicU64::new(0);
+
+// Counts what a program would take from its heap, a realloc is a new allocation as on chain
+struct CountingAllocator;
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        HEAP_BYTES.fetch_add(layout.size() as u64, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        HEAP_BYTES.fetch_add(new_size as u64, Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+// ==== RUNNER =======
+
+// Samples taken of each function, the median is reported
+const SAMPLES: usize = 15;
+// Shortest sample, calls are batched until a sample lasts this long
+const SAMPLE_TIME: Duration = Duration::from_micros(500);
+const MAX_BATCH: usize = 1 << 16;
+
+struct Measure {
+    name: String,
+    allocations: u64,
+    heap_bytes: u64,
+    ns_per_iter: f64,
+}
+
+#[derive(Default)]
+struct Runner {
+    // Only the benchmarks whose name contains it are run
+    filter: Option<String>,
+    measures: Vec<Measure>,
+}
+
+impl Runner {
+    // `setup` builds the input of a call outside of what is measured
+    fn bench<I, O>(&mut self, name: &str, setup: impl Fn() -> I, routine: impl Fn(I) -> O) {
+        if matches!(&self.filter, Some(filter) if !name.contains(filter.as_str())) {
+            return;
+        }
+
+        let input = setup();
+        let (allocations, heap_bytes) = counters();
+        let output = black_box(routine(black_box(input)));
+        let (allocations, heap_bytes) = (
+            ALLOCATIONS.load(Ordering::Relaxed) - allocations,
+            HEAP_BYTES.load(Ordering::Relaxed) - heap_bytes,
+        );
+        drop(output);
+
+        let mut batch = 1;
+        while batch < MAX_BATCH && time(batch, &setup, &routine) < SAMPLE_TIME {
+            batch *= 2;
+        }
+        let mut samples: Vec<f64> = (0..SAMPLES)
+            .map(|_| time(batch, &setup, &routine).as_nanos() as f64 / batch as f64)
+            .collect();
+        samples.sort_by(|a, b| a.total_cmp(b));
+
+        self.measures.push(Measure {
+            name: name.to_string(),
+            allocations,
+            heap_bytes,
+            ns_per_iter: samples[SAMPLES / 2],
+        });
+    }
+}
+
+fn counters() -> (u64, u64) {
+    (
+        ALLOCATIONS.load(Ordering::Relaxed),
+        HEAP_BYTES.load(Ordering::Relaxed),
+    )
+}
+
+// Inputs are built and outputs dropped out of the timed loop
+fn time<I, O>(batch: usize, setup: &impl Fn() -> I, routine: &impl Fn(I) -> O) -> Duration {
+    let inputs: Vec<I> = (0..batch).map(|_| setup()).collect();
+    let mut outputs = Vec::with_capacity(batch);
+
+    let start = Instant::now();
+    for input in inputs {
+        outputs.push(black_box(routine(black_box(input))));
+    }
+    let elapsed = start.elapsed();
+
+    drop(outputs);
+    elapsed
+}
+
+// ==== FIXTURES =======
+
+const NAME_CAP: usize = 50;
+const VEC_CAP: usize = 16;
+
+// A full name, the worst case of a `cap:50` string
+fn name() -> String {
+    "x".repeat(NAME_CAP)
+}
+
+fn packed<const N: usize>(pack: impl Fn(&mut [u8])) -> [u8; N] {
+    let mut buf = [0; N];
+    pack(&mut buf);
+    buf
+}
+
+// The fields of an account as the runtime lends them
+struct Account {
+    key: Pubkey,
+    owner: Pubkey,
+    lamports: u64,
+    data: Vec<u8>,
+    is_signer: bool,
+    executable: bool,
+}
+
+impl Account {
+    fn new(owner: Pubkey, data: Vec<u8>) -> Self {
+        Account {
+            key: Pubkey::new_unique(),
+            owner,
+            lamports: 1_000_000,
+            data,
+            is_signer: false,
+            executable: false,
+        }
+    }
+
+    fn info(&mut self) -> AccountInfo<'_> {
+        AccountInfo::new(
+            &self.key,
+            self.is_signer,
+            false,
+            &mut self.lamports,
+            &mut self.data,
+            &self.owner,
+            self.executable,
+            0,
+        )
+    }
+}
+
+// ==== BENCHMARKS =======
+
+macro_rules! primitive {
+    ($runner:expr, $size:literal, $value:expr, $pack:ident, $unpack:ident, $try_unpack:ident) => {
+        let buf: [u8; $size] = packed(|buf| {
+            $pack(buf, $value);
+        });
+        $runner.bench(
+            concat!("rust_utils/", stringify!($pack)),
+            || (),
+            |_| {
+                let mut buf = [0u8; $size];
+                $pack(&mut buf, $value);
+                buf
+            },
+        );
+        $runner.bench(
+            concat!("rust_utils/", stringify!($unpack)),
+            || (),
+            |_| $unpack(&buf).1,
+        );
+        $runner.bench(
+            concat!("rust_utils/", stringify!($try_unpack)),
+            || (),
+            |_| $try_unpack(&buf).map(|(_, value)| value),
+        );
+    };
+}
+
+fn codec(runner: &mut Runner) {
+    primitive!(runner, 1, true, pack_bool, unpack_bool, try_unpack_bool);
+    primitive!(runner, 1, 7, pack_u8, unpack_u8, try_unpack_u8);
+    primitive!(runner, 1, -7, pack_i8, unpack_i8, try_unpack_i8);
+    primitive!(runner, 2, 7, pack_u16, unpack_u16, try_unpack_u16);
+    primitive!(runner, 2, -7, pack_i16, unpack_i16, try_unpack_i16);
+    primitive!(runner, 4, 7, pack_u32, unpack_u32, try_unpack_u32);
+    primitive!(runner, 4, -7, pack_i32, unpack_i32, try_unpack_i32);
+    primitive!(runner, 8, 7, pack_u64, unpack_u64, try_unpack_u64);
+    primitive!(runner, 8, -7, pack_i64, unpack_i64, try_unpack_i64);
+    primitive!(runner, 16, 7, pack_u128, unpack_u128, try_unpack_u128);
+    primitive!(runner, 16, -7, pack_i128, unpack_i128, try_unpack_i128);
+    primitive!(runner, 4, 0.5, pack_f32, unpack_f32, try_unpack_f32);
+    primitive!(runner, 8, 0.5, pack_f64, unpack_f64, try_unpack_f64);
+    primitive!(
+        runner,
+        32,
+        Pubkey::new_from_array([7; 32]),
+        pack_pubkey,
+        unpack_pubkey,
+        try_unpack_pubkey
+    );
+
+    // Strings, at the cap of their field
+    let state_string: [u8; 4 + NAME_CAP] = packed(|buf| {
+        pack_string(buf, name(), 4 + NAME_CAP);
+    });
+    runner.bench("rust_utils/pack_string", name, |name| {
+        let mut buf = [0u8; 4 + NAME_CAP];
+        pack_string(&mut buf, name, 4 + NAME_CAP);
+        buf
+    });
+    runner.bench("rust_utils/try_pack_string", name, |name| {
+        let mut buf = [0u8; 4 + NAME_CAP];
+        let packed = try_pack_string(&mut buf, name, 4 + NAME_CAP).is_ok();
+        (buf, packed)
+    });
+    runner.bench(
+        "rust_utils/state_unpack_string",
+        || (),
+        |_| state_unpack_string(&state_string, 4 + NAME_CAP).1,
+    );
+    runner.bench(
+        "rust_utils/try_state_unpack_string",
+        || (),
+        |_| try_state_unpack_string(&state_string, 4 + NAME_CAP).map(|(_, value)| value),
+    );
+    runner.bench(
+        "rust_utils/instructions_unpack_string",
+        || (),
+        |_| instructions_unpack_string(&state_string).1,
+    );
+    runner.bench(
+        "rust_utils/try_instructions_unpack_string",
+        || (),
+        |_| try_instructions_unpack_string(&state_string).map(|(_, value)| value),
+    );
+    runner.bench("string_utils/validate_string", name, |name| {
+        validate_string(name, NAME_CAP, StringPolicy::strict())
+    });
+    runner.bench(
+        "string_utils/validate_string_bytes",
+        || (),
+        |_| validate_string_bytes(&state_string[4..], NAME_CAP, StringPolicy::strict()),
+    );
+
+    // Options of a u64, holding a value
+    let option: [u8; 9] = packed(|buf| {
+        pack_option(buf, pack_u64, Some(7), 0);
+    });
+    runner.bench(
+        "rust_utils/pack_option",
+        || (),
+        |_| {
+            let mut buf = [0u8; 9];
+            pack_option(&mut buf, pack_u64, Some(7), 0);
+            buf
+        },
+    );
+    runner.bench(
+        "rust_utils/state_unpack_option",
+        || (),
+        |_| state_unpack_option(&option, unpack_u64, 8).1,
+    );
+    runner.bench(
+        "rust_utils/try_state_unpack_option",
+        || (),
+        |_| try_state_unpack_option(&option, try_unpack_u64, 8).map(|(_, value)| value),
+    );
+    runner.bench(
+        "rust_utils/instructions_unpack_option",
+        || (),
+        |_| instructions_unpack_option(&option, unpack_u64).1,
+    );
+    runner.bench(
+        "rust_utils/try_instructions_unpack_option",
+        || (),
+        |_| try_instructions_unpack_option(&option, try_unpack_u64).map(|(_, value)| value),
+    );
+
+    // Vectors of u64, at their cap
+    let items = || (0..VEC_CAP as u64).collect::<Vec<_>>();
+    let vec: [u8; 4 + VEC_CAP * 8] = packed(|buf| {
+        pack_vec(buf, VEC_CAP, 8, pack_u64, items());
+    });
+    runner.bench("rust_utils/pack_vec", items, |items| {
+        let mut buf = [0u8; 4 + VEC_CAP * 8];
+        pack_vec(&mut buf, VEC_CAP, 8, pack_u64, items);
+        buf
+    });
+    runner.bench(
+        "rust_utils/state_unpack_vec",
+        || (),
+        |_| state_unpack_vec(&vec, VEC_CAP, 8, unpack_u64).1,
+    );
+    runner.bench(
+        "rust_utils/try_state_unpack_vec",
+        || (),
+        |_| try_state_unpack_vec(&vec, VEC_CAP, 8, try_unpack_u64).map(|(_, value)| value),
+    );
+    runner.bench(
+        "rust_utils/instructions_unpack_vec",
+        || (),
+        |_| instructions_unpack_vec(&vec, 8, unpack_u64).1,
+    );
+    runner.bench(
+        "rust_utils/try_instructions_unpack_vec",
+        || (),
+        |_| try_instructions_unpack_vec(&vec, 8, try_unpack_u64).map(|(_, value)| value),
+    );
+
+    // Delegates, the largest vectors of the library
+    let delegates = || {
+        (0..VEC_CAP)
+            .map(|i| Delegate {
+                key: Pubkey::new_from_array([i as u8; 32]),
+                role: DelegateRole::Contributor,
+            })
+            .collect::<Vec<_>>()
+    };
+    let packed_delegates: [u8; 4 + VEC_CAP * DELEGATE_SIZE] = packed(|buf| {
+        pack_delegates(buf, VEC_CAP, delegates());
+    });
+    runner.bench("delegate_utils/pack_delegates", delegates, |delegates| {
+        let mut buf = [0u8; 4 + VEC_CAP * DELEGATE_SIZE];
+        pack_delegates(&mut buf, VEC_CAP, delegates);
+        buf
+    });
+    runner.bench(
+        "delegate_utils/state_unpack_delegates",
+        || (),
+        |_| {
+            state_unpack_delegates(&packed_delegates, VEC_CAP)
+                .unwrap()
+                .1
+        },
+    );
+}
+
+fn checks(runner: &mut Runner) {
+    let program_id = Pubkey::new_unique();
+    let seeds: &[&[u8]] = &[b"record", &[7; 32]];
+    let (address, _) = Pubkey::find_program_address(seeds, &program_id);
+
+    let mut record = Account::new(program_id, vec![0; 72]);
+    record.key = address;
+    record.is_signer = true;
+    let record = record.info();
+
+    let mut executable = Account::new(Pubkey::new_unique(), Vec::new());
+    executable.executable = true;
+    let executable = executable.info();
+
+    runner.bench(
+        "sec_utils/verify_ownership",
+        || (),
+        |_| verify_ownership(&program_id, &[&record]),
+    );
+    runner.bench(
+        "sec_utils/verify_len",
+        || (),
+        |_| verify_len(72, &[&record]),
+    );
+    runner.bench(
+        "sec_utils/verify_pda",
+        || (),
+        |_| verify_pda(&record, seeds, &program_id),
+    );
+    runner.bench(
+        "sec_utils/verify_signers",
+        || (),
+        |_| verify_signers(&[&record]),
+    );
+    runner.bench(
+        "sec_utils/verify_is_executable",
+        || (),
+        |_| verify_is_executable(&[&executable]),
+    );
+    runner.bench(
+        "sec_utils/verify_signer_address",
+        || (),
+        |_| verify_signer_address(record.key, &address),
+    );
+
+    // The signer is the last of a full delegate list
+    let delegates: Vec<_> = (0..VEC_CAP)
+        .map(|i| Delegate {
+            key: if i + 1 == VEC_CAP {
+                address
+            } else {
+                Pubkey::new_unique()
+            },
+            role: DelegateRole::Admin,
+        })
+        .collect();
+    let owner = Pubkey::new_unique();
+    runner.bench(
+        "sec_utils/verify_delegate_role",
+        || (),
+        |_| verify_delegate_role(&record, &owner, &delegates, DelegateRole::Contributor),
+    );
+
+    let mut token_program = Account::new(Pubkey::new_unique(), Vec::new());
+    token_program.key = spl_token_program::id();
+    token_program.executable = true;
+    let token_program = token_program.info();
+    runner.bench(
+        "sec_utils/verify_token_program",
+        || (),
+        |_| verify_token_program(&token_program),
+    );
+
+    let mut mint = Account::new(spl_token_program::id(), vec![0; MINT_LEN]);
+    mint.data[MINT_IS_INITIALIZED_OFFSET] = 1;
+    let mint_key = mint.key;
+    let mint = mint.info();
+    runner.bench(
+        "sec_utils/verify_mint",
+        || (),
+        |_| verify_mint(&mint, &mint_key),
+    );
+
+    let mut token_data = vec![0; TOKEN_ACCOUNT_LEN];
+    let dst = pack_pubkey(&mut token_data, mint_key);
+    let dst = pack_pubkey(dst, owner);
+    pack_u64(dst, 100);
+    token_data[TOKEN_ACCOUNT_STATE_OFFSET] = 1;
+    let mut token_account = Account::new(spl_token_program::id(), token_data);
+    let token_account = token_account.info();
+    runner.bench(
+        "sec_utils/verify_token_account",
+        || (),
+        |_| verify_token_account(&token_account, &mint_key, &owner),
+    );
+}
+
+// ==== REPORT =======
+
+fn report(measures: &[Measure]) -> Value {
+    json!({
+        "benchmarks": measures
+            .iter()
+            .map(|measure| {
+                json!({
+                    "name": measure.name,
+                    "allocations": measure.allocations,
+                    "heap_bytes": measure.heap_bytes,
+                    "ns_per_iter": (measure.ns_per_iter * 10.0).round() / 10.0,
+                })
+            })
+            .collect::<Vec<_>>(),
+    })
+}
+
+// Allocations are the same on every run, a function allocating more than in the baseline is a
+// regression. Times only vary with the machine, they are printed next to the baseline ones
+fn compare(measures: &[Measure], baseline: &Value) -> Vec<String> {
+    let empty = Vec::new();
+    let baseline = baseline["benchmarks"].as_array().unwrap_or(&empty);
+    let mut regressions = Vec::new();
+
+    for measure in measures {
+        let previous = baseline.iter().find(|b| b["name"] == measure.name.as_str());
+        let column = |field: &str, value: u64| match previous.and_then(|p| p[field].as_u64()) {
+            Some(previous) if previous != value => format!("{} (was {})", value, previous),
+            _ => value.to_string(),
+        };
+        let time = match previous.and_then(|p| p["ns_per_iter"].as_f64()) {
+            Some(previous) => format!("{:.1} ns (was {:.1})", measure.ns_per_iter, previous),
+            None => format!("{:.1} ns (new)", measure.ns_per_iter),
+        };
+        println!(
+            "{:<48} {:>14} allocs {:>16} bytes {:>28}",
+            measure.name,
+            column("allocations", measure.allocations),
+            column("heap_bytes", measure.heap_bytes),
+            time
+        );
+
+        if let Some(previous) = previous {
+            let allocations = previous["allocations"].as_u64().unwrap_or(0);
+            let heap_bytes = previous["heap_bytes"].as_u64().unwrap_or(0);
+            if measure.allocations > allocations || measure.heap_bytes > heap_bytes {
+                regressions.push(format!(
+                    "{}: {} allocations of {} bytes, {} of {} bytes before",
+                    measure.name, measure.allocations, measure.heap_bytes, allocations, heap_bytes
+                ));
+            }
+        }
+    }
+
+    regressions
+}
+
+fn main() {
+    // `cargo bench` passes --bench, any other argument filters benchmarks by name
+    let filter = env::args().skip(1).find(|arg| !arg.starts_with("--"));
+
+    let mut runner = Runner {
+        filter: filter.clone(),
+        ..Runner::default()
+    };
+    codec(&mut runner);
+    checks(&mut runner);
+
+    let manifest_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let baseline_path = manifest_dir.join("benches").join("costs.json");
+    let report_dir = manifest_dir.join("target").join("bench");
+    let report = serde_json::to_string_pretty(&report(&runner.measures)).unwrap() + "\n";
+    fs::create_dir_all(&report_dir).unwrap();
+    fs::write(report_dir.join("costs.json"), &report).unwrap();
+
+    if env::var("UPDATE_GOLDEN").is_ok() && filter.is_none() {
+        fs::write(&baseline_path, &report).unwrap();
+        println!("wrote {}", baseline_path.display());
+        return;
+    }
+
+    let baseline = fs::read_to_string(&baseline_path)
+        .ok()
+        .and_then(|baseline| serde_json::from_str(&baseline).ok())
+        .unwrap_or(Value::Null);
+    let regressions = compare(&runner.measures, &baseline);
+    if !regressions.is_empty() {
+        eprintln!("\nheap regressions against {}:", baseline_path.display());
+        for regression in regressions {
+            eprintln!("  {}", regression);
+        }
+        exit(1);
+    }
+}
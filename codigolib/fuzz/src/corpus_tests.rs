@@ -0,0 +1,92 @@
+use crate::*;
+use std::fs;
+use std::path::Path;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Seeds of a target, in name order
+    fn corpus(target: &str) -> Vec<(String, Vec<u8>)> {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("corpus")
+            .join(target);
+        let mut paths: Vec<_> = fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .collect();
+        paths.sort();
+
+        paths
+            .into_iter()
+            .map(|path| {
+                let name = path.file_name().unwrap().to_string_lossy().to_string();
+                (name, fs::read(path).unwrap())
+            })
+            .collect()
+    }
+
+    // The seeds are valid values, so the fuzzer starts from inputs reaching past the length checks
+    #[test]
+    fn it_replays_the_state_unpack_corpus() {
+        for (name, data) in corpus("state_unpack") {
+            check_state_unpack(&data);
+
+            let mut input = data.as_slice();
+            let shape = Shape::read(&mut input);
+            assert!(try_unpack_state(input, &shape).is_ok(), "{}", name);
+        }
+    }
+
+    #[test]
+    fn it_replays_the_instructions_unpack_corpus() {
+        for (name, data) in corpus("instructions_unpack") {
+            check_instructions_unpack(&data);
+
+            let mut input = data.as_slice();
+            let shape = Shape::read(&mut input);
+            assert!(try_unpack_instruction(input, &shape).is_ok(), "{}", name);
+        }
+    }
+
+    #[test]
+    fn it_replays_the_budget_tracker_decode_corpus() {
+        for (name, data) in corpus("budget_tracker_decode") {
+            check_budget_tracker_decode(&data);
+
+            let decoded = BudgetTrackerInstruction::unpack(&data).is_ok()
+                || Record::try_unpack(&data).is_ok();
+            assert!(decoded, "{}", name);
+        }
+    }
+
+    #[test]
+    fn it_rejects_length_prefixes_past_the_input() {
+        // A u128 vector announcing 2^32 - 1 elements
+        let huge: &[u8] = &[16, 0, 9, 0xff, 0xff, 0xff, 0xff, 0, 0];
+        check_instructions_unpack(huge);
+
+        let mut input = huge;
+        let shape = Shape::read(&mut input);
+        assert!(try_unpack_instruction(input, &shape).is_err());
+    }
+
+    #[test]
+    fn it_survives_empty_and_truncated_inputs() {
+        for (_, data) in corpus("budget_tracker_decode") {
+            for len in 0..data.len() {
+                check_budget_tracker_decode(&data[..len]);
+            }
+        }
+        for (_, data) in corpus("state_unpack") {
+            for len in 0..data.len() {
+                check_state_unpack(&data[..len]);
+            }
+        }
+        for (_, data) in corpus("instructions_unpack") {
+            for len in 0..data.len() {
+                check_instructions_unpack(&data[..len]);
+            }
+        }
+    }
+}
@@ -0,0 +1,251 @@
+use budget_tracker::instructions::BudgetTrackerInstruction;
+use budget_tracker::state::{Record, State};
+use codigolib::rust_utils::*;
+use solana_program::program_error::ProgramError;
+use solana_program::pubkey::Pubkey;
+
+#[cfg(test)]
+pub mod corpus_tests;
+
+// ==== TARGETS =======
+
+// Every input starts with the type to decode, see `Shape::read`, followed by the bytes decoded with
+// the account layout of that type. Decoding never panics, and a decoded value takes exactly the
+// size of its field
+pub fn check_state_unpack(data: &[u8]) {
+    let mut input = data;
+    let shape = Shape::read(&mut input);
+
+    if let Ok((rest, value)) = try_unpack_state(input, &shape) {
+        assert_eq!(
+            input.len() - rest.len(),
+            shape.state_size(),
+            "{:?} decoded as {:?}",
+            shape,
+            value
+        );
+    }
+}
+
+// Same as `check_state_unpack` with the instruction layout, a decoded value takes the bytes of
+// its length prefixes and of the elements they announce
+pub fn check_instructions_unpack(data: &[u8]) {
+    let mut input = data;
+    let shape = Shape::read(&mut input);
+
+    if let Ok((rest, value)) = try_unpack_instruction(input, &shape) {
+        assert_eq!(
+            input.len() - rest.len(),
+            shape.instruction_size(&value),
+            "{:?} decoded as {:?}",
+            shape,
+            value
+        );
+    }
+}
+
+// The decoders generated for the budget tracker example, instruction data is whatever a
+// transaction carries
+pub fn check_budget_tracker_decode(data: &[u8]) {
+    let _ = BudgetTrackerInstruction::unpack(data);
+
+    if let Ok((rest, record)) = Record::try_unpack(data) {
+        assert_eq!(data.len() - rest.len(), Record::LEN);
+
+        // Anything accepted packs back into a record decoded the same way
+        let mut buf = vec![0; Record::LEN];
+        record.pack(&mut buf);
+        assert_eq!(Record::try_unpack(&buf).map(|(_, value)| value), Ok(record));
+    }
+}
+
+// ==== SHAPES =======
+
+// Nesting allowed by `Shape::read`, enough for an optional vector of optional values
+const MAX_DEPTH: usize = 3;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Shape {
+    Bool,
+    U8,
+    I8,
+    U16,
+    I16,
+    U32,
+    I32,
+    U64,
+    I64,
+    U128,
+    I128,
+    F32,
+    F64,
+    Pubkey,
+    Option(Box<Shape>),
+    String { cap: usize },
+    Vec { cap: usize, elem: Box<Shape> },
+}
+
+// Floats are kept as bits so NaN compares equal to itself
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Bool(bool),
+    U8(u8),
+    I8(i8),
+    U16(u16),
+    I16(i16),
+    U32(u32),
+    I32(i32),
+    U64(u64),
+    I64(i64),
+    U128(u128),
+    I128(i128),
+    F32(u32),
+    F64(u64),
+    Pubkey(Pubkey),
+    Option(Option<Box<Value>>),
+    String(String),
+    Vec(Vec<Value>),
+}
+
+impl Shape {
+    // A byte per type, in the order of the variants, strings and vectors are followed by their
+    // cap. Vector elements have a fixed size as the CIDL validator requires. Missing bytes read
+    // as zero
+    pub fn read(input: &mut &[u8]) -> Shape {
+        Shape::read_at(input, 0, false)
+    }
+
+    fn read_at(input: &mut &[u8], depth: usize, fixed_size: bool) -> Shape {
+        let kinds = if depth >= MAX_DEPTH {
+            14
+        } else if fixed_size {
+            15
+        } else {
+            17
+        };
+
+        match next_byte(input) % kinds {
+            0 => Shape::Bool,
+            1 => Shape::U8,
+            2 => Shape::I8,
+            3 => Shape::U16,
+            4 => Shape::I16,
+            5 => Shape::U32,
+            6 => Shape::I32,
+            7 => Shape::U64,
+            8 => Shape::I64,
+            9 => Shape::U128,
+            10 => Shape::I128,
+            11 => Shape::F32,
+            12 => Shape::F64,
+            13 => Shape::Pubkey,
+            14 => Shape::Option(Box::new(Shape::read_at(input, depth + 1, fixed_size))),
+            15 => Shape::String {
+                cap: next_byte(input) as usize % 64,
+            },
+            _ => Shape::Vec {
+                cap: next_byte(input) as usize % 16,
+                elem: Box::new(Shape::read_at(input, depth + 1, true)),
+            },
+        }
+    }
+
+    pub fn state_size(&self) -> usize {
+        match self {
+            Shape::Bool | Shape::U8 | Shape::I8 => 1,
+            Shape::U16 | Shape::I16 => 2,
+            Shape::U32 | Shape::I32 | Shape::F32 => 4,
+            Shape::U64 | Shape::I64 | Shape::F64 => 8,
+            Shape::U128 | Shape::I128 => 16,
+            Shape::Pubkey => 32,
+            Shape::Option(inner) => 1 + inner.state_size(),
+            Shape::String { cap } => 4 + cap,
+            Shape::Vec { cap, elem } => 4 + cap * elem.state_size(),
+        }
+    }
+
+    pub fn instruction_size(&self, value: &Value) -> usize {
+        match (self, value) {
+            (Shape::Option(inner), Value::Option(v)) => {
+                1 + v.as_ref().map_or(0, |v| inner.instruction_size(v))
+            }
+            (Shape::String { .. }, Value::String(s)) => 4 + s.len(),
+            (Shape::Vec { elem, .. }, Value::Vec(items)) => 4 + items.len() * elem.state_size(),
+            _ => self.state_size(),
+        }
+    }
+}
+
+fn next_byte(input: &mut &[u8]) -> u8 {
+    match input.split_first() {
+        Some((byte, rest)) => {
+            *input = rest;
+            *byte
+        }
+        None => 0,
+    }
+}
+
+// ==== DECODING =======
+
+type Decoded<'a> = Result<(&'a [u8], Value), ProgramError>;
+
+fn map<T>(decoded: Result<(&[u8], T), ProgramError>, f: impl Fn(T) -> Value) -> Decoded<'_> {
+    decoded.map(|(buf, value)| (buf, f(value)))
+}
+
+// Fixed size values have the same layout in accounts and instructions
+fn try_unpack_fixed<'a>(buf: &'a [u8], shape: &Shape) -> Decoded<'a> {
+    match shape {
+        Shape::Bool => map(try_unpack_bool(buf), Value::Bool),
+        Shape::U8 => map(try_unpack_u8(buf), Value::U8),
+        Shape::I8 => map(try_unpack_i8(buf), Value::I8),
+        Shape::U16 => map(try_unpack_u16(buf), Value::U16),
+        Shape::I16 => map(try_unpack_i16(buf), Value::I16),
+        Shape::U32 => map(try_unpack_u32(buf), Value::U32),
+        Shape::I32 => map(try_unpack_i32(buf), Value::I32),
+        Shape::U64 => map(try_unpack_u64(buf), Value::U64),
+        Shape::I64 => map(try_unpack_i64(buf), Value::I64),
+        Shape::U128 => map(try_unpack_u128(buf), Value::U128),
+        Shape::I128 => map(try_unpack_i128(buf), Value::I128),
+        Shape::F32 => map(try_unpack_f32(buf), |v| Value::F32(v.to_bits())),
+        Shape::F64 => map(try_unpack_f64(buf), |v| Value::F64(v.to_bits())),
+        Shape::Pubkey => map(try_unpack_pubkey(buf), Value::Pubkey),
+        other => panic!("{:?} has no fixed size", other),
+    }
+}
+
+// As the generated `State::try_unpack` decodes account fields
+pub fn try_unpack_state<'a>(buf: &'a [u8], shape: &Shape) -> Decoded<'a> {
+    match shape {
+        Shape::Option(inner) => map(
+            try_state_unpack_option(buf, |buf| try_unpack_state(buf, inner), inner.state_size()),
+            |v| Value::Option(v.map(Box::new)),
+        ),
+        Shape::String { cap } => map(try_state_unpack_string(buf, 4 + cap), Value::String),
+        Shape::Vec { cap, elem } => map(
+            try_state_unpack_vec(buf, *cap, elem.state_size(), |buf| {
+                try_unpack_state(buf, elem)
+            }),
+            Value::Vec,
+        ),
+        _ => try_unpack_fixed(buf, shape),
+    }
+}
+
+// As the generated instruction decoders read their arguments, vector elements keep their account
+// layout
+pub fn try_unpack_instruction<'a>(buf: &'a [u8], shape: &Shape) -> Decoded<'a> {
+    match shape {
+        Shape::Option(inner) => map(
+            try_instructions_unpack_option(buf, |buf| try_unpack_instruction(buf, inner)),
+            |v| Value::Option(v.map(Box::new)),
+        ),
+        Shape::String { .. } => map(try_instructions_unpack_string(buf), Value::String),
+        Shape::Vec { elem, .. } => map(
+            try_instructions_unpack_vec(buf, elem.state_size(), |buf| try_unpack_state(buf, elem)),
+            Value::Vec,
+        ),
+        _ => try_unpack_fixed(buf, shape),
+    }
+}
@@ -0,0 +1,377 @@
+use crate::errors::CodecError;
+use crate::rust_utils::*;
+use proptest::prelude::*;
+use solana_program::pubkey::Pubkey;
+
+// Round trips of arbitrary values through the codec. Failing cases are shrunk by proptest and
+// saved under proptest-regressions/ so they are replayed first on the next run
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Written after every packed value to catch writes past its size. The value itself is packed
+    // over zeroes, like a new account, as `pack_bool` refuses to overwrite anything but 0 and 1
+    const CANARY: u8 = 0xAA;
+    const CANARY_LEN: usize = 8;
+
+    // Type of a field as the CIDL describes it, `cap` bounds strings and vectors in accounts
+    #[derive(Debug, Clone)]
+    enum Shape {
+        Bool,
+        U8,
+        I8,
+        U16,
+        I16,
+        U32,
+        I32,
+        U64,
+        I64,
+        U128,
+        I128,
+        F32,
+        F64,
+        Pubkey,
+        String { cap: usize },
+        Option(Box<Shape>),
+        Vec { cap: usize, elem: Box<Shape> },
+    }
+
+    // Floats are kept as bits so NaN compares equal to itself
+    #[derive(Debug, Clone, PartialEq)]
+    enum Value {
+        Bool(bool),
+        U8(u8),
+        I8(i8),
+        U16(u16),
+        I16(i16),
+        U32(u32),
+        I32(i32),
+        U64(u64),
+        I64(i64),
+        U128(u128),
+        I128(i128),
+        F32(u32),
+        F64(u64),
+        Pubkey(Pubkey),
+        String(String),
+        Option(Option<Box<Value>>),
+        Vec(Vec<Value>),
+    }
+
+    fn state_size(shape: &Shape) -> usize {
+        match shape {
+            Shape::Bool | Shape::U8 | Shape::I8 => 1,
+            Shape::U16 | Shape::I16 => 2,
+            Shape::U32 | Shape::I32 | Shape::F32 => 4,
+            Shape::U64 | Shape::I64 | Shape::F64 => 8,
+            Shape::U128 | Shape::I128 => 16,
+            Shape::Pubkey => 32,
+            Shape::String { cap } => 4 + cap,
+            Shape::Option(inner) => 1 + state_size(inner),
+            Shape::Vec { cap, elem } => 4 + cap * state_size(elem),
+        }
+    }
+
+    // Strings and vectors are length-prefixed, options without a value only take their flag
+    fn instruction_size(shape: &Shape, value: &Value) -> usize {
+        match (shape, value) {
+            (Shape::String { .. }, Value::String(s)) => 4 + s.len(),
+            (Shape::Option(inner), Value::Option(v)) => {
+                1 + v.as_ref().map_or(0, |v| instruction_size(inner, v))
+            }
+            (Shape::Vec { elem, .. }, Value::Vec(items)) => 4 + items.len() * state_size(elem),
+            _ => state_size(shape),
+        }
+    }
+
+    fn default_value(shape: &Shape) -> Value {
+        match shape {
+            Shape::Bool => Value::Bool(false),
+            Shape::U8 => Value::U8(0),
+            Shape::I8 => Value::I8(0),
+            Shape::U16 => Value::U16(0),
+            Shape::I16 => Value::I16(0),
+            Shape::U32 => Value::U32(0),
+            Shape::I32 => Value::I32(0),
+            Shape::U64 => Value::U64(0),
+            Shape::I64 => Value::I64(0),
+            Shape::U128 => Value::U128(0),
+            Shape::I128 => Value::I128(0),
+            Shape::F32 => Value::F32(0),
+            Shape::F64 => Value::F64(0),
+            Shape::Pubkey => Value::Pubkey(Pubkey::default()),
+            Shape::String { .. } => Value::String(String::new()),
+            Shape::Option(_) => Value::Option(None),
+            Shape::Vec { .. } => Value::Vec(Vec::new()),
+        }
+    }
+
+    // Fixed size values are packed the same way in accounts and instructions
+    fn pack_fixed<'a>(buf: &'a mut [u8], value: &Value) -> &'a mut [u8] {
+        match value {
+            Value::Bool(v) => pack_bool(buf, *v),
+            Value::U8(v) => pack_u8(buf, *v),
+            Value::I8(v) => pack_i8(buf, *v),
+            Value::U16(v) => pack_u16(buf, *v),
+            Value::I16(v) => pack_i16(buf, *v),
+            Value::U32(v) => pack_u32(buf, *v),
+            Value::I32(v) => pack_i32(buf, *v),
+            Value::U64(v) => pack_u64(buf, *v),
+            Value::I64(v) => pack_i64(buf, *v),
+            Value::U128(v) => pack_u128(buf, *v),
+            Value::I128(v) => pack_i128(buf, *v),
+            Value::F32(v) => pack_f32(buf, f32::from_bits(*v)),
+            Value::F64(v) => pack_f64(buf, f64::from_bits(*v)),
+            Value::Pubkey(v) => pack_pubkey(buf, *v),
+            other => panic!("{:?} has no fixed size", other),
+        }
+    }
+
+    fn unpack_fixed<'a>(buf: &'a [u8], shape: &Shape) -> (&'a [u8], Value) {
+        match shape {
+            Shape::Bool => map(unpack_bool(buf), Value::Bool),
+            Shape::U8 => map(unpack_u8(buf), Value::U8),
+            Shape::I8 => map(unpack_i8(buf), Value::I8),
+            Shape::U16 => map(unpack_u16(buf), Value::U16),
+            Shape::I16 => map(unpack_i16(buf), Value::I16),
+            Shape::U32 => map(unpack_u32(buf), Value::U32),
+            Shape::I32 => map(unpack_i32(buf), Value::I32),
+            Shape::U64 => map(unpack_u64(buf), Value::U64),
+            Shape::I64 => map(unpack_i64(buf), Value::I64),
+            Shape::U128 => map(unpack_u128(buf), Value::U128),
+            Shape::I128 => map(unpack_i128(buf), Value::I128),
+            Shape::F32 => map(unpack_f32(buf), |v| Value::F32(v.to_bits())),
+            Shape::F64 => map(unpack_f64(buf), |v| Value::F64(v.to_bits())),
+            Shape::Pubkey => map(unpack_pubkey(buf), Value::Pubkey),
+            other => panic!("{:?} has no fixed size", other),
+        }
+    }
+
+    fn map<T>((buf, value): (&[u8], T), f: impl Fn(T) -> Value) -> (&[u8], Value) {
+        (buf, f(value))
+    }
+
+    // Account layout, as the generated `State` implementations pack their fields
+    fn pack_state<'a>(buf: &'a mut [u8], shape: &Shape, value: &Value) -> &'a mut [u8] {
+        match (shape, value) {
+            (Shape::String { cap }, Value::String(s)) => pack_string(buf, s.clone(), 4 + cap),
+            (Shape::Option(inner), Value::Option(v)) => pack_option(
+                buf,
+                |buf, v: Value| pack_state(buf, inner, &v),
+                v.as_deref().cloned(),
+                default_value(inner),
+            ),
+            (Shape::Vec { cap, elem }, Value::Vec(items)) => pack_vec(
+                buf,
+                *cap,
+                state_size(elem),
+                |buf, v: Value| pack_state(buf, elem, &v),
+                items.clone(),
+            ),
+            _ => pack_fixed(buf, value),
+        }
+    }
+
+    fn unpack_state<'a>(buf: &'a [u8], shape: &Shape) -> (&'a [u8], Value) {
+        match shape {
+            Shape::String { cap } => map(state_unpack_string(buf, 4 + cap), Value::String),
+            Shape::Option(inner) => map(
+                state_unpack_option(buf, |buf| unpack_state(buf, inner), state_size(inner)),
+                |v| Value::Option(v.map(Box::new)),
+            ),
+            Shape::Vec { cap, elem } => map(
+                state_unpack_vec(buf, *cap, state_size(elem), |buf| unpack_state(buf, elem)),
+                Value::Vec,
+            ),
+            _ => unpack_fixed(buf, shape),
+        }
+    }
+
+    // Instruction layout, as the generated clients pack their arguments
+    fn pack_instruction<'a>(buf: &'a mut [u8], shape: &Shape, value: &Value) -> &'a mut [u8] {
+        match (shape, value) {
+            (Shape::String { .. }, Value::String(s)) => pack_string(buf, s.clone(), 4 + s.len()),
+            (Shape::Option(inner), Value::Option(Some(v))) => {
+                pack_instruction(pack_bool(buf, true), inner, v)
+            }
+            (Shape::Option(_), Value::Option(None)) => pack_bool(buf, false),
+            (Shape::Vec { elem, .. }, Value::Vec(items)) => pack_vec(
+                buf,
+                items.len(),
+                state_size(elem),
+                |buf, v: Value| pack_state(buf, elem, &v),
+                items.clone(),
+            ),
+            _ => pack_fixed(buf, value),
+        }
+    }
+
+    fn unpack_instruction<'a>(buf: &'a [u8], shape: &Shape) -> (&'a [u8], Value) {
+        match shape {
+            Shape::String { .. } => map(instructions_unpack_string(buf), Value::String),
+            Shape::Option(inner) => map(
+                instructions_unpack_option(buf, |buf| unpack_instruction(buf, inner)),
+                |v| Value::Option(v.map(Box::new)),
+            ),
+            // Elements keep their account layout
+            Shape::Vec { elem, .. } => map(
+                instructions_unpack_vec(buf, state_size(elem), |buf| unpack_state(buf, elem)),
+                Value::Vec,
+            ),
+            _ => unpack_fixed(buf, shape),
+        }
+    }
+
+    fn fixed_shape() -> impl Strategy<Value = Shape> {
+        prop_oneof![
+            Just(Shape::Bool),
+            Just(Shape::U8),
+            Just(Shape::I8),
+            Just(Shape::U16),
+            Just(Shape::I16),
+            Just(Shape::U32),
+            Just(Shape::I32),
+            Just(Shape::U64),
+            Just(Shape::I64),
+            Just(Shape::U128),
+            Just(Shape::I128),
+            Just(Shape::F32),
+            Just(Shape::F64),
+            Just(Shape::Pubkey),
+        ]
+    }
+
+    // Vector elements must have a fixed size, as the CIDL validator requires
+    fn element_shape() -> impl Strategy<Value = Shape> {
+        fixed_shape().prop_recursive(2, 4, 1, |inner| {
+            inner.prop_map(|shape| Shape::Option(Box::new(shape)))
+        })
+    }
+
+    fn shape() -> impl Strategy<Value = Shape> {
+        let leaf = prop_oneof![
+            3 => fixed_shape(),
+            1 => (0..=12usize).prop_map(|cap| Shape::String { cap }),
+        ];
+        leaf.prop_recursive(3, 12, 1, |inner| {
+            prop_oneof![
+                inner.prop_map(|shape| Shape::Option(Box::new(shape))),
+                (0..=5usize, element_shape()).prop_map(|(cap, elem)| Shape::Vec {
+                    cap,
+                    elem: Box::new(elem),
+                }),
+            ]
+        })
+    }
+
+    // Lengths at the cap and just below it are picked as often as any other
+    fn length_up_to(cap: usize) -> impl Strategy<Value = usize> {
+        prop_oneof![Just(cap), Just(cap.saturating_sub(1)), 0..=cap]
+    }
+
+    // Strings of exactly `len` ASCII bytes, or multi-byte text cut to at most `len` bytes
+    fn string_up_to(cap: usize) -> BoxedStrategy<String> {
+        let ascii = length_up_to(cap).prop_flat_map(|len| {
+            prop::collection::vec(0x20u8..0x7f, len)
+                .prop_map(|bytes| String::from_utf8(bytes).unwrap())
+        });
+        let unicode = prop::collection::vec(any::<char>(), 0..=cap).prop_map(move |chars| {
+            let mut s = String::new();
+            for c in chars {
+                if s.len() + c.len_utf8() > cap {
+                    break;
+                }
+                s.push(c);
+            }
+            s
+        });
+        prop_oneof![3 => ascii, 1 => unicode].boxed()
+    }
+
+    fn value(shape: &Shape) -> BoxedStrategy<Value> {
+        match shape {
+            Shape::Bool => any::<bool>().prop_map(Value::Bool).boxed(),
+            Shape::U8 => any::<u8>().prop_map(Value::U8).boxed(),
+            Shape::I8 => any::<i8>().prop_map(Value::I8).boxed(),
+            Shape::U16 => any::<u16>().prop_map(Value::U16).boxed(),
+            Shape::I16 => any::<i16>().prop_map(Value::I16).boxed(),
+            Shape::U32 => any::<u32>().prop_map(Value::U32).boxed(),
+            Shape::I32 => any::<i32>().prop_map(Value::I32).boxed(),
+            Shape::U64 => any::<u64>().prop_map(Value::U64).boxed(),
+            Shape::I64 => any::<i64>().prop_map(Value::I64).boxed(),
+            Shape::U128 => any::<u128>().prop_map(Value::U128).boxed(),
+            Shape::I128 => any::<i128>().prop_map(Value::I128).boxed(),
+            Shape::F32 => any::<u32>().prop_map(Value::F32).boxed(),
+            Shape::F64 => any::<u64>().prop_map(Value::F64).boxed(),
+            Shape::Pubkey => any::<[u8; 32]>()
+                .prop_map(|bytes| Value::Pubkey(Pubkey::new_from_array(bytes)))
+                .boxed(),
+            Shape::String { cap } => string_up_to(*cap).prop_map(Value::String).boxed(),
+            Shape::Option(inner) => prop::option::of(value(inner))
+                .prop_map(|v| Value::Option(v.map(Box::new)))
+                .boxed(),
+            Shape::Vec { cap, elem } => {
+                let elem = value(elem);
+                length_up_to(*cap)
+                    .prop_flat_map(move |len| prop::collection::vec(elem.clone(), len))
+                    .prop_map(Value::Vec)
+                    .boxed()
+            }
+        }
+    }
+
+    fn field(size: usize) -> Vec<u8> {
+        let mut buf = vec![0; size + CANARY_LEN];
+        buf[size..].fill(CANARY);
+        buf
+    }
+
+    fn shaped_value() -> impl Strategy<Value = (Shape, Value)> {
+        shape().prop_flat_map(|shape| {
+            let value = value(&shape);
+            (Just(shape), value)
+        })
+    }
+
+    proptest! {
+        #[test]
+        fn it_round_trips_the_state_layout((shape, value) in shaped_value()) {
+            let size = state_size(&shape);
+            let mut buf = field(size);
+
+            let rest = pack_state(&mut buf, &shape, &value).len();
+            prop_assert_eq!(rest, CANARY_LEN, "packing must advance by the field size");
+            prop_assert!(buf[size..].iter().all(|byte| *byte == CANARY), "wrote past the field");
+
+            let (rest, unpacked) = unpack_state(&buf, &shape);
+            prop_assert_eq!(rest.len(), CANARY_LEN, "unpacking must consume the field size");
+            prop_assert_eq!(unpacked, value);
+        }
+
+        #[test]
+        fn it_round_trips_the_instruction_layout((shape, value) in shaped_value()) {
+            let size = instruction_size(&shape, &value);
+            let mut buf = field(size);
+
+            let rest = pack_instruction(&mut buf, &shape, &value).len();
+            prop_assert_eq!(rest, CANARY_LEN, "packing must advance by the value size");
+            prop_assert!(buf[size..].iter().all(|byte| *byte == CANARY), "wrote past the value");
+
+            let (rest, unpacked) = unpack_instruction(&buf, &shape);
+            prop_assert_eq!(rest.len(), CANARY_LEN, "unpacking must consume the value size");
+            prop_assert_eq!(unpacked, value);
+        }
+
+        #[test]
+        fn it_rejects_strings_over_their_cap(cap in 0..=12usize, extra in 1..=4usize) {
+            let mut buf = vec![CANARY; 4 + cap + CANARY_LEN];
+            let data = "x".repeat(cap + extra);
+
+            prop_assert_eq!(
+                try_pack_string(&mut buf, data, 4 + cap).unwrap_err(),
+                CodecError::StringTooLong.into()
+            );
+            prop_assert!(buf.iter().all(|byte| *byte == CANARY));
+        }
+    }
+}
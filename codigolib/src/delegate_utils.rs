@@ -0,0 +1,263 @@
+use crate::errors::*;
+use crate::rust_utils::*;
+use solana_program::{entrypoint::ProgramResult, program_error::ProgramError, pubkey::Pubkey};
+
+// Packed size of a single Delegate: pubkey + role
+pub const DELEGATE_SIZE: usize = 33;
+
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum DelegateRole {
+    // Can read the account, no mutating instruction is allowed
+    #[default]
+    Viewer = 0,
+    // Can register movements on the account
+    Contributor = 1,
+    // Can do everything the owner does, including managing delegates
+    Admin = 2,
+}
+
+impl DelegateRole {
+    pub fn from_u8(value: u8) -> Option<DelegateRole> {
+        match value {
+            0 => Some(DelegateRole::Viewer),
+            1 => Some(DelegateRole::Contributor),
+            2 => Some(DelegateRole::Admin),
+            _ => None,
+        }
+    }
+
+    pub fn allows(&self, required_role: DelegateRole) -> bool {
+        *self >= required_role
+    }
+}
+
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct Delegate {
+    pub key: Pubkey,
+    pub role: DelegateRole,
+}
+
+pub fn pack_delegate(buf: &mut [u8], data: Delegate) -> &mut [u8] {
+    let dst = pack_pubkey(buf, data.key);
+    pack_u8(dst, data.role as u8)
+}
+
+// For delegates packed by the codec, panics on a role byte it never writes as `unpack_bool` does
+pub fn unpack_delegate(buf: &[u8]) -> (&[u8], Delegate) {
+    let (dst, key) = unpack_pubkey(buf);
+    let (dst, role) = unpack_u8(dst);
+    match DelegateRole::from_u8(role) {
+        Some(role) => (dst, Delegate { key, role }),
+        None => panic!("{}: Invalid delegate role", role),
+    }
+}
+
+// A role byte the program never wrote is an error
+pub fn try_unpack_delegate(buf: &[u8]) -> Result<(&[u8], Delegate), ProgramError> {
+    let (dst, key) = try_unpack_pubkey(buf)?;
+    let (dst, role) = try_unpack_u8(dst)?;
+    let role = DelegateRole::from_u8(role).ok_or(CodecError::InvalidDelegateRole)?;
+    Ok((dst, Delegate { key, role }))
+}
+
+pub fn pack_delegates(buf: &mut [u8], cap: usize, data: Vec<Delegate>) -> &mut [u8] {
+    pack_vec(buf, cap, DELEGATE_SIZE, pack_delegate, data)
+}
+
+pub fn state_unpack_delegates(
+    buf: &[u8],
+    cap: usize,
+) -> Result<(&[u8], Vec<Delegate>), ProgramError> {
+    try_state_unpack_vec(buf, cap, DELEGATE_SIZE, try_unpack_delegate)
+}
+
+pub fn find_delegate<'a>(delegates: &'a [Delegate], key: &Pubkey) -> Option<&'a Delegate> {
+    delegates.iter().find(|delegate| delegate.key == *key)
+}
+
+// Grants the role to the given key, replacing the previous role if it was already a delegate
+pub fn add_delegate(
+    delegates: &mut Vec<Delegate>,
+    cap: usize,
+    delegate: Delegate,
+) -> ProgramResult {
+    if let Some(existing) = delegates.iter_mut().find(|d| d.key == delegate.key) {
+        existing.role = delegate.role;
+        return Ok(());
+    }
+
+    if delegates.len() >= cap {
+        return Err(SecurityError::DelegateLimitReached.into());
+    }
+
+    delegates.push(delegate);
+    Ok(())
+}
+
+pub fn remove_delegate(delegates: &mut Vec<Delegate>, key: &Pubkey) -> ProgramResult {
+    match delegates.iter().position(|delegate| delegate.key == *key) {
+        Some(index) => {
+            delegates.remove(index);
+            Ok(())
+        }
+        None => Err(SecurityError::DelegateNotFound.into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_program::program_error::ProgramError;
+
+    fn delegate(role: DelegateRole) -> Delegate {
+        Delegate {
+            key: Pubkey::new_unique(),
+            role,
+        }
+    }
+
+    #[test]
+    fn it_role_allows_lower_or_equal_roles() {
+        assert!(DelegateRole::Admin.allows(DelegateRole::Admin));
+        assert!(DelegateRole::Admin.allows(DelegateRole::Contributor));
+        assert!(DelegateRole::Admin.allows(DelegateRole::Viewer));
+        assert!(DelegateRole::Contributor.allows(DelegateRole::Viewer));
+        assert!(!DelegateRole::Contributor.allows(DelegateRole::Admin));
+        assert!(!DelegateRole::Viewer.allows(DelegateRole::Contributor));
+        assert!(!DelegateRole::Viewer.allows(DelegateRole::Admin));
+    }
+
+    #[test]
+    fn it_role_from_u8() {
+        assert_eq!(DelegateRole::from_u8(0), Some(DelegateRole::Viewer));
+        assert_eq!(DelegateRole::from_u8(1), Some(DelegateRole::Contributor));
+        assert_eq!(DelegateRole::from_u8(2), Some(DelegateRole::Admin));
+        assert_eq!(DelegateRole::from_u8(3), None);
+        assert_eq!(DelegateRole::from_u8(255), None);
+    }
+
+    #[test]
+    fn it_pack_unpack_delegates() {
+        let big: &mut [u8] = &mut [0; 180];
+        let cap = 3;
+        let delegates = vec![
+            delegate(DelegateRole::Viewer),
+            delegate(DelegateRole::Admin),
+        ];
+
+        let r = pack_delegates(big, cap, delegates.clone());
+        assert_eq!(r.len(), big.len() - (4 + cap * DELEGATE_SIZE));
+
+        let (ptr, unpacked) = state_unpack_delegates(big, cap).unwrap();
+        assert_eq!(unpacked, delegates);
+        assert_eq!(ptr.len(), big.len() - (4 + cap * DELEGATE_SIZE));
+    }
+
+    #[test]
+    fn it_pack_unpack_delegate() {
+        let big: &mut [u8] = &mut [0; DELEGATE_SIZE + 1];
+        let admin = delegate(DelegateRole::Admin);
+
+        let r = pack_delegate(big, admin);
+        assert_eq!(r.len(), 1);
+        assert_eq!(unpack_delegate(big), (&big[DELEGATE_SIZE..], admin));
+        assert_eq!(try_unpack_delegate(big), Ok((&big[DELEGATE_SIZE..], admin)));
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid delegate role")]
+    fn it_unpack_delegate_panics_on_invalid_role() {
+        let big: &mut [u8] = &mut [0; DELEGATE_SIZE];
+        big[32] = 3;
+        unpack_delegate(big);
+    }
+
+    #[test]
+    fn it_rejects_unpacking_invalid_role() {
+        let big: &mut [u8] = &mut [0; DELEGATE_SIZE];
+        big[32] = 3;
+        assert_eq!(
+            try_unpack_delegate(big).unwrap_err(),
+            CodecError::InvalidDelegateRole.into()
+        );
+        assert_eq!(
+            try_unpack_delegate(&big[..DELEGATE_SIZE - 1]).unwrap_err(),
+            CodecError::BufferTooShort.into()
+        );
+
+        let mut packed = vec![0; 4 + DELEGATE_SIZE];
+        pack_delegates(&mut packed, 1, vec![delegate(DelegateRole::Admin)]);
+        packed[4 + 32] = 255;
+        assert_eq!(
+            state_unpack_delegates(&packed, 1).unwrap_err(),
+            CodecError::InvalidDelegateRole.into()
+        );
+    }
+
+    #[test]
+    fn it_rejects_unpacking_over_capacity_delegates() {
+        let big: &mut [u8] = &mut [0; 180];
+        big[0] = 3;
+        assert_eq!(
+            state_unpack_delegates(big, 2).unwrap_err(),
+            CodecError::VectorTooLong.into()
+        );
+    }
+
+    #[test]
+    fn it_add_delegate() {
+        let mut delegates = Vec::new();
+        let viewer = delegate(DelegateRole::Viewer);
+
+        assert_eq!(Ok(()), add_delegate(&mut delegates, 2, viewer));
+        assert_eq!(delegates, vec![viewer]);
+        assert_eq!(find_delegate(&delegates, &viewer.key), Some(&viewer));
+    }
+
+    #[test]
+    fn it_add_existing_delegate_updates_role() {
+        let mut delegates = Vec::new();
+        let viewer = delegate(DelegateRole::Viewer);
+        let promoted = Delegate {
+            key: viewer.key,
+            role: DelegateRole::Admin,
+        };
+
+        assert_eq!(Ok(()), add_delegate(&mut delegates, 1, viewer));
+        assert_eq!(Ok(()), add_delegate(&mut delegates, 1, promoted));
+        assert_eq!(delegates, vec![promoted]);
+    }
+
+    #[test]
+    fn it_add_delegate_over_capacity_fails() {
+        let mut delegates = vec![delegate(DelegateRole::Viewer)];
+
+        assert_eq!(
+            Err(ProgramError::from(SecurityError::DelegateLimitReached)),
+            add_delegate(&mut delegates, 1, delegate(DelegateRole::Contributor))
+        );
+        assert_eq!(delegates.len(), 1);
+    }
+
+    #[test]
+    fn it_remove_delegate() {
+        let viewer = delegate(DelegateRole::Viewer);
+        let admin = delegate(DelegateRole::Admin);
+        let mut delegates = vec![viewer, admin];
+
+        assert_eq!(Ok(()), remove_delegate(&mut delegates, &viewer.key));
+        assert_eq!(delegates, vec![admin]);
+        assert_eq!(find_delegate(&delegates, &viewer.key), None);
+    }
+
+    #[test]
+    fn it_remove_unknown_delegate_fails() {
+        let mut delegates = vec![delegate(DelegateRole::Viewer)];
+
+        assert_eq!(
+            Err(ProgramError::from(SecurityError::DelegateNotFound)),
+            remove_delegate(&mut delegates, &Pubkey::new_unique())
+        );
+        assert_eq!(delegates.len(), 1);
+    }
+}
@@ -22,6 +22,33 @@ pub enum SecurityError {
 
     #[error("Unrecognized Signer Address")]
     UnrecognizedSignerAddress,
+
+    #[error("Insufficient Delegate Role")]
+    InsufficientDelegateRole,
+
+    #[error("Delegate Limit Reached")]
+    DelegateLimitReached,
+
+    #[error("Delegate Not Found")]
+    DelegateNotFound,
+
+    #[error("Invalid Token Program")]
+    InvalidTokenProgram,
+
+    #[error("Token Account Not Initialized")]
+    TokenAccountNotInitialized,
+
+    #[error("Token Mint Mismatch")]
+    TokenMintMismatch,
+
+    #[error("Token Account Owner Mismatch")]
+    TokenOwnerMismatch,
+
+    #[error("Token Transfer Required")]
+    TokenTransferRequired,
+
+    #[error("Token Balance Mismatch")]
+    TokenBalanceMismatch,
 }
 
 impl From<SecurityError> for ProgramError {
@@ -29,3 +56,62 @@ impl From<SecurityError> for ProgramError {
         ProgramError::Custom(e as u32)
     }
 }
+
+// The security errors are truncated to their low 32 bits by `as u32`, so codes past them start at
+// 0x100. Bases of u32 keep the discriminants portable
+const CODIGO_CODEC_BASE_ERROR: u32 = 0x100;
+
+#[derive(Error, Debug, Copy, Clone)]
+#[repr(u32)]
+pub enum CodecError {
+    #[error("String Exceeds Field Capacity")]
+    StringTooLong = CODIGO_CODEC_BASE_ERROR,
+
+    #[error("String Is Not Valid UTF-8")]
+    InvalidUtf8,
+
+    #[error("String Contains Control Characters")]
+    ControlCharacter,
+
+    #[error("Buffer Too Short")]
+    BufferTooShort,
+
+    #[error("Unsupported State Version")]
+    UnsupportedStateVersion,
+
+    #[error("Invalid Boolean Value")]
+    InvalidBoolValue,
+
+    #[error("Vector Exceeds Field Capacity")]
+    VectorTooLong,
+
+    #[error("Invalid Delegate Role")]
+    InvalidDelegateRole,
+
+    #[error("Invalid Event Length")]
+    InvalidEventLength,
+}
+
+impl From<CodecError> for ProgramError {
+    fn from(e: CodecError) -> Self {
+        ProgramError::Custom(e as u32)
+    }
+}
+
+const CODIGO_MATH_BASE_ERROR: u32 = 0x200;
+
+#[derive(Error, Debug, Copy, Clone)]
+#[repr(u32)]
+pub enum MathError {
+    #[error("Arithmetic Overflow")]
+    Overflow = CODIGO_MATH_BASE_ERROR,
+
+    #[error("Arithmetic Underflow")]
+    Underflow,
+}
+
+impl From<MathError> for ProgramError {
+    fn from(e: MathError) -> Self {
+        ProgramError::Custom(e as u32)
+    }
+}
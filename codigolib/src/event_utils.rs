@@ -0,0 +1,351 @@
+use crate::errors::CodecError;
+use crate::rust_utils::*;
+use solana_program::{log::sol_log_data, program_error::ProgramError, pubkey::Pubkey};
+
+// Prefix the runtime adds to every `sol_log_data` entry in the transaction logs
+pub const PROGRAM_DATA_PREFIX: &str = "Program data: ";
+
+pub trait Event: Sized {
+    const DISCRIMINATOR: u8;
+    // Packed size of the event fields, without the discriminator
+    const LEN: usize;
+    // Name and size of each field in packing order, as `State::FIELDS`
+    const FIELDS: &'static [(&'static str, usize)] = &[];
+
+    fn pack<'a>(&self, buf: &'a mut [u8]) -> &'a mut [u8];
+    fn unpack(buf: &[u8]) -> (&[u8], Self);
+
+    // Like `unpack` for logs that may not have been packed by the codec
+    fn try_unpack(buf: &[u8]) -> Result<(&[u8], Self), ProgramError> {
+        check_len(buf, Self::LEN)?;
+        Ok(Self::unpack(buf))
+    }
+}
+
+pub fn pack_event<E: Event>(event: &E) -> Vec<u8> {
+    let mut data = vec![0u8; 1 + E::LEN];
+    let dst = pack_u8(&mut data, E::DISCRIMINATOR);
+    event.pack(dst);
+    data
+}
+
+// None for the data of another event, an error when the discriminator matches but the fields
+// can't be read
+pub fn unpack_event<E: Event>(data: &[u8]) -> Result<Option<E>, ProgramError> {
+    if data.first() != Some(&E::DISCRIMINATOR) {
+        return Ok(None);
+    }
+    if data.len() != 1 + E::LEN {
+        return Err(CodecError::InvalidEventLength.into());
+    }
+
+    let (_, event) = E::try_unpack(&data[1..])?;
+    Ok(Some(event))
+}
+
+pub fn emit_event<E: Event>(event: &E) {
+    sol_log_data(&[&pack_event(event)]);
+}
+
+// Returns the decoded fields of a "Program data:" log line
+pub fn parse_program_data(log: &str) -> Option<Vec<Vec<u8>>> {
+    log.strip_prefix(PROGRAM_DATA_PREFIX)?
+        .split_whitespace()
+        .map(|field| base64::decode(field).ok())
+        .collect()
+}
+
+// Decodes every event of type E emitted by `program_id` in the transaction logs, in emission
+// order. Data lines belong to the program of the innermost "Program <id> invoke [n]" line not
+// closed yet by its "success" or "failed" line, so the data of the programs it invokes, or of
+// other instructions, is left out
+pub fn decode_events<E: Event, S: AsRef<str>>(
+    program_id: &Pubkey,
+    logs: &[S],
+) -> Result<Vec<E>, ProgramError> {
+    let program_id = program_id.to_string();
+    let mut invoked: Vec<&str> = Vec::new();
+    let mut events = Vec::new();
+
+    for log in logs {
+        let log = log.as_ref();
+        if log.starts_with(PROGRAM_DATA_PREFIX) {
+            if invoked.last() != Some(&program_id.as_str()) {
+                continue;
+            }
+            let fields = parse_program_data(log).ok_or(ProgramError::InvalidArgument)?;
+            if let [data] = fields.as_slice() {
+                events.extend(unpack_event(data)?);
+            }
+        } else if let Some(frame) = log.strip_prefix("Program ") {
+            match frame.split_whitespace().collect::<Vec<_>>().as_slice() {
+                // Only lines naming a program open or close a frame, a program can log "success"
+                // itself and its "Program log: success" line must not close it
+                [id, ..] if id.parse::<Pubkey>().is_err() => {}
+                [id, "invoke", _] => invoked.push(id),
+                [_, "success"] | [_, "failed:", ..] => {
+                    invoked.pop();
+                }
+                _ => {}
+            }
+        }
+    }
+
+    Ok(events)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TestRuntime;
+    use solana_program::{
+        account_info::AccountInfo, entrypoint::ProgramResult, instruction::Instruction,
+    };
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct RecordCreated {
+        owner: Pubkey,
+        name: String,
+    }
+
+    impl Event for RecordCreated {
+        const DISCRIMINATOR: u8 = 0;
+        const LEN: usize = 32 + 54;
+
+        fn pack<'a>(&self, buf: &'a mut [u8]) -> &'a mut [u8] {
+            let dst = pack_pubkey(buf, self.owner);
+            pack_string(dst, self.name.clone(), 54)
+        }
+
+        fn unpack(buf: &[u8]) -> (&[u8], Self) {
+            let (dst, owner) = unpack_pubkey(buf);
+            let (dst, name) = state_unpack_string(dst, 54);
+            (dst, RecordCreated { owner, name })
+        }
+
+        fn try_unpack(buf: &[u8]) -> Result<(&[u8], Self), ProgramError> {
+            let (dst, owner) = try_unpack_pubkey(buf)?;
+            let (dst, name) = try_state_unpack_string(dst, 54)?;
+            Ok((dst, RecordCreated { owner, name }))
+        }
+    }
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct IncomeRegistered {
+        amount: u32,
+        total_balance: i64,
+    }
+
+    impl Event for IncomeRegistered {
+        const DISCRIMINATOR: u8 = 1;
+        const LEN: usize = 4 + 8;
+
+        fn pack<'a>(&self, buf: &'a mut [u8]) -> &'a mut [u8] {
+            let dst = pack_u32(buf, self.amount);
+            pack_i64(dst, self.total_balance)
+        }
+
+        fn unpack(buf: &[u8]) -> (&[u8], Self) {
+            let (dst, amount) = unpack_u32(buf);
+            let (dst, total_balance) = unpack_i64(dst);
+            (
+                dst,
+                IncomeRegistered {
+                    amount,
+                    total_balance,
+                },
+            )
+        }
+    }
+
+    fn program_data_log(data: &[u8]) -> String {
+        format!("{}{}", PROGRAM_DATA_PREFIX, base64::encode(data))
+    }
+
+    fn income(amount: u32) -> IncomeRegistered {
+        IncomeRegistered {
+            amount,
+            total_balance: amount as i64,
+        }
+    }
+
+    // Emits the income of every byte of the instruction data
+    fn process_emitting_instruction(
+        _program_id: &Pubkey,
+        _accounts: &[AccountInfo],
+        data: &[u8],
+    ) -> ProgramResult {
+        for amount in data {
+            emit_event(&income(*amount as u32));
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn it_pack_unpack_event() {
+        let event = IncomeRegistered {
+            amount: 100,
+            total_balance: -50,
+        };
+
+        let data = pack_event(&event);
+        assert_eq!(data.len(), 1 + IncomeRegistered::LEN);
+        assert_eq!(data[0], IncomeRegistered::DISCRIMINATOR);
+        assert_eq!(unpack_event::<IncomeRegistered>(&data), Ok(Some(event)));
+    }
+
+    #[test]
+    fn it_unpack_event_checks_discriminator_and_len() {
+        let data = pack_event(&income(100));
+
+        assert_eq!(unpack_event::<RecordCreated>(&data), Ok(None));
+        assert_eq!(unpack_event::<IncomeRegistered>(&[]), Ok(None));
+        assert_eq!(
+            unpack_event::<IncomeRegistered>(&data[..5]),
+            Err(CodecError::InvalidEventLength.into())
+        );
+        assert_eq!(
+            unpack_event::<IncomeRegistered>(&[data.clone(), vec![0]].concat()),
+            Err(CodecError::InvalidEventLength.into())
+        );
+    }
+
+    #[test]
+    fn it_unpack_event_checks_the_fields() {
+        let mut data = pack_event(&RecordCreated {
+            owner: Pubkey::new_unique(),
+            name: "John Doe".to_string(),
+        });
+
+        data[33] = 51;
+        assert_eq!(
+            unpack_event::<RecordCreated>(&data),
+            Err(CodecError::StringTooLong.into())
+        );
+        data[33] = 8;
+        data[37] = 0xff;
+        assert_eq!(
+            unpack_event::<RecordCreated>(&data),
+            Err(CodecError::InvalidUtf8.into())
+        );
+    }
+
+    #[test]
+    fn it_parse_program_data() {
+        assert_eq!(
+            parse_program_data("Program data: AQID BA=="),
+            Some(vec![vec![1, 2, 3], vec![4]])
+        );
+        assert_eq!(parse_program_data("Program log: Instruction: Income"), None);
+        assert_eq!(parse_program_data("Program data: !!!"), None);
+    }
+
+    #[test]
+    fn it_decode_events_from_logs() {
+        let program_id = Pubkey::new_unique();
+        let created = RecordCreated {
+            owner: Pubkey::new_unique(),
+            name: "John Doe".to_string(),
+        };
+
+        let logs = vec![
+            format!("Program {} invoke [1]", program_id),
+            program_data_log(&pack_event(&created)),
+            "Program log: unrelated".to_string(),
+            program_data_log(&pack_event(&income(100))),
+            format!(
+                "Program {} consumed 1200 of 200000 compute units",
+                program_id
+            ),
+            format!("Program {} success", program_id),
+        ];
+
+        assert_eq!(
+            decode_events::<RecordCreated, _>(&program_id, &logs),
+            Ok(vec![created])
+        );
+        assert_eq!(
+            decode_events::<IncomeRegistered, _>(&program_id, &logs),
+            Ok(vec![income(100)])
+        );
+    }
+
+    #[test]
+    fn it_decode_events_of_the_program_only() {
+        let program_id = Pubkey::new_unique();
+        let other = Pubkey::new_unique();
+
+        let logs = vec![
+            format!("Program {} invoke [1]", other),
+            program_data_log(&pack_event(&income(1))),
+            format!("Program {} success", other),
+            format!("Program {} invoke [1]", program_id),
+            program_data_log(&pack_event(&income(2))),
+            format!("Program {} invoke [2]", other),
+            program_data_log(&pack_event(&income(3))),
+            format!("Program {} failed: custom program error: 0x1", other),
+            program_data_log(&pack_event(&income(4))),
+            format!("Program {} success", program_id),
+            program_data_log(&pack_event(&income(5))),
+        ];
+
+        assert_eq!(
+            decode_events::<IncomeRegistered, _>(&program_id, &logs),
+            Ok(vec![income(2), income(4)])
+        );
+    }
+
+    #[test]
+    fn it_decode_events_after_logs_looking_like_frames() {
+        let program_id = Pubkey::new_unique();
+
+        let logs = vec![
+            format!("Program {} invoke [1]", program_id),
+            "Program log: success".to_string(),
+            program_data_log(&pack_event(&income(1))),
+            "Program log: failed: not enough funds".to_string(),
+            "Program return: success AQ==".to_string(),
+            program_data_log(&pack_event(&income(2))),
+            format!("Program {} success", program_id),
+        ];
+
+        assert_eq!(
+            decode_events::<IncomeRegistered, _>(&program_id, &logs),
+            Ok(vec![income(1), income(2)])
+        );
+    }
+
+    #[test]
+    fn it_decode_events_rejects_invalid_data_of_the_program() {
+        let program_id = Pubkey::new_unique();
+        let logs = |data: String| {
+            vec![
+                format!("Program {} invoke [1]", program_id),
+                data,
+                format!("Program {} success", program_id),
+            ]
+        };
+
+        assert_eq!(
+            decode_events::<IncomeRegistered, _>(&program_id, &logs(program_data_log(&[1, 2]))),
+            Err(CodecError::InvalidEventLength.into())
+        );
+        assert_eq!(
+            decode_events::<IncomeRegistered, _>(&program_id, &logs("Program data: !!!".into())),
+            Err(ProgramError::InvalidArgument)
+        );
+    }
+
+    #[test]
+    fn it_emit_event_logs_program_data() {
+        let mut runtime = TestRuntime::new(Pubkey::new_unique(), process_emitting_instruction);
+        let instruction = Instruction::new_with_bytes(runtime.program_id, &[7, 3], vec![]);
+
+        runtime.process(&instruction, &[]).unwrap();
+
+        assert_eq!(
+            decode_events::<IncomeRegistered, _>(&runtime.program_id, runtime.logs()),
+            Ok(vec![income(7), income(3)])
+        );
+    }
+}
@@ -1,6 +1,16 @@
+pub mod delegate_utils;
 pub mod errors;
+pub mod event_utils;
+pub mod migration_utils;
 pub mod rust_utils;
 pub mod sec_utils;
+pub mod string_utils;
+#[cfg(any(test, feature = "test-utils"))]
+pub mod testing;
+pub mod token_utils;
+
+#[cfg(test)]
+pub mod codec_prop_tests;
 
 #[cfg(test)]
 pub mod rust_utils_tests;
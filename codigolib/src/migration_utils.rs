@@ -0,0 +1,340 @@
+use crate::errors::CodecError;
+use crate::rust_utils::*;
+use crate::sec_utils::*;
+use solana_program::{
+    account_info::AccountInfo, entrypoint::ProgramResult, program::invoke,
+    program_error::ProgramError, pubkey::Pubkey, rent::Rent, system_instruction,
+};
+
+// Versioned states start with a version byte followed by the layout of that version
+pub const VERSION_SIZE: usize = 1;
+
+// Version of the states written before the version byte was added. Their first byte belongs to
+// the first field, so they are recognized by their length alone: no versioned layout may have the
+// same length
+pub const UNVERSIONED: u8 = 0;
+
+// Rewrites the `old_len` bytes of `from_version` into the layout of `from_version + 1`. The
+// destination is zero-initialized with `new_len` bytes and the version byte is set after
+// `rewrite` returns.
+pub struct Migration {
+    pub from_version: u8,
+    pub old_len: usize,
+    pub new_len: usize,
+    pub rewrite: fn(&[u8], &mut [u8]),
+}
+
+pub fn state_unpack_version(buf: &[u8]) -> (&[u8], u8) {
+    unpack_u8(buf)
+}
+
+pub fn pack_version(buf: &mut [u8], version: u8) -> &mut [u8] {
+    pack_u8(buf, version)
+}
+
+pub fn latest_version(base_version: u8, migrations: &[Migration]) -> u8 {
+    migrations
+        .iter()
+        .filter_map(|migration| migration.from_version.checked_add(1))
+        .max()
+        .unwrap_or(base_version)
+        .max(base_version)
+}
+
+// Upgrades the data through every migration step until it reaches the latest version. Processors
+// can use it to accept old accounts read-only, or `migrate_account` to persist the upgrade.
+pub fn migrate_data(
+    data: &[u8],
+    base_version: u8,
+    migrations: &[Migration],
+) -> Result<Vec<u8>, ProgramError> {
+    let latest = latest_version(base_version, migrations);
+    let mut current = data.to_vec();
+
+    let unversioned = migrations
+        .iter()
+        .find(|migration| migration.from_version == UNVERSIONED);
+    let mut version = match unversioned {
+        Some(migration) if current.len() == migration.old_len => UNVERSIONED,
+        // A version byte of 0 is the first byte of an unversioned state of the wrong length
+        _ => match try_unpack_u8(&current)?.1 {
+            UNVERSIONED => return Err(CodecError::UnsupportedStateVersion.into()),
+            version => version,
+        },
+    };
+
+    while version != latest {
+        let migration = migrations
+            .iter()
+            .find(|migration| migration.from_version == version)
+            .ok_or(CodecError::UnsupportedStateVersion)?;
+        let next = version
+            .checked_add(1)
+            .ok_or(CodecError::UnsupportedStateVersion)?;
+        if current.len() < migration.old_len || migration.new_len < VERSION_SIZE {
+            return Err(CodecError::BufferTooShort.into());
+        }
+
+        let mut upgraded = vec![0u8; migration.new_len];
+        (migration.rewrite)(&current[..migration.old_len], &mut upgraded);
+        pack_version(&mut upgraded, next);
+        current = upgraded;
+        version = next;
+    }
+
+    Ok(current)
+}
+
+pub fn rent_difference(rent: &Rent, new_len: usize, current_lamports: u64) -> u64 {
+    rent.minimum_balance(new_len)
+        .saturating_sub(current_lamports)
+}
+
+// Rewrites the account into the latest layout, reallocating it and paying the rent difference
+// from `payer`. Must be called with the accounts received by the program entrypoint.
+pub fn migrate_account<'a>(
+    program_id: &Pubkey,
+    account: &AccountInfo<'a>,
+    payer: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>,
+    base_version: u8,
+    migrations: &[Migration],
+    rent: &Rent,
+) -> ProgramResult {
+    verify_signers(&[payer])?;
+    verify_ownership(program_id, &[account])?;
+
+    let data = migrate_data(&account.try_borrow_data()?, base_version, migrations)?;
+
+    let lamports = rent_difference(rent, data.len(), account.lamports());
+    if lamports > 0 {
+        invoke(
+            &system_instruction::transfer(payer.key, account.key, lamports),
+            &[payer.clone(), account.clone(), system_program.clone()],
+        )?;
+    }
+
+    account.realloc(data.len(), false)?;
+    account.try_borrow_mut_data()?.copy_from_slice(&data);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::errors::SecurityError;
+    use crate::testing::TestAccount;
+
+    // v0: name (cap:50) + moves + outcome + income + total_balance, without a version byte
+    const RECORD_V0_LEN: usize = 54 + 2 + 4 + 4 + 8;
+    // v1: version + name (cap:50) + moves + outcome + income + total_balance
+    const RECORD_V1_LEN: usize = VERSION_SIZE + 54 + 2 + 4 + 4 + 8;
+    // v2: v1 + currency (cap:3)
+    const RECORD_V2_LEN: usize = RECORD_V1_LEN + 7;
+    // v3: v2 + last_updated
+    const RECORD_V3_LEN: usize = RECORD_V2_LEN + 8;
+
+    fn record_v0_to_v1(old: &[u8], new: &mut [u8]) {
+        new[VERSION_SIZE..].copy_from_slice(old);
+    }
+
+    fn record_v1_to_v2(old: &[u8], new: &mut [u8]) {
+        new[..RECORD_V1_LEN].copy_from_slice(&old[..RECORD_V1_LEN]);
+        pack_string(&mut new[RECORD_V1_LEN..], "USD".to_string(), 7);
+    }
+
+    fn record_v2_to_v3(old: &[u8], new: &mut [u8]) {
+        new[..RECORD_V2_LEN].copy_from_slice(&old[..RECORD_V2_LEN]);
+        pack_i64(&mut new[RECORD_V2_LEN..], -1);
+    }
+
+    const MIGRATIONS: &[Migration] = &[
+        Migration {
+            from_version: UNVERSIONED,
+            old_len: RECORD_V0_LEN,
+            new_len: RECORD_V1_LEN,
+            rewrite: record_v0_to_v1,
+        },
+        Migration {
+            from_version: 1,
+            old_len: RECORD_V1_LEN,
+            new_len: RECORD_V2_LEN,
+            rewrite: record_v1_to_v2,
+        },
+        Migration {
+            from_version: 2,
+            old_len: RECORD_V2_LEN,
+            new_len: RECORD_V3_LEN,
+            rewrite: record_v2_to_v3,
+        },
+    ];
+
+    fn pack_record_v1(buf: &mut [u8]) -> &mut [u8] {
+        let dst = pack_version(buf, 1);
+        let dst = pack_string(dst, "John Doe".to_string(), 54);
+        let dst = pack_u16(dst, 2);
+        let dst = pack_u32(dst, 50);
+        let dst = pack_u32(dst, 100);
+        pack_i64(dst, 50)
+    }
+
+    fn assert_record_v1_fields(buf: &[u8]) -> &[u8] {
+        let (dst, name) = state_unpack_string(buf, 54);
+        let (dst, moves) = unpack_u16(dst);
+        let (dst, outcome) = unpack_u32(dst);
+        let (dst, income) = unpack_u32(dst);
+        let (dst, total_balance) = unpack_i64(dst);
+        assert_eq!(name, "John Doe".to_string());
+        assert_eq!((moves, outcome, income, total_balance), (2, 50, 100, 50));
+        dst
+    }
+
+    #[test]
+    fn it_latest_version() {
+        assert_eq!(latest_version(1, MIGRATIONS), 3);
+        assert_eq!(latest_version(1, &[]), 1);
+    }
+
+    #[test]
+    fn it_migrates_v1_to_latest() {
+        let v1: &mut [u8] = &mut [0; RECORD_V1_LEN];
+        pack_record_v1(v1);
+
+        let v3 = migrate_data(v1, 1, MIGRATIONS).unwrap();
+        assert_eq!(v3.len(), RECORD_V3_LEN);
+
+        let (dst, version) = state_unpack_version(&v3);
+        assert_eq!(version, 3);
+        let dst = assert_record_v1_fields(dst);
+        let (dst, currency) = state_unpack_string(dst, 7);
+        let (dst, last_updated) = unpack_i64(dst);
+        assert_eq!(currency, "USD".to_string());
+        assert_eq!(last_updated, -1);
+        assert_eq!(dst.len(), 0);
+    }
+
+    #[test]
+    fn it_migrates_unversioned_to_latest() {
+        let v1: &mut [u8] = &mut [0; RECORD_V1_LEN];
+        pack_record_v1(v1);
+
+        let v3 = migrate_data(&v1[VERSION_SIZE..], 1, MIGRATIONS).unwrap();
+        assert_eq!(v3, migrate_data(v1, 1, MIGRATIONS).unwrap());
+    }
+
+    #[test]
+    fn it_migrates_v2_to_latest() {
+        let v2: &mut [u8] = &mut [0; RECORD_V2_LEN];
+        let dst = pack_record_v1(v2);
+        pack_string(dst, "EUR".to_string(), 7);
+        v2[0] = 2;
+
+        let v3 = migrate_data(v2, 1, MIGRATIONS).unwrap();
+        assert_eq!(v3.len(), RECORD_V3_LEN);
+
+        let (dst, version) = state_unpack_version(&v3);
+        assert_eq!(version, 3);
+        let dst = assert_record_v1_fields(dst);
+        let (_, currency) = state_unpack_string(dst, 7);
+        assert_eq!(currency, "EUR".to_string());
+    }
+
+    #[test]
+    fn it_keeps_latest_version_untouched() {
+        let v3: &mut [u8] = &mut [7; RECORD_V3_LEN];
+        v3[0] = 3;
+
+        assert_eq!(migrate_data(v3, 1, MIGRATIONS).unwrap(), v3.to_vec());
+    }
+
+    #[test]
+    fn it_rejects_unknown_versions() {
+        let v0: &mut [u8] = &mut [0; RECORD_V1_LEN];
+        let v4: &mut [u8] = &mut [0; RECORD_V3_LEN];
+        v4[0] = 4;
+
+        assert_eq!(
+            Err(CodecError::UnsupportedStateVersion.into()),
+            migrate_data(v0, 1, MIGRATIONS)
+        );
+        assert_eq!(
+            Err(CodecError::UnsupportedStateVersion.into()),
+            migrate_data(v4, 1, MIGRATIONS)
+        );
+        assert_eq!(
+            Err(CodecError::BufferTooShort.into()),
+            migrate_data(&[], 1, MIGRATIONS)
+        );
+    }
+
+    #[test]
+    fn it_rejects_data_shorter_than_its_version() {
+        let v1: &mut [u8] = &mut [0; RECORD_V1_LEN];
+        pack_record_v1(v1);
+
+        assert_eq!(
+            Err(CodecError::BufferTooShort.into()),
+            migrate_data(&v1[..RECORD_V1_LEN - 2], 1, MIGRATIONS)
+        );
+        assert_eq!(
+            Err(CodecError::BufferTooShort.into()),
+            migrate_data(&v1[..VERSION_SIZE], 1, MIGRATIONS)
+        );
+    }
+
+    #[test]
+    fn it_rejects_migrations_past_the_last_version() {
+        let last = &[Migration {
+            from_version: u8::MAX,
+            old_len: 1,
+            new_len: 1,
+            rewrite: |_, _| {},
+        }];
+
+        assert_eq!(latest_version(1, last), 1);
+        assert_eq!(
+            Err(CodecError::UnsupportedStateVersion.into()),
+            migrate_data(&[u8::MAX], 1, last)
+        );
+    }
+
+    #[test]
+    fn it_migrates_only_accounts_of_the_program() {
+        let program_id = Pubkey::new_unique();
+        let mut record = TestAccount::new().writable().zeroed(RECORD_V1_LEN);
+        let mut payer = TestAccount::new()
+            .signer()
+            .writable()
+            .lamports(1_000_000_000);
+        let mut system_program = TestAccount::new().key(solana_program::system_program::id());
+
+        assert_eq!(
+            migrate_account(
+                &program_id,
+                &record.info(),
+                &payer.info(),
+                &system_program.info(),
+                1,
+                MIGRATIONS,
+                &Rent::default(),
+            ),
+            Err(SecurityError::WrongAccountOwner.into())
+        );
+        assert_eq!(record.data, vec![0; RECORD_V1_LEN]);
+    }
+
+    #[test]
+    fn it_rent_difference() {
+        let rent = Rent::default();
+        let v1_rent = rent.minimum_balance(RECORD_V1_LEN);
+        let v3_rent = rent.minimum_balance(RECORD_V3_LEN);
+
+        assert_eq!(
+            rent_difference(&rent, RECORD_V3_LEN, v1_rent),
+            v3_rent - v1_rent
+        );
+        assert_eq!(rent_difference(&rent, RECORD_V3_LEN, v3_rent), 0);
+        assert_eq!(rent_difference(&rent, RECORD_V1_LEN, v3_rent), 0);
+    }
+}
@@ -1,4 +1,5 @@
-use solana_program::pubkey::Pubkey;
+use crate::errors::CodecError;
+use solana_program::{program_error::ProgramError, pubkey::Pubkey};
 
 use std::ptr;
 
@@ -22,6 +23,25 @@ pub fn check_valid_bool_value(buf: &[u8]) {
         panic!("{:?}: Invalid boolean value", buf)
     }
 }
+// Implemented by the types generated from a CIDL, shared so crates generated from different CIDL
+// files can pack each other's types
+pub trait State: Sized {
+    const LEN: usize;
+    // Name and size of each field in packing order, generated types list them so a packed value
+    // can be annotated field by field
+    const FIELDS: &'static [(&'static str, usize)] = &[];
+
+    fn pack<'a>(&self, buf: &'a mut [u8]) -> &'a mut [u8];
+    fn unpack(buf: &[u8]) -> (&[u8], Self);
+
+    // Like `unpack` for data that may not have been packed by the codec, generated types also
+    // check the value of every field
+    fn try_unpack(buf: &[u8]) -> Result<(&[u8], Self), ProgramError> {
+        check_len(buf, Self::LEN)?;
+        Ok(Self::unpack(buf))
+    }
+}
+
 // Generic Pack
 
 // float
@@ -91,6 +111,21 @@ pub fn pack_string(buf: &mut [u8], data: String, field_size: usize) -> &mut [u8]
     buf[4..4 + data.len()].copy_from_slice(&data.as_bytes());
     &mut buf[field_size..]
 }
+pub fn try_pack_string(
+    buf: &mut [u8],
+    data: String,
+    field_size: usize,
+) -> Result<&mut [u8], ProgramError> {
+    // The length prefix alone takes 4 bytes
+    if field_size < 4 || buf.len() < field_size {
+        return Err(CodecError::BufferTooShort.into());
+    }
+    if data.len() > field_size - 4 {
+        return Err(CodecError::StringTooLong.into());
+    }
+
+    Ok(pack_string(buf, data, field_size))
+}
 pub fn pack_option<F, T>(buf: &mut [u8], f: F, data: Option<T>, null_value: T) -> &mut [u8]
 where
     F: Fn(&mut [u8], T) -> &mut [u8],
@@ -215,6 +250,17 @@ pub fn instructions_unpack_string(buf: &[u8]) -> (&[u8], String) {
         String::from_utf8(data_src).unwrap(),
     );
 }
+pub fn try_instructions_unpack_string(buf: &[u8]) -> Result<(&[u8], String), ProgramError> {
+    if buf.len() < 4 {
+        return Err(CodecError::BufferTooShort.into());
+    }
+    let (data_buf, data_len) = unpack_u32(buf);
+    let data_src = data_buf
+        .get(..data_len as usize)
+        .ok_or(CodecError::BufferTooShort)?;
+    let data = String::from_utf8(data_src.to_vec()).map_err(|_| CodecError::InvalidUtf8)?;
+    Ok((&data_buf[data_len as usize..], data))
+}
 pub fn state_unpack_string(buf: &[u8], field_size: usize) -> (&[u8], String) {
     // Get the string length
     let mut data_len_src: [u8; 4] = [0 as u8; 4];
@@ -305,3 +351,185 @@ pub fn pack_pubkey(buf: &mut [u8], data: Pubkey) -> &mut [u8] {
     buf[0..32].copy_from_slice(&data.to_bytes());
     &mut buf[32..]
 }
+
+// Checked Unpack
+// Same layouts as the unpack functions, for bytes that may not come from the codec such as
+// instruction data. Malformed input is an error instead of a panic, and length prefixes are
+// checked against the buffer before anything is allocated
+
+pub fn check_len(buf: &[u8], len: usize) -> Result<(), ProgramError> {
+    if buf.len() < len {
+        return Err(CodecError::BufferTooShort.into());
+    }
+    Ok(())
+}
+
+pub fn try_unpack_bool(buf: &[u8]) -> Result<(&[u8], bool), ProgramError> {
+    check_len(buf, 1)?;
+    if buf[0] > 1 {
+        return Err(CodecError::InvalidBoolValue.into());
+    }
+    Ok(unpack_bool(buf))
+}
+pub fn try_unpack_u8(buf: &[u8]) -> Result<(&[u8], u8), ProgramError> {
+    check_len(buf, 1)?;
+    Ok(unpack_u8(buf))
+}
+pub fn try_unpack_i8(buf: &[u8]) -> Result<(&[u8], i8), ProgramError> {
+    check_len(buf, 1)?;
+    Ok(unpack_i8(buf))
+}
+pub fn try_unpack_u16(buf: &[u8]) -> Result<(&[u8], u16), ProgramError> {
+    check_len(buf, 2)?;
+    Ok(unpack_u16(buf))
+}
+pub fn try_unpack_i16(buf: &[u8]) -> Result<(&[u8], i16), ProgramError> {
+    check_len(buf, 2)?;
+    Ok(unpack_i16(buf))
+}
+pub fn try_unpack_u32(buf: &[u8]) -> Result<(&[u8], u32), ProgramError> {
+    check_len(buf, 4)?;
+    Ok(unpack_u32(buf))
+}
+pub fn try_unpack_i32(buf: &[u8]) -> Result<(&[u8], i32), ProgramError> {
+    check_len(buf, 4)?;
+    Ok(unpack_i32(buf))
+}
+pub fn try_unpack_f32(buf: &[u8]) -> Result<(&[u8], f32), ProgramError> {
+    check_len(buf, 4)?;
+    Ok(unpack_f32(buf))
+}
+pub fn try_unpack_u64(buf: &[u8]) -> Result<(&[u8], u64), ProgramError> {
+    check_len(buf, 8)?;
+    Ok(unpack_u64(buf))
+}
+pub fn try_unpack_i64(buf: &[u8]) -> Result<(&[u8], i64), ProgramError> {
+    check_len(buf, 8)?;
+    Ok(unpack_i64(buf))
+}
+pub fn try_unpack_f64(buf: &[u8]) -> Result<(&[u8], f64), ProgramError> {
+    check_len(buf, 8)?;
+    Ok(unpack_f64(buf))
+}
+pub fn try_unpack_u128(buf: &[u8]) -> Result<(&[u8], u128), ProgramError> {
+    check_len(buf, 16)?;
+    Ok(unpack_u128(buf))
+}
+pub fn try_unpack_i128(buf: &[u8]) -> Result<(&[u8], i128), ProgramError> {
+    check_len(buf, 16)?;
+    Ok(unpack_i128(buf))
+}
+pub fn try_unpack_pubkey(buf: &[u8]) -> Result<(&[u8], Pubkey), ProgramError> {
+    check_len(buf, 32)?;
+    Ok(unpack_pubkey(buf))
+}
+
+pub fn try_state_unpack_string(
+    buf: &[u8],
+    field_size: usize,
+) -> Result<(&[u8], String), ProgramError> {
+    check_len(buf, field_size.max(4))?;
+    let (data_buf, data_len) = unpack_u32(buf);
+    if data_len as usize > field_size.saturating_sub(4) {
+        return Err(CodecError::StringTooLong.into());
+    }
+    let data_src = &data_buf[..data_len as usize];
+    let data = String::from_utf8(data_src.to_vec()).map_err(|_| CodecError::InvalidUtf8)?;
+    Ok((&buf[field_size..], data))
+}
+
+// The field takes `internal_size` bytes after the flag whether the value is there or not
+pub fn try_state_unpack_option<F, T>(
+    buf: &[u8],
+    f: F,
+    internal_size: usize,
+) -> Result<(&[u8], Option<T>), ProgramError>
+where
+    F: Fn(&[u8]) -> Result<(&[u8], T), ProgramError>,
+{
+    check_len(buf, 1 + internal_size)?;
+    let (dst, non_null) = try_unpack_bool(buf)?;
+    let data = if non_null { Some(f(dst)?.1) } else { None };
+    Ok((&buf[1 + internal_size..], data))
+}
+
+pub fn try_instructions_unpack_option<F, T>(
+    buf: &[u8],
+    f: F,
+) -> Result<(&[u8], Option<T>), ProgramError>
+where
+    F: Fn(&[u8]) -> Result<(&[u8], T), ProgramError>,
+{
+    let (dst, non_null) = try_unpack_bool(buf)?;
+    if non_null {
+        let (xdst, x) = f(dst)?;
+        return Ok((xdst, Some(x)));
+    }
+    Ok((dst, None))
+}
+
+// Elements take `subtype_size` bytes each, a length prefix asking for more elements than the
+// buffer holds is rejected before allocating them
+pub fn try_instructions_unpack_vec<F, T>(
+    buf: &[u8],
+    subtype_size: usize,
+    f: F,
+) -> Result<(&[u8], Vec<T>), ProgramError>
+where
+    F: Fn(&[u8]) -> Result<(&[u8], T), ProgramError>,
+{
+    // No codec type is empty, zero-size elements would let the length prefix alone allocate
+    // up to u32::MAX of them
+    if subtype_size == 0 {
+        return Err(CodecError::BufferTooShort.into());
+    }
+    let (data_buf, vec_len) = try_unpack_u32(buf)?;
+    let data_size = (vec_len as usize)
+        .checked_mul(subtype_size)
+        .filter(|size| *size <= data_buf.len())
+        .ok_or(CodecError::BufferTooShort)?;
+    let ret = try_unpack_elements(&data_buf[..data_size], vec_len as usize, subtype_size, f)?;
+    Ok((&data_buf[data_size..], ret))
+}
+
+pub fn try_state_unpack_vec<F, T>(
+    buf: &[u8],
+    cap: usize,
+    subtype_size: usize,
+    f: F,
+) -> Result<(&[u8], Vec<T>), ProgramError>
+where
+    F: Fn(&[u8]) -> Result<(&[u8], T), ProgramError>,
+{
+    let field_size = cap
+        .checked_mul(subtype_size)
+        .and_then(|size| size.checked_add(4))
+        .ok_or(CodecError::BufferTooShort)?;
+    check_len(buf, field_size)?;
+    let (data_buf, vec_len) = unpack_u32(buf);
+    if vec_len as usize > cap {
+        return Err(CodecError::VectorTooLong.into());
+    }
+    let data_size = vec_len as usize * subtype_size;
+    let ret = try_unpack_elements(&data_buf[..data_size], vec_len as usize, subtype_size, f)?;
+    Ok((&buf[field_size..], ret))
+}
+
+// Each element is read from its own `subtype_size` bytes, so an element function reading more
+// than that fails instead of reading the next one
+fn try_unpack_elements<F, T>(
+    buf: &[u8],
+    vec_len: usize,
+    subtype_size: usize,
+    f: F,
+) -> Result<Vec<T>, ProgramError>
+where
+    F: Fn(&[u8]) -> Result<(&[u8], T), ProgramError>,
+{
+    let mut ret = Vec::with_capacity(vec_len);
+    for idx in 0..vec_len {
+        let (_, item) = f(&buf[idx * subtype_size..(idx + 1) * subtype_size])?;
+        ret.push(item);
+    }
+    Ok(ret)
+}
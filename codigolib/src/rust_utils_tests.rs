@@ -1,3 +1,4 @@
+use crate::errors::CodecError;
 use crate::rust_utils::*;
 use solana_program::pubkey::Pubkey;
 
@@ -413,4 +414,177 @@ mod tests {
 
         pack_string(big, a.clone(), 30);
     }
+
+    #[test]
+    fn it_try_pack_unpack_string() {
+        let big: &mut [u8] = &mut [0; 180];
+
+        let a = "é".repeat(13);
+        let r = try_pack_string(big, a.clone(), 30).unwrap();
+
+        assert_eq!(r.len(), big.len() - 30);
+        let (_, value_a) = state_unpack_string(big, 30);
+        assert_eq!(value_a, a);
+    }
+
+    #[test]
+    fn it_try_pack_invalid_string() {
+        let big: &mut [u8] = &mut [0; 180];
+
+        let a = String::from("Something longer than accepted capacity");
+        assert_eq!(
+            Err(CodecError::StringTooLong.into()),
+            try_pack_string(big, a, 30)
+        );
+
+        // 13 two bytes chars take 26 bytes, the extra one crosses the capacity
+        let b = "é".repeat(14);
+        assert_eq!(
+            Err(CodecError::StringTooLong.into()),
+            try_pack_string(big, b, 30)
+        );
+    }
+
+    #[test]
+    fn it_try_pack_string_short_buffer() {
+        let small: &mut [u8] = &mut [0; 10];
+
+        assert_eq!(
+            Err(CodecError::BufferTooShort.into()),
+            try_pack_string(small, "Lorem".to_string(), 30)
+        );
+        // A field without room for the length prefix fits no string, not even an empty one
+        assert_eq!(
+            Err(CodecError::BufferTooShort.into()),
+            try_pack_string(small, String::new(), 3)
+        );
+    }
+
+    #[test]
+    fn it_try_instructions_unpack_string() {
+        let client_buffer: &[u8] = &[0x05, 0x00, 0x00, 0x00, 0x4c, 0x6f, 0x72, 0x65, 0x6d, 0x01];
+
+        let (ptr, value) = try_instructions_unpack_string(client_buffer).unwrap();
+        assert_eq!(value, "Lorem".to_string());
+        assert_eq!(ptr, &[0x01]);
+    }
+
+    #[test]
+    fn it_try_instructions_unpack_invalid_string() {
+        let truncated: &[u8] = &[0x05, 0x00, 0x00, 0x00, 0x4c, 0x6f];
+        let no_len: &[u8] = &[0x05, 0x00];
+        let invalid_utf8: &[u8] = &[0x02, 0x00, 0x00, 0x00, 0x4c, 0xc3];
+
+        assert_eq!(
+            Err(CodecError::BufferTooShort.into()),
+            try_instructions_unpack_string(truncated)
+        );
+        assert_eq!(
+            Err(CodecError::BufferTooShort.into()),
+            try_instructions_unpack_string(no_len)
+        );
+        assert_eq!(
+            Err(CodecError::InvalidUtf8.into()),
+            try_instructions_unpack_string(invalid_utf8)
+        );
+    }
+
+    #[test]
+    fn it_try_unpack_primitives() {
+        let buffer: &[u8] = &[0x01, 0x02, 0x03];
+
+        assert_eq!(try_unpack_u16(buffer), Ok((&buffer[2..], 0x0201)));
+        assert_eq!(try_unpack_bool(buffer), Ok((&buffer[1..], true)));
+        assert_eq!(
+            Err(CodecError::BufferTooShort.into()),
+            try_unpack_u32(buffer)
+        );
+        assert_eq!(
+            Err(CodecError::InvalidBoolValue.into()),
+            try_unpack_bool(&buffer[1..])
+        );
+        assert_eq!(Err(CodecError::BufferTooShort.into()), try_unpack_bool(&[]));
+    }
+
+    #[test]
+    fn it_try_state_unpack_string() {
+        let mut big = [0; 12];
+        pack_string(&mut big, "Lorem".to_string(), 10);
+
+        let (ptr, value) = try_state_unpack_string(&big, 10).unwrap();
+        assert_eq!(value, "Lorem");
+        assert_eq!(ptr.len(), 2);
+
+        // The length prefix claims more than the field holds
+        big[0] = 7;
+        assert_eq!(
+            Err(CodecError::StringTooLong.into()),
+            try_state_unpack_string(&big, 10)
+        );
+        assert_eq!(
+            Err(CodecError::BufferTooShort.into()),
+            try_state_unpack_string(&big[..9], 10)
+        );
+    }
+
+    #[test]
+    fn it_try_unpack_options() {
+        let some: &[u8] = &[0x01, 0x05, 0x00, 0xff];
+        let none: &[u8] = &[0x00, 0xaa, 0xbb, 0xff];
+
+        assert_eq!(
+            try_state_unpack_option(some, try_unpack_u16, 2),
+            Ok((&some[3..], Some(5)))
+        );
+        assert_eq!(
+            try_state_unpack_option(none, try_unpack_u16, 2),
+            Ok((&none[3..], None))
+        );
+        assert_eq!(
+            try_instructions_unpack_option(none, try_unpack_u16),
+            Ok((&none[1..], None))
+        );
+        assert_eq!(
+            Err(CodecError::BufferTooShort.into()),
+            try_state_unpack_option(&none[..2], try_unpack_u16, 2)
+        );
+        assert_eq!(
+            Err(CodecError::InvalidBoolValue.into()),
+            try_instructions_unpack_option(&[0x02, 0x00, 0x00], try_unpack_u16)
+        );
+    }
+
+    #[test]
+    fn it_try_unpack_vectors() {
+        let instruction: &[u8] = &[0x02, 0x00, 0x00, 0x00, 0x01, 0x00, 0x02, 0x00, 0xff];
+        assert_eq!(
+            try_instructions_unpack_vec(instruction, 2, try_unpack_u16),
+            Ok((&instruction[8..], vec![1, 2]))
+        );
+
+        // Nothing is allocated for a length prefix the buffer cannot hold
+        let huge: &[u8] = &[0xff, 0xff, 0xff, 0xff, 0x01, 0x00];
+        assert_eq!(
+            Err(CodecError::BufferTooShort.into()),
+            try_instructions_unpack_vec(huge, 2, try_unpack_u16)
+        );
+        assert_eq!(
+            Err(CodecError::BufferTooShort.into()),
+            try_instructions_unpack_vec(huge, 0, |buf| Ok((buf, ())))
+        );
+
+        let state: &[u8] = &[0x01, 0x00, 0x00, 0x00, 0x07, 0x00, 0xaa, 0xbb, 0xff];
+        assert_eq!(
+            try_state_unpack_vec(state, 2, 2, try_unpack_u16),
+            Ok((&state[8..], vec![7]))
+        );
+        assert_eq!(
+            Err(CodecError::VectorTooLong.into()),
+            try_state_unpack_vec(state, 0, 2, try_unpack_u16)
+        );
+        assert_eq!(
+            Err(CodecError::BufferTooShort.into()),
+            try_state_unpack_vec(&state[..7], 2, 2, try_unpack_u16)
+        );
+    }
 }
@@ -1,4 +1,6 @@
+use crate::delegate_utils::*;
 use crate::errors::*;
+use crate::token_utils::*;
 use solana_program::{account_info::AccountInfo, entrypoint::ProgramResult, pubkey::Pubkey};
 
 // ==== SECURITY RELATED =======
@@ -65,77 +67,111 @@ pub fn verify_signer_address(signer_address: &Pubkey, expected_address: &Pubkey)
     Ok(())
 }
 
+// The owner is always allowed, any other signer needs a delegate entry with at least the required role
+pub fn verify_delegate_role(
+    signer: &AccountInfo,
+    owner: &Pubkey,
+    delegates: &[Delegate],
+    required_role: DelegateRole,
+) -> ProgramResult {
+    verify_signers(&[signer])?;
+
+    if signer.key == owner {
+        return Ok(());
+    }
+
+    match find_delegate(delegates, signer.key) {
+        Some(delegate) if delegate.role.allows(required_role) => Ok(()),
+        Some(_) => Err(SecurityError::InsufficientDelegateRole.into()),
+        None => Err(SecurityError::UnrecognizedSignerAddress.into()),
+    }
+}
+
+pub fn verify_token_program(account_info: &AccountInfo) -> ProgramResult {
+    if *account_info.key != spl_token_program::id() {
+        return Err(SecurityError::InvalidTokenProgram.into());
+    }
+
+    verify_is_executable(&[account_info])
+}
+
+pub fn verify_mint(account_info: &AccountInfo, expected_mint: &Pubkey) -> ProgramResult {
+    if account_info.key != expected_mint {
+        return Err(SecurityError::TokenMintMismatch.into());
+    }
+
+    verify_ownership(&spl_token_program::id(), &[account_info])?;
+    verify_len(MINT_LEN, &[account_info])?;
+
+    if account_info.try_borrow_data()?[MINT_IS_INITIALIZED_OFFSET] == 0 {
+        return Err(SecurityError::TokenAccountNotInitialized.into());
+    }
+
+    Ok(())
+}
+
+pub fn verify_token_account(
+    account_info: &AccountInfo,
+    mint: &Pubkey,
+    owner: &Pubkey,
+) -> ProgramResult {
+    verify_ownership(&spl_token_program::id(), &[account_info])?;
+    verify_len(TOKEN_ACCOUNT_LEN, &[account_info])?;
+
+    let data = account_info.try_borrow_data()?;
+    if data[TOKEN_ACCOUNT_STATE_OFFSET] == 0 {
+        return Err(SecurityError::TokenAccountNotInitialized.into());
+    }
+
+    let (_, token_account) = unpack_token_account(&data);
+    if token_account.mint != *mint {
+        return Err(SecurityError::TokenMintMismatch.into());
+    }
+    if token_account.owner != *owner {
+        return Err(SecurityError::TokenOwnerMismatch.into());
+    }
+
+    Ok(())
+}
+
 ///========= TESTS ==========///
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::cell::RefCell;
-    use std::rc::Rc;
+    use crate::rust_utils::*;
+    use crate::testing::TestAccount;
 
     #[test]
     fn it_verify_ownership_is_ok() {
         let owner: &Pubkey = &Pubkey::new_unique();
-        let zero64: &mut u64 = &mut 0u64;
-        let zerou8: &mut [u8; 1] = &mut ([0u8]);
-
-        let ai: AccountInfo = AccountInfo {
-            key: &Pubkey::new_unique(),
-            is_signer: false,
-            is_writable: true,
-            lamports: Rc::new(RefCell::new(zero64)),
-            owner: owner,
-            executable: false,
-            rent_epoch: 0,
-            data: Rc::new(RefCell::new(zerou8)),
-        };
+        let mut account = TestAccount::new().writable().owner(*owner).zeroed(1);
+
+        let ai = account.info();
         assert_ne!(Ok(()), verify_ownership(&Pubkey::new_unique(), &[&ai]));
         assert_eq!(Ok(()), verify_ownership(owner, &[&ai]));
     }
 
     #[test]
     fn it_test_verify_len_is_ok() {
-        let owner: &Pubkey = &Pubkey::new_unique();
-        let zero64: &mut u64 = &mut 0u64;
-        let zerou8: &mut [u8; 1] = &mut ([0u8]);
-
-        let ai: AccountInfo = AccountInfo {
-            key: &Pubkey::new_unique(),
-            is_signer: false,
-            is_writable: true,
-            lamports: Rc::new(RefCell::new(zero64)),
-            owner: owner,
-            executable: false,
-            rent_epoch: 0,
-            data: Rc::new(RefCell::new(zerou8)),
-        };
+        let mut account = TestAccount::new().writable().zeroed(1);
+
+        let ai = account.info();
         assert_eq!(Ok(()), verify_len(1, &[&ai]));
         assert_ne!(Ok(()), verify_len(2, &[&ai]));
     }
 
     #[test]
     fn it_test_verify_pda_is_ok() {
-        let zero64: &mut u64 = &mut 0u64;
-        let zerou8: &mut [u8; 1] = &mut ([0u8]);
-
         let program_id = &Pubkey::new_unique();
         let wrong_program_id = &Pubkey::new_unique();
 
         let seeds = &[b"pda" as &[u8]];
         let wrong_seeds = &[b"pba" as &[u8]];
 
-        let (received_address, _) = Pubkey::find_program_address(seeds, program_id);
-
-        let ai: AccountInfo = AccountInfo {
-            key: &received_address,
-            is_signer: false,
-            is_writable: true,
-            lamports: Rc::new(RefCell::new(zero64)),
-            owner: program_id,
-            executable: false,
-            rent_epoch: 0,
-            data: Rc::new(RefCell::new(zerou8)),
-        };
+        let mut account = TestAccount::new().writable().pda(seeds, program_id);
+
+        let ai = account.info();
         assert_eq!(Ok(()), verify_pda(&ai, seeds, program_id));
         assert_ne!(Ok(()), verify_pda(&ai, wrong_seeds, program_id));
         assert_ne!(Ok(()), verify_pda(&ai, seeds, wrong_program_id));
@@ -144,50 +180,13 @@ mod tests {
 
     #[test]
     fn it_test_verify_signers_is_ok() {
-        let owner: &Pubkey = &Pubkey::new_unique();
-        let zero64: &mut u64 = &mut 0u64;
-        let zerou8: &mut [u8; 1] = &mut ([0u8]);
-
-        let ai_signer: AccountInfo = AccountInfo {
-            key: &Pubkey::new_unique(),
-            is_signer: true,
-            is_writable: true,
-            lamports: Rc::new(RefCell::new(zero64)),
-            owner: owner,
-            executable: false,
-            rent_epoch: 0,
-            data: Rc::new(RefCell::new(zerou8)),
-        };
+        let mut signer = TestAccount::new().signer().writable();
+        let mut not_signer = TestAccount::new().writable();
+        let mut signer_not_writable = TestAccount::new().signer();
 
-        let owner: &Pubkey = &Pubkey::new_unique();
-        let zero64: &mut u64 = &mut 0u64;
-        let zerou8: &mut [u8; 1] = &mut ([0u8]);
-
-        let ai_not_signer: AccountInfo = AccountInfo {
-            key: &Pubkey::new_unique(),
-            is_signer: false,
-            is_writable: true,
-            lamports: Rc::new(RefCell::new(zero64)),
-            owner: owner,
-            executable: false,
-            rent_epoch: 0,
-            data: Rc::new(RefCell::new(zerou8)),
-        };
-
-        let owner: &Pubkey = &Pubkey::new_unique();
-        let zero64: &mut u64 = &mut 0u64;
-        let zerou8: &mut [u8; 1] = &mut ([0u8]);
-
-        let ai_signer_not_writable: AccountInfo = AccountInfo {
-            key: &Pubkey::new_unique(),
-            is_signer: true,
-            is_writable: false,
-            lamports: Rc::new(RefCell::new(zero64)),
-            owner: owner,
-            executable: false,
-            rent_epoch: 0,
-            data: Rc::new(RefCell::new(zerou8)),
-        };
+        let ai_signer = signer.info();
+        let ai_not_signer = not_signer.info();
+        let ai_signer_not_writable = signer_not_writable.info();
 
         assert_eq!(
             Ok(()),
@@ -200,50 +199,13 @@ mod tests {
 
     #[test]
     fn it_test_verify_is_executable_is_ok() {
-        let owner: &Pubkey = &Pubkey::new_unique();
-        let zero64: &mut u64 = &mut 0u64;
-        let zerou8: &mut [u8; 1] = &mut ([0u8]);
-
-        let ai_executable: AccountInfo = AccountInfo {
-            key: &Pubkey::new_unique(),
-            is_signer: true,
-            is_writable: true,
-            lamports: Rc::new(RefCell::new(zero64)),
-            owner: owner,
-            executable: true,
-            rent_epoch: 0,
-            data: Rc::new(RefCell::new(zerou8)),
-        };
+        let mut executable = TestAccount::new().signer().writable().executable();
+        let mut not_executable = TestAccount::new().signer().writable();
+        let mut other_executable_signer = TestAccount::new().signer().executable();
 
-        let owner: &Pubkey = &Pubkey::new_unique();
-        let zero64: &mut u64 = &mut 0u64;
-        let zerou8: &mut [u8; 1] = &mut ([0u8]);
-
-        let ai_not_executable: AccountInfo = AccountInfo {
-            key: &Pubkey::new_unique(),
-            is_signer: true,
-            is_writable: true,
-            lamports: Rc::new(RefCell::new(zero64)),
-            owner: owner,
-            executable: false,
-            rent_epoch: 0,
-            data: Rc::new(RefCell::new(zerou8)),
-        };
-
-        let owner: &Pubkey = &Pubkey::new_unique();
-        let zero64: &mut u64 = &mut 0u64;
-        let zerou8: &mut [u8; 1] = &mut ([0u8]);
-
-        let ai_other_executable_signer: AccountInfo = AccountInfo {
-            key: &Pubkey::new_unique(),
-            is_signer: true,
-            is_writable: false,
-            lamports: Rc::new(RefCell::new(zero64)),
-            owner: owner,
-            executable: true,
-            rent_epoch: 0,
-            data: Rc::new(RefCell::new(zerou8)),
-        };
+        let ai_executable = executable.info();
+        let ai_not_executable = not_executable.info();
+        let ai_other_executable_signer = other_executable_signer.info();
 
         assert_eq!(Ok(()), verify_is_executable(&[&ai_executable]));
         assert_eq!(
@@ -260,23 +222,245 @@ mod tests {
     #[test]
     fn it_test_verify_signer_address_is_ok() {
         let signer: &Pubkey = &Pubkey::new_unique();
-        let zero64: &mut u64 = &mut 0u64;
-        let zerou8: &mut [u8; 1] = &mut ([0u8]);
-
-        let ai: AccountInfo = AccountInfo {
-            key: signer,
-            is_signer: true,
-            is_writable: true,
-            lamports: Rc::new(RefCell::new(zero64)),
-            owner: &Pubkey::new_unique(),
-            executable: false,
-            rent_epoch: 0,
-            data: Rc::new(RefCell::new(zerou8)),
-        };
+        let mut account = TestAccount::new().key(*signer).signer().writable();
 
+        let ai = account.info();
         let not_expected_address: &Pubkey = &Pubkey::new_unique();
 
         assert_eq!(Ok(()), verify_signer_address(&ai.key, signer));
         assert_ne!(Ok(()), verify_signer_address(&ai.key, not_expected_address));
     }
+
+    #[test]
+    fn it_test_verify_delegate_role_is_ok() {
+        let owner: &Pubkey = &Pubkey::new_unique();
+        let contributor: &Pubkey = &Pubkey::new_unique();
+        let mut owner_account = TestAccount::new().key(*owner).signer().writable();
+        let mut contributor_account = TestAccount::new().key(*contributor).signer().writable();
+
+        let ai_owner = owner_account.info();
+        let ai_contributor = contributor_account.info();
+
+        let delegates = &[Delegate {
+            key: *contributor,
+            role: DelegateRole::Contributor,
+        }];
+
+        assert_eq!(
+            Ok(()),
+            verify_delegate_role(&ai_owner, owner, &[], DelegateRole::Admin)
+        );
+        assert_eq!(
+            Ok(()),
+            verify_delegate_role(&ai_contributor, owner, delegates, DelegateRole::Viewer)
+        );
+        assert_eq!(
+            Ok(()),
+            verify_delegate_role(&ai_contributor, owner, delegates, DelegateRole::Contributor)
+        );
+    }
+
+    #[test]
+    fn it_test_verify_delegate_role_rejects_insufficient_role() {
+        let owner: &Pubkey = &Pubkey::new_unique();
+        let viewer: &Pubkey = &Pubkey::new_unique();
+        let mut viewer_account = TestAccount::new().key(*viewer).signer().writable();
+
+        let ai_viewer = viewer_account.info();
+
+        let viewer_delegates = &[Delegate {
+            key: *viewer,
+            role: DelegateRole::Viewer,
+        }];
+        let contributor_delegates = &[Delegate {
+            key: *viewer,
+            role: DelegateRole::Contributor,
+        }];
+
+        assert_eq!(
+            Err(SecurityError::InsufficientDelegateRole.into()),
+            verify_delegate_role(
+                &ai_viewer,
+                owner,
+                viewer_delegates,
+                DelegateRole::Contributor
+            )
+        );
+        assert_eq!(
+            Err(SecurityError::InsufficientDelegateRole.into()),
+            verify_delegate_role(&ai_viewer, owner, viewer_delegates, DelegateRole::Admin)
+        );
+        assert_eq!(
+            Err(SecurityError::InsufficientDelegateRole.into()),
+            verify_delegate_role(
+                &ai_viewer,
+                owner,
+                contributor_delegates,
+                DelegateRole::Admin
+            )
+        );
+    }
+
+    #[test]
+    fn it_test_verify_delegate_role_rejects_unknown_signer() {
+        let owner: &Pubkey = &Pubkey::new_unique();
+        let mut stranger = TestAccount::new().signer().writable();
+
+        let ai_stranger = stranger.info();
+
+        let delegates = &[Delegate {
+            key: Pubkey::new_unique(),
+            role: DelegateRole::Admin,
+        }];
+
+        assert_eq!(
+            Err(SecurityError::UnrecognizedSignerAddress.into()),
+            verify_delegate_role(&ai_stranger, owner, &[], DelegateRole::Viewer)
+        );
+        assert_eq!(
+            Err(SecurityError::UnrecognizedSignerAddress.into()),
+            verify_delegate_role(&ai_stranger, owner, delegates, DelegateRole::Viewer)
+        );
+    }
+
+    #[test]
+    fn it_test_verify_delegate_role_requires_signature() {
+        let owner: &Pubkey = &Pubkey::new_unique();
+        let admin: &Pubkey = &Pubkey::new_unique();
+        let mut owner_not_signer = TestAccount::new().key(*owner).writable();
+        let mut admin_not_signer = TestAccount::new().key(*admin).writable();
+
+        let ai_owner_not_signer = owner_not_signer.info();
+        let ai_admin_not_signer = admin_not_signer.info();
+
+        let delegates = &[Delegate {
+            key: *admin,
+            role: DelegateRole::Admin,
+        }];
+
+        assert_eq!(
+            Err(SecurityError::SignerNotRecognized.into()),
+            verify_delegate_role(&ai_owner_not_signer, owner, delegates, DelegateRole::Viewer)
+        );
+        assert_eq!(
+            Err(SecurityError::SignerNotRecognized.into()),
+            verify_delegate_role(&ai_admin_not_signer, owner, delegates, DelegateRole::Viewer)
+        );
+    }
+
+    #[test]
+    fn it_test_verify_token_program_is_ok() {
+        let mut token_program = TestAccount::new().key(spl_token_program::id()).executable();
+        let mut fake_token_program = TestAccount::new().executable();
+
+        let ai_token_program = token_program.info();
+        let ai_fake_token_program = fake_token_program.info();
+
+        assert_eq!(Ok(()), verify_token_program(&ai_token_program));
+        assert_eq!(
+            Err(SecurityError::InvalidTokenProgram.into()),
+            verify_token_program(&ai_fake_token_program)
+        );
+    }
+
+    #[test]
+    fn it_test_verify_mint_is_ok() {
+        let mint: &Pubkey = &Pubkey::new_unique();
+        let mut mint_data = [0u8; MINT_LEN];
+        mint_data[MINT_IS_INITIALIZED_OFFSET] = 1;
+
+        let mut mint_account = TestAccount::new()
+            .key(*mint)
+            .owner(spl_token_program::id())
+            .data(mint_data);
+        let mut mint_wrong_owner = TestAccount::new().key(*mint).data(mint_data);
+        let mut mint_not_initialized = TestAccount::new()
+            .key(*mint)
+            .owner(spl_token_program::id())
+            .zeroed(MINT_LEN);
+
+        let ai_mint = mint_account.info();
+        let ai_mint_wrong_owner = mint_wrong_owner.info();
+        let ai_mint_not_initialized = mint_not_initialized.info();
+
+        assert_eq!(Ok(()), verify_mint(&ai_mint, mint));
+        assert_eq!(
+            Err(SecurityError::TokenMintMismatch.into()),
+            verify_mint(&ai_mint, &Pubkey::new_unique())
+        );
+        assert_eq!(
+            Err(SecurityError::WrongAccountOwner.into()),
+            verify_mint(&ai_mint_wrong_owner, mint)
+        );
+        assert_eq!(
+            Err(SecurityError::TokenAccountNotInitialized.into()),
+            verify_mint(&ai_mint_not_initialized, mint)
+        );
+    }
+
+    #[test]
+    fn it_test_verify_token_account_is_ok() {
+        let mint: &Pubkey = &Pubkey::new_unique();
+        let owner: &Pubkey = &Pubkey::new_unique();
+        let mut token_data = [0u8; TOKEN_ACCOUNT_LEN];
+        let dst = pack_pubkey(&mut token_data, *mint);
+        let dst = pack_pubkey(dst, *owner);
+        pack_u64(dst, 100);
+        token_data[TOKEN_ACCOUNT_STATE_OFFSET] = 1;
+
+        let mut token_account = TestAccount::new()
+            .writable()
+            .owner(spl_token_program::id())
+            .data(token_data);
+
+        let ai_token_account = token_account.info();
+
+        assert_eq!(Ok(()), verify_token_account(&ai_token_account, mint, owner));
+        assert_eq!(
+            Err(SecurityError::TokenMintMismatch.into()),
+            verify_token_account(&ai_token_account, &Pubkey::new_unique(), owner)
+        );
+        assert_eq!(
+            Err(SecurityError::TokenOwnerMismatch.into()),
+            verify_token_account(&ai_token_account, mint, &Pubkey::new_unique())
+        );
+    }
+
+    #[test]
+    fn it_test_verify_token_account_rejects_invalid_accounts() {
+        let mint: &Pubkey = &Pubkey::new_unique();
+        let owner: &Pubkey = &Pubkey::new_unique();
+        let mut token_data = [0u8; TOKEN_ACCOUNT_LEN];
+        let dst = pack_pubkey(&mut token_data, *mint);
+        pack_pubkey(dst, *owner);
+        let not_initialized_data = token_data;
+        token_data[TOKEN_ACCOUNT_STATE_OFFSET] = 1;
+
+        let mut wrong_owner = TestAccount::new().writable().data(token_data);
+        let mut not_initialized = TestAccount::new()
+            .writable()
+            .owner(spl_token_program::id())
+            .data(not_initialized_data);
+        let mut wrong_len = TestAccount::new()
+            .writable()
+            .owner(spl_token_program::id())
+            .zeroed(1);
+
+        let ai_wrong_owner = wrong_owner.info();
+        let ai_not_initialized = not_initialized.info();
+        let ai_wrong_len = wrong_len.info();
+
+        assert_eq!(
+            Err(SecurityError::WrongAccountOwner.into()),
+            verify_token_account(&ai_wrong_owner, mint, owner)
+        );
+        assert_eq!(
+            Err(SecurityError::TokenAccountNotInitialized.into()),
+            verify_token_account(&ai_not_initialized, mint, owner)
+        );
+        assert_eq!(
+            Err(SecurityError::InvalidAccountLen.into()),
+            verify_token_account(&ai_wrong_len, mint, owner)
+        );
+    }
 }
@@ -0,0 +1,178 @@
+use crate::errors::CodecError;
+use solana_program::program_error::ProgramError;
+
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct StringPolicy {
+    // Remove leading and trailing whitespace before checking the capacity
+    pub trim_whitespace: bool,
+    // Reject strings containing control characters such as '\0', '\n' or '\t'
+    pub reject_control_chars: bool,
+}
+
+impl StringPolicy {
+    pub fn strict() -> StringPolicy {
+        StringPolicy {
+            trim_whitespace: true,
+            reject_control_chars: true,
+        }
+    }
+}
+
+// Normalizes the string following the policy and checks it fits in `cap` bytes (the CIDL `cap:N`
+// attribute, without the 4 bytes length prefix). Capacity is measured in UTF-8 bytes, not chars.
+pub fn validate_string(
+    data: String,
+    cap: usize,
+    policy: StringPolicy,
+) -> Result<String, ProgramError> {
+    let data = if policy.trim_whitespace {
+        data.trim().to_string()
+    } else {
+        data
+    };
+
+    if policy.reject_control_chars && data.chars().any(char::is_control) {
+        return Err(CodecError::ControlCharacter.into());
+    }
+
+    if data.len() > cap {
+        return Err(CodecError::StringTooLong.into());
+    }
+
+    Ok(data)
+}
+
+pub fn validate_string_bytes(
+    data: &[u8],
+    cap: usize,
+    policy: StringPolicy,
+) -> Result<String, ProgramError> {
+    let data = String::from_utf8(data.to_vec()).map_err(|_| CodecError::InvalidUtf8)?;
+    validate_string(data, cap, policy)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NAME_CAP: usize = 50;
+
+    #[test]
+    fn it_accepts_string_at_capacity() {
+        let name = "a".repeat(NAME_CAP);
+
+        assert_eq!(
+            Ok(name.clone()),
+            validate_string(name, NAME_CAP, StringPolicy::default())
+        );
+    }
+
+    #[test]
+    fn it_rejects_string_over_capacity() {
+        assert_eq!(
+            Err(CodecError::StringTooLong.into()),
+            validate_string("a".repeat(NAME_CAP + 1), NAME_CAP, StringPolicy::default())
+        );
+    }
+
+    #[test]
+    fn it_accepts_multibyte_string_at_capacity() {
+        // 25 two bytes chars fill the 50 bytes exactly
+        let name = "é".repeat(25);
+        assert_eq!(name.len(), NAME_CAP);
+
+        assert_eq!(
+            Ok(name.clone()),
+            validate_string(name, NAME_CAP, StringPolicy::default())
+        );
+
+        // 12 four bytes chars plus two ascii chars
+        let name = "🦀".repeat(12) + "ab";
+        assert_eq!(name.len(), NAME_CAP);
+
+        assert_eq!(
+            Ok(name.clone()),
+            validate_string(name, NAME_CAP, StringPolicy::default())
+        );
+    }
+
+    #[test]
+    fn it_rejects_multibyte_string_crossing_capacity() {
+        // 50 chars but 51 bytes, the last char does not fit
+        let name = "a".repeat(49) + "é";
+        assert_eq!(name.chars().count(), NAME_CAP);
+
+        assert_eq!(
+            Err(CodecError::StringTooLong.into()),
+            validate_string(name, NAME_CAP, StringPolicy::default())
+        );
+
+        let name = "a".repeat(47) + "🦀";
+        assert_eq!(name.chars().count(), 48);
+        assert_eq!(name.len(), NAME_CAP + 1);
+
+        assert_eq!(
+            Err(CodecError::StringTooLong.into()),
+            validate_string(name, NAME_CAP, StringPolicy::default())
+        );
+    }
+
+    #[test]
+    fn it_trims_whitespace_before_checking_capacity() {
+        let name = format!("  {}\n", "é".repeat(25));
+
+        assert_eq!(
+            Err(CodecError::StringTooLong.into()),
+            validate_string(name.clone(), NAME_CAP, StringPolicy::default())
+        );
+        assert_eq!(
+            Ok("é".repeat(25)),
+            validate_string(name, NAME_CAP, StringPolicy::strict())
+        );
+    }
+
+    #[test]
+    fn it_rejects_control_characters() {
+        let policy = StringPolicy {
+            trim_whitespace: false,
+            reject_control_chars: true,
+        };
+
+        assert_eq!(
+            Err(CodecError::ControlCharacter.into()),
+            validate_string("John\0Doe".to_string(), NAME_CAP, policy)
+        );
+        assert_eq!(
+            Err(CodecError::ControlCharacter.into()),
+            validate_string("John Doe\n".to_string(), NAME_CAP, policy)
+        );
+        assert_eq!(
+            Ok("John\0Doe".to_string()),
+            validate_string("John\0Doe".to_string(), NAME_CAP, StringPolicy::default())
+        );
+    }
+
+    #[test]
+    fn it_strict_policy_keeps_inner_whitespace() {
+        assert_eq!(
+            Ok("John Doe".to_string()),
+            validate_string(" John Doe ".to_string(), NAME_CAP, StringPolicy::strict())
+        );
+        assert_eq!(
+            Err(CodecError::ControlCharacter.into()),
+            validate_string(" John\tDoe ".to_string(), NAME_CAP, StringPolicy::strict())
+        );
+    }
+
+    #[test]
+    fn it_validates_utf8_bytes() {
+        assert_eq!(
+            Ok("José".to_string()),
+            validate_string_bytes("José".as_bytes(), NAME_CAP, StringPolicy::default())
+        );
+        assert_eq!(
+            Err(CodecError::InvalidUtf8.into()),
+            validate_string_bytes(&[0x4a, 0xc3], NAME_CAP, StringPolicy::default())
+        );
+    }
+}
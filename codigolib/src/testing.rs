@@ -0,0 +1,25 @@
+use std::any;
+
+mod attack;
+mod fixtures;
+pub mod gen;
+mod runtime;
+mod snapshot;
+#[cfg(test)]
+mod test_program;
+mod token_program;
+
+pub use attack::*;
+pub use fixtures::*;
+pub use runtime::*;
+pub use snapshot::*;
+pub use token_program::TokenError;
+
+// The message of a caught panic, as the panic hook prints it
+fn panic_message(panic: &(dyn any::Any + Send)) -> String {
+    panic
+        .downcast_ref::<String>()
+        .cloned()
+        .or_else(|| panic.downcast_ref::<&str>().map(|s| s.to_string()))
+        .unwrap_or_default()
+}
@@ -0,0 +1,545 @@
+use super::runtime::system_error;
+use super::{panic_message, TestAccount, TestRuntime};
+use crate::errors::{CodecError, SecurityError};
+use crate::token_utils::spl_token_program;
+use solana_program::{
+    instruction::Instruction, program_error::ProgramError, pubkey::Pubkey,
+    system_instruction::SystemError,
+};
+use std::fmt::Write;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+// Substitutions an attacker can make in the accounts of an instruction, each one should be
+// stopped by a check of sec_utils or of the generated processor
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Attack {
+    // An account of the program now owned by another program
+    WrongOwner,
+    // A signer that did not sign
+    NonSigner,
+    // Another account of the program in place of the PDA
+    WrongPda,
+    // An account of the program with one more byte of data
+    WrongLength,
+    // Another account of the instruction passed a second time in place of this one
+    Duplicate,
+    // A regular account in place of a program
+    NonExecutable,
+    // The account of another user of the program, as their record, in place of this one
+    ForeignAccount,
+    // Another wallet signing in place of a read-only signer, the owner the seeds name
+    ForeignSigner,
+}
+
+// What the attacked account is for the instruction, a duplicate put in its place fails the
+// checks of that kind of account
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Position {
+    Signer,
+    Program,
+    // An account of the program, or one the instruction creates for it
+    State,
+    // A mint or a token account of the token program
+    Token,
+    Other,
+}
+
+impl Attack {
+    pub const ALL: [Attack; 8] = [
+        Attack::WrongOwner,
+        Attack::NonSigner,
+        Attack::WrongPda,
+        Attack::WrongLength,
+        Attack::Duplicate,
+        Attack::NonExecutable,
+        Attack::ForeignAccount,
+        Attack::ForeignSigner,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Attack::WrongOwner => "wrong_owner",
+            Attack::NonSigner => "non_signer",
+            Attack::WrongPda => "wrong_pda",
+            Attack::WrongLength => "wrong_length",
+            Attack::Duplicate => "duplicate",
+            Attack::NonExecutable => "non_executable",
+            Attack::ForeignAccount => "foreign_account",
+            Attack::ForeignSigner => "foreign_signer",
+        }
+    }
+
+    // Errors of the checks that stop the attack on an account at `position`. Programs are checked
+    // by address before being invoked, the system program refuses to create an account already
+    // in use, processors refuse to `init` an account with data, migrations refuse a layout they
+    // don't know and stubs check the mint and owner of token accounts
+    pub fn expected(&self, position: Position) -> Vec<ProgramError> {
+        let in_use = system_error(SystemError::AccountAlreadyInUse);
+        match self {
+            Attack::WrongOwner => vec![
+                SecurityError::WrongAccountOwner.into(),
+                in_use,
+                ProgramError::AccountAlreadyInitialized,
+            ],
+            Attack::NonSigner => vec![SecurityError::SignerNotRecognized.into()],
+            Attack::WrongPda | Attack::ForeignAccount => {
+                vec![SecurityError::NotExpectedAddress.into()]
+            }
+            Attack::WrongLength => vec![
+                SecurityError::InvalidAccountLen.into(),
+                in_use,
+                ProgramError::AccountAlreadyInitialized,
+                CodecError::UnsupportedStateVersion.into(),
+            ],
+            Attack::Duplicate => match position {
+                Position::Signer => Attack::NonSigner.expected(position),
+                Position::Program => Attack::NonExecutable.expected(position),
+                Position::State => [Attack::WrongOwner, Attack::WrongPda, Attack::WrongLength]
+                    .iter()
+                    .flat_map(|attack| attack.expected(position))
+                    .collect(),
+                Position::Token => [Attack::WrongOwner, Attack::WrongLength, Attack::WrongPda]
+                    .iter()
+                    .flat_map(|attack| attack.expected(position))
+                    .chain([
+                        SecurityError::TokenAccountNotInitialized.into(),
+                        SecurityError::TokenMintMismatch.into(),
+                        SecurityError::TokenOwnerMismatch.into(),
+                    ])
+                    .collect(),
+                Position::Other => Vec::new(),
+            },
+            Attack::NonExecutable => vec![
+                ProgramError::IncorrectProgramId,
+                SecurityError::ExecutableAccountExpected.into(),
+                SecurityError::InvalidTokenProgram.into(),
+            ],
+            // The address of the account derived from the new signer changes too when it is a
+            // seed the program reads from the signer itself
+            Attack::ForeignSigner => vec![
+                SecurityError::UnrecognizedSignerAddress.into(),
+                SecurityError::NotExpectedAddress.into(),
+            ],
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Outcome {
+    // Stopped by one of the expected checks
+    Rejected(ProgramError),
+    // Stopped, but not by a check expected for the attack
+    WrongError(ProgramError),
+    // The instruction went through, a check is missing
+    Accepted,
+    Panicked(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Mutation {
+    // Position of the attacked account in the instruction
+    pub account: usize,
+    pub attack: Attack,
+    // Position of the account passed again, for duplicates
+    pub substitute: Option<usize>,
+    pub outcome: Outcome,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AttackReport {
+    pub instruction: String,
+    pub mutations: Vec<Mutation>,
+}
+
+impl AttackReport {
+    pub fn failures(&self) -> Vec<&Mutation> {
+        self.mutations
+            .iter()
+            .filter(|mutation| !matches!(mutation.outcome, Outcome::Rejected(_)))
+            .collect()
+    }
+
+    // Rejected and attempted mutations of `attack`, None when no account is exposed to it
+    pub fn coverage(&self, attack: Attack) -> Option<(usize, usize)> {
+        let attempted: Vec<_> = self
+            .mutations
+            .iter()
+            .filter(|mutation| mutation.attack == attack)
+            .collect();
+        let rejected = attempted
+            .iter()
+            .filter(|mutation| matches!(mutation.outcome, Outcome::Rejected(_)))
+            .count();
+
+        (!attempted.is_empty()).then_some((rejected, attempted.len()))
+    }
+}
+
+// Processes `instruction` once per account and attack it is exposed to, each time with that
+// single substitution made from the state of `runtime`. The instruction must succeed as given
+pub fn attack(
+    runtime: &TestRuntime,
+    name: &str,
+    instruction: &Instruction,
+    signers: &[Pubkey],
+) -> AttackReport {
+    if let Err(e) = runtime.clone().process(instruction, signers) {
+        panic!("{} fails before being attacked: {:?}", name, e);
+    }
+
+    let mut mutations = Vec::new();
+    for (index, meta) in instruction.accounts.iter().enumerate() {
+        let account = runtime.load(&meta.pubkey);
+        let owned = account.owner == runtime.program_id && account.lamports > 0;
+        let token = account.owner == spl_token_program::id() && !account.executable;
+        // Not there yet, the instruction creates it
+        let created = meta.is_writable
+            && !meta.is_signer
+            && !owned
+            && !account.executable
+            && account.data.is_empty();
+        let position = if meta.is_signer {
+            Position::Signer
+        } else if account.executable {
+            Position::Program
+        } else if owned || created {
+            Position::State
+        } else if token {
+            Position::Token
+        } else {
+            Position::Other
+        };
+        let foreign = (owned || created)
+            .then(|| foreign_account(runtime, instruction, &account, created))
+            .flatten();
+
+        for attack in Attack::ALL {
+            let substitutes: Vec<Option<usize>> = match attack {
+                // A signer passed in place of another signer is a choice of the signers, as a
+                // wallet paying for the records it owns
+                Attack::Duplicate => instruction
+                    .accounts
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, other)| other.pubkey != meta.pubkey)
+                    .filter(|(_, other)| !(meta.is_signer && other.is_signer))
+                    .map(|(other, _)| Some(other))
+                    .collect(),
+                Attack::WrongOwner | Attack::WrongPda | Attack::WrongLength if owned || created => {
+                    vec![None]
+                }
+                // A token account may be any account of its mint and owner, its address is only
+                // checked when it is a PDA the attack can't tell apart, so only its owner and
+                // length are attacked
+                Attack::WrongOwner | Attack::WrongLength if token => vec![None],
+                Attack::NonSigner if meta.is_signer => vec![None],
+                Attack::NonExecutable if account.executable => vec![None],
+                Attack::ForeignAccount if foreign.is_some() => vec![None],
+                // A writable signer pays, anyone can
+                Attack::ForeignSigner if meta.is_signer && !meta.is_writable => vec![None],
+                _ => Vec::new(),
+            };
+
+            for substitute in substitutes {
+                let mut runtime = runtime.clone();
+                let mut mutated = instruction.clone();
+                let mut signers = signers.to_vec();
+                let target = &mut mutated.accounts[index];
+
+                match attack {
+                    // An account of the instruction is squatted before it creates it
+                    Attack::WrongOwner if created => runtime.add_account(
+                        account
+                            .clone()
+                            .owner(Pubkey::new_unique())
+                            .zeroed(1)
+                            .rent_exempt(),
+                    ),
+                    Attack::WrongOwner => {
+                        runtime.add_account(account.clone().owner(Pubkey::new_unique()))
+                    }
+                    Attack::NonSigner => {
+                        target.is_signer = false;
+                        signers.retain(|signer| *signer != meta.pubkey);
+                    }
+                    Attack::WrongPda => {
+                        let impostor = account.clone().key(Pubkey::new_unique());
+                        target.pubkey = impostor.key;
+                        runtime.add_account(impostor);
+                    }
+                    Attack::WrongLength if created => runtime.add_account(
+                        account
+                            .clone()
+                            .owner(runtime.program_id)
+                            .zeroed(1)
+                            .rent_exempt(),
+                    ),
+                    Attack::WrongLength => {
+                        let mut account = account.clone();
+                        account.data.push(0);
+                        runtime.add_account(account);
+                    }
+                    Attack::Duplicate => {
+                        if let Some(other) = substitute {
+                            target.pubkey = instruction.accounts[other].pubkey;
+                        }
+                    }
+                    Attack::NonExecutable => {
+                        let impostor = TestAccount::new().lamports(1_000_000_000);
+                        target.pubkey = impostor.key;
+                        runtime.add_account(impostor);
+                    }
+                    Attack::ForeignAccount => {
+                        target.pubkey = foreign.unwrap();
+                    }
+                    Attack::ForeignSigner => {
+                        let attacker = runtime.add_payer(1_000_000_000);
+                        target.pubkey = attacker;
+                        signers.retain(|signer| *signer != meta.pubkey);
+                        signers.push(attacker);
+                    }
+                }
+                // The attacker can't sign for the accounts put in place of a signer
+                if target.is_signer && !signers.contains(&target.pubkey) {
+                    target.is_signer = false;
+                }
+
+                let result = catch_unwind(AssertUnwindSafe(|| runtime.process(&mutated, &signers)));
+                let outcome = match result {
+                    Ok(Ok(())) => Outcome::Accepted,
+                    Ok(Err(e)) if attack.expected(position).contains(&e) => Outcome::Rejected(e),
+                    Ok(Err(e)) => Outcome::WrongError(e),
+                    Err(panic) => Outcome::Panicked(panic_message(panic.as_ref())),
+                };
+
+                mutations.push(Mutation {
+                    account: index,
+                    attack,
+                    substitute,
+                    outcome,
+                });
+            }
+        }
+    }
+
+    AttackReport {
+        instruction: name.to_string(),
+        mutations,
+    }
+}
+
+// Another account of the program outside of the instruction, of the same length unless the
+// instruction creates `account`. The smallest address is taken so reports are reproducible
+fn foreign_account(
+    runtime: &TestRuntime,
+    instruction: &Instruction,
+    account: &TestAccount,
+    created: bool,
+) -> Option<Pubkey> {
+    runtime
+        .accounts
+        .values()
+        .filter(|other| other.owner == runtime.program_id && other.lamports > 0)
+        .filter(|other| created || other.data.len() == account.data.len())
+        .filter(|other| {
+            instruction
+                .accounts
+                .iter()
+                .all(|meta| meta.pubkey != other.key)
+        })
+        .map(|other| other.key)
+        .min()
+}
+
+// One row per instruction and one column per attack, a cell holds the rejected and attempted
+// mutations, `-` when no account of the instruction is exposed to the attack
+pub fn coverage_matrix(reports: &[AttackReport]) -> String {
+    let width = reports
+        .iter()
+        .map(|report| report.instruction.len())
+        .max()
+        .unwrap_or(0)
+        .max("instruction".len());
+
+    let mut out = format!("{:<width$}", "instruction");
+    for attack in Attack::ALL {
+        write!(out, "  {}", attack.name()).unwrap();
+    }
+    out.push('\n');
+
+    for report in reports {
+        write!(out, "{:<width$}", report.instruction).unwrap();
+        for attack in Attack::ALL {
+            let cell = match report.coverage(attack) {
+                Some((rejected, attempted)) => format!("{}/{}", rejected, attempted),
+                None => "-".to_string(),
+            };
+            write!(out, "  {:<1$}", cell, attack.name().len()).unwrap();
+        }
+        out.truncate(out.trim_end().len());
+        out.push('\n');
+    }
+
+    out
+}
+
+// Fails with every mutation that was not rejected by an expected check, and the matrix
+pub fn assert_rejected(reports: &[AttackReport]) {
+    let mut failures = String::new();
+    for report in reports {
+        for mutation in report.failures() {
+            let substitute = mutation
+                .substitute
+                .map_or(String::new(), |other| format!(" with account {}", other));
+            writeln!(
+                failures,
+                "{}: {} of account {}{}: {:?}",
+                report.instruction,
+                mutation.attack.name(),
+                mutation.account,
+                substitute,
+                mutation.outcome
+            )
+            .unwrap();
+        }
+    }
+
+    assert!(
+        failures.is_empty(),
+        "unexpected outcomes:\n{}\n{}",
+        failures,
+        coverage_matrix(reports)
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::test_program::*;
+    use solana_program::instruction::AccountMeta;
+
+    fn update_record(runtime: &TestRuntime, payer: &Pubkey, tag: u8) -> Instruction {
+        Instruction::new_with_bytes(
+            runtime.program_id,
+            &[tag],
+            vec![
+                AccountMeta::new(record_address(runtime, payer), false),
+                AccountMeta::new_readonly(*payer, true),
+            ],
+        )
+    }
+
+    #[test]
+    fn it_rejects_every_attack_on_checked_instructions() {
+        let mut runtime = runtime();
+        let payer = runtime.add_payer(1_000_000_000);
+        // The record of another user, put in place of the record of `payer`
+        let other = runtime.add_payer(1_000_000_000);
+        runtime
+            .process(&create_record(&runtime, &other), &[other])
+            .unwrap();
+        let create = attack(
+            &runtime,
+            "create",
+            &create_record(&runtime, &payer),
+            &[payer],
+        );
+
+        runtime
+            .process(&create_record(&runtime, &payer), &[payer])
+            .unwrap();
+        let update = attack(
+            &runtime,
+            "update",
+            &update_record(&runtime, &payer, 4),
+            &[payer],
+        );
+
+        let reports = [create, update];
+        assert_rejected(&reports);
+        assert_eq!(
+            coverage_matrix(&reports),
+            "instruction  wrong_owner  non_signer  wrong_pda  wrong_length  duplicate  non_executable  foreign_account  foreign_signer\n\
+             create       1/1          1/1         1/1        1/1           6/6        1/1             1/1              -\n\
+             update       1/1          1/1         1/1        1/1           2/2        -               1/1              1/1\n"
+        );
+    }
+
+    #[test]
+    fn it_expects_duplicates_to_fail_the_check_of_their_position() {
+        assert_eq!(
+            Attack::Duplicate.expected(Position::Signer),
+            vec![SecurityError::SignerNotRecognized.into()]
+        );
+        assert_eq!(
+            Attack::Duplicate.expected(Position::Program),
+            Attack::NonExecutable.expected(Position::Program)
+        );
+        assert!(!Attack::Duplicate
+            .expected(Position::State)
+            .contains(&SecurityError::SignerNotRecognized.into()));
+        let token = Attack::Duplicate.expected(Position::Token);
+        assert!(token.contains(&SecurityError::TokenOwnerMismatch.into()));
+        assert!(token.contains(&SecurityError::NotExpectedAddress.into()));
+        assert!(Attack::Duplicate.expected(Position::Other).is_empty());
+    }
+
+    #[test]
+    fn it_finds_missing_checks() {
+        let mut runtime = runtime();
+        let payer = runtime.add_payer(1_000_000_000);
+        runtime
+            .process(&create_record(&runtime, &payer), &[payer])
+            .unwrap();
+
+        let report = attack(
+            &runtime,
+            "update",
+            &update_record(&runtime, &payer, 5),
+            &[payer],
+        );
+
+        assert_eq!(report.coverage(Attack::WrongOwner), Some((0, 1)));
+        assert_eq!(report.coverage(Attack::WrongPda), Some((1, 1)));
+        assert_eq!(
+            report.failures(),
+            vec![&Mutation {
+                account: 0,
+                attack: Attack::WrongOwner,
+                substitute: None,
+                outcome: Outcome::Accepted,
+            }]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "update: wrong_owner of account 0: Accepted")]
+    fn it_fails_on_attacks_that_are_not_rejected() {
+        let mut runtime = runtime();
+        let payer = runtime.add_payer(1_000_000_000);
+        runtime
+            .process(&create_record(&runtime, &payer), &[payer])
+            .unwrap();
+
+        assert_rejected(&[attack(
+            &runtime,
+            "update",
+            &update_record(&runtime, &payer, 5),
+            &[payer],
+        )]);
+    }
+
+    #[test]
+    #[should_panic(expected = "update fails before being attacked")]
+    fn it_only_attacks_instructions_that_succeed() {
+        let mut runtime = runtime();
+        let payer = runtime.add_payer(1_000_000_000);
+
+        attack(
+            &runtime,
+            "update",
+            &update_record(&runtime, &payer, 4),
+            &[payer],
+        );
+    }
+}
@@ -0,0 +1,264 @@
+use crate::rust_utils::*;
+use crate::token_utils::*;
+use solana_program::{account_info::AccountInfo, pubkey::Pubkey, rent::Rent};
+
+// An account owned by the test, `info` lends it out as the AccountInfo a program receives.
+// Lamports and data written through the AccountInfo stay in the TestAccount afterwards
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TestAccount {
+    pub key: Pubkey,
+    pub owner: Pubkey,
+    pub lamports: u64,
+    pub data: Vec<u8>,
+    pub is_signer: bool,
+    pub is_writable: bool,
+    pub executable: bool,
+    pub rent_epoch: u64,
+    // Bump of the address when it was derived with `pda`
+    pub bump: Option<u8>,
+}
+
+impl Default for TestAccount {
+    fn default() -> Self {
+        TestAccount::new()
+    }
+}
+
+impl TestAccount {
+    // A read-only, non signer account with a unique key, owned by the system program
+    pub fn new() -> Self {
+        TestAccount {
+            key: Pubkey::new_unique(),
+            owner: Pubkey::default(),
+            lamports: 0,
+            data: Vec::new(),
+            is_signer: false,
+            is_writable: false,
+            executable: false,
+            rent_epoch: 0,
+            bump: None,
+        }
+    }
+
+    pub fn key(mut self, key: Pubkey) -> Self {
+        self.key = key;
+        self
+    }
+
+    pub fn signer(mut self) -> Self {
+        self.is_signer = true;
+        self
+    }
+
+    pub fn writable(mut self) -> Self {
+        self.is_writable = true;
+        self
+    }
+
+    pub fn executable(mut self) -> Self {
+        self.executable = true;
+        self
+    }
+
+    pub fn owner(mut self, owner: Pubkey) -> Self {
+        self.owner = owner;
+        self
+    }
+
+    pub fn lamports(mut self, lamports: u64) -> Self {
+        self.lamports = lamports;
+        self
+    }
+
+    pub fn data(mut self, data: impl Into<Vec<u8>>) -> Self {
+        self.data = data.into();
+        self
+    }
+
+    // `len` zeroed bytes, like an account just created by the system program
+    pub fn zeroed(self, len: usize) -> Self {
+        self.data(vec![0; len])
+    }
+
+    // Data holding `value` packed with its account layout
+    pub fn state<T: State>(self, value: &T) -> Self {
+        self.data(pack_state(value))
+    }
+
+    // Enough lamports for the current data to be rent exempt
+    pub fn rent_exempt(mut self) -> Self {
+        self.lamports = Rent::default().minimum_balance(self.data.len());
+        self
+    }
+
+    // Address derived from `seeds`, owned by `program_id` as a program's PDA would be
+    pub fn pda(mut self, seeds: &[&[u8]], program_id: &Pubkey) -> Self {
+        let (key, bump) = Pubkey::find_program_address(seeds, program_id);
+        self.key = key;
+        self.owner = *program_id;
+        self.bump = Some(bump);
+        self
+    }
+
+    // An initialized mint of the token program, rent exempt. Its authorities and supply are not
+    // read by the token instructions of the runtime
+    pub fn mint(self) -> Self {
+        let mut data = vec![0; MINT_LEN];
+        data[MINT_IS_INITIALIZED_OFFSET] = 1;
+        self.owner(spl_token_program::id()).data(data).rent_exempt()
+    }
+
+    // An initialized token account of `mint` holding `amount` tokens that `owner` can move, rent
+    // exempt
+    pub fn token_account(self, mint: &Pubkey, owner: &Pubkey, amount: u64) -> Self {
+        let mut data = vec![0; TOKEN_ACCOUNT_LEN];
+        let dst = pack_pubkey(&mut data, *mint);
+        let dst = pack_pubkey(dst, *owner);
+        pack_u64(dst, amount);
+        data[TOKEN_ACCOUNT_STATE_OFFSET] = 1;
+        self.owner(spl_token_program::id()).data(data).rent_exempt()
+    }
+
+    pub fn info(&mut self) -> AccountInfo<'_> {
+        AccountInfo::new(
+            &self.key,
+            self.is_signer,
+            self.is_writable,
+            &mut self.lamports,
+            &mut self.data,
+            &self.owner,
+            self.executable,
+            self.rent_epoch,
+        )
+    }
+
+    // The account data unpacked with the account layout of `T`
+    pub fn read<T: State>(&self) -> T {
+        unpack_state(&self.data)
+    }
+}
+
+// AccountInfos of several accounts at once, in order, for `process_instruction`
+pub fn infos(accounts: &mut [TestAccount]) -> Vec<AccountInfo<'_>> {
+    accounts.iter_mut().map(TestAccount::info).collect()
+}
+
+pub fn pack_state<T: State>(value: &T) -> Vec<u8> {
+    let mut data = vec![0; T::LEN];
+    value.pack(&mut data);
+    data
+}
+
+pub fn unpack_state<T: State>(data: &[u8]) -> T {
+    let (_, value) = T::unpack(data);
+    value
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::test_program::Record;
+
+    #[test]
+    fn it_builds_account_infos() {
+        let owner = Pubkey::new_unique();
+        let mut account = TestAccount::new()
+            .signer()
+            .writable()
+            .owner(owner)
+            .lamports(10)
+            .data([1, 2, 3]);
+        let key = account.key;
+
+        let info = account.info();
+        assert_eq!(*info.key, key);
+        assert_eq!(*info.owner, owner);
+        assert!(info.is_signer);
+        assert!(info.is_writable);
+        assert!(!info.executable);
+        assert_eq!(info.lamports(), 10);
+        assert_eq!(*info.data.borrow(), [1, 2, 3]);
+
+        let defaults = TestAccount::new();
+        assert_eq!(defaults.owner, Pubkey::default());
+        assert!(!defaults.is_signer && !defaults.is_writable);
+        assert!(defaults.data.is_empty());
+        assert_ne!(defaults.key, key);
+    }
+
+    #[test]
+    fn it_keeps_writes_made_through_the_account_info() {
+        let mut account = TestAccount::new().writable().zeroed(4).lamports(5);
+
+        {
+            let info = account.info();
+            info.data.borrow_mut()[0] = 7;
+            **info.lamports.borrow_mut() += 10;
+        }
+
+        assert_eq!(account.data, vec![7, 0, 0, 0]);
+        assert_eq!(account.lamports, 15);
+    }
+
+    #[test]
+    fn it_derives_pda_accounts() {
+        let program_id = Pubkey::new_unique();
+        let seeds: &[&[u8]] = &[b"record", b"owner"];
+        let account = TestAccount::new().pda(seeds, &program_id);
+
+        let (key, bump) = Pubkey::find_program_address(seeds, &program_id);
+        assert_eq!(account.key, key);
+        assert_eq!(account.owner, program_id);
+        assert_eq!(account.bump, Some(bump));
+    }
+
+    #[test]
+    fn it_packs_state_with_the_codec() {
+        let record = Record {
+            name: "John Doe".to_string(),
+            moves: 2,
+            total_balance: -750,
+        };
+        let account = TestAccount::new().state(&record).rent_exempt();
+
+        assert_eq!(account.data.len(), Record::LEN);
+        assert_eq!(account.data, pack_state(&record));
+        assert_eq!(account.read::<Record>(), record);
+        assert_eq!(unpack_state::<Record>(&account.data), record);
+        assert_eq!(
+            account.lamports,
+            Rent::default().minimum_balance(Record::LEN)
+        );
+    }
+
+    #[test]
+    fn it_builds_token_program_accounts() {
+        let mint = TestAccount::new().mint();
+        let owner = Pubkey::new_unique();
+        let mut account = TestAccount::new().token_account(&mint.key, &owner, 7);
+
+        assert_eq!(mint.owner, spl_token_program::id());
+        assert_eq!(mint.data.len(), MINT_LEN);
+        assert_eq!(
+            crate::sec_utils::verify_token_account(&account.info(), &mint.key, &owner),
+            Ok(())
+        );
+        assert_eq!(unpack_token_account(&account.data).1.amount, 7);
+        assert_eq!(
+            account.lamports,
+            Rent::default().minimum_balance(TOKEN_ACCOUNT_LEN)
+        );
+    }
+
+    #[test]
+    fn it_lends_several_accounts() {
+        let mut accounts = [TestAccount::new().signer(), TestAccount::new()];
+        let keys = [accounts[0].key, accounts[1].key];
+
+        let infos = infos(&mut accounts);
+        assert_eq!(infos.len(), 2);
+        assert_eq!(*infos[0].key, keys[0]);
+        assert!(infos[0].is_signer);
+        assert_eq!(*infos[1].key, keys[1]);
+    }
+}
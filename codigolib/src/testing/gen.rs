@@ -0,0 +1,288 @@
+use super::panic_message;
+use fastrand::Rng;
+use solana_program::pubkey::Pubkey;
+use std::env;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+// Random values for every type of the codec, all drawn from one seed so a failing test can be
+// replayed exactly. Composite types implement Generate field by field, with `string` and `vec`
+// for the fields that have a cap:
+//
+//     impl Generate for Record {
+//         fn generate(gen: &mut Gen) -> Self {
+//             Record { name: gen.string(50), moves: gen.value(), .. }
+//         }
+//     }
+
+// Environment variable replaying a seed printed by `Gen::from_env` or `check`
+pub const SEED_VAR: &str = "TEST_SEED";
+
+pub struct Gen {
+    rng: Rng,
+    seed: u64,
+}
+
+impl Gen {
+    pub fn new(seed: u64) -> Self {
+        Gen {
+            rng: Rng::with_seed(seed),
+            seed,
+        }
+    }
+
+    // The seed of TEST_SEED, or a random one. It is printed so the test output of a failure
+    // shows how to replay it
+    pub fn from_env() -> Self {
+        let seed = env_seed().unwrap_or_else(|| fastrand::u64(..));
+        println!("{}={}", SEED_VAR, seed);
+        Gen::new(seed)
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    // For values the codec types do not cover, e.g. numbers in a range
+    pub fn rng(&self) -> &Rng {
+        &self.rng
+    }
+
+    pub fn value<T: Generate>(&mut self) -> T {
+        T::generate(self)
+    }
+
+    // A character taking at most `max_len` bytes, from 1 to 4 byte UTF-8 alike
+    pub fn char(&mut self, max_len: usize) -> char {
+        match self.rng.usize(1..=max_len.clamp(1, 4)) {
+            1 => self.rng.char('\u{0}'..'\u{80}'),
+            2 => self.rng.char('\u{80}'..'\u{800}'),
+            3 => self.rng.char('\u{800}'..'\u{10000}'),
+            _ => self.rng.char('\u{10000}'..=char::MAX),
+        }
+    }
+
+    // A string of at most `cap` bytes, as the codec counts them
+    pub fn string(&mut self, cap: usize) -> String {
+        let len = self.len(cap);
+        let mut value = String::with_capacity(len);
+        while value.len() < len {
+            let c = self.char(len - value.len());
+            value.push(c);
+        }
+        value
+    }
+
+    pub fn vec<T>(&mut self, cap: usize, mut element: impl FnMut(&mut Gen) -> T) -> Vec<T> {
+        let len = self.len(cap);
+        (0..len).map(|_| element(self)).collect()
+    }
+
+    pub fn option<T>(&mut self, value: impl FnOnce(&mut Gen) -> T) -> Option<T> {
+        match self.rng.bool() {
+            true => Some(value(self)),
+            false => None,
+        }
+    }
+
+    pub fn pubkey(&mut self) -> Pubkey {
+        let mut bytes = [0; 32];
+        self.rng.fill(&mut bytes);
+        Pubkey::new_from_array(bytes)
+    }
+
+    // Empty and full lengths are where the codec breaks, they come up more often than the others
+    fn len(&mut self, cap: usize) -> usize {
+        match self.rng.u8(..8) {
+            0 => 0,
+            1 => cap,
+            _ => self.rng.usize(..=cap),
+        }
+    }
+}
+
+fn env_seed() -> Option<u64> {
+    let seed = env::var(SEED_VAR).ok()?;
+    Some(
+        seed.parse()
+            .unwrap_or_else(|_| panic!("{}={} is not a seed", SEED_VAR, seed)),
+    )
+}
+
+pub trait Generate {
+    fn generate(gen: &mut Gen) -> Self;
+}
+
+macro_rules! generate_primitive {
+    ($($t:ident),*) => {
+        $(
+            impl Generate for $t {
+                fn generate(gen: &mut Gen) -> Self {
+                    gen.rng.$t(..)
+                }
+            }
+        )*
+    };
+}
+
+generate_primitive!(u8, u16, u32, u64, u128, i8, i16, i32, i64, i128);
+
+impl Generate for bool {
+    fn generate(gen: &mut Gen) -> Self {
+        gen.rng.bool()
+    }
+}
+
+impl Generate for Pubkey {
+    fn generate(gen: &mut Gen) -> Self {
+        gen.pubkey()
+    }
+}
+
+impl<T: Generate> Generate for Option<T> {
+    fn generate(gen: &mut Gen) -> Self {
+        gen.option(T::generate)
+    }
+}
+
+// Runs `test` once per case with a new seed, or only with TEST_SEED when it is set. A failing
+// case panics with its seed
+pub fn check(cases: usize, test: impl Fn(&mut Gen)) {
+    let seeds = match env_seed() {
+        Some(seed) => vec![seed],
+        None => (0..cases).map(|_| fastrand::u64(..)).collect(),
+    };
+
+    for seed in seeds {
+        let mut gen = Gen::new(seed);
+        if let Err(panic) = catch_unwind(AssertUnwindSafe(|| test(&mut gen))) {
+            panic!(
+                "{}\nreplay with {}={}",
+                panic_message(panic.as_ref()),
+                SEED_VAR,
+                seed
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rust_utils::*;
+    use crate::testing::{pack_state, unpack_state};
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct Record {
+        name: String,
+        moves: u16,
+        owner: Option<Pubkey>,
+        scores: Vec<i32>,
+    }
+
+    impl Generate for Record {
+        fn generate(gen: &mut Gen) -> Self {
+            Record {
+                name: gen.string(10),
+                moves: gen.value(),
+                owner: gen.value(),
+                scores: gen.vec(3, Gen::value),
+            }
+        }
+    }
+
+    impl State for Record {
+        const LEN: usize = 14 + 2 + 33 + 16;
+
+        fn pack<'a>(&self, buf: &'a mut [u8]) -> &'a mut [u8] {
+            let buf = pack_string(buf, self.name.clone(), 14);
+            let buf = pack_u16(buf, self.moves);
+            let buf = pack_option(buf, pack_pubkey, self.owner, Pubkey::default());
+            pack_vec(buf, 3, 4, pack_i32, self.scores.clone())
+        }
+
+        fn unpack(buf: &[u8]) -> (&[u8], Self) {
+            let (buf, name) = state_unpack_string(buf, 14);
+            let (buf, moves) = unpack_u16(buf);
+            let (buf, owner) = state_unpack_option(buf, unpack_pubkey, 32);
+            let (buf, scores) = state_unpack_vec(buf, 3, 4, unpack_i32);
+            (
+                buf,
+                Record {
+                    name,
+                    moves,
+                    owner,
+                    scores,
+                },
+            )
+        }
+    }
+
+    #[test]
+    fn it_replays_a_seed() {
+        let mut a = Gen::new(42);
+        let mut b = Gen::new(42);
+        for _ in 0..100 {
+            assert_eq!(a.value::<Record>(), b.value::<Record>());
+            assert_eq!(a.value::<i128>(), b.value::<i128>());
+        }
+        assert_eq!(a.seed(), 42);
+        assert_ne!(
+            Gen::new(43).value::<Record>(),
+            Gen::new(42).value::<Record>()
+        );
+    }
+
+    #[test]
+    fn it_generates_strings_up_to_their_cap() {
+        let mut gen = Gen::new(1);
+        let strings: Vec<_> = (0..1000).map(|_| gen.string(10)).collect();
+
+        assert!(strings.iter().all(|s| s.len() <= 10));
+        assert!(strings.iter().any(|s| s.is_empty()));
+        assert!(strings.iter().any(|s| s.len() == 10));
+        for width in 1..=4 {
+            assert!(strings
+                .iter()
+                .any(|s| s.chars().any(|c| c.len_utf8() == width)));
+        }
+        assert!((0..100).all(|_| gen.string(0).is_empty()));
+    }
+
+    #[test]
+    fn it_generates_vectors_and_options() {
+        let mut gen = Gen::new(2);
+        let vectors: Vec<_> = (0..1000).map(|_| gen.vec(5, Gen::value::<u8>)).collect();
+        assert!(vectors.iter().all(|v| v.len() <= 5));
+        assert!(vectors.iter().any(|v| v.is_empty()));
+        assert!(vectors.iter().any(|v| v.len() == 5));
+
+        let options: Vec<Option<u64>> = (0..100).map(|_| gen.value()).collect();
+        assert!(options.iter().any(Option::is_none));
+        assert!(options.iter().any(Option::is_some));
+    }
+
+    #[test]
+    fn it_round_trips_generated_state() {
+        check(200, |gen| {
+            let record: Record = gen.value();
+            assert_eq!(unpack_state::<Record>(&pack_state(&record)), record);
+        });
+    }
+
+    #[test]
+    fn it_reports_the_seed_of_a_failing_case() {
+        let failure = catch_unwind(|| {
+            check(1000, |gen| {
+                let record: Record = gen.value();
+                assert!(record.name.len() < 10, "full name");
+            })
+        })
+        .unwrap_err();
+
+        let message = panic_message(failure.as_ref());
+        let (error, seed) = message.split_once("\nreplay with TEST_SEED=").unwrap();
+        assert!(error.starts_with("full name"));
+        let record: Record = Gen::new(seed.parse().unwrap()).value();
+        assert_eq!(record.name.len(), 10);
+    }
+}
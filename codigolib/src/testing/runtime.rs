@@ -0,0 +1,835 @@
+use super::token_program::process_token_instruction;
+use super::TestAccount;
+use crate::errors::MathError;
+use crate::event_utils::PROGRAM_DATA_PREFIX;
+use crate::rust_utils::State;
+use crate::token_utils::*;
+use solana_program::{
+    account_info::AccountInfo,
+    bpf_loader,
+    clock::Clock,
+    entrypoint::{ProcessInstruction, ProgramResult, MAX_PERMITTED_DATA_INCREASE, SUCCESS},
+    instruction::Instruction,
+    program_error::{ProgramError, UNSUPPORTED_SYSVAR},
+    program_stubs::{set_syscall_stubs, SyscallStubs},
+    program_utils::limited_deserialize,
+    pubkey,
+    pubkey::Pubkey,
+    rent::Rent,
+    system_instruction::{SystemError, SystemInstruction, MAX_PERMITTED_DATA_LENGTH},
+    system_program,
+};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::sync::Once;
+
+// Owner of the native programs, the system program among them
+const NATIVE_LOADER_ID: Pubkey = pubkey!("NativeLoader1111111111111111111111111111111");
+
+// Largest system instruction the stand-in decodes, the size of a transaction packet
+const MAX_SYSTEM_INSTRUCTION_LEN: u64 = 1232;
+
+// Runs a program's `process_instruction` in-process, in place of a validator. Accounts are kept
+// between instructions, signatures are checked against the signers given to `process`, the
+// system program and the token instructions of token_utils are the programs reachable through CPI
+// and `Clock::get`/`Rent::get` return `clock` and `rent`. An instruction that fails leaves every
+// account as it was
+#[derive(Clone)]
+pub struct TestRuntime {
+    pub program_id: Pubkey,
+    pub clock: Clock,
+    pub rent: Rent,
+    processor: ProcessInstruction,
+    pub(super) accounts: HashMap<Pubkey, TestAccount>,
+    logs: Vec<String>,
+}
+
+impl TestRuntime {
+    pub fn new(program_id: Pubkey, processor: ProcessInstruction) -> Self {
+        TestRuntime {
+            program_id,
+            clock: Clock::default(),
+            rent: Rent::default(),
+            processor,
+            accounts: HashMap::new(),
+            logs: Vec::new(),
+        }
+    }
+
+    // Stores `account` as it is on chain, signer and writable flags come from each instruction
+    pub fn add_account(&mut self, mut account: TestAccount) {
+        account.is_signer = false;
+        account.is_writable = false;
+        self.accounts.insert(account.key, account);
+    }
+
+    // A system account holding `lamports`, to sign instructions and pay for new accounts
+    pub fn add_payer(&mut self, lamports: u64) -> Pubkey {
+        let payer = TestAccount::new().lamports(lamports);
+        let key = payer.key;
+        self.add_account(payer);
+        key
+    }
+
+    // An initialized mint of the token program
+    pub fn add_mint(&mut self) -> Pubkey {
+        let mint = TestAccount::new().mint();
+        let key = mint.key;
+        self.add_account(mint);
+        key
+    }
+
+    // A token account of `mint` holding `amount` tokens that `owner` can move
+    pub fn add_token_account(&mut self, mint: &Pubkey, owner: &Pubkey, amount: u64) -> Pubkey {
+        let account = TestAccount::new().token_account(mint, owner, amount);
+        let key = account.key;
+        self.add_account(account);
+        key
+    }
+
+    // None while the account does not exist, as for an address without lamports on chain
+    pub fn account(&self, key: &Pubkey) -> Option<&TestAccount> {
+        self.accounts.get(key)
+    }
+
+    // The data of `key` unpacked with the account layout of `T`
+    pub fn read<T: State>(&self, key: &Pubkey) -> Option<T> {
+        self.account(key).map(TestAccount::read)
+    }
+
+    // The mint, owner and amount of `key`, None unless it is a token account
+    pub fn token_account(&self, key: &Pubkey) -> Option<TokenAccount> {
+        self.account(key)
+            .filter(|account| {
+                account.owner == spl_token_program::id() && account.data.len() == TOKEN_ACCOUNT_LEN
+            })
+            .map(|account| unpack_token_account(&account.data).1)
+    }
+
+    // Logs of the last instruction processed, framed by the invoke and success or failed lines
+    // of each program as in the transaction logs of a validator
+    pub fn logs(&self) -> &[String] {
+        &self.logs
+    }
+
+    pub fn process(&mut self, instruction: &Instruction, signers: &[Pubkey]) -> ProgramResult {
+        self.logs.clear();
+        if instruction.program_id != self.program_id {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+
+        // An account listed several times is lent once and shared, as on chain
+        let mut accounts: Vec<TestAccount> = Vec::new();
+        let mut positions = Vec::with_capacity(instruction.accounts.len());
+        for meta in &instruction.accounts {
+            if meta.is_signer && !signers.contains(&meta.pubkey) {
+                return Err(ProgramError::MissingRequiredSignature);
+            }
+
+            let position = match accounts.iter().position(|a| a.key == meta.pubkey) {
+                Some(position) => position,
+                None => {
+                    accounts.push(self.load(&meta.pubkey));
+                    accounts.len() - 1
+                }
+            };
+            accounts[position].is_signer |= meta.is_signer;
+            accounts[position].is_writable |= meta.is_writable;
+            positions.push(position);
+        }
+        let before = accounts.clone();
+
+        install_syscall_stubs();
+        CONTEXT.with(|context| {
+            *context.borrow_mut() = Some(Context {
+                program_id: self.program_id,
+                clock: self.clock.clone(),
+                rent: self.rent,
+                invoked: Vec::new(),
+                logs: Vec::new(),
+            })
+        });
+
+        let mut memory: Vec<_> = accounts.iter().map(AccountMemory::new).collect();
+        let (result, after) = {
+            let infos = runtime_infos(&mut accounts, &mut memory);
+            let ordered: Vec<_> = positions.iter().map(|&p| infos[p].clone()).collect();
+            log_invoke(&self.program_id, 1);
+            let result = (self.processor)(&self.program_id, &ordered, &instruction.data);
+            log_result(&self.program_id, &result);
+
+            // The system program replaces the data of the accounts it allocates, the
+            // AccountInfos hold the final state
+            let after: Vec<_> = infos
+                .iter()
+                .map(|info| (info.lamports(), info.data.borrow().to_vec(), *info.owner))
+                .collect();
+            (result, after)
+        };
+        let context = CONTEXT.with(|context| context.borrow_mut().take());
+        let (invoked, logs) = context.map_or_else(Default::default, |c| (c.invoked, c.logs));
+        self.logs = logs;
+        result?;
+
+        self.commit(before, after, &invoked);
+
+        Ok(())
+    }
+
+    pub(super) fn load(&self, key: &Pubkey) -> TestAccount {
+        if let Some(account) = self.accounts.get(key) {
+            return account.clone();
+        }
+
+        if *key == system_program::id() {
+            TestAccount::new()
+                .key(*key)
+                .owner(NATIVE_LOADER_ID)
+                .executable()
+                .lamports(1)
+        } else if *key == self.program_id || *key == spl_token_program::id() {
+            TestAccount::new()
+                .key(*key)
+                .owner(bpf_loader::id())
+                .executable()
+                .lamports(1)
+        } else {
+            TestAccount::new().key(*key)
+        }
+    }
+
+    // Writes that the runtime would reject are bugs of the program under test, they fail the
+    // test instead of surfacing as an error
+    fn commit(
+        &mut self,
+        before: Vec<TestAccount>,
+        after: Vec<(u64, Vec<u8>, Pubkey)>,
+        invoked: &[Pubkey],
+    ) {
+        let lamports_before: u128 = before.iter().map(|a| a.lamports as u128).sum();
+        let lamports_after: u128 = after.iter().map(|(l, _, _)| *l as u128).sum();
+        assert_eq!(
+            lamports_before, lamports_after,
+            "the instruction changed the total of lamports"
+        );
+
+        for (mut account, (lamports, data, owner)) in before.into_iter().zip(after) {
+            let debited = lamports < account.lamports;
+            let modified = debited || data != account.data || owner != account.owner;
+            if modified || lamports != account.lamports {
+                assert!(account.is_writable, "{} is read-only", account.key);
+            }
+            if modified {
+                assert!(
+                    account.owner == self.program_id || invoked.contains(&account.key),
+                    "{} is owned by {}, not by the program",
+                    account.key,
+                    account.owner
+                );
+            }
+
+            account.lamports = lamports;
+            account.data = data;
+            account.owner = owner;
+            if account.lamports == 0 {
+                self.accounts.remove(&account.key);
+            } else {
+                self.add_account(account);
+            }
+        }
+    }
+}
+
+// ==== SYSCALLS =======
+
+// What the stubs need from the instruction being processed on this thread
+struct Context {
+    program_id: Pubkey,
+    clock: Clock,
+    rent: Rent,
+    // Accounts written by the system or token program through CPI
+    invoked: Vec<Pubkey>,
+    logs: Vec<String>,
+}
+
+thread_local! {
+    static CONTEXT: RefCell<Option<Context>> = const { RefCell::new(None) };
+}
+
+static SYSCALL_STUBS: Once = Once::new();
+
+// The stubs are global, outside of `TestRuntime::process` they behave as the default ones
+fn install_syscall_stubs() {
+    SYSCALL_STUBS.call_once(|| {
+        set_syscall_stubs(Box::new(TestSyscallStubs));
+    });
+}
+
+struct TestSyscallStubs;
+
+impl SyscallStubs for TestSyscallStubs {
+    fn sol_invoke_signed(
+        &self,
+        instruction: &Instruction,
+        account_infos: &[AccountInfo],
+        signers_seeds: &[&[&[u8]]],
+    ) -> ProgramResult {
+        let (program_id, rent) = match CONTEXT
+            .with(|context| context.borrow().as_ref().map(|c| (c.program_id, c.rent)))
+        {
+            Some(context) => context,
+            // Outside of `TestRuntime::process` there is no program to invoke from, a CPI that
+            // did nothing would hide a test not running through the runtime
+            None => return Err(ProgramError::InvalidArgument),
+        };
+        if instruction.program_id != system_program::id()
+            && instruction.program_id != spl_token_program::id()
+        {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+
+        let pda_signers = signers_seeds
+            .iter()
+            .map(|seeds| Pubkey::create_program_address(seeds, &program_id))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| ProgramError::InvalidSeeds)?;
+
+        let mut accounts = Vec::with_capacity(instruction.accounts.len());
+        for meta in &instruction.accounts {
+            let info = account_infos
+                .iter()
+                .find(|info| *info.key == meta.pubkey)
+                .ok_or(ProgramError::NotEnoughAccountKeys)?;
+            if meta.is_signer && !info.is_signer && !pda_signers.contains(info.key) {
+                return Err(ProgramError::MissingRequiredSignature);
+            }
+            if meta.is_writable && !info.is_writable {
+                return Err(ProgramError::InvalidArgument);
+            }
+            // The invoked program sees the privileges of the instruction, PDAs signed by the seeds
+            let mut info = info.clone();
+            info.is_signer = meta.is_signer;
+            accounts.push(info);
+        }
+
+        CONTEXT.with(|context| {
+            if let Some(context) = context.borrow_mut().as_mut() {
+                context.invoked.extend(
+                    instruction
+                        .accounts
+                        .iter()
+                        .filter(|meta| meta.is_writable)
+                        .map(|meta| meta.pubkey),
+                );
+            }
+        });
+
+        log_invoke(&instruction.program_id, 2);
+        let result = if instruction.program_id == system_program::id() {
+            process_system_instruction(&accounts, &instruction.data)
+        } else {
+            process_token_instruction(&accounts, &instruction.data, &rent)
+        };
+        log_result(&instruction.program_id, &result);
+        result
+    }
+
+    fn sol_log(&self, message: &str) {
+        log(format!("Program log: {}", message));
+    }
+
+    fn sol_log_data(&self, fields: &[&[u8]]) {
+        let fields: Vec<_> = fields.iter().map(base64::encode).collect();
+        log(format!("{}{}", PROGRAM_DATA_PREFIX, fields.join(" ")));
+    }
+
+    fn sol_get_clock_sysvar(&self, var_addr: *mut u8) -> u64 {
+        match CONTEXT.with(|context| context.borrow().as_ref().map(|c| c.clock.clone())) {
+            Some(clock) => {
+                // `Clock::get` passes the address of its own Clock
+                unsafe { std::ptr::write(var_addr as *mut Clock, clock) };
+                SUCCESS
+            }
+            None => UNSUPPORTED_SYSVAR,
+        }
+    }
+
+    fn sol_get_rent_sysvar(&self, var_addr: *mut u8) -> u64 {
+        match CONTEXT.with(|context| context.borrow().as_ref().map(|c| c.rent)) {
+            Some(rent) => {
+                // `Rent::get` passes the address of its own Rent
+                unsafe { std::ptr::write(var_addr as *mut Rent, rent) };
+                SUCCESS
+            }
+            None => UNSUPPORTED_SYSVAR,
+        }
+    }
+}
+
+// Kept with the instruction being processed, printed as the default stubs do otherwise
+fn log(line: String) {
+    CONTEXT.with(|context| match context.borrow_mut().as_mut() {
+        Some(context) => context.logs.push(line),
+        None => println!("{}", line),
+    });
+}
+
+fn log_invoke(program_id: &Pubkey, depth: usize) {
+    log(format!("Program {} invoke [{}]", program_id, depth));
+}
+
+fn log_result(program_id: &Pubkey, result: &ProgramResult) {
+    match result {
+        Ok(()) => log(format!("Program {} success", program_id)),
+        Err(e) => log(format!("Program {} failed: {}", program_id, e)),
+    }
+}
+
+// The system instructions that move lamports and create accounts, with the checks of the system
+// program
+fn process_system_instruction(accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    let instruction: SystemInstruction = limited_deserialize(data, MAX_SYSTEM_INSTRUCTION_LEN)
+        .map_err(|_| ProgramError::InvalidInstructionData)?;
+
+    match (instruction, accounts) {
+        (
+            SystemInstruction::CreateAccount {
+                lamports,
+                space,
+                owner,
+            },
+            [from, to, ..],
+        ) => {
+            if to.lamports() > 0 {
+                return Err(system_error(SystemError::AccountAlreadyInUse));
+            }
+            allocate(to, space)?;
+            to.assign(&owner);
+            transfer(from, to, lamports)
+        }
+        (SystemInstruction::Transfer { lamports }, [from, to, ..]) => transfer(from, to, lamports),
+        (SystemInstruction::Assign { owner }, [account, ..]) => {
+            if !account.is_signer {
+                return Err(ProgramError::MissingRequiredSignature);
+            }
+            if *account.owner != system_program::id() && *account.owner != owner {
+                return Err(system_error(SystemError::InvalidProgramId));
+            }
+            account.assign(&owner);
+            Ok(())
+        }
+        (SystemInstruction::Allocate { space }, [account, ..]) => allocate(account, space),
+        (
+            SystemInstruction::CreateAccount { .. }
+            | SystemInstruction::Transfer { .. }
+            | SystemInstruction::Assign { .. }
+            | SystemInstruction::Allocate { .. },
+            _,
+        ) => Err(ProgramError::NotEnoughAccountKeys),
+        // Nonces and seeded addresses are not needed by the generated programs
+        _ => Err(ProgramError::InvalidInstructionData),
+    }
+}
+
+// The key and data of an account laid out as the runtime serializes them for a program:
+// `AccountInfo::realloc` reads the original data length right before the key, writes the new
+// length right before the data and grows it into the MAX_PERMITTED_DATA_INCREASE bytes after it
+struct AccountMemory {
+    key: [u32; 9],
+    data: Vec<u64>,
+    len: usize,
+}
+
+impl AccountMemory {
+    fn new(account: &TestAccount) -> Self {
+        let len = account.data.len();
+        let mut key = [0u32; 9];
+        key[0] = len as u32;
+        let mut data = vec![0u64; 1 + (len + MAX_PERMITTED_DATA_INCREASE).div_ceil(8)];
+        data[0] = len as u64;
+
+        let mut memory = AccountMemory { key, data, len };
+        // SAFETY: the arrays are larger than the bytes written, u8 has no alignment
+        unsafe {
+            let key = memory.key.as_mut_ptr().add(1) as *mut u8;
+            std::ptr::copy_nonoverlapping(account.key.as_ref().as_ptr(), key, 32);
+            let data = memory.data.as_mut_ptr().add(1) as *mut u8;
+            std::ptr::copy_nonoverlapping(account.data.as_ptr(), data, len);
+        }
+        memory
+    }
+}
+
+// AccountInfos as `infos` builds them, over memory that `realloc` can grow in place
+fn runtime_infos<'a>(
+    accounts: &'a mut [TestAccount],
+    memory: &'a mut [AccountMemory],
+) -> Vec<AccountInfo<'a>> {
+    accounts
+        .iter_mut()
+        .zip(memory.iter_mut())
+        .map(|(account, memory)| {
+            // SAFETY: Pubkey is a transparent [u8; 32], the slice stays within `memory.data`
+            let (key, data) = unsafe {
+                let key = &*(memory.key.as_ptr().add(1) as *const Pubkey);
+                let data = memory.data.as_mut_ptr().add(1) as *mut u8;
+                (key, std::slice::from_raw_parts_mut(data, memory.len))
+            };
+            AccountInfo::new(
+                key,
+                account.is_signer,
+                account.is_writable,
+                &mut account.lamports,
+                data,
+                &account.owner,
+                account.executable,
+                account.rent_epoch,
+            )
+        })
+        .collect()
+}
+
+fn allocate(account: &AccountInfo, space: u64) -> ProgramResult {
+    if !account.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if !account.data_is_empty() || *account.owner != system_program::id() {
+        return Err(system_error(SystemError::AccountAlreadyInUse));
+    }
+    if space > MAX_PERMITTED_DATA_LENGTH {
+        return Err(system_error(SystemError::InvalidAccountDataLength));
+    }
+
+    // The data grows in place into the memory of the account, which bounds it to
+    // MAX_PERMITTED_DATA_INCREASE bytes as for a CPI on chain
+    account.realloc(space as usize, true)
+}
+
+fn transfer(from: &AccountInfo, to: &AccountInfo, lamports: u64) -> ProgramResult {
+    if !from.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if !from.data_is_empty() || *from.owner != system_program::id() {
+        return Err(ProgramError::InvalidArgument);
+    }
+    if from.lamports() < lamports {
+        return Err(system_error(SystemError::ResultWithNegativeLamports));
+    }
+    let to_lamports = to
+        .lamports()
+        .checked_add(lamports)
+        .ok_or(MathError::Overflow)?;
+
+    **from.lamports.borrow_mut() -= lamports;
+    **to.lamports.borrow_mut() = to_lamports;
+
+    Ok(())
+}
+
+pub(super) fn system_error(e: SystemError) -> ProgramError {
+    ProgramError::Custom(e as u32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rust_utils::*;
+    use crate::testing::test_program::*;
+    use crate::testing::TokenError;
+    use solana_program::instruction::AccountMeta;
+    use solana_program::program::invoke_signed;
+    use solana_program::system_instruction;
+
+    #[test]
+    fn it_creates_accounts_through_the_system_program() {
+        let mut runtime = runtime();
+        let payer = runtime.add_payer(1_000_000_000);
+        let rent = Rent::default().minimum_balance(Record::LEN);
+
+        assert_eq!(
+            runtime.process(&create_record(&runtime, &payer), &[payer]),
+            Ok(())
+        );
+
+        let record = runtime.account(&record_address(&runtime, &payer)).unwrap();
+        assert_eq!(record.owner, runtime.program_id);
+        assert_eq!(record.data, vec![0; Record::LEN]);
+        assert_eq!(record.lamports, rent);
+        assert_eq!(
+            runtime.account(&payer).unwrap().lamports,
+            1_000_000_000 - rent
+        );
+        assert_eq!(
+            runtime.read::<Record>(&record_address(&runtime, &payer)),
+            Some(Record::default())
+        );
+        assert_eq!(
+            runtime.logs(),
+            [
+                format!("Program {} invoke [1]", runtime.program_id),
+                format!("Program {} invoke [2]", system_program::id()),
+                format!("Program {} success", system_program::id()),
+                format!("Program {} success", runtime.program_id),
+            ]
+        );
+    }
+
+    // Accounts created through a CPI grow in the memory of the account, as on chain
+    #[test]
+    fn it_creates_accounts_within_the_cpi_data_increase() {
+        let mut runtime = runtime();
+        let payer = runtime.add_payer(1_000_000_000);
+        let mut create = create_record(&runtime, &payer);
+
+        create.data = [
+            &[0][..],
+            &(MAX_PERMITTED_DATA_INCREASE as u32 + 1).to_le_bytes(),
+        ]
+        .concat();
+        assert_eq!(
+            runtime.process(&create, &[payer]),
+            Err(ProgramError::InvalidRealloc)
+        );
+        assert_eq!(runtime.account(&record_address(&runtime, &payer)), None);
+
+        create.data = [
+            &[0][..],
+            &(MAX_PERMITTED_DATA_INCREASE as u32).to_le_bytes(),
+        ]
+        .concat();
+        runtime.process(&create, &[payer]).unwrap();
+        let record = runtime.account(&record_address(&runtime, &payer)).unwrap();
+        assert_eq!(record.data, vec![0; MAX_PERMITTED_DATA_INCREASE]);
+    }
+
+    #[test]
+    fn it_requires_the_signatures_of_the_instruction() {
+        let mut runtime = runtime();
+        let payer = runtime.add_payer(1_000_000_000);
+
+        assert_eq!(
+            runtime.process(&create_record(&runtime, &payer), &[]),
+            Err(ProgramError::MissingRequiredSignature)
+        );
+        assert_eq!(runtime.account(&record_address(&runtime, &payer)), None);
+    }
+
+    #[test]
+    fn it_rejects_invalid_system_instructions() {
+        let mut runtime = runtime();
+        let payer = runtime.add_payer(1_000_000_000);
+        runtime
+            .process(&create_record(&runtime, &payer), &[payer])
+            .unwrap();
+
+        assert_eq!(
+            runtime.process(&create_record(&runtime, &payer), &[payer]),
+            Err(ProgramError::Custom(
+                SystemError::AccountAlreadyInUse as u32
+            ))
+        );
+
+        let poor = runtime.add_payer(10);
+        assert_eq!(
+            runtime.process(&create_record(&runtime, &poor), &[poor]),
+            Err(ProgramError::Custom(
+                SystemError::ResultWithNegativeLamports as u32
+            ))
+        );
+        assert_eq!(runtime.account(&poor).unwrap().lamports, 10);
+        assert_eq!(runtime.account(&record_address(&runtime, &poor)), None);
+    }
+
+    #[test]
+    fn it_checks_transfers_like_the_system_program() {
+        let mut from = TestAccount::new().writable().lamports(100);
+        let mut to = TestAccount::new().writable().lamports(u64::MAX - 5);
+        let transfer = system_instruction::transfer(&from.key, &to.key, 10).data;
+
+        assert_eq!(
+            process_system_instruction(&[from.info(), to.info()], &transfer),
+            Err(ProgramError::MissingRequiredSignature)
+        );
+        from.is_signer = true;
+        assert_eq!(
+            process_system_instruction(&[from.info(), to.info()], &transfer),
+            Err(MathError::Overflow.into())
+        );
+        assert_eq!((from.lamports, to.lamports), (100, u64::MAX - 5));
+
+        to.lamports = 0;
+        assert_eq!(
+            process_system_instruction(&[from.info(), to.info()], &transfer),
+            Ok(())
+        );
+        assert_eq!((from.lamports, to.lamports), (90, 10));
+    }
+
+    #[test]
+    fn it_fails_to_invoke_outside_of_the_runtime() {
+        install_syscall_stubs();
+        let mut from = TestAccount::new().signer().writable().lamports(100);
+        let mut to = TestAccount::new().writable();
+        let instruction = system_instruction::transfer(&from.key, &to.key, 10);
+
+        assert_eq!(
+            invoke_signed(&instruction, &[from.info(), to.info()], &[]),
+            Err(ProgramError::InvalidArgument)
+        );
+        assert_eq!(from.lamports, 100);
+    }
+
+    #[test]
+    fn it_serves_the_clock_and_rent_sysvars() {
+        let mut runtime = runtime();
+        let payer = runtime.add_payer(1_000_000_000);
+        runtime.rent.lamports_per_byte_year *= 2;
+        runtime
+            .process(&create_record(&runtime, &payer), &[payer])
+            .unwrap();
+        let record = record_address(&runtime, &payer);
+        assert_eq!(
+            runtime.account(&record).unwrap().lamports,
+            runtime.rent.minimum_balance(Record::LEN)
+        );
+
+        runtime.clock.unix_timestamp = 1_700_000_000;
+        runtime
+            .process(&on_record(&runtime, &payer, 1, true), &[])
+            .unwrap();
+
+        let (_, timestamp) = unpack_i64(&runtime.account(&record).unwrap().data);
+        assert_eq!(timestamp, 1_700_000_000);
+    }
+
+    #[test]
+    fn it_reallocates_accounts_in_place() {
+        let mut runtime = runtime();
+        let payer = runtime.add_payer(1_000_000_000);
+        runtime
+            .process(&create_record(&runtime, &payer), &[payer])
+            .unwrap();
+        let record = record_address(&runtime, &payer);
+        let program_id = runtime.program_id;
+        let realloc = |len: usize| {
+            let mut data = vec![6];
+            data.extend_from_slice(&(len as u32).to_le_bytes());
+            Instruction::new_with_bytes(program_id, &data, vec![AccountMeta::new(record, false)])
+        };
+
+        let len = Record::LEN + MAX_PERMITTED_DATA_INCREASE;
+        assert_eq!(
+            runtime.process(&realloc(len + 1), &[]),
+            Err(ProgramError::InvalidRealloc)
+        );
+        runtime.process(&realloc(len), &[]).unwrap();
+        let mut expected = vec![0; len];
+        expected[len - 1] = 1;
+        assert_eq!(runtime.account(&record).unwrap().data, expected);
+
+        runtime.process(&realloc(1), &[]).unwrap();
+        assert_eq!(runtime.account(&record).unwrap().data, vec![1]);
+    }
+
+    #[test]
+    fn it_leaves_accounts_unchanged_when_the_instruction_fails() {
+        let mut runtime = runtime();
+        let payer = runtime.add_payer(1_000_000_000);
+        runtime
+            .process(&create_record(&runtime, &payer), &[payer])
+            .unwrap();
+        let record = record_address(&runtime, &payer);
+        let before = runtime.account(&record).cloned();
+
+        assert_eq!(
+            runtime.process(&on_record(&runtime, &payer, 2, true), &[]),
+            Err(ProgramError::Custom(7))
+        );
+        assert_eq!(runtime.account(&record).cloned(), before);
+        assert_eq!(
+            runtime.logs().last().unwrap(),
+            &format!(
+                "Program {} failed: Custom program error: 0x7",
+                runtime.program_id
+            )
+        );
+    }
+
+    #[test]
+    fn it_transfers_tokens_through_the_token_program() {
+        let mut runtime = runtime();
+        let mint = runtime.add_mint();
+        let vault = vault_address(&runtime);
+        runtime.add_account(
+            TestAccount::new()
+                .key(vault)
+                .token_account(&mint, &vault, 100),
+        );
+        let destination = runtime.add_token_account(&mint, &Pubkey::new_unique(), 0);
+
+        assert_eq!(
+            runtime.process(&transfer_from_vault(&runtime, &destination, 30), &[]),
+            Ok(())
+        );
+        assert_eq!(runtime.token_account(&vault).unwrap().amount, 70);
+        assert_eq!(runtime.token_account(&destination).unwrap().amount, 30);
+        assert_eq!(
+            runtime.logs()[1..3],
+            [
+                format!("Program {} invoke [2]", spl_token_program::id()),
+                format!("Program {} success", spl_token_program::id()),
+            ]
+        );
+
+        assert_eq!(
+            runtime.process(&transfer_from_vault(&runtime, &destination, 71), &[]),
+            Err(TokenError::InsufficientFunds.into())
+        );
+        assert_eq!(runtime.token_account(&vault).unwrap().amount, 70);
+    }
+
+    // Only the program deriving the vault can sign for it
+    #[test]
+    fn it_requires_the_signature_of_the_token_owner() {
+        let mut runtime = runtime();
+        let mint = runtime.add_mint();
+        let vault = vault_address(&runtime);
+        let owner = Pubkey::new_unique();
+        runtime.add_account(
+            TestAccount::new()
+                .key(vault)
+                .token_account(&mint, &owner, 100),
+        );
+        let destination = runtime.add_token_account(&mint, &owner, 0);
+
+        assert_eq!(
+            runtime.process(&transfer_from_vault(&runtime, &destination, 30), &[]),
+            Err(TokenError::OwnerMismatch.into())
+        );
+        assert_eq!(runtime.token_account(&vault).unwrap().amount, 100);
+        assert_eq!(runtime.token_account(&mint), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "is read-only")]
+    fn it_fails_on_writes_to_read_only_accounts() {
+        let mut runtime = runtime();
+        let payer = runtime.add_payer(1_000_000_000);
+        runtime
+            .process(&create_record(&runtime, &payer), &[payer])
+            .unwrap();
+
+        let _ = runtime.process(&on_record(&runtime, &payer, 3, false), &[]);
+    }
+
+    #[test]
+    #[should_panic(expected = "not by the program")]
+    fn it_fails_on_writes_to_accounts_of_other_programs() {
+        let mut runtime = runtime();
+        let payer = runtime.add_payer(1_000_000_000);
+        let record = record_address(&runtime, &payer);
+        runtime.add_account(TestAccount::new().key(record).zeroed(4).rent_exempt());
+
+        let _ = runtime.process(&on_record(&runtime, &payer, 3, true), &[]);
+    }
+}
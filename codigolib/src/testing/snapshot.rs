@@ -0,0 +1,467 @@
+use super::pack_state;
+use crate::event_utils::{pack_event, Event};
+use crate::rust_utils::State;
+use std::fmt::Write;
+use std::path::Path;
+use std::{any, env, fs};
+
+// Bytes per line of a layout dump
+const DUMP_WIDTH: usize = 16;
+
+// `value` packed with its account layout as a hex dump, one block per field of `T::FIELDS` with
+// its offset, name, bytes and their printable characters. A type without FIELDS is one block
+pub fn layout_dump<T: State>(value: &T) -> String {
+    bytes_dump(short_type_name::<T>(), &pack_state(value), T::FIELDS)
+}
+
+// `event` as `emit_event` logs it, its discriminator followed by the fields of `E::FIELDS`
+pub fn event_dump<E: Event>(event: &E) -> String {
+    let fields = if E::FIELDS.is_empty() {
+        vec![("", E::LEN)]
+    } else {
+        E::FIELDS.to_vec()
+    };
+    bytes_dump(
+        short_type_name::<E>(),
+        &pack_event(event),
+        &[&[("discriminator", 1)], fields.as_slice()].concat(),
+    )
+}
+
+// `data` as a hex dump titled `name`, one block per field. Fields have to cover `data` exactly,
+// no fields is one block
+pub fn bytes_dump(name: &str, data: &[u8], fields: &[(&str, usize)]) -> String {
+    let fields = if fields.is_empty() {
+        &[("", data.len())][..]
+    } else {
+        fields
+    };
+    let size: usize = fields.iter().map(|(_, size)| size).sum();
+    assert_eq!(
+        size,
+        data.len(),
+        "the fields of {} don't match its bytes",
+        name
+    );
+    let width = fields.iter().map(|(name, _)| name.len()).max().unwrap_or(0);
+
+    let mut out = format!("# {}, {} bytes\n", name, data.len());
+    let mut offset = 0;
+    for (name, size) in fields {
+        let bytes = &data[offset..offset + size];
+        for (line, chunk) in bytes.chunks(DUMP_WIDTH).enumerate() {
+            let label = if line == 0 {
+                format!("{:04x}  {:<width$}", offset, name)
+            } else {
+                String::new()
+            };
+            let ascii: String = chunk
+                .iter()
+                .map(|&b| {
+                    if b.is_ascii_graphic() || b == b' ' {
+                        b as char
+                    } else {
+                        '.'
+                    }
+                })
+                .collect();
+            writeln!(
+                out,
+                "{:<label_width$} | {:<hex_width$} | {}",
+                label,
+                hex(chunk),
+                ascii,
+                label_width = width + 6,
+                hex_width = DUMP_WIDTH * 3 - 1,
+            )
+            .unwrap();
+        }
+        offset += size;
+    }
+
+    out
+}
+
+// The same type generated in a program and in its client dumps the same
+fn short_type_name<T>() -> &'static str {
+    any::type_name::<T>()
+        .rsplit("::")
+        .next()
+        .unwrap_or_default()
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct DumpField {
+    name: String,
+    offset: usize,
+    bytes: Vec<u8>,
+}
+
+// A field starts on a line with an offset, lines without one continue it
+fn parse_layout(dump: &str) -> (String, Vec<DumpField>) {
+    let mut header = String::new();
+    let mut fields: Vec<DumpField> = Vec::new();
+
+    for line in dump.lines() {
+        if let Some(rest) = line.strip_prefix('#') {
+            header = rest.trim().to_string();
+            continue;
+        }
+        let mut columns = line.splitn(3, '|');
+        let label = columns.next().unwrap_or_default();
+        let bytes = columns
+            .next()
+            .unwrap_or_default()
+            .split_whitespace()
+            .filter_map(|byte| u8::from_str_radix(byte, 16).ok());
+
+        let mut words = label.split_whitespace();
+        match words.next() {
+            Some(offset) => fields.push(DumpField {
+                name: words.next().unwrap_or_default().to_string(),
+                offset: usize::from_str_radix(offset, 16).unwrap_or_default(),
+                bytes: bytes.collect(),
+            }),
+            None => {
+                if let Some(field) = fields.last_mut() {
+                    field.bytes.extend(bytes);
+                }
+            }
+        }
+    }
+
+    (header, fields)
+}
+
+// What changed from the `expected` dump to `actual`, field by field: `~` for a field with other
+// bytes or at another offset, `+` for a new field and `-` for a removed one
+pub fn layout_diff(expected: &str, actual: &str) -> Vec<String> {
+    let (expected_header, expected) = parse_layout(expected);
+    let (actual_header, actual) = parse_layout(actual);
+    let mut diff = Vec::new();
+
+    if expected_header != actual_header {
+        diff.push(format!("~ {} -> {}", expected_header, actual_header));
+    }
+
+    for field in &actual {
+        let Some(previous) = expected.iter().find(|e| e.name == field.name) else {
+            diff.push(format!(
+                "+ {} at {:04x}: {}",
+                field.name,
+                field.offset,
+                hex(&field.bytes)
+            ));
+            continue;
+        };
+        if previous == field {
+            continue;
+        }
+
+        let mut line = format!("~ {}", field.name);
+        if previous.offset != field.offset {
+            write!(
+                line,
+                " moved from {:04x} to {:04x}",
+                previous.offset, field.offset
+            )
+            .unwrap();
+        } else {
+            write!(line, " at {:04x}", field.offset).unwrap();
+        }
+        if previous.bytes != field.bytes {
+            line += &bytes_diff(&previous.bytes, &field.bytes);
+        }
+        diff.push(line);
+    }
+
+    for field in &expected {
+        if !actual.iter().any(|a| a.name == field.name) {
+            diff.push(format!(
+                "- {} at {:04x}: {}",
+                field.name,
+                field.offset,
+                hex(&field.bytes)
+            ));
+        }
+    }
+
+    diff
+}
+
+// Only the changed bytes of fields keeping their size, e.g. `[0..2] 02 00 -> 03 00`
+fn bytes_diff(expected: &[u8], actual: &[u8]) -> String {
+    if expected.len() != actual.len() {
+        return format!(
+            ", {} bytes instead of {}: {} -> {}",
+            actual.len(),
+            expected.len(),
+            hex(expected),
+            hex(actual)
+        );
+    }
+
+    let changed: Vec<_> = (0..expected.len())
+        .filter(|&i| expected[i] != actual[i])
+        .collect();
+    let (first, last) = (changed[0], changed[changed.len() - 1] + 1);
+    format!(
+        ": [{}..{}] {} -> {}",
+        first,
+        last,
+        hex(&expected[first..last]),
+        hex(&actual[first..last])
+    )
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn check_snapshot(path: &Path, actual: &str) -> Result<(), String> {
+    let expected = fs::read_to_string(path).map_err(|_| {
+        format!(
+            "no layout snapshot at {}, run with UPDATE_GOLDEN=1 to write it",
+            path.display()
+        )
+    })?;
+    if expected == actual {
+        return Ok(());
+    }
+
+    let mut diff = layout_diff(&expected, actual);
+    if diff.is_empty() {
+        diff.push("same bytes, the dump is formatted differently".to_string());
+    }
+    Err(format!(
+        "layout of {} changed, run with UPDATE_GOLDEN=1 if it is intended:\n  {}",
+        path.display(),
+        diff.join("\n  ")
+    ))
+}
+
+// Compares `dump` to the one checked in at `path`, UPDATE_GOLDEN=1 writes it instead
+#[track_caller]
+pub fn assert_snapshot(path: impl AsRef<Path>, dump: &str) {
+    let path = path.as_ref();
+    if env::var_os("UPDATE_GOLDEN").is_some() {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).unwrap();
+        }
+        fs::write(path, dump).unwrap();
+        return;
+    }
+
+    if let Err(e) = check_snapshot(path, dump) {
+        panic!("{}", e);
+    }
+}
+
+// Compares `value` packed with its account layout to the dump checked in at `path`
+#[track_caller]
+pub fn assert_layout_snapshot<T: State>(path: impl AsRef<Path>, value: &T) {
+    assert_snapshot(path, &layout_dump(value));
+}
+
+// Compares `event` as it is logged to the dump checked in at `path`
+#[track_caller]
+pub fn assert_event_snapshot<E: Event>(path: impl AsRef<Path>, event: &E) {
+    assert_snapshot(path, &event_dump(event));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rust_utils::*;
+    use crate::testing::test_program::Record;
+    use solana_program::pubkey::Pubkey;
+
+    // A state without annotated fields
+    #[derive(Debug, PartialEq)]
+    struct Balance(i64);
+
+    impl State for Balance {
+        const LEN: usize = 8;
+
+        fn pack<'a>(&self, buf: &'a mut [u8]) -> &'a mut [u8] {
+            pack_i64(buf, self.0)
+        }
+
+        fn unpack(buf: &[u8]) -> (&[u8], Self) {
+            let (buf, balance) = unpack_i64(buf);
+            (buf, Balance(balance))
+        }
+    }
+
+    #[test]
+    fn it_dumps_the_layout_of_state() {
+        let record = Record {
+            name: "John Doe".to_string(),
+            moves: 2,
+            total_balance: -750,
+        };
+        assert_eq!(
+            layout_dump(&record),
+            "# Record, 24 bytes
+0000  name          | 08 00 00 00 4a 6f 68 6e 20 44 6f 65 00 00       | ....John Doe..
+000e  moves         | 02 00                                           | ..
+0010  total_balance | 12 fd ff ff ff ff ff ff                         | ........
+"
+        );
+        assert_eq!(
+            layout_dump(&Balance(-750)),
+            "# Balance, 8 bytes
+0000   | 12 fd ff ff ff ff ff ff                         | ........
+"
+        );
+    }
+
+    // An event annotated field by field
+    #[derive(Debug, PartialEq)]
+    struct IncomeRegistered {
+        amount: u32,
+        total_balance: i64,
+    }
+
+    impl Event for IncomeRegistered {
+        const DISCRIMINATOR: u8 = 1;
+        const LEN: usize = 4 + 8;
+        const FIELDS: &'static [(&'static str, usize)] = &[("amount", 4), ("total_balance", 8)];
+
+        fn pack<'a>(&self, buf: &'a mut [u8]) -> &'a mut [u8] {
+            let buf = pack_u32(buf, self.amount);
+            pack_i64(buf, self.total_balance)
+        }
+
+        fn unpack(buf: &[u8]) -> (&[u8], Self) {
+            let (buf, amount) = unpack_u32(buf);
+            let (buf, total_balance) = unpack_i64(buf);
+            (
+                buf,
+                IncomeRegistered {
+                    amount,
+                    total_balance,
+                },
+            )
+        }
+    }
+
+    #[test]
+    fn it_dumps_events_as_they_are_logged() {
+        let event = IncomeRegistered {
+            amount: 100,
+            total_balance: -750,
+        };
+        assert_eq!(
+            event_dump(&event),
+            "# IncomeRegistered, 13 bytes
+0000  discriminator | 01                                              | .
+0001  amount        | 64 00 00 00                                     | d...
+0005  total_balance | 12 fd ff ff ff ff ff ff                         | ........
+"
+        );
+    }
+
+    #[test]
+    fn it_dumps_bytes_by_field() {
+        assert_eq!(
+            bytes_dump(
+                "register_income",
+                &[1, 100, 0, 0, 0],
+                &[("tag", 1), ("amount", 4)]
+            ),
+            "# register_income, 5 bytes
+0000  tag    | 01                                              | .
+0001  amount | 64 00 00 00                                     | d...
+"
+        );
+        assert_eq!(
+            bytes_dump("register_income", &[1, 100, 0, 0, 0], &[]),
+            "# register_income, 5 bytes
+0000   | 01 64 00 00 00                                  | .d...
+"
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "the fields of register_income don't match its bytes")]
+    fn it_requires_fields_to_cover_the_bytes() {
+        bytes_dump("register_income", &[1, 100, 0, 0, 0], &[("tag", 1)]);
+    }
+
+    #[test]
+    fn it_diffs_layouts_by_field() {
+        let record = Record {
+            name: "John Doe".to_string(),
+            moves: 2,
+            total_balance: -750,
+        };
+        let dump = layout_dump(&record);
+        assert!(layout_diff(&dump, &dump).is_empty());
+
+        let moved = Record { moves: 3, ..record };
+        assert_eq!(
+            layout_diff(&dump, &layout_dump(&moved)),
+            ["~ moves at 000e: [0..1] 02 -> 03"]
+        );
+
+        // The end of the name split into a new field
+        let resized = dump.replace(
+            "0000  name          | 08 00 00 00 4a 6f 68 6e 20 44 6f 65 00 00",
+            "0000  name          | 08 00 00 00 4a 6f 68 6e 20 44 6f 65\n000c  flag          | 00 00",
+        );
+        assert_eq!(
+            layout_diff(&dump, &resized),
+            [
+                "~ name at 0000, 12 bytes instead of 14: 08 00 00 00 4a 6f 68 6e 20 44 6f 65 00 00 -> 08 00 00 00 4a 6f 68 6e 20 44 6f 65",
+                "+ flag at 000c: 00 00",
+            ]
+        );
+        assert_eq!(
+            layout_diff(&dump, &layout_dump(&Balance(-750))),
+            [
+                "~ Record, 24 bytes -> Balance, 8 bytes",
+                "+  at 0000: 12 fd ff ff ff ff ff ff",
+                "- name at 0000: 08 00 00 00 4a 6f 68 6e 20 44 6f 65 00 00",
+                "- moves at 000e: 02 00",
+                "- total_balance at 0010: 12 fd ff ff ff ff ff ff",
+            ]
+        );
+    }
+
+    #[test]
+    fn it_checks_layout_snapshots() {
+        let path = env::temp_dir().join(format!("{}.layout", Pubkey::new_unique()));
+        let record = Record {
+            name: "John Doe".to_string(),
+            moves: 2,
+            total_balance: -750,
+        };
+        assert!(check_snapshot(&path, &layout_dump(&record))
+            .unwrap_err()
+            .starts_with("no layout snapshot at"));
+
+        fs::write(&path, layout_dump(&record)).unwrap();
+        assert_eq!(check_snapshot(&path, &layout_dump(&record)), Ok(()));
+
+        let changed = Record {
+            name: record.name.clone(),
+            total_balance: 750,
+            ..record
+        };
+        let error = check_snapshot(&path, &layout_dump(&changed)).unwrap_err();
+        assert!(error.ends_with(
+            "\n  ~ total_balance at 0010: [0..8] 12 fd ff ff ff ff ff ff -> ee 02 00 00 00 00 00 00"
+        ), "{}", error);
+
+        // Same bytes with another spacing
+        fs::write(&path, layout_dump(&record).replace(" | ", "|")).unwrap();
+        let error = check_snapshot(&path, &layout_dump(&record)).unwrap_err();
+        assert!(error.ends_with("the dump is formatted differently"));
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,202 @@
+use crate::errors::SecurityError;
+use crate::rust_utils::*;
+use crate::sec_utils::*;
+use crate::testing::TestRuntime;
+use crate::token_utils::*;
+use solana_program::{
+    account_info::AccountInfo,
+    clock::Clock,
+    entrypoint::ProgramResult,
+    instruction::{AccountMeta, Instruction},
+    program::invoke_signed,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    rent::Rent,
+    system_instruction, system_program,
+    sysvar::Sysvar,
+};
+
+// The state and the program the tests of the testing modules run against
+
+#[derive(Debug, Default, PartialEq)]
+pub(super) struct Record {
+    pub(super) name: String,
+    pub(super) moves: u16,
+    pub(super) total_balance: i64,
+}
+
+impl State for Record {
+    const LEN: usize = 24;
+    const FIELDS: &'static [(&'static str, usize)] =
+        &[("name", 14), ("moves", 2), ("total_balance", 8)];
+
+    fn pack<'a>(&self, buf: &'a mut [u8]) -> &'a mut [u8] {
+        let buf = pack_string(buf, self.name.clone(), 14);
+        let buf = pack_u16(buf, self.moves);
+        pack_i64(buf, self.total_balance)
+    }
+
+    fn unpack(buf: &[u8]) -> (&[u8], Self) {
+        let (buf, name) = state_unpack_string(buf, 14);
+        let (buf, moves) = unpack_u16(buf);
+        let (buf, total_balance) = unpack_i64(buf);
+        (
+            buf,
+            Record {
+                name,
+                moves,
+                total_balance,
+            },
+        )
+    }
+}
+
+// 0 creates the record PDA of the payer, of the u32 length that follows the tag if any,
+// 1 writes the clock into it, 2 writes into it and fails, 3 writes into the account whether
+// it is writable or not, 4 updates the record of its signer with the checks of a generated
+// processor, 5 reads it without the owner check, 6 reallocates it to the u32 length that
+// follows the tag and 7 transfers the u64 amount that follows the tag out of the token account at
+// the vault PDA, its own authority
+pub(super) fn process_test_instruction(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    match data.first() {
+        Some(0) => {
+            let [record, payer, system_program] = accounts else {
+                return Err(ProgramError::NotEnoughAccountKeys);
+            };
+            verify_signers(&[payer])?;
+            if *system_program.key != system_program::id() {
+                return Err(ProgramError::IncorrectProgramId);
+            }
+            let (address, bump) =
+                Pubkey::find_program_address(&[b"record", payer.key.as_ref()], program_id);
+            if *record.key != address {
+                return Err(SecurityError::NotExpectedAddress.into());
+            }
+            // A Record unless the data gives another length
+            let len = match data.get(1..) {
+                Some(len) if !len.is_empty() => try_unpack_u32(len)?.1 as usize,
+                _ => Record::LEN,
+            };
+            let lamports = Rent::get()?.minimum_balance(len);
+
+            invoke_signed(
+                &system_instruction::create_account(
+                    payer.key, record.key, lamports, len as u64, program_id,
+                ),
+                &[payer.clone(), record.clone(), system_program.clone()],
+                &[&[b"record", payer.key.as_ref(), &[bump]]],
+            )
+        }
+        Some(1) => {
+            let clock = Clock::get()?;
+            pack_i64(&mut accounts[0].data.borrow_mut(), clock.unix_timestamp);
+            Ok(())
+        }
+        Some(2) => {
+            accounts[0].data.borrow_mut()[0] = 1;
+            Err(ProgramError::Custom(7))
+        }
+        Some(3) => {
+            accounts[0].data.borrow_mut()[0] = 1;
+            Ok(())
+        }
+        Some(tag @ (4 | 5)) => {
+            let [record, signer] = accounts else {
+                return Err(ProgramError::NotEnoughAccountKeys);
+            };
+            verify_signers(&[signer])?;
+            verify_pda(record, &[b"record", signer.key.as_ref()], program_id)?;
+            if *tag == 4 {
+                verify_ownership(program_id, &[record])?;
+            }
+            verify_len(Record::LEN, &[record])?;
+            if *tag == 4 {
+                record.data.borrow_mut()[0] = 1;
+            }
+            Ok(())
+        }
+        Some(6) => {
+            let (_, len) = try_unpack_u32(&data[1..])?;
+            accounts[0].realloc(len as usize, true)?;
+            accounts[0].data.borrow_mut()[len as usize - 1] = 1;
+            Ok(())
+        }
+        Some(7) => {
+            let [vault, destination, token_program] = accounts else {
+                return Err(ProgramError::NotEnoughAccountKeys);
+            };
+            let (_, amount) = try_unpack_u64(&data[1..])?;
+            let (_, bump) = Pubkey::find_program_address(&[b"vault"], program_id);
+            transfer_tokens(
+                token_program,
+                vault,
+                destination,
+                vault,
+                amount,
+                &[&[b"vault", &[bump]]],
+            )
+        }
+        _ => Err(ProgramError::InvalidInstructionData),
+    }
+}
+
+pub(super) fn runtime() -> TestRuntime {
+    TestRuntime::new(Pubkey::new_unique(), process_test_instruction)
+}
+
+pub(super) fn record_address(runtime: &TestRuntime, payer: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"record", payer.as_ref()], &runtime.program_id).0
+}
+
+pub(super) fn create_record(runtime: &TestRuntime, payer: &Pubkey) -> Instruction {
+    Instruction::new_with_bytes(
+        runtime.program_id,
+        &[0],
+        vec![
+            AccountMeta::new(record_address(runtime, payer), false),
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+    )
+}
+
+pub(super) fn on_record(
+    runtime: &TestRuntime,
+    payer: &Pubkey,
+    tag: u8,
+    writable: bool,
+) -> Instruction {
+    let record = record_address(runtime, payer);
+    let meta = if writable {
+        AccountMeta::new(record, false)
+    } else {
+        AccountMeta::new_readonly(record, false)
+    };
+    Instruction::new_with_bytes(runtime.program_id, &[tag], vec![meta])
+}
+
+pub(super) fn vault_address(runtime: &TestRuntime) -> Pubkey {
+    Pubkey::find_program_address(&[b"vault"], &runtime.program_id).0
+}
+
+pub(super) fn transfer_from_vault(
+    runtime: &TestRuntime,
+    destination: &Pubkey,
+    amount: u64,
+) -> Instruction {
+    let mut data = vec![7];
+    data.extend_from_slice(&amount.to_le_bytes());
+    Instruction::new_with_bytes(
+        runtime.program_id,
+        &data,
+        vec![
+            AccountMeta::new(vault_address(runtime), false),
+            AccountMeta::new(*destination, false),
+            AccountMeta::new_readonly(spl_token_program::id(), false),
+        ],
+    )
+}
@@ -0,0 +1,267 @@
+use crate::rust_utils::*;
+use crate::token_utils::*;
+use solana_program::{
+    account_info::AccountInfo, entrypoint::ProgramResult, program_error::ProgramError,
+    pubkey::Pubkey, rent::Rent,
+};
+
+// Errors of the SPL token program, returned as custom program errors with these codes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenError {
+    NotRentExempt = 0,
+    InsufficientFunds = 1,
+    InvalidMint = 2,
+    MintMismatch = 3,
+    OwnerMismatch = 4,
+    AlreadyInUse = 6,
+    Overflow = 14,
+}
+
+impl From<TokenError> for ProgramError {
+    fn from(e: TokenError) -> Self {
+        ProgramError::Custom(e as u32)
+    }
+}
+
+const TRANSFER: u8 = 3;
+const INITIALIZE_ACCOUNT3: u8 = 18;
+
+const TOKEN_ACCOUNT_AMOUNT_OFFSET: usize = 64;
+const TOKEN_ACCOUNT_INITIALIZED: u8 = 1;
+
+// The token instructions of token_utils, Transfer and InitializeAccount3, with the checks of the
+// token program. Delegates, frozen accounts and native SOL accounts are not supported
+pub(super) fn process_token_instruction(
+    accounts: &[AccountInfo],
+    data: &[u8],
+    rent: &Rent,
+) -> ProgramResult {
+    let (tag, buf) = data
+        .split_first()
+        .ok_or(ProgramError::InvalidInstructionData)?;
+
+    match (*tag, accounts) {
+        (TRANSFER, [source, destination, authority, ..]) => {
+            let (_, amount) = try_unpack_u64(buf)?;
+            transfer(source, destination, authority, amount)
+        }
+        (INITIALIZE_ACCOUNT3, [account, mint, ..]) => {
+            let (_, owner) = try_unpack_pubkey(buf)?;
+            initialize_account(account, mint, &owner, rent)
+        }
+        (TRANSFER | INITIALIZE_ACCOUNT3, _) => Err(ProgramError::NotEnoughAccountKeys),
+        _ => Err(ProgramError::InvalidInstructionData),
+    }
+}
+
+fn initialize_account(
+    account: &AccountInfo,
+    mint: &AccountInfo,
+    owner: &Pubkey,
+    rent: &Rent,
+) -> ProgramResult {
+    if *account.owner != spl_token_program::id() {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    if account.data_len() != TOKEN_ACCOUNT_LEN {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    if account.data.borrow()[TOKEN_ACCOUNT_STATE_OFFSET] != 0 {
+        return Err(TokenError::AlreadyInUse.into());
+    }
+    if !rent.is_exempt(account.lamports(), TOKEN_ACCOUNT_LEN) {
+        return Err(TokenError::NotRentExempt.into());
+    }
+    let is_mint = *mint.owner == spl_token_program::id()
+        && mint.data_len() == MINT_LEN
+        && mint.data.borrow()[MINT_IS_INITIALIZED_OFFSET] != 0;
+    if !is_mint {
+        return Err(TokenError::InvalidMint.into());
+    }
+
+    let mut data = account.data.borrow_mut();
+    let dst = pack_pubkey(&mut data, *mint.key);
+    pack_pubkey(dst, *owner);
+    data[TOKEN_ACCOUNT_STATE_OFFSET] = TOKEN_ACCOUNT_INITIALIZED;
+
+    Ok(())
+}
+
+fn transfer(
+    source: &AccountInfo,
+    destination: &AccountInfo,
+    authority: &AccountInfo,
+    amount: u64,
+) -> ProgramResult {
+    let from = token_account(source)?;
+    let to = token_account(destination)?;
+    if from.mint != to.mint {
+        return Err(TokenError::MintMismatch.into());
+    }
+    if from.owner != *authority.key {
+        return Err(TokenError::OwnerMismatch.into());
+    }
+    if !authority.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if from.amount < amount {
+        return Err(TokenError::InsufficientFunds.into());
+    }
+    // The token program accepts a transfer to the source itself, nothing moves
+    if source.key == destination.key {
+        return Ok(());
+    }
+    let to_amount = to.amount.checked_add(amount).ok_or(TokenError::Overflow)?;
+
+    set_amount(source, from.amount - amount);
+    set_amount(destination, to_amount);
+
+    Ok(())
+}
+
+fn token_account(info: &AccountInfo) -> Result<TokenAccount, ProgramError> {
+    if *info.owner != spl_token_program::id() {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let data = info.data.borrow();
+    if data.len() != TOKEN_ACCOUNT_LEN {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    if data[TOKEN_ACCOUNT_STATE_OFFSET] == 0 {
+        return Err(ProgramError::UninitializedAccount);
+    }
+
+    let (_, token_account) = unpack_token_account(&data);
+    Ok(token_account)
+}
+
+fn set_amount(info: &AccountInfo, amount: u64) {
+    let mut data = info.data.borrow_mut();
+    pack_u64(&mut data[TOKEN_ACCOUNT_AMOUNT_OFFSET..], amount);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TestAccount;
+
+    fn process(accounts: &mut [TestAccount], data: &[u8]) -> ProgramResult {
+        let infos: Vec<_> = accounts.iter_mut().map(TestAccount::info).collect();
+        process_token_instruction(&infos, data, &Rent::default())
+    }
+
+    fn amount(account: &TestAccount) -> u64 {
+        unpack_token_account(&account.data).1.amount
+    }
+
+    #[test]
+    fn it_transfers_tokens_signed_by_the_owner() {
+        let mint = Pubkey::new_unique();
+        let mut owner = TestAccount::new().signer();
+        let source = TestAccount::new().token_account(&mint, &owner.key, 100);
+        let destination = TestAccount::new().token_account(&mint, &Pubkey::new_unique(), 5);
+        let transfer = |amount| {
+            token_transfer_instruction(
+                &spl_token_program::id(),
+                &source.key,
+                &destination.key,
+                &owner.key,
+                amount,
+            )
+            .data
+        };
+        let (data, too_much) = (transfer(40), transfer(61));
+        let mut accounts = [source.clone(), destination.clone(), owner.clone()];
+
+        assert_eq!(process(&mut accounts, &data), Ok(()));
+        assert_eq!((amount(&accounts[0]), amount(&accounts[1])), (60, 45));
+        assert_eq!(
+            process(&mut accounts, &too_much),
+            Err(TokenError::InsufficientFunds.into())
+        );
+
+        owner.is_signer = false;
+        let mut accounts = [source.clone(), destination.clone(), owner];
+        assert_eq!(
+            process(&mut accounts, &data),
+            Err(ProgramError::MissingRequiredSignature)
+        );
+
+        let stranger = TestAccount::new().signer();
+        let mut accounts = [source, destination, stranger];
+        assert_eq!(
+            process(&mut accounts, &data),
+            Err(TokenError::OwnerMismatch.into())
+        );
+        assert_eq!((amount(&accounts[0]), amount(&accounts[1])), (100, 5));
+    }
+
+    #[test]
+    fn it_only_transfers_between_accounts_of_the_same_mint() {
+        let owner = TestAccount::new().signer();
+        let source = TestAccount::new().token_account(&Pubkey::new_unique(), &owner.key, 100);
+        let destination =
+            TestAccount::new().token_account(&Pubkey::new_unique(), &Pubkey::new_unique(), 0);
+        let data = token_transfer_instruction(
+            &spl_token_program::id(),
+            &source.key,
+            &destination.key,
+            &owner.key,
+            1,
+        )
+        .data;
+
+        let mut accounts = [source.clone(), destination.clone(), owner.clone()];
+        assert_eq!(
+            process(&mut accounts, &data),
+            Err(TokenError::MintMismatch.into())
+        );
+
+        let mut accounts = [source.clone(), destination.zeroed(TOKEN_ACCOUNT_LEN), owner];
+        assert_eq!(
+            process(&mut accounts, &data),
+            Err(ProgramError::UninitializedAccount)
+        );
+    }
+
+    #[test]
+    fn it_initializes_token_accounts() {
+        let mint = TestAccount::new().mint();
+        let owner = Pubkey::new_unique();
+        let account = TestAccount::new()
+            .owner(spl_token_program::id())
+            .zeroed(TOKEN_ACCOUNT_LEN)
+            .rent_exempt()
+            .writable();
+        let data = initialize_token_account_instruction(
+            &spl_token_program::id(),
+            &account.key,
+            &mint.key,
+            &owner,
+        )
+        .data;
+
+        let mut accounts = [account.clone(), mint.clone()];
+        assert_eq!(process(&mut accounts, &data), Ok(()));
+        assert_eq!(
+            accounts[0].data,
+            TestAccount::new().token_account(&mint.key, &owner, 0).data
+        );
+        assert_eq!(
+            process(&mut accounts, &data),
+            Err(TokenError::AlreadyInUse.into())
+        );
+
+        let mut accounts = [account.clone().lamports(1), mint.clone()];
+        assert_eq!(
+            process(&mut accounts, &data),
+            Err(TokenError::NotRentExempt.into())
+        );
+
+        let mut accounts = [account, mint.zeroed(MINT_LEN)];
+        assert_eq!(
+            process(&mut accounts, &data),
+            Err(TokenError::InvalidMint.into())
+        );
+    }
+}
@@ -0,0 +1,225 @@
+use crate::rust_utils::*;
+use solana_program::{
+    account_info::AccountInfo,
+    entrypoint::ProgramResult,
+    instruction::{AccountMeta, Instruction},
+    program::{invoke, invoke_signed},
+    pubkey::Pubkey,
+};
+
+pub mod spl_token_program {
+    solana_program::declare_id!("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA");
+}
+
+// Packed sizes of the SPL token program accounts
+pub const TOKEN_ACCOUNT_LEN: usize = 165;
+pub const MINT_LEN: usize = 82;
+
+// Offsets of the initialization flags, 0 means the account was never initialized
+pub const TOKEN_ACCOUNT_STATE_OFFSET: usize = 108;
+pub const MINT_IS_INITIALIZED_OFFSET: usize = 45;
+
+const TOKEN_TRANSFER_INSTRUCTION: u8 = 3;
+const TOKEN_INITIALIZE_ACCOUNT3_INSTRUCTION: u8 = 18;
+
+// A token account at a PDA of the program that is its own authority, the transfers out of it are
+// signed with `signer_seeds`, its seeds followed by the bump
+pub struct TokenAccountPDA<'a, 'b> {
+    pub info: &'a AccountInfo<'b>,
+    pub signer_seeds: &'a [&'a [u8]],
+}
+
+impl<'a, 'b> TokenAccountPDA<'a, 'b> {
+    pub fn new(info: &'a AccountInfo<'b>, signer_seeds: &'a [&'a [u8]]) -> Self {
+        TokenAccountPDA { info, signer_seeds }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct TokenAccount {
+    pub mint: Pubkey,
+    pub owner: Pubkey,
+    pub amount: u64,
+}
+
+// Only the leading mint/owner/amount fields are decoded, the pointer moves the whole account
+pub fn unpack_token_account(buf: &[u8]) -> (&[u8], TokenAccount) {
+    let (dst, mint) = unpack_pubkey(buf);
+    let (dst, owner) = unpack_pubkey(dst);
+    let (_, amount) = unpack_u64(dst);
+    (
+        &buf[TOKEN_ACCOUNT_LEN..],
+        TokenAccount {
+            mint,
+            owner,
+            amount,
+        },
+    )
+}
+
+pub fn token_transfer_instruction(
+    token_program_id: &Pubkey,
+    source: &Pubkey,
+    destination: &Pubkey,
+    authority: &Pubkey,
+    amount: u64,
+) -> Instruction {
+    let mut data = vec![0u8; 9];
+    let dst = pack_u8(&mut data, TOKEN_TRANSFER_INSTRUCTION);
+    pack_u64(dst, amount);
+
+    Instruction {
+        program_id: *token_program_id,
+        accounts: vec![
+            AccountMeta::new(*source, false),
+            AccountMeta::new(*destination, false),
+            AccountMeta::new_readonly(*authority, true),
+        ],
+        data,
+    }
+}
+
+// InitializeAccount3, the owner is passed in the data so the instruction needs no rent sysvar
+pub fn initialize_token_account_instruction(
+    token_program_id: &Pubkey,
+    account: &Pubkey,
+    mint: &Pubkey,
+    owner: &Pubkey,
+) -> Instruction {
+    let mut data = vec![0u8; 33];
+    let dst = pack_u8(&mut data, TOKEN_INITIALIZE_ACCOUNT3_INSTRUCTION);
+    pack_pubkey(dst, *owner);
+
+    Instruction {
+        program_id: *token_program_id,
+        accounts: vec![
+            AccountMeta::new(*account, false),
+            AccountMeta::new_readonly(*mint, false),
+        ],
+        data,
+    }
+}
+
+// Turns `account`, created with TOKEN_ACCOUNT_LEN bytes for the token program, into an empty token
+// account of `mint` whose tokens only `owner` can move
+pub fn initialize_token_account<'a>(
+    token_program: &AccountInfo<'a>,
+    account: &AccountInfo<'a>,
+    mint: &AccountInfo<'a>,
+    owner: &Pubkey,
+) -> ProgramResult {
+    let instruction =
+        initialize_token_account_instruction(token_program.key, account.key, mint.key, owner);
+
+    invoke(
+        &instruction,
+        &[account.clone(), mint.clone(), token_program.clone()],
+    )
+}
+
+// Moves `amount` tokens through a CPI to the token program. Pass the vault seeds (with bump) as
+// `signer_seeds` when the authority is a PDA owned by the calling program, or `&[]` otherwise.
+pub fn transfer_tokens<'a>(
+    token_program: &AccountInfo<'a>,
+    source: &AccountInfo<'a>,
+    destination: &AccountInfo<'a>,
+    authority: &AccountInfo<'a>,
+    amount: u64,
+    signer_seeds: &[&[&[u8]]],
+) -> ProgramResult {
+    let instruction = token_transfer_instruction(
+        token_program.key,
+        source.key,
+        destination.key,
+        authority.key,
+        amount,
+    );
+
+    invoke_signed(
+        &instruction,
+        &[
+            source.clone(),
+            destination.clone(),
+            authority.clone(),
+            token_program.clone(),
+        ],
+        signer_seeds,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_unpack_token_account() {
+        let mint = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+        let big: &mut [u8] = &mut [0; TOKEN_ACCOUNT_LEN + 10];
+
+        let dst = pack_pubkey(big, mint);
+        let dst = pack_pubkey(dst, owner);
+        pack_u64(dst, 1_000);
+
+        let (ptr, token_account) = unpack_token_account(big);
+        assert_eq!(
+            token_account,
+            TokenAccount {
+                mint,
+                owner,
+                amount: 1_000
+            }
+        );
+        assert_eq!(ptr.len(), 10);
+    }
+
+    #[test]
+    fn it_builds_token_transfer_instruction() {
+        let source = Pubkey::new_unique();
+        let destination = Pubkey::new_unique();
+        let authority = Pubkey::new_unique();
+
+        let instruction = token_transfer_instruction(
+            &spl_token_program::id(),
+            &source,
+            &destination,
+            &authority,
+            258,
+        );
+
+        assert_eq!(instruction.program_id, spl_token_program::id());
+        assert_eq!(instruction.data, [3, 2, 1, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(
+            instruction.accounts,
+            vec![
+                AccountMeta::new(source, false),
+                AccountMeta::new(destination, false),
+                AccountMeta::new_readonly(authority, true),
+            ]
+        );
+    }
+
+    #[test]
+    fn it_builds_initialize_token_account_instruction() {
+        let account = Pubkey::new_unique();
+        let mint = Pubkey::new_unique();
+
+        let instruction = initialize_token_account_instruction(
+            &spl_token_program::id(),
+            &account,
+            &mint,
+            &account,
+        );
+
+        assert_eq!(instruction.program_id, spl_token_program::id());
+        assert_eq!(instruction.data[0], 18);
+        assert_eq!(instruction.data[1..], account.to_bytes());
+        assert_eq!(
+            instruction.accounts,
+            vec![
+                AccountMeta::new(account, false),
+                AccountMeta::new_readonly(mint, false),
+            ]
+        );
+    }
+}
@@ -0,0 +1,764 @@
+use codigolib::rust_utils::*;
+use serde_json::{json, Map, Value as Json};
+use solana_program::pubkey::Pubkey;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+// Golden vectors of the wire format, shared with the TypeScript SDK (sdk/test/vectors.ts). Each
+// vector holds a type descriptor, a value and the bytes of that value in the account or the
+// instruction layout. Run with UPDATE_GOLDEN=1 to rewrite tests/vectors after an intended change
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    enum Layout {
+        State,
+        Instruction,
+    }
+
+    // Descriptor of a type, written to the vectors as a string for primitives and as an object
+    // with a `kind` otherwise
+    #[derive(Debug, Clone, PartialEq)]
+    enum Ty {
+        Bool,
+        U8,
+        I8,
+        U16,
+        I16,
+        U32,
+        I32,
+        U64,
+        I64,
+        U128,
+        I128,
+        F32,
+        F64,
+        Pubkey,
+        String {
+            cap: usize,
+        },
+        Option(Box<Ty>),
+        Vec {
+            cap: usize,
+            elem: Box<Ty>,
+        },
+        Struct {
+            name: String,
+            fields: Vec<(String, Ty)>,
+        },
+    }
+
+    #[derive(Debug, Clone, PartialEq)]
+    enum Val {
+        Bool(bool),
+        U8(u8),
+        I8(i8),
+        U16(u16),
+        I16(i16),
+        U32(u32),
+        I32(i32),
+        U64(u64),
+        I64(i64),
+        U128(u128),
+        I128(i128),
+        F32(f32),
+        F64(f64),
+        Pubkey(Pubkey),
+        String(String),
+        Option(Option<Box<Val>>),
+        Vec(Vec<Val>),
+        Struct(Vec<(String, Val)>),
+    }
+
+    struct Vector {
+        name: &'static str,
+        layout: Layout,
+        ty: Ty,
+        value: Val,
+    }
+
+    const PRIMITIVES: [(&str, Ty); 14] = [
+        ("bool", Ty::Bool),
+        ("u8", Ty::U8),
+        ("i8", Ty::I8),
+        ("u16", Ty::U16),
+        ("i16", Ty::I16),
+        ("u32", Ty::U32),
+        ("i32", Ty::I32),
+        ("u64", Ty::U64),
+        ("i64", Ty::I64),
+        ("u128", Ty::U128),
+        ("i128", Ty::I128),
+        ("f32", Ty::F32),
+        ("f64", Ty::F64),
+        ("pubkey", Ty::Pubkey),
+    ];
+
+    // ==== CODEC =======
+
+    fn state_size(ty: &Ty) -> usize {
+        match ty {
+            Ty::Bool | Ty::U8 | Ty::I8 => 1,
+            Ty::U16 | Ty::I16 => 2,
+            Ty::U32 | Ty::I32 | Ty::F32 => 4,
+            Ty::U64 | Ty::I64 | Ty::F64 => 8,
+            Ty::U128 | Ty::I128 => 16,
+            Ty::Pubkey => 32,
+            Ty::String { cap } => 4 + cap,
+            Ty::Option(inner) => 1 + state_size(inner),
+            Ty::Vec { cap, elem } => 4 + cap * state_size(elem),
+            Ty::Struct { fields, .. } => fields.iter().map(|(_, ty)| state_size(ty)).sum(),
+        }
+    }
+
+    fn null_value(ty: &Ty) -> Val {
+        match ty {
+            Ty::Bool => Val::Bool(false),
+            Ty::U8 => Val::U8(0),
+            Ty::I8 => Val::I8(0),
+            Ty::U16 => Val::U16(0),
+            Ty::I16 => Val::I16(0),
+            Ty::U32 => Val::U32(0),
+            Ty::I32 => Val::I32(0),
+            Ty::U64 => Val::U64(0),
+            Ty::I64 => Val::I64(0),
+            Ty::U128 => Val::U128(0),
+            Ty::I128 => Val::I128(0),
+            Ty::F32 => Val::F32(0.0),
+            Ty::F64 => Val::F64(0.0),
+            Ty::Pubkey => Val::Pubkey(Pubkey::default()),
+            Ty::String { .. } => Val::String(String::new()),
+            Ty::Option(_) => Val::Option(None),
+            Ty::Vec { .. } => Val::Vec(Vec::new()),
+            Ty::Struct { fields, .. } => Val::Struct(
+                fields
+                    .iter()
+                    .map(|(name, ty)| (name.clone(), null_value(ty)))
+                    .collect(),
+            ),
+        }
+    }
+
+    // Struct fields and vector elements always use the account layout, as generated code does
+    fn pack<'a>(buf: &'a mut [u8], ty: &Ty, value: &Val, layout: Layout) -> &'a mut [u8] {
+        match (ty, value) {
+            (Ty::String { cap }, Val::String(s)) => match layout {
+                Layout::State => pack_string(buf, s.clone(), 4 + cap),
+                Layout::Instruction => pack_string(buf, s.clone(), 4 + s.len()),
+            },
+            (Ty::Option(inner), Val::Option(v)) => match layout {
+                Layout::State => pack_option(
+                    buf,
+                    |buf, v: Val| pack(buf, inner, &v, layout),
+                    v.as_deref().cloned(),
+                    null_value(inner),
+                ),
+                Layout::Instruction => match v {
+                    Some(v) => pack(pack_bool(buf, true), inner, v, layout),
+                    None => pack_bool(buf, false),
+                },
+            },
+            (Ty::Vec { cap, elem }, Val::Vec(items)) => {
+                let cap = match layout {
+                    Layout::State => *cap,
+                    Layout::Instruction => items.len(),
+                };
+                pack_vec(
+                    buf,
+                    cap,
+                    state_size(elem),
+                    |buf, v: Val| pack(buf, elem, &v, Layout::State),
+                    items.clone(),
+                )
+            }
+            (Ty::Struct { fields, .. }, Val::Struct(values)) => {
+                let mut buf = buf;
+                for ((_, ty), (_, value)) in fields.iter().zip(values) {
+                    buf = pack(buf, ty, value, Layout::State);
+                }
+                buf
+            }
+            (_, Val::Bool(v)) => pack_bool(buf, *v),
+            (_, Val::U8(v)) => pack_u8(buf, *v),
+            (_, Val::I8(v)) => pack_i8(buf, *v),
+            (_, Val::U16(v)) => pack_u16(buf, *v),
+            (_, Val::I16(v)) => pack_i16(buf, *v),
+            (_, Val::U32(v)) => pack_u32(buf, *v),
+            (_, Val::I32(v)) => pack_i32(buf, *v),
+            (_, Val::U64(v)) => pack_u64(buf, *v),
+            (_, Val::I64(v)) => pack_i64(buf, *v),
+            (_, Val::U128(v)) => pack_u128(buf, *v),
+            (_, Val::I128(v)) => pack_i128(buf, *v),
+            (_, Val::F32(v)) => pack_f32(buf, *v),
+            (_, Val::F64(v)) => pack_f64(buf, *v),
+            (_, Val::Pubkey(v)) => pack_pubkey(buf, *v),
+            _ => panic!("{:?} is not a value of {:?}", value, ty),
+        }
+    }
+
+    fn unpack<'a>(buf: &'a [u8], ty: &Ty, layout: Layout) -> (&'a [u8], Val) {
+        match ty {
+            Ty::Bool => map(unpack_bool(buf), Val::Bool),
+            Ty::U8 => map(unpack_u8(buf), Val::U8),
+            Ty::I8 => map(unpack_i8(buf), Val::I8),
+            Ty::U16 => map(unpack_u16(buf), Val::U16),
+            Ty::I16 => map(unpack_i16(buf), Val::I16),
+            Ty::U32 => map(unpack_u32(buf), Val::U32),
+            Ty::I32 => map(unpack_i32(buf), Val::I32),
+            Ty::U64 => map(unpack_u64(buf), Val::U64),
+            Ty::I64 => map(unpack_i64(buf), Val::I64),
+            Ty::U128 => map(unpack_u128(buf), Val::U128),
+            Ty::I128 => map(unpack_i128(buf), Val::I128),
+            Ty::F32 => map(unpack_f32(buf), Val::F32),
+            Ty::F64 => map(unpack_f64(buf), Val::F64),
+            Ty::Pubkey => map(unpack_pubkey(buf), Val::Pubkey),
+            Ty::String { cap } => match layout {
+                Layout::State => map(state_unpack_string(buf, 4 + cap), Val::String),
+                Layout::Instruction => map(instructions_unpack_string(buf), Val::String),
+            },
+            Ty::Option(inner) => {
+                let unpacked = match layout {
+                    Layout::State => state_unpack_option(
+                        buf,
+                        |buf| unpack(buf, inner, layout),
+                        state_size(inner),
+                    ),
+                    Layout::Instruction => {
+                        instructions_unpack_option(buf, |buf| unpack(buf, inner, layout))
+                    }
+                };
+                map(unpacked, |v| Val::Option(v.map(Box::new)))
+            }
+            Ty::Vec { cap, elem } => {
+                let size = state_size(elem);
+                let unpacked = match layout {
+                    Layout::State => {
+                        state_unpack_vec(buf, *cap, size, |buf| unpack(buf, elem, Layout::State))
+                    }
+                    Layout::Instruction => {
+                        instructions_unpack_vec(buf, size, |buf| unpack(buf, elem, Layout::State))
+                    }
+                };
+                map(unpacked, Val::Vec)
+            }
+            Ty::Struct { fields, .. } => {
+                let mut buf = buf;
+                let mut values = Vec::new();
+                for (name, ty) in fields {
+                    let (rest, value) = unpack(buf, ty, Layout::State);
+                    values.push((name.clone(), value));
+                    buf = rest;
+                }
+                (buf, Val::Struct(values))
+            }
+        }
+    }
+
+    fn map<T>((buf, value): (&[u8], T), f: impl Fn(T) -> Val) -> (&[u8], Val) {
+        (buf, f(value))
+    }
+
+    fn encode(ty: &Ty, value: &Val, layout: Layout) -> Vec<u8> {
+        let mut buf = vec![0; 1024];
+        let rest = pack(&mut buf, ty, value, layout).len();
+        buf.truncate(1024 - rest);
+        buf
+    }
+
+    // ==== JSON =======
+
+    fn ty_to_json(ty: &Ty) -> Json {
+        if let Some((name, _)) = PRIMITIVES.iter().find(|(_, primitive)| primitive == ty) {
+            return json!(name);
+        }
+        match ty {
+            Ty::String { cap } => json!({ "kind": "string", "cap": cap }),
+            Ty::Option(inner) => json!({ "kind": "option", "inner": ty_to_json(inner) }),
+            Ty::Vec { cap, elem } => {
+                json!({ "kind": "vec", "cap": cap, "elem": ty_to_json(elem) })
+            }
+            Ty::Struct { name, fields } => {
+                let fields: Vec<_> = fields
+                    .iter()
+                    .map(|(name, ty)| json!({ "name": name, "type": ty_to_json(ty) }))
+                    .collect();
+                json!({ "kind": "struct", "name": name, "fields": fields })
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    fn ty_from_json(json: &Json) -> Ty {
+        if let Some(name) = json.as_str() {
+            let (_, ty) = PRIMITIVES
+                .iter()
+                .find(|(primitive, _)| *primitive == name)
+                .unwrap_or_else(|| panic!("unknown type `{}`", name));
+            return ty.clone();
+        }
+        let cap = || json["cap"].as_u64().unwrap() as usize;
+        match json["kind"].as_str().unwrap() {
+            "string" => Ty::String { cap: cap() },
+            "option" => Ty::Option(Box::new(ty_from_json(&json["inner"]))),
+            "vec" => Ty::Vec {
+                cap: cap(),
+                elem: Box::new(ty_from_json(&json["elem"])),
+            },
+            "struct" => Ty::Struct {
+                name: json["name"].as_str().unwrap().to_string(),
+                fields: json["fields"]
+                    .as_array()
+                    .unwrap()
+                    .iter()
+                    .map(|field| {
+                        let name = field["name"].as_str().unwrap().to_string();
+                        (name, ty_from_json(&field["type"]))
+                    })
+                    .collect(),
+            },
+            other => panic!("unknown kind `{}`", other),
+        }
+    }
+
+    // 64 and 128 bit integers are decimal strings, JSON numbers lose precision past 2^53.
+    // Public keys are base58 strings
+    fn val_to_json(value: &Val) -> Json {
+        match value {
+            Val::Bool(v) => json!(v),
+            Val::U8(v) => json!(v),
+            Val::I8(v) => json!(v),
+            Val::U16(v) => json!(v),
+            Val::I16(v) => json!(v),
+            Val::U32(v) => json!(v),
+            Val::I32(v) => json!(v),
+            Val::U64(v) => json!(v.to_string()),
+            Val::I64(v) => json!(v.to_string()),
+            Val::U128(v) => json!(v.to_string()),
+            Val::I128(v) => json!(v.to_string()),
+            Val::F32(v) => json!(v),
+            Val::F64(v) => json!(v),
+            Val::Pubkey(v) => json!(v.to_string()),
+            Val::String(v) => json!(v),
+            Val::Option(v) => v.as_deref().map_or(Json::Null, val_to_json),
+            Val::Vec(items) => Json::Array(items.iter().map(val_to_json).collect()),
+            Val::Struct(fields) => Json::Object(
+                fields
+                    .iter()
+                    .map(|(name, value)| (name.clone(), val_to_json(value)))
+                    .collect::<Map<_, _>>(),
+            ),
+        }
+    }
+
+    fn val_from_json(ty: &Ty, json: &Json) -> Val {
+        let int = || json.as_i64().unwrap();
+        let big = || json.as_str().unwrap();
+        match ty {
+            Ty::Bool => Val::Bool(json.as_bool().unwrap()),
+            Ty::U8 => Val::U8(int() as u8),
+            Ty::I8 => Val::I8(int() as i8),
+            Ty::U16 => Val::U16(int() as u16),
+            Ty::I16 => Val::I16(int() as i16),
+            Ty::U32 => Val::U32(int() as u32),
+            Ty::I32 => Val::I32(int() as i32),
+            Ty::U64 => Val::U64(big().parse().unwrap()),
+            Ty::I64 => Val::I64(big().parse().unwrap()),
+            Ty::U128 => Val::U128(big().parse().unwrap()),
+            Ty::I128 => Val::I128(big().parse().unwrap()),
+            Ty::F32 => Val::F32(json.as_f64().unwrap() as f32),
+            Ty::F64 => Val::F64(json.as_f64().unwrap()),
+            Ty::Pubkey => Val::Pubkey(Pubkey::from_str(big()).unwrap()),
+            Ty::String { .. } => Val::String(big().to_string()),
+            Ty::Option(inner) => Val::Option(match json {
+                Json::Null => None,
+                json => Some(Box::new(val_from_json(inner, json))),
+            }),
+            Ty::Vec { elem, .. } => Val::Vec(
+                json.as_array()
+                    .unwrap()
+                    .iter()
+                    .map(|item| val_from_json(elem, item))
+                    .collect(),
+            ),
+            Ty::Struct { fields, .. } => Val::Struct(
+                fields
+                    .iter()
+                    .map(|(name, ty)| (name.clone(), val_from_json(ty, &json[name])))
+                    .collect(),
+            ),
+        }
+    }
+
+    fn to_hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+    }
+
+    fn from_hex(hex: &str) -> Vec<u8> {
+        (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+            .collect()
+    }
+
+    // ==== VECTORS =======
+
+    fn vector(name: &'static str, layout: Layout, ty: Ty, value: Val) -> Vector {
+        Vector {
+            name,
+            layout,
+            ty,
+            value,
+        }
+    }
+
+    fn some(value: Val) -> Val {
+        Val::Option(Some(Box::new(value)))
+    }
+
+    fn option(ty: Ty) -> Ty {
+        Ty::Option(Box::new(ty))
+    }
+
+    fn vec_of(cap: usize, elem: Ty) -> Ty {
+        Ty::Vec {
+            cap,
+            elem: Box::new(elem),
+        }
+    }
+
+    fn string(value: &str) -> Val {
+        Val::String(value.to_string())
+    }
+
+    fn key() -> Pubkey {
+        let bytes: Vec<u8> = (1..=32).collect();
+        Pubkey::new_from_array(bytes.try_into().unwrap())
+    }
+
+    // Fixed size values take the same bytes in both layouts
+    fn primitives() -> Vec<Vector> {
+        use Layout::State;
+        vec![
+            vector("bool_false", State, Ty::Bool, Val::Bool(false)),
+            vector("bool_true", State, Ty::Bool, Val::Bool(true)),
+            vector("u8_max", State, Ty::U8, Val::U8(u8::MAX)),
+            vector("i8_min", State, Ty::I8, Val::I8(i8::MIN)),
+            vector("i8_negative", State, Ty::I8, Val::I8(-2)),
+            vector("u16", State, Ty::U16, Val::U16(513)),
+            vector("u16_max", State, Ty::U16, Val::U16(u16::MAX)),
+            vector("i16_min", State, Ty::I16, Val::I16(i16::MIN)),
+            vector("u32", State, Ty::U32, Val::U32(1000)),
+            vector("u32_max", State, Ty::U32, Val::U32(u32::MAX)),
+            vector("i32_negative", State, Ty::I32, Val::I32(-750)),
+            vector("i32_min", State, Ty::I32, Val::I32(i32::MIN)),
+            vector("u64_max", State, Ty::U64, Val::U64(u64::MAX)),
+            vector("u64_past_2_53", State, Ty::U64, Val::U64((1 << 53) + 1)),
+            vector("i64_negative", State, Ty::I64, Val::I64(-750)),
+            vector("i64_min", State, Ty::I64, Val::I64(i64::MIN)),
+            vector("u128_max", State, Ty::U128, Val::U128(u128::MAX)),
+            vector("u128_high_half", State, Ty::U128, Val::U128(1 << 100)),
+            vector("i128_minus_one", State, Ty::I128, Val::I128(-1)),
+            vector("i128_min", State, Ty::I128, Val::I128(i128::MIN)),
+            vector("f32", State, Ty::F32, Val::F32(1.5)),
+            vector("f32_negative", State, Ty::F32, Val::F32(-0.25)),
+            vector("f64", State, Ty::F64, Val::F64(std::f64::consts::PI)),
+            vector("f64_negative_zero", State, Ty::F64, Val::F64(-0.0)),
+            vector(
+                "pubkey_default",
+                State,
+                Ty::Pubkey,
+                Val::Pubkey(Pubkey::default()),
+            ),
+            vector("pubkey", State, Ty::Pubkey, Val::Pubkey(key())),
+        ]
+    }
+
+    fn strings() -> Vec<Vector> {
+        use Layout::{Instruction, State};
+        vec![
+            vector("state", State, Ty::String { cap: 10 }, string("Lorem")),
+            vector("state_empty", State, Ty::String { cap: 4 }, string("")),
+            vector("state_at_cap", State, Ty::String { cap: 4 }, string("four")),
+            // The cap counts bytes, not characters
+            vector("state_utf8", State, Ty::String { cap: 6 }, string("José")),
+            vector(
+                "instruction",
+                Instruction,
+                Ty::String { cap: 10 },
+                string("Lorem"),
+            ),
+            vector(
+                "instruction_empty",
+                Instruction,
+                Ty::String { cap: 10 },
+                string(""),
+            ),
+            vector(
+                "instruction_utf8",
+                Instruction,
+                Ty::String { cap: 10 },
+                string("José"),
+            ),
+        ]
+    }
+
+    fn options() -> Vec<Vector> {
+        use Layout::{Instruction, State};
+        vec![
+            vector("state_some", State, option(Ty::U32), some(Val::U32(7))),
+            // The value is replaced by zeroes, the field keeps its size
+            vector("state_none", State, option(Ty::U32), Val::Option(None)),
+            vector("state_some_zero", State, option(Ty::U8), some(Val::U8(0))),
+            vector(
+                "state_some_string",
+                State,
+                option(Ty::String { cap: 6 }),
+                some(string("abc")),
+            ),
+            vector(
+                "state_none_string",
+                State,
+                option(Ty::String { cap: 6 }),
+                Val::Option(None),
+            ),
+            vector(
+                "state_some_pubkey",
+                State,
+                option(Ty::Pubkey),
+                some(Val::Pubkey(key())),
+            ),
+            vector(
+                "instruction_some",
+                Instruction,
+                option(Ty::U64),
+                some(Val::U64(5)),
+            ),
+            // Only the flag is written
+            vector(
+                "instruction_none",
+                Instruction,
+                option(Ty::U64),
+                Val::Option(None),
+            ),
+            vector(
+                "instruction_some_zero",
+                Instruction,
+                option(Ty::U8),
+                some(Val::U8(0)),
+            ),
+            vector(
+                "instruction_some_false",
+                Instruction,
+                option(Ty::Bool),
+                some(Val::Bool(false)),
+            ),
+            vector(
+                "instruction_some_string",
+                Instruction,
+                option(Ty::String { cap: 10 }),
+                some(string("hi")),
+            ),
+        ]
+    }
+
+    fn vecs() -> Vec<Vector> {
+        use Layout::{Instruction, State};
+        let u16s = |values: &[u16]| Val::Vec(values.iter().map(|v| Val::U16(*v)).collect());
+        vec![
+            vector("state", State, vec_of(3, Ty::U16), u16s(&[1, 2])),
+            vector("state_empty", State, vec_of(2, Ty::U16), u16s(&[])),
+            vector("state_at_cap", State, vec_of(2, Ty::U16), u16s(&[9, 8])),
+            vector(
+                "state_pubkeys",
+                State,
+                vec_of(2, Ty::Pubkey),
+                Val::Vec(vec![Val::Pubkey(key())]),
+            ),
+            // Elements keep the account layout of their type
+            vector(
+                "state_options",
+                State,
+                vec_of(3, option(Ty::U32)),
+                Val::Vec(vec![some(Val::U32(1)), Val::Option(None)]),
+            ),
+            vector(
+                "instruction",
+                Instruction,
+                vec_of(5, Ty::U16),
+                u16s(&[1, 2, 3]),
+            ),
+            vector(
+                "instruction_empty",
+                Instruction,
+                vec_of(5, Ty::U16),
+                u16s(&[]),
+            ),
+            vector(
+                "instruction_i64",
+                Instruction,
+                vec_of(5, Ty::I64),
+                Val::Vec(vec![Val::I64(-1), Val::I64(i64::MAX)]),
+            ),
+        ]
+    }
+
+    // The account of the budget tracker example, see cidl.yaml
+    fn record() -> Ty {
+        Ty::Struct {
+            name: "Record".to_string(),
+            fields: vec![
+                ("version".to_string(), Ty::U8),
+                ("name".to_string(), Ty::String { cap: 50 }),
+                ("moves".to_string(), Ty::U16),
+                ("outcome".to_string(), Ty::U32),
+                ("income".to_string(), Ty::U32),
+                ("total_balance".to_string(), Ty::I64),
+                ("delegates".to_string(), vec_of(5, delegate())),
+                ("mint".to_string(), Ty::Pubkey),
+                ("vault".to_string(), Ty::Pubkey),
+            ],
+        }
+    }
+
+    // A `sol:delegate`, packed as its key and its role
+    fn delegate() -> Ty {
+        Ty::Struct {
+            name: "Delegate".to_string(),
+            fields: vec![
+                ("key".to_string(), Ty::Pubkey),
+                ("role".to_string(), Ty::U8),
+            ],
+        }
+    }
+
+    fn records() -> Vec<Vector> {
+        let value = |name: &str, moves, outcome, income, total_balance, delegates: &[u8]| {
+            let delegates = delegates
+                .iter()
+                .map(|role| {
+                    Val::Struct(vec![
+                        ("key".to_string(), Val::Pubkey(key())),
+                        ("role".to_string(), Val::U8(*role)),
+                    ])
+                })
+                .collect();
+            Val::Struct(vec![
+                ("version".to_string(), Val::U8(2)),
+                ("name".to_string(), string(name)),
+                ("moves".to_string(), Val::U16(moves)),
+                ("outcome".to_string(), Val::U32(outcome)),
+                ("income".to_string(), Val::U32(income)),
+                ("total_balance".to_string(), Val::I64(total_balance)),
+                ("delegates".to_string(), Val::Vec(delegates)),
+                ("mint".to_string(), Val::Pubkey(key())),
+                ("vault".to_string(), Val::Pubkey(Pubkey::default())),
+            ])
+        };
+        vec![
+            vector("empty", Layout::State, record(), null_value(&record())),
+            vector(
+                "john_doe",
+                Layout::State,
+                record(),
+                value("John Doe", 2, 250, 1000, 750, &[1]),
+            ),
+            vector(
+                "name_at_cap",
+                Layout::State,
+                record(),
+                value(
+                    &"x".repeat(50),
+                    u16::MAX,
+                    u32::MAX,
+                    0,
+                    i64::MIN,
+                    &[0, 1, 2, 2, 2],
+                ),
+            ),
+        ]
+    }
+
+    fn groups() -> Vec<(&'static str, Vec<Vector>)> {
+        vec![
+            ("primitives", primitives()),
+            ("strings", strings()),
+            ("options", options()),
+            ("vecs", vecs()),
+            ("records", records()),
+        ]
+    }
+
+    fn vectors_dir() -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/vectors")
+    }
+
+    fn render(vectors: &[Vector]) -> String {
+        let entries: Vec<_> = vectors
+            .iter()
+            .map(|vector| {
+                let layout = match vector.layout {
+                    Layout::State => "state",
+                    Layout::Instruction => "instruction",
+                };
+                json!({
+                    "name": vector.name,
+                    "layout": layout,
+                    "type": ty_to_json(&vector.ty),
+                    "value": val_to_json(&vector.value),
+                    "hex": to_hex(&encode(&vector.ty, &vector.value, vector.layout)),
+                })
+            })
+            .collect();
+        serde_json::to_string_pretty(&entries).unwrap() + "\n"
+    }
+
+    #[test]
+    fn it_matches_the_golden_vectors() {
+        let dir = vectors_dir();
+        if env::var_os("UPDATE_GOLDEN").is_some() {
+            fs::create_dir_all(&dir).unwrap();
+        }
+
+        for (group, vectors) in groups() {
+            let path = dir.join(format!("{}.json", group));
+            let rendered = render(&vectors);
+            if env::var_os("UPDATE_GOLDEN").is_some() {
+                fs::write(&path, &rendered).unwrap();
+            }
+            let checked_in = fs::read_to_string(&path)
+                .unwrap_or_else(|_| panic!("{} is missing", path.display()));
+            assert_eq!(rendered, checked_in, "{}", path.display());
+        }
+    }
+
+    // Reads the checked in files back, the way another implementation of the codec would
+    #[test]
+    fn it_round_trips_the_golden_vectors() {
+        for (group, _) in groups() {
+            let path = vectors_dir().join(format!("{}.json", group));
+            let vectors: Json = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+
+            for entry in vectors.as_array().unwrap() {
+                let name = format!("{}/{}", group, entry["name"].as_str().unwrap());
+                let layout = match entry["layout"].as_str().unwrap() {
+                    "state" => Layout::State,
+                    _ => Layout::Instruction,
+                };
+                let ty = ty_from_json(&entry["type"]);
+                let value = val_from_json(&ty, &entry["value"]);
+                let bytes = from_hex(entry["hex"].as_str().unwrap());
+
+                assert_eq!(encode(&ty, &value, layout), bytes, "{}", name);
+                let (rest, unpacked) = unpack(&bytes, &ty, layout);
+                assert!(rest.is_empty(), "{} leaves {} bytes", name, rest.len());
+                assert_eq!(unpacked, value, "{}", name);
+                if layout == Layout::State {
+                    assert_eq!(bytes.len(), state_size(&ty), "{}", name);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,257 @@
+use crate::*;
+use budget_tracker_sdk::constants::INSTRUCTIONS;
+use codigolib::delegate_utils::{Delegate, DelegateRole};
+use codigolib::testing::*;
+use codigolib::token_utils::spl_token_program;
+use solana_program::instruction::AccountMeta;
+use solana_program::system_program;
+
+type Attacked = (Instruction, Vec<Pubkey>, AttackReport);
+
+// Attacks `instruction` under the name of its method, with `case` telling apart the states an
+// instruction is attacked from. The instruction is kept with its report and the accounts of the
+// token program it takes
+fn attack_method(
+    runtime: &TestRuntime,
+    case: Option<&str>,
+    instruction: &Instruction,
+    signers: &[Pubkey],
+) -> Attacked {
+    let (method, _) = INSTRUCTIONS
+        .iter()
+        .find(|(_, tag)| instruction.data[0] == *tag)
+        .unwrap();
+    let name = match case {
+        Some(case) => format!("{} ({})", method, case),
+        None => method.to_string(),
+    };
+    let tokens = instruction
+        .accounts
+        .iter()
+        .map(|meta| meta.pubkey)
+        .filter(|key| {
+            runtime
+                .account(key)
+                .is_some_and(|account| account.owner == spl_token_program::id())
+        })
+        .collect();
+    (
+        instruction.clone(),
+        tokens,
+        attack(runtime, &name, instruction, signers),
+    )
+}
+
+// Every instruction of the budget tracker, from a state where it succeeds. The owner of the
+// record is not the fee payer so that each signature is attacked on its own, and another user
+// has a record to put in place of the owner's
+fn reports() -> Vec<Attacked> {
+    let mut program = BudgetTracker::new();
+    program.create_user_record("Jane Doe").unwrap();
+    let fee_payer = program.fee_payer;
+    let owner = program.runtime.add_payer(FEE_PAYER_LAMPORTS);
+    let program_id = program.program_id();
+    let create = instructions::create_user_record(
+        &program_id,
+        "John Doe".to_string(),
+        owner,
+        owner,
+        fee_payer,
+    );
+
+    let mut reports = vec![attack_method(
+        &program.runtime,
+        Some("new"),
+        &create,
+        &[fee_payer, owner],
+    )];
+
+    program
+        .runtime
+        .process(&create, &[fee_payer, owner])
+        .unwrap();
+    reports.push(attack_method(
+        &program.runtime,
+        Some("existing"),
+        &create,
+        &[fee_payer, owner],
+    ));
+    reports.push(attack_method(
+        &program.runtime,
+        None,
+        &instructions::register_income(&program_id, 100, owner, owner),
+        &[owner],
+    ));
+    reports.push(attack_method(
+        &program.runtime,
+        None,
+        &instructions::register_outcome(&program_id, 50, owner, owner),
+        &[owner],
+    ));
+
+    let contributor = Delegate {
+        key: program.runtime.add_payer(FEE_PAYER_LAMPORTS),
+        role: DelegateRole::Contributor,
+    };
+    let add_delegate = instructions::add_delegate(&program_id, contributor, owner, owner);
+    reports.push(attack_method(
+        &program.runtime,
+        None,
+        &add_delegate,
+        &[owner],
+    ));
+    program.runtime.process(&add_delegate, &[owner]).unwrap();
+    reports.push(attack_method(
+        &program.runtime,
+        Some("delegate"),
+        &instructions::register_income(&program_id, 100, owner, contributor.key),
+        &[contributor.key],
+    ));
+    reports.push(attack_method(
+        &program.runtime,
+        None,
+        &instructions::remove_delegate(&program_id, contributor.key, owner, owner),
+        &[owner],
+    ));
+    reports.push(attack_method(
+        &program.runtime,
+        None,
+        &instructions::update_user_name(&program_id, "Jane Doe".to_string(), owner, owner),
+        &[owner],
+    ));
+
+    // The record is bound to a mint, moves tokens and pays them all back to be closed
+    let (record, _) = Record::find_address(&program_id, &owner);
+    let mint = program.runtime.add_mint();
+    let wallet = program.runtime.add_token_account(&mint, &owner, 1000);
+    let bind = instructions::bind_mint(&program_id, mint, owner, record, owner, fee_payer);
+    reports.push(attack_method(
+        &program.runtime,
+        None,
+        &bind,
+        &[fee_payer, owner],
+    ));
+    program.runtime.process(&bind, &[fee_payer, owner]).unwrap();
+    let income =
+        instructions::register_token_income(&program_id, 100, wallet, owner, record, owner);
+    reports.push(attack_method(&program.runtime, None, &income, &[owner]));
+    program.runtime.process(&income, &[owner]).unwrap();
+    let outcome =
+        instructions::register_token_outcome(&program_id, 100, wallet, owner, record, owner);
+    reports.push(attack_method(&program.runtime, None, &outcome, &[owner]));
+    program.runtime.process(&outcome, &[owner]).unwrap();
+
+    reports.push(attack_method(
+        &program.runtime,
+        None,
+        &instructions::close_record(&program_id, owner, owner, fee_payer),
+        &[fee_payer, owner],
+    ));
+    // Both records go back to the layout before the version byte, the other user's record
+    // keeps being a foreign account of the same length
+    program.add_unversioned_record(&owner, &Record::default());
+    program.add_unversioned_record(&fee_payer, &Record::default());
+    reports.push(attack_method(
+        &program.runtime,
+        None,
+        &instructions::migrate_record(&program_id, owner, owner, fee_payer),
+        &[fee_payer, owner],
+    ));
+
+    reports
+}
+
+// Mutations of `instruction` each attack makes. The records and the vault it creates are the
+// accounts of the program, the `tokens` of the token program only have their owner and length
+// attacked, and any other account can be duplicated in place of an account but a signer in
+// place of another signer
+fn expected_attempts(instruction: &Instruction, tokens: &[Pubkey]) -> Vec<(Attack, usize)> {
+    let accounts = &instruction.accounts;
+    let count = |f: &dyn Fn(&AccountMeta) -> bool| accounts.iter().filter(|meta| f(meta)).count();
+    let is_program =
+        |meta: &AccountMeta| [system_program::id(), spl_token_program::id()].contains(&meta.pubkey);
+    let programs = count(&is_program);
+    let token_accounts = count(&|meta| tokens.contains(&meta.pubkey));
+    let records = count(&|meta| !meta.is_signer && !is_program(meta)) - token_accounts;
+    let duplicates = accounts
+        .iter()
+        .map(|meta| {
+            accounts
+                .iter()
+                .filter(|other| other.pubkey != meta.pubkey)
+                .filter(|other| !(meta.is_signer && other.is_signer))
+                .count()
+        })
+        .sum();
+
+    vec![
+        (Attack::WrongOwner, records + token_accounts),
+        (Attack::NonSigner, count(&|meta| meta.is_signer)),
+        (Attack::WrongPda, records),
+        (Attack::WrongLength, records + token_accounts),
+        (Attack::Duplicate, duplicates),
+        (Attack::NonExecutable, programs),
+        (Attack::ForeignAccount, records),
+        (
+            Attack::ForeignSigner,
+            count(&|meta| meta.is_signer && !meta.is_writable),
+        ),
+    ]
+}
+
+#[test]
+fn it_rejects_every_account_substitution() {
+    let attacked = reports();
+    let reports: Vec<_> = attacked
+        .iter()
+        .map(|(_, _, report)| report.clone())
+        .collect();
+    assert_rejected(&reports);
+
+    for (instruction, tokens, report) in &attacked {
+        for (attack, attempts) in expected_attempts(instruction, tokens) {
+            assert_eq!(
+                report.coverage(attack),
+                (attempts > 0).then_some((attempts, attempts)),
+                "{} of {}\n{}",
+                attack.name(),
+                report.instruction,
+                coverage_matrix(&reports)
+            );
+        }
+    }
+}
+
+// Every instruction of the program is attacked, a new instruction fails this test until it is
+// added to the reports
+#[test]
+fn it_attacks_every_instruction() {
+    let reports = reports();
+    for (method, _) in INSTRUCTIONS {
+        assert!(
+            reports
+                .iter()
+                .any(|(_, _, report)| report.instruction.split(' ').next() == Some(method)),
+            "{} is not attacked",
+            method
+        );
+    }
+}
+
+// Every instruction writes a record, so each of its accounts must be attacked. Only programs
+// can be replaced by a regular account, and only the instructions paying rent take one
+#[test]
+fn it_attacks_every_instruction_writing_a_record() {
+    for (_, _, report) in reports() {
+        for attack in Attack::ALL {
+            if attack != Attack::NonExecutable {
+                assert!(
+                    report.coverage(attack).is_some(),
+                    "{} is not exposed to {}",
+                    report.instruction,
+                    attack.name()
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,1020 @@
+use crate::*;
+use budget_tracker::stubs::add_delegate::DelegateAdded;
+use budget_tracker::stubs::bind_mint::MintBound;
+use budget_tracker::stubs::close_record::RecordClosed;
+use budget_tracker::stubs::create_user_record::RecordCreated;
+use budget_tracker::stubs::migrate_record::{
+    RecordMigrated, RECORD_UNVERSIONED_LEN, RECORD_V1_LEN, RECORD_VERSION,
+};
+use budget_tracker::stubs::register_income::IncomeRegistered;
+use budget_tracker::stubs::register_outcome::OutcomeRegistered;
+use budget_tracker::stubs::remove_delegate::DelegateRemoved;
+use budget_tracker::stubs::update_user_name::UserNameUpdated;
+use codigolib::delegate_utils::{Delegate, DelegateRole};
+use codigolib::errors::{CodecError, MathError, SecurityError};
+use codigolib::event_utils::decode_events;
+use codigolib::testing::gen::*;
+use codigolib::testing::{TestAccount, TokenError};
+use solana_program::rent::Rent;
+use solana_program::system_instruction::SystemError;
+use solana_program::system_program;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(name: &str, moves: u16, income: u32, outcome: u32, total_balance: i64) -> Record {
+        Record {
+            version: RECORD_VERSION,
+            name: name.to_string(),
+            moves,
+            outcome,
+            income,
+            total_balance,
+            delegates: vec![],
+            mint: Pubkey::default(),
+            vault: Pubkey::default(),
+        }
+    }
+
+    // The steps of app.ts in the README
+    #[test]
+    fn it_tracks_incomes_and_outcomes() {
+        let mut program = BudgetTracker::new();
+        let fee_payer = program.fee_payer;
+
+        program.create_user_record("John Doe").unwrap();
+        assert_eq!(
+            program.get_record(&fee_payer),
+            Ok(record("John Doe", 0, 0, 0, 0))
+        );
+
+        program.register_income(100).unwrap();
+        assert_eq!(
+            program.get_record(&fee_payer),
+            Ok(record("John Doe", 1, 100, 0, 100))
+        );
+
+        program.register_outcome(50).unwrap();
+        assert_eq!(
+            program.get_record(&fee_payer),
+            Ok(record("John Doe", 2, 100, 50, 50))
+        );
+    }
+
+    // Indexers rebuild the history of a record from the logs of each transaction
+    #[test]
+    fn it_emits_an_event_for_every_change_of_the_balance() {
+        let mut program = BudgetTracker::new();
+        let program_id = program.program_id();
+        let (address, _) = Record::find_address(&program_id, &program.fee_payer);
+
+        program.create_user_record(" John Doe ").unwrap();
+        assert_eq!(
+            decode_events::<RecordCreated, _>(&program_id, program.runtime.logs()),
+            Ok(vec![RecordCreated {
+                record: address,
+                name: "John Doe".to_string(),
+            }])
+        );
+
+        program.register_income(100).unwrap();
+        assert_eq!(
+            decode_events::<IncomeRegistered, _>(&program_id, program.runtime.logs()),
+            Ok(vec![IncomeRegistered {
+                record: address,
+                amount: 100,
+                total_balance: 100,
+            }])
+        );
+
+        program.register_outcome(130).unwrap();
+        assert_eq!(
+            decode_events::<OutcomeRegistered, _>(&program_id, program.runtime.logs()),
+            Ok(vec![OutcomeRegistered {
+                record: address,
+                amount: 130,
+                total_balance: -30,
+            }])
+        );
+        assert_eq!(
+            decode_events::<IncomeRegistered, _>(&program_id, program.runtime.logs()),
+            Ok(vec![])
+        );
+
+        // Neither a failed move nor creating the record again emits anything
+        assert_eq!(
+            program.register_income(u32::MAX),
+            Err(MathError::Overflow.into())
+        );
+        assert_eq!(
+            decode_events::<IncomeRegistered, _>(&program_id, program.runtime.logs()),
+            Ok(vec![])
+        );
+        program.create_user_record("Jane Doe").unwrap();
+        assert_eq!(
+            decode_events::<RecordCreated, _>(&program_id, program.runtime.logs()),
+            Ok(vec![])
+        );
+    }
+
+    #[test]
+    fn it_emits_an_event_for_every_change_of_the_record() {
+        let mut program = BudgetTracker::new();
+        let program_id = program.program_id();
+        let fee_payer = program.fee_payer;
+        let address = program.add_unversioned_record(&fee_payer, &record("John Doe", 0, 0, 0, 0));
+        let jane = delegate(Pubkey::new_unique(), DelegateRole::Admin);
+
+        program.migrate_record().unwrap();
+        assert_eq!(
+            decode_events::<RecordMigrated, _>(&program_id, program.runtime.logs()),
+            Ok(vec![RecordMigrated {
+                record: address,
+                version: RECORD_VERSION,
+            }])
+        );
+
+        program.add_delegate(jane).unwrap();
+        assert_eq!(
+            decode_events::<DelegateAdded, _>(&program_id, program.runtime.logs()),
+            Ok(vec![DelegateAdded {
+                record: address,
+                delegate: jane,
+            }])
+        );
+
+        program.remove_delegate(jane.key).unwrap();
+        assert_eq!(
+            decode_events::<DelegateRemoved, _>(&program_id, program.runtime.logs()),
+            Ok(vec![DelegateRemoved {
+                record: address,
+                delegate: jane.key,
+            }])
+        );
+
+        program.update_user_name(" John Smith ").unwrap();
+        assert_eq!(
+            decode_events::<UserNameUpdated, _>(&program_id, program.runtime.logs()),
+            Ok(vec![UserNameUpdated {
+                record: address,
+                name: "John Smith".to_string(),
+            }])
+        );
+
+        program.register_outcome(30).unwrap();
+        program.close_record().unwrap();
+        assert_eq!(
+            decode_events::<RecordClosed, _>(&program_id, program.runtime.logs()),
+            Ok(vec![RecordClosed {
+                record: address,
+                total_balance: -30,
+            }])
+        );
+    }
+
+    // Any name up to the cap of the CIDL without control characters, then incomes and outcomes
+    // small enough for the u32 sums
+    #[test]
+    fn it_tracks_random_moves() {
+        check(50, |gen| {
+            let mut program = BudgetTracker::new();
+            let fee_payer = program.fee_payer;
+            let name: String = gen.string(50).chars().filter(|c| !c.is_control()).collect();
+            program.create_user_record(&name).unwrap();
+
+            let mut expected = record(name.trim(), 0, 0, 0, 0);
+            for amount in gen.vec(20, |gen| gen.rng().u32(..=u16::MAX as u32)) {
+                if gen.value() {
+                    program.register_income(amount).unwrap();
+                    expected.income += amount;
+                    expected.total_balance += amount as i64;
+                } else {
+                    program.register_outcome(amount).unwrap();
+                    expected.outcome += amount;
+                    expected.total_balance -= amount as i64;
+                }
+                expected.moves += 1;
+            }
+
+            assert_eq!(program.get_record(&fee_payer), Ok(expected));
+        });
+    }
+
+    #[test]
+    fn it_pays_the_record_from_the_fee_payer() {
+        let mut program = BudgetTracker::new();
+        let fee_payer = program.fee_payer;
+        let rent = Rent::default().minimum_balance(Record::LEN);
+
+        program.create_user_record("John Doe").unwrap();
+
+        let (address, _) = Record::find_address(&program.program_id(), &fee_payer);
+        let account = program.runtime.account(&address).unwrap();
+        assert_eq!(account.owner, program.program_id());
+        assert_eq!(account.data.len(), Record::LEN);
+        assert_eq!(account.lamports, rent);
+
+        let payer = program.runtime.account(&fee_payer).unwrap();
+        assert_eq!(payer.owner, system_program::id());
+        assert_eq!(payer.lamports, FEE_PAYER_LAMPORTS - rent);
+    }
+
+    #[test]
+    fn it_creates_records_whose_address_was_funded() {
+        let mut program = BudgetTracker::new();
+        let fee_payer = program.fee_payer;
+        let rent = Rent::default().minimum_balance(Record::LEN);
+        let (address, _) = Record::find_address(&program.program_id(), &fee_payer);
+        program
+            .runtime
+            .add_account(TestAccount::new().key(address).lamports(1_000));
+
+        program.create_user_record("John Doe").unwrap();
+
+        let account = program.runtime.account(&address).unwrap();
+        assert_eq!(account.owner, program.program_id());
+        assert_eq!(account.lamports, rent);
+        assert_eq!(
+            program.runtime.account(&fee_payer).unwrap().lamports,
+            FEE_PAYER_LAMPORTS - (rent - 1_000)
+        );
+        assert_eq!(
+            program.get_record(&fee_payer),
+            Ok(record("John Doe", 0, 0, 0, 0))
+        );
+    }
+
+    // The stub of create_user_record only runs for a new record, calling it again changes nothing
+    #[test]
+    fn it_keeps_the_record_when_created_again() {
+        let mut program = BudgetTracker::new();
+        let fee_payer = program.fee_payer;
+        program.create_user_record("John Doe").unwrap();
+        program.register_income(100).unwrap();
+        let lamports = program.runtime.account(&fee_payer).unwrap().lamports;
+
+        program.create_user_record("Jane Doe").unwrap();
+
+        assert_eq!(
+            program.get_record(&fee_payer),
+            Ok(record("John Doe", 1, 100, 0, 100))
+        );
+        assert_eq!(
+            program.runtime.account(&fee_payer).unwrap().lamports,
+            lamports
+        );
+    }
+
+    #[test]
+    fn it_keeps_the_records_of_each_user() {
+        let mut program = BudgetTracker::new();
+        let john = program.fee_payer;
+        program.create_user_record("John Doe").unwrap();
+
+        let jane = program.runtime.add_payer(FEE_PAYER_LAMPORTS);
+        program.fee_payer = jane;
+        program.create_user_record("Jane Doe").unwrap();
+        program.register_outcome(30).unwrap();
+
+        assert_eq!(
+            program.get_record(&john),
+            Ok(record("John Doe", 0, 0, 0, 0))
+        );
+        assert_eq!(
+            program.get_record(&jane),
+            Ok(record("Jane Doe", 1, 0, 30, -30))
+        );
+    }
+
+    #[test]
+    fn it_requires_the_fee_payer_signature() {
+        let mut program = BudgetTracker::new();
+        let fee_payer = program.fee_payer;
+        let instruction = instructions::create_user_record(
+            &program.program_id(),
+            "John Doe".to_string(),
+            fee_payer,
+            fee_payer,
+            fee_payer,
+        );
+
+        assert_eq!(
+            program.runtime.process(&instruction, &[]),
+            Err(ProgramError::MissingRequiredSignature)
+        );
+        assert_eq!(
+            program.get_record(&fee_payer),
+            Err(ProgramError::UninitializedAccount)
+        );
+    }
+
+    #[test]
+    fn it_requires_the_owner_of_the_record_to_sign() {
+        let mut program = BudgetTracker::new();
+        let john = program.fee_payer;
+        program.create_user_record("John Doe").unwrap();
+        let jane = program.runtime.add_payer(FEE_PAYER_LAMPORTS);
+        let program_id = program.program_id();
+
+        // Jane signs for John's record
+        program.fee_payer = jane;
+        assert_eq!(
+            program.send(instructions::register_outcome(&program_id, 50, john, jane)),
+            Err(SecurityError::UnrecognizedSignerAddress.into())
+        );
+        assert_eq!(
+            program.send(instructions::create_user_record(
+                &program_id,
+                "Jane Doe".to_string(),
+                john,
+                jane,
+                jane,
+            )),
+            Err(SecurityError::UnrecognizedSignerAddress.into())
+        );
+        // John is the authority but does not sign
+        let mut instruction = instructions::register_income(&program_id, 100, john, john);
+        instruction.accounts[1].is_signer = false;
+        assert_eq!(
+            program.send(instruction),
+            Err(SecurityError::SignerNotRecognized.into())
+        );
+        assert_eq!(
+            program.get_record(&john),
+            Ok(record("John Doe", 0, 0, 0, 0))
+        );
+    }
+
+    // The cap counts UTF-8 bytes: 25 two-byte chars fit the 50 bytes, 17 three-byte chars don't
+    #[test]
+    fn it_validates_the_user_name() {
+        let mut program = BudgetTracker::new();
+        let fee_payer = program.fee_payer;
+
+        assert_eq!(
+            program.create_user_record(&"€".repeat(17)),
+            Err(CodecError::StringTooLong.into())
+        );
+        assert_eq!(
+            program.create_user_record("John\0Doe"),
+            Err(CodecError::ControlCharacter.into())
+        );
+        assert_eq!(
+            program.get_record(&fee_payer),
+            Err(ProgramError::UninitializedAccount)
+        );
+
+        let name = "é".repeat(25);
+        program
+            .create_user_record(&format!("  {}\n", name))
+            .unwrap();
+        assert_eq!(
+            program.get_record(&fee_payer),
+            Ok(record(&name, 0, 0, 0, 0))
+        );
+
+        assert_eq!(
+            program.update_user_name(&format!("{}a", name)),
+            Err(CodecError::StringTooLong.into())
+        );
+        assert_eq!(
+            program.update_user_name("Jane\u{7f}Doe"),
+            Err(CodecError::ControlCharacter.into())
+        );
+        assert_eq!(
+            program.get_record(&fee_payer),
+            Ok(record(&name, 0, 0, 0, 0))
+        );
+
+        let name = format!("{}ab", "€".repeat(16));
+        program.update_user_name(&format!("\t{} ", name)).unwrap();
+        assert_eq!(
+            program.get_record(&fee_payer),
+            Ok(record(&name, 0, 0, 0, 0))
+        );
+    }
+
+    fn delegate(key: Pubkey, role: DelegateRole) -> Delegate {
+        Delegate { key, role }
+    }
+
+    #[test]
+    fn it_lets_delegates_register_moves() {
+        let mut program = BudgetTracker::new();
+        let john = program.fee_payer;
+        program.create_user_record("John Doe").unwrap();
+        let jane = program.runtime.add_payer(FEE_PAYER_LAMPORTS);
+        program
+            .add_delegate(delegate(jane, DelegateRole::Contributor))
+            .unwrap();
+        let program_id = program.program_id();
+
+        // Jane pays and signs for John's record
+        program.fee_payer = jane;
+        program
+            .send(instructions::register_income(&program_id, 100, john, jane))
+            .unwrap();
+        program
+            .send(instructions::register_outcome(&program_id, 30, john, jane))
+            .unwrap();
+
+        let mut expected = record("John Doe", 2, 100, 30, 70);
+        expected.delegates = vec![delegate(jane, DelegateRole::Contributor)];
+        assert_eq!(program.get_record(&john), Ok(expected));
+    }
+
+    #[test]
+    fn it_rejects_delegates_without_the_role() {
+        let mut program = BudgetTracker::new();
+        let john = program.fee_payer;
+        program.create_user_record("John Doe").unwrap();
+        let jane = program.runtime.add_payer(FEE_PAYER_LAMPORTS);
+        let bob = program.runtime.add_payer(FEE_PAYER_LAMPORTS);
+        program
+            .add_delegate(delegate(jane, DelegateRole::Viewer))
+            .unwrap();
+        let program_id = program.program_id();
+        program.fee_payer = jane;
+
+        // A viewer can't register moves
+        assert_eq!(
+            program.send(instructions::register_income(&program_id, 100, john, jane)),
+            Err(SecurityError::InsufficientDelegateRole.into())
+        );
+
+        // A contributor can't manage delegates, not even its own role
+        program.fee_payer = john;
+        program
+            .add_delegate(delegate(jane, DelegateRole::Contributor))
+            .unwrap();
+        program.fee_payer = jane;
+        assert_eq!(
+            program.send(instructions::add_delegate(
+                &program_id,
+                delegate(jane, DelegateRole::Admin),
+                john,
+                jane,
+            )),
+            Err(SecurityError::InsufficientDelegateRole.into())
+        );
+        assert_eq!(
+            program.send(instructions::remove_delegate(&program_id, jane, john, jane)),
+            Err(SecurityError::InsufficientDelegateRole.into())
+        );
+
+        // A wallet that is not a delegate can't manage them either
+        program.fee_payer = bob;
+        assert_eq!(
+            program.send(instructions::add_delegate(
+                &program_id,
+                delegate(bob, DelegateRole::Admin),
+                john,
+                bob,
+            )),
+            Err(SecurityError::UnrecognizedSignerAddress.into())
+        );
+
+        let mut expected = record("John Doe", 0, 0, 0, 0);
+        expected.delegates = vec![delegate(jane, DelegateRole::Contributor)];
+        assert_eq!(program.get_record(&john), Ok(expected));
+    }
+
+    #[test]
+    fn it_lets_admins_manage_delegates() {
+        let mut program = BudgetTracker::new();
+        let john = program.fee_payer;
+        program.create_user_record("John Doe").unwrap();
+        let jane = program.runtime.add_payer(FEE_PAYER_LAMPORTS);
+        let bob = program.runtime.add_payer(FEE_PAYER_LAMPORTS);
+        program
+            .add_delegate(delegate(jane, DelegateRole::Admin))
+            .unwrap();
+        let program_id = program.program_id();
+
+        program.fee_payer = jane;
+        program
+            .send(instructions::add_delegate(
+                &program_id,
+                delegate(bob, DelegateRole::Contributor),
+                john,
+                jane,
+            ))
+            .unwrap();
+        program.fee_payer = bob;
+        program
+            .send(instructions::register_income(&program_id, 100, john, bob))
+            .unwrap();
+
+        program.fee_payer = jane;
+        program
+            .send(instructions::remove_delegate(&program_id, bob, john, jane))
+            .unwrap();
+        program.fee_payer = bob;
+        assert_eq!(
+            program.send(instructions::register_income(&program_id, 100, john, bob)),
+            Err(SecurityError::UnrecognizedSignerAddress.into())
+        );
+
+        let mut expected = record("John Doe", 1, 100, 0, 100);
+        expected.delegates = vec![delegate(jane, DelegateRole::Admin)];
+        assert_eq!(program.get_record(&john), Ok(expected));
+    }
+
+    #[test]
+    fn it_lets_admins_rename_the_record() {
+        let mut program = BudgetTracker::new();
+        let john = program.fee_payer;
+        program.create_user_record("John Doe").unwrap();
+        let jane = program.runtime.add_payer(FEE_PAYER_LAMPORTS);
+        let bob = program.runtime.add_payer(FEE_PAYER_LAMPORTS);
+        program
+            .add_delegate(delegate(jane, DelegateRole::Admin))
+            .unwrap();
+        program
+            .add_delegate(delegate(bob, DelegateRole::Contributor))
+            .unwrap();
+        let program_id = program.program_id();
+
+        program.fee_payer = bob;
+        assert_eq!(
+            program.send(instructions::update_user_name(
+                &program_id,
+                "Bob".to_string(),
+                john,
+                bob,
+            )),
+            Err(SecurityError::InsufficientDelegateRole.into())
+        );
+
+        program.fee_payer = jane;
+        program
+            .send(instructions::update_user_name(
+                &program_id,
+                "John Smith".to_string(),
+                john,
+                jane,
+            ))
+            .unwrap();
+
+        let mut expected = record("John Smith", 0, 0, 0, 0);
+        expected.delegates = vec![
+            delegate(jane, DelegateRole::Admin),
+            delegate(bob, DelegateRole::Contributor),
+        ];
+        assert_eq!(program.get_record(&john), Ok(expected));
+    }
+
+    // Records created before the version byte are rewritten in place, the fee payer paying the
+    // rent of the added bytes
+    #[test]
+    fn it_migrates_records_created_before_the_version_byte() {
+        let mut program = BudgetTracker::new();
+        let fee_payer = program.fee_payer;
+        let address =
+            program.add_unversioned_record(&fee_payer, &record("John Doe", 2, 100, 50, 50));
+        let old_rent = Rent::default().minimum_balance(RECORD_UNVERSIONED_LEN);
+        let rent = Rent::default().minimum_balance(Record::LEN);
+
+        assert_eq!(
+            program.register_income(100),
+            Err(SecurityError::InvalidAccountLen.into())
+        );
+
+        program.migrate_record().unwrap();
+
+        let account = program.runtime.account(&address).unwrap();
+        assert_eq!(account.data.len(), Record::LEN);
+        assert_eq!(account.lamports, rent);
+        assert_eq!(
+            program.runtime.account(&fee_payer).unwrap().lamports,
+            FEE_PAYER_LAMPORTS - (rent - old_rent)
+        );
+        assert_eq!(
+            program.get_record(&fee_payer),
+            Ok(record("John Doe", 2, 100, 50, 50))
+        );
+
+        // Already in the latest layout, migrating again changes nothing
+        program.migrate_record().unwrap();
+        assert_eq!(
+            program.runtime.account(&fee_payer).unwrap().lamports,
+            FEE_PAYER_LAMPORTS - (rent - old_rent)
+        );
+
+        program.register_income(100).unwrap();
+        assert_eq!(
+            program.get_record(&fee_payer),
+            Ok(record("John Doe", 3, 200, 50, 150))
+        );
+    }
+
+    // Version 1 records gain the mint and the vault, left to the default pubkey
+    #[test]
+    fn it_migrates_records_of_version_1() {
+        let mut program = BudgetTracker::new();
+        let fee_payer = program.fee_payer;
+        let mut v1 = record("John Doe", 2, 100, 50, 50);
+        v1.delegates = vec![delegate(Pubkey::new_unique(), DelegateRole::Viewer)];
+        let address = program.add_v1_record(&fee_payer, &v1);
+        let old_rent = Rent::default().minimum_balance(RECORD_V1_LEN);
+        let rent = Rent::default().minimum_balance(Record::LEN);
+
+        assert_eq!(
+            program.register_income(100),
+            Err(SecurityError::InvalidAccountLen.into())
+        );
+
+        program.migrate_record().unwrap();
+
+        let account = program.runtime.account(&address).unwrap();
+        assert_eq!(account.data.len(), Record::LEN);
+        assert_eq!(
+            program.runtime.account(&fee_payer).unwrap().lamports,
+            FEE_PAYER_LAMPORTS - (rent - old_rent)
+        );
+        assert_eq!(program.get_record(&fee_payer), Ok(v1));
+    }
+
+    #[test]
+    fn it_only_lets_the_owner_migrate_the_record() {
+        let mut program = BudgetTracker::new();
+        let john = program.fee_payer;
+        let address = program.add_unversioned_record(&john, &record("John Doe", 0, 0, 0, 0));
+        let jane = program.runtime.add_payer(FEE_PAYER_LAMPORTS);
+        let program_id = program.program_id();
+
+        program.fee_payer = jane;
+        assert_eq!(
+            program.send(instructions::migrate_record(&program_id, john, jane, jane)),
+            Err(SecurityError::UnrecognizedSignerAddress.into())
+        );
+        assert_eq!(
+            program.runtime.account(&address).unwrap().data.len(),
+            RECORD_UNVERSIONED_LEN
+        );
+    }
+
+    // The rent of the record goes back to the fee payer and the address can be used again
+    #[test]
+    fn it_closes_the_record() {
+        let mut program = BudgetTracker::new();
+        let fee_payer = program.fee_payer;
+        program.create_user_record("John Doe").unwrap();
+        program.register_income(100).unwrap();
+        let (address, _) = Record::find_address(&program.program_id(), &fee_payer);
+
+        program.close_record().unwrap();
+
+        assert!(program.runtime.account(&address).is_none());
+        assert_eq!(
+            program.runtime.account(&fee_payer).unwrap().lamports,
+            FEE_PAYER_LAMPORTS
+        );
+        assert_eq!(
+            program.register_income(100),
+            Err(SecurityError::WrongAccountOwner.into())
+        );
+        assert_eq!(
+            program.close_record(),
+            Err(SecurityError::WrongAccountOwner.into())
+        );
+
+        program.create_user_record("John Doe").unwrap();
+        assert_eq!(
+            program.get_record(&fee_payer),
+            Ok(record("John Doe", 0, 0, 0, 0))
+        );
+    }
+
+    #[test]
+    fn it_only_lets_the_owner_close_the_record() {
+        let mut program = BudgetTracker::new();
+        let john = program.fee_payer;
+        program.create_user_record("John Doe").unwrap();
+        let jane = program.runtime.add_payer(FEE_PAYER_LAMPORTS);
+        program
+            .add_delegate(delegate(jane, DelegateRole::Admin))
+            .unwrap();
+        let program_id = program.program_id();
+
+        program.fee_payer = jane;
+        assert_eq!(
+            program.send(instructions::close_record(&program_id, john, jane, jane)),
+            Err(SecurityError::UnrecognizedSignerAddress.into())
+        );
+
+        let mut expected = record("John Doe", 0, 0, 0, 0);
+        expected.delegates = vec![delegate(jane, DelegateRole::Admin)];
+        assert_eq!(program.get_record(&john), Ok(expected));
+    }
+
+    // Incomes move tokens into the vault and outcomes pay them back, the balance of the record
+    // stays the amount the vault holds
+    #[test]
+    fn it_backs_the_balance_with_tokens() {
+        let mut program = BudgetTracker::new();
+        let program_id = program.program_id();
+        let fee_payer = program.fee_payer;
+        let (address, _) = Record::find_address(&program_id, &fee_payer);
+        let (mint, wallet) = program.create_token_record(1000);
+        let vault = program.vault();
+
+        assert_eq!(
+            decode_events::<MintBound, _>(&program_id, program.runtime.logs()),
+            Ok(vec![MintBound {
+                record: address,
+                mint,
+                vault,
+            }])
+        );
+        let token_account = program.runtime.token_account(&vault).unwrap();
+        assert_eq!((token_account.mint, token_account.owner), (mint, vault));
+
+        program.register_token_income(wallet, 400).unwrap();
+        assert_eq!(
+            decode_events::<IncomeRegistered, _>(&program_id, program.runtime.logs()),
+            Ok(vec![IncomeRegistered {
+                record: address,
+                amount: 400,
+                total_balance: 400,
+            }])
+        );
+        program.register_token_outcome(wallet, 150).unwrap();
+
+        let mut expected = record("John Doe", 2, 400, 150, 250);
+        expected.mint = mint;
+        expected.vault = vault;
+        assert_eq!(program.get_record(&fee_payer), Ok(expected.clone()));
+        assert_eq!(program.token_amount(&wallet), 750);
+        assert_eq!(program.token_amount(&vault), 250);
+
+        // The vault can't pay more than it holds, so the balance can't go negative
+        assert_eq!(
+            program.register_token_outcome(wallet, 251),
+            Err(TokenError::InsufficientFunds.into())
+        );
+        assert_eq!(
+            program.register_token_income(wallet, 751),
+            Err(TokenError::InsufficientFunds.into())
+        );
+        assert_eq!(program.get_record(&fee_payer), Ok(expected));
+        assert_eq!(program.token_amount(&vault), 250);
+    }
+
+    // Counters without tokens would no longer match the vault
+    #[test]
+    fn it_requires_token_transfers_on_token_backed_records() {
+        let mut program = BudgetTracker::new();
+        let (_, wallet) = program.create_token_record(1000);
+        let vault = program.vault();
+        program.register_token_income(wallet, 100).unwrap();
+
+        assert_eq!(
+            program.register_income(100),
+            Err(SecurityError::TokenTransferRequired.into())
+        );
+        assert_eq!(
+            program.register_outcome(100),
+            Err(SecurityError::TokenTransferRequired.into())
+        );
+        assert_eq!(
+            program.close_record(),
+            Err(SecurityError::TokenTransferRequired.into())
+        );
+
+        // Once the tokens are paid out the record closes, the emptied vault stays
+        program.register_token_outcome(wallet, 100).unwrap();
+        program.close_record().unwrap();
+        assert_eq!(program.token_amount(&wallet), 1000);
+        assert!(program.runtime.token_account(&vault).is_some());
+    }
+
+    #[test]
+    fn it_binds_a_mint_once_before_any_income() {
+        let mut program = BudgetTracker::new();
+        let fee_payer = program.fee_payer;
+        let mint = program.runtime.add_mint();
+        program.create_user_record("John Doe").unwrap();
+        program.register_income(100).unwrap();
+
+        assert_eq!(
+            program.bind_mint(mint),
+            Err(SecurityError::TokenBalanceMismatch.into())
+        );
+        let wallet = program.runtime.add_token_account(&mint, &fee_payer, 0);
+        assert_eq!(
+            program.bind_mint(wallet),
+            Err(SecurityError::InvalidAccountLen.into())
+        );
+
+        program.register_outcome(100).unwrap();
+        program.bind_mint(mint).unwrap();
+        assert_eq!(program.get_record(&fee_payer).unwrap().mint, mint);
+
+        let other = program.runtime.add_mint();
+        assert_eq!(
+            program.bind_mint(other),
+            Err(ProgramError::AccountAlreadyInitialized)
+        );
+        assert_eq!(program.get_record(&fee_payer).unwrap().mint, mint);
+    }
+
+    // Tokens only come from and go to accounts of the mint owned by the signer, and only through
+    // the vault of the record
+    #[test]
+    fn it_rejects_token_accounts_of_another_mint_or_owner() {
+        let mut program = BudgetTracker::new();
+        let program_id = program.program_id();
+        let john = program.fee_payer;
+        let (mint, wallet) = program.create_token_record(1000);
+        program.register_token_income(wallet, 500).unwrap();
+        let jane = program.runtime.add_payer(FEE_PAYER_LAMPORTS);
+        let other_mint = program.runtime.add_mint();
+        let other_wallet = program.runtime.add_token_account(&other_mint, &john, 1000);
+        let jane_wallet = program.runtime.add_token_account(&mint, &jane, 1000);
+
+        assert_eq!(
+            program.register_token_income(other_wallet, 100),
+            Err(SecurityError::TokenMintMismatch.into())
+        );
+        assert_eq!(
+            program.register_token_outcome(other_wallet, 100),
+            Err(SecurityError::TokenMintMismatch.into())
+        );
+        assert_eq!(
+            program.register_token_income(jane_wallet, 100),
+            Err(SecurityError::TokenOwnerMismatch.into())
+        );
+        assert_eq!(
+            program.register_token_outcome(jane_wallet, 100),
+            Err(SecurityError::TokenOwnerMismatch.into())
+        );
+
+        // The vault of another record bound to the same mint
+        program.fee_payer = jane;
+        program.create_user_record("Jane Doe").unwrap();
+        program.bind_mint(mint).unwrap();
+        let (jane_record, _) = Record::find_address(&program_id, &jane);
+        program.register_token_income(jane_wallet, 100).unwrap();
+        program.fee_payer = john;
+        assert_eq!(
+            program.send(instructions::register_token_outcome(
+                &program_id,
+                100,
+                wallet,
+                john,
+                jane_record,
+                john,
+            )),
+            Err(SecurityError::NotExpectedAddress.into())
+        );
+
+        assert_eq!(program.token_amount(&wallet), 500);
+        assert_eq!(program.token_amount(&program.vault()), 500);
+        assert_eq!(program.get_record(&john).unwrap().total_balance, 500);
+    }
+
+    // Contributors add their own tokens, only the owner is paid out of the vault
+    #[test]
+    fn it_only_lets_the_owner_withdraw_tokens() {
+        let mut program = BudgetTracker::new();
+        let program_id = program.program_id();
+        let john = program.fee_payer;
+        let (mint, _) = program.create_token_record(0);
+        let jane = program.runtime.add_payer(FEE_PAYER_LAMPORTS);
+        program
+            .add_delegate(delegate(jane, DelegateRole::Admin))
+            .unwrap();
+        let jane_wallet = program.runtime.add_token_account(&mint, &jane, 1000);
+        let (record, _) = Record::find_address(&program_id, &john);
+
+        program.fee_payer = jane;
+        program
+            .send(instructions::register_token_income(
+                &program_id,
+                300,
+                jane_wallet,
+                john,
+                record,
+                jane,
+            ))
+            .unwrap();
+        assert_eq!(
+            program.send(instructions::register_token_outcome(
+                &program_id,
+                300,
+                jane_wallet,
+                john,
+                record,
+                jane,
+            )),
+            Err(SecurityError::UnrecognizedSignerAddress.into())
+        );
+
+        assert_eq!(program.token_amount(&jane_wallet), 700);
+        assert_eq!(program.get_record(&john).unwrap().total_balance, 300);
+    }
+
+    #[test]
+    fn it_limits_the_delegates_of_a_record() {
+        let mut program = BudgetTracker::new();
+        let john = program.fee_payer;
+        program.create_user_record("John Doe").unwrap();
+        let delegates: Vec<Delegate> = (0..5)
+            .map(|_| delegate(Pubkey::new_unique(), DelegateRole::Viewer))
+            .collect();
+        for delegate in &delegates {
+            program.add_delegate(*delegate).unwrap();
+        }
+
+        assert_eq!(
+            program.add_delegate(delegate(Pubkey::new_unique(), DelegateRole::Viewer)),
+            Err(SecurityError::DelegateLimitReached.into())
+        );
+        assert_eq!(
+            program.remove_delegate(Pubkey::new_unique()),
+            Err(SecurityError::DelegateNotFound.into())
+        );
+
+        // Changing the role of a delegate takes no new slot
+        program
+            .add_delegate(delegate(delegates[0].key, DelegateRole::Admin))
+            .unwrap();
+        program.remove_delegate(delegates[1].key).unwrap();
+
+        let mut expected = record("John Doe", 0, 0, 0, 0);
+        expected.delegates = vec![
+            delegate(delegates[0].key, DelegateRole::Admin),
+            delegates[2],
+            delegates[3],
+            delegates[4],
+        ];
+        assert_eq!(program.get_record(&john), Ok(expected));
+    }
+
+    #[test]
+    fn it_rejects_moves_that_overflow_the_record() {
+        let mut program = BudgetTracker::new();
+        let fee_payer = program.fee_payer;
+        program.create_user_record("John Doe").unwrap();
+        program.register_income(u32::MAX).unwrap();
+
+        assert_eq!(program.register_income(1), Err(MathError::Overflow.into()));
+        assert_eq!(
+            program.get_record(&fee_payer),
+            Ok(record("John Doe", 1, u32::MAX, 0, u32::MAX as i64))
+        );
+
+        let (address, _) = Record::find_address(&program.program_id(), &fee_payer);
+        let full = record("John Doe", u16::MAX, 0, 0, 0);
+        let account = program.runtime.account(&address).unwrap().clone();
+        program.runtime.add_account(account.state(&full));
+        assert_eq!(program.register_income(1), Err(MathError::Overflow.into()));
+
+        let full = record("John Doe", 0, 0, 0, i64::MIN);
+        let account = program.runtime.account(&address).unwrap().clone();
+        program.runtime.add_account(account.state(&full));
+        assert_eq!(
+            program.register_outcome(1),
+            Err(MathError::Underflow.into())
+        );
+        assert_eq!(program.get_record(&fee_payer), Ok(full));
+    }
+
+    #[test]
+    fn it_rejects_moves_before_the_record_exists() {
+        let mut program = BudgetTracker::new();
+        let fee_payer = program.fee_payer;
+
+        assert_eq!(
+            program.register_income(100),
+            Err(SecurityError::WrongAccountOwner.into())
+        );
+        assert_eq!(
+            program.get_record(&fee_payer),
+            Err(ProgramError::UninitializedAccount)
+        );
+    }
+
+    #[test]
+    fn it_rejects_fee_payers_without_enough_lamports() {
+        let mut program = BudgetTracker::new();
+        program.fee_payer = program.runtime.add_payer(1_000);
+
+        assert_eq!(
+            program.create_user_record("John Doe"),
+            Err(ProgramError::Custom(
+                SystemError::ResultWithNegativeLamports as u32
+            ))
+        );
+        assert_eq!(
+            program
+                .runtime
+                .account(&program.fee_payer)
+                .unwrap()
+                .lamports,
+            1_000
+        );
+    }
+}
@@ -0,0 +1,259 @@
+use budget_tracker::instructions::BudgetTrackerInstruction;
+use budget_tracker::stubs::add_delegate::DelegateAdded;
+use budget_tracker::stubs::bind_mint::MintBound;
+use budget_tracker::stubs::close_record::RecordClosed;
+use budget_tracker::stubs::create_user_record::RecordCreated;
+use budget_tracker::stubs::migrate_record::RecordMigrated;
+use budget_tracker::stubs::register_income::IncomeRegistered;
+use budget_tracker::stubs::register_outcome::OutcomeRegistered;
+use budget_tracker::stubs::remove_delegate::DelegateRemoved;
+use budget_tracker::stubs::update_user_name::UserNameUpdated;
+use budget_tracker_sdk::constants::INSTRUCTIONS;
+use budget_tracker_sdk::instructions;
+use codigolib::delegate_utils::*;
+use codigolib::testing::*;
+use solana_program::instruction::Instruction;
+use solana_program::pubkey::Pubkey;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot(path: &str) -> String {
+        format!("{}/snapshots/{}", env!("CARGO_MANIFEST_DIR"), path)
+    }
+
+    // The program and its client pack the same bytes, pinned by one snapshot per sample
+    fn assert_layouts(path: &str, record: budget_tracker_sdk::types::Record) {
+        let path = snapshot(path);
+        assert_layout_snapshot(
+            &path,
+            &budget_tracker::state::Record {
+                version: record.version,
+                name: record.name.clone(),
+                moves: record.moves,
+                outcome: record.outcome,
+                income: record.income,
+                total_balance: record.total_balance,
+                delegates: record.delegates.clone(),
+                mint: record.mint,
+                vault: record.vault,
+            },
+        );
+        assert_layout_snapshot(&path, &record);
+    }
+
+    fn delegate(byte: u8, role: DelegateRole) -> Delegate {
+        Delegate {
+            key: Pubkey::new_from_array([byte; 32]),
+            role,
+        }
+    }
+
+    #[test]
+    fn it_keeps_the_layout_of_records() {
+        assert_layouts(
+            "record.layout",
+            budget_tracker_sdk::types::Record {
+                version: 2,
+                name: "John Doe".to_string(),
+                moves: 2,
+                outcome: 50,
+                income: 100,
+                total_balance: 50,
+                delegates: vec![delegate(1, DelegateRole::Contributor)],
+                mint: Pubkey::default(),
+                vault: Pubkey::default(),
+            },
+        );
+    }
+
+    #[test]
+    fn it_keeps_the_layout_of_full_records() {
+        assert_layouts(
+            "record_full.layout",
+            budget_tracker_sdk::types::Record {
+                version: 2,
+                name: "x".repeat(50),
+                moves: 65535,
+                outcome: 1000,
+                income: 250,
+                total_balance: -750,
+                delegates: vec![
+                    delegate(1, DelegateRole::Viewer),
+                    delegate(2, DelegateRole::Contributor),
+                    delegate(3, DelegateRole::Admin),
+                    delegate(4, DelegateRole::Admin),
+                    delegate(0xff, DelegateRole::Admin),
+                ],
+                mint: Pubkey::new_from_array([8; 32]),
+                vault: Pubkey::new_from_array([9; 32]),
+            },
+        );
+    }
+
+    // One sample per instruction as the client packs it. Strings take their length and bytes, so
+    // the fields of each sample are listed here
+    fn payloads() -> Vec<(Instruction, Vec<(&'static str, usize)>)> {
+        let program_id = Pubkey::new_from_array([0xee; 32]);
+        let owner = Pubkey::new_from_array([7; 32]);
+        let seed = ("user_record_seed_signer", 32);
+        let mint = Pubkey::new_from_array([8; 32]);
+        let record = Pubkey::new_from_array([6; 32]);
+        let vault_seed = ("vault_seed_record", 32);
+
+        vec![
+            (
+                instructions::create_user_record(
+                    &program_id,
+                    "John Doe".to_string(),
+                    owner,
+                    owner,
+                    owner,
+                ),
+                vec![("tag", 1), ("user_name", 4 + 8), seed],
+            ),
+            (
+                instructions::register_income(&program_id, 100, owner, owner),
+                vec![("tag", 1), ("amount", 4), seed],
+            ),
+            (
+                instructions::register_outcome(&program_id, 50, owner, owner),
+                vec![("tag", 1), ("amount", 4), seed],
+            ),
+            (
+                instructions::add_delegate(
+                    &program_id,
+                    delegate(1, DelegateRole::Contributor),
+                    owner,
+                    owner,
+                ),
+                vec![("tag", 1), ("delegate", DELEGATE_SIZE), seed],
+            ),
+            (
+                instructions::remove_delegate(
+                    &program_id,
+                    Pubkey::new_from_array([1; 32]),
+                    owner,
+                    owner,
+                ),
+                vec![("tag", 1), ("delegate", 32), seed],
+            ),
+            (
+                instructions::update_user_name(&program_id, "John Smith".to_string(), owner, owner),
+                vec![("tag", 1), ("user_name", 4 + 10), seed],
+            ),
+            (
+                instructions::migrate_record(&program_id, owner, owner, owner),
+                vec![("tag", 1), seed],
+            ),
+            (
+                instructions::close_record(&program_id, owner, owner, owner),
+                vec![("tag", 1), seed],
+            ),
+            (
+                instructions::bind_mint(&program_id, mint, owner, record, owner, owner),
+                vec![("tag", 1), seed, vault_seed],
+            ),
+            (
+                instructions::register_token_income(&program_id, 100, owner, owner, record, owner),
+                vec![("tag", 1), ("amount", 4), seed, vault_seed],
+            ),
+            (
+                instructions::register_token_outcome(&program_id, 50, owner, owner, record, owner),
+                vec![("tag", 1), ("amount", 4), seed, vault_seed],
+            ),
+        ]
+    }
+
+    // Every instruction of the client has a payload snapshot the program can read
+    #[test]
+    fn it_keeps_the_payload_of_every_instruction() {
+        let payloads = payloads();
+        for (name, tag) in INSTRUCTIONS {
+            let Some((instruction, fields)) = payloads.iter().find(|(i, _)| i.data[0] == tag)
+            else {
+                panic!("{} has no payload sample", name);
+            };
+
+            assert!(
+                BudgetTrackerInstruction::unpack(&instruction.data).is_ok(),
+                "the program can't read the payload of {}",
+                name
+            );
+            assert_snapshot(
+                snapshot(&format!("instructions/{}.layout", name)),
+                &bytes_dump(name, &instruction.data, fields),
+            );
+        }
+    }
+
+    #[test]
+    fn it_keeps_the_layout_of_events() {
+        let record = Pubkey::new_from_array([7; 32]);
+
+        assert_event_snapshot(
+            snapshot("events/record_created.layout"),
+            &RecordCreated {
+                record,
+                name: "John Doe".to_string(),
+            },
+        );
+        assert_event_snapshot(
+            snapshot("events/income_registered.layout"),
+            &IncomeRegistered {
+                record,
+                amount: 100,
+                total_balance: 100,
+            },
+        );
+        assert_event_snapshot(
+            snapshot("events/outcome_registered.layout"),
+            &OutcomeRegistered {
+                record,
+                amount: 150,
+                total_balance: -50,
+            },
+        );
+        assert_event_snapshot(
+            snapshot("events/delegate_added.layout"),
+            &DelegateAdded {
+                record,
+                delegate: delegate(1, DelegateRole::Contributor),
+            },
+        );
+        assert_event_snapshot(
+            snapshot("events/delegate_removed.layout"),
+            &DelegateRemoved {
+                record,
+                delegate: Pubkey::new_from_array([1; 32]),
+            },
+        );
+        assert_event_snapshot(
+            snapshot("events/user_name_updated.layout"),
+            &UserNameUpdated {
+                record,
+                name: "John Smith".to_string(),
+            },
+        );
+        assert_event_snapshot(
+            snapshot("events/record_migrated.layout"),
+            &RecordMigrated { record, version: 1 },
+        );
+        assert_event_snapshot(
+            snapshot("events/record_closed.layout"),
+            &RecordClosed {
+                record,
+                total_balance: -50,
+            },
+        );
+        assert_event_snapshot(
+            snapshot("events/mint_bound.layout"),
+            &MintBound {
+                record,
+                mint: Pubkey::new_from_array([8; 32]),
+                vault: Pubkey::new_from_array([9; 32]),
+            },
+        );
+    }
+}
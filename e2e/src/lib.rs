@@ -0,0 +1,232 @@
+use budget_tracker::processor::process_instruction;
+use budget_tracker::stubs::migrate_record::{RECORD_UNVERSIONED_LEN, RECORD_V1_LEN};
+use budget_tracker_sdk::instructions;
+use budget_tracker_sdk::types::*;
+use codigolib::delegate_utils::Delegate;
+use codigolib::testing::{pack_state, TestAccount, TestRuntime};
+use solana_program::entrypoint::ProgramResult;
+use solana_program::instruction::Instruction;
+use solana_program::program_error::ProgramError;
+use solana_program::pubkey::Pubkey;
+
+#[cfg(test)]
+pub mod attack_tests;
+
+#[cfg(test)]
+pub mod budget_tracker_tests;
+
+#[cfg(test)]
+pub mod layout_tests;
+
+// Lamports of the fee payer, enough for many records
+pub const FEE_PAYER_LAMPORTS: u64 = 10_000_000_000;
+
+// The budget tracker processor running in a TestRuntime, called as app.ts calls the deployed
+// program: instructions come from the client and are signed by a funded fee payer
+pub struct BudgetTracker {
+    pub runtime: TestRuntime,
+    pub fee_payer: Pubkey,
+}
+
+impl Default for BudgetTracker {
+    fn default() -> Self {
+        BudgetTracker::new()
+    }
+}
+
+impl BudgetTracker {
+    pub fn new() -> Self {
+        let mut runtime = TestRuntime::new(Pubkey::new_unique(), process_instruction);
+        let fee_payer = runtime.add_payer(FEE_PAYER_LAMPORTS);
+
+        BudgetTracker { runtime, fee_payer }
+    }
+
+    pub fn program_id(&self) -> Pubkey {
+        self.runtime.program_id
+    }
+
+    // Processes `instruction` signed by the fee payer
+    pub fn send(&mut self, instruction: Instruction) -> ProgramResult {
+        let signers = [self.fee_payer];
+        self.runtime.process(&instruction, &signers)
+    }
+
+    pub fn create_user_record(&mut self, user_name: &str) -> ProgramResult {
+        self.send(instructions::create_user_record(
+            &self.program_id(),
+            user_name.to_string(),
+            self.fee_payer,
+            self.fee_payer,
+            self.fee_payer,
+        ))
+    }
+
+    pub fn register_income(&mut self, amount: u32) -> ProgramResult {
+        self.send(instructions::register_income(
+            &self.program_id(),
+            amount,
+            self.fee_payer,
+            self.fee_payer,
+        ))
+    }
+
+    pub fn register_outcome(&mut self, amount: u32) -> ProgramResult {
+        self.send(instructions::register_outcome(
+            &self.program_id(),
+            amount,
+            self.fee_payer,
+            self.fee_payer,
+        ))
+    }
+
+    pub fn add_delegate(&mut self, delegate: Delegate) -> ProgramResult {
+        self.send(instructions::add_delegate(
+            &self.program_id(),
+            delegate,
+            self.fee_payer,
+            self.fee_payer,
+        ))
+    }
+
+    pub fn remove_delegate(&mut self, delegate: Pubkey) -> ProgramResult {
+        self.send(instructions::remove_delegate(
+            &self.program_id(),
+            delegate,
+            self.fee_payer,
+            self.fee_payer,
+        ))
+    }
+
+    pub fn update_user_name(&mut self, user_name: &str) -> ProgramResult {
+        self.send(instructions::update_user_name(
+            &self.program_id(),
+            user_name.to_string(),
+            self.fee_payer,
+            self.fee_payer,
+        ))
+    }
+
+    pub fn migrate_record(&mut self) -> ProgramResult {
+        self.send(instructions::migrate_record(
+            &self.program_id(),
+            self.fee_payer,
+            self.fee_payer,
+            self.fee_payer,
+        ))
+    }
+
+    pub fn close_record(&mut self) -> ProgramResult {
+        self.send(instructions::close_record(
+            &self.program_id(),
+            self.fee_payer,
+            self.fee_payer,
+            self.fee_payer,
+        ))
+    }
+
+    // The vault holding the tokens of the record of the fee payer once it is bound to a mint
+    pub fn vault(&self) -> Pubkey {
+        let (record, _) = Record::find_address(&self.program_id(), &self.fee_payer);
+        Vault::find_address(&self.program_id(), &record).0
+    }
+
+    pub fn bind_mint(&mut self, mint: Pubkey) -> ProgramResult {
+        let (record, _) = Record::find_address(&self.program_id(), &self.fee_payer);
+        self.send(instructions::bind_mint(
+            &self.program_id(),
+            mint,
+            self.fee_payer,
+            record,
+            self.fee_payer,
+            self.fee_payer,
+        ))
+    }
+
+    pub fn register_token_income(&mut self, source: Pubkey, amount: u32) -> ProgramResult {
+        let (record, _) = Record::find_address(&self.program_id(), &self.fee_payer);
+        self.send(instructions::register_token_income(
+            &self.program_id(),
+            amount,
+            source,
+            self.fee_payer,
+            record,
+            self.fee_payer,
+        ))
+    }
+
+    pub fn register_token_outcome(&mut self, destination: Pubkey, amount: u32) -> ProgramResult {
+        let (record, _) = Record::find_address(&self.program_id(), &self.fee_payer);
+        self.send(instructions::register_token_outcome(
+            &self.program_id(),
+            amount,
+            destination,
+            self.fee_payer,
+            record,
+            self.fee_payer,
+        ))
+    }
+
+    // A record of the fee payer backed by a new mint, with a token account of the fee payer
+    // holding `amount` tokens of it
+    pub fn create_token_record(&mut self, amount: u64) -> (Pubkey, Pubkey) {
+        let mint = self.runtime.add_mint();
+        let wallet = self
+            .runtime
+            .add_token_account(&mint, &self.fee_payer, amount);
+        self.create_user_record("John Doe").unwrap();
+        self.bind_mint(mint).unwrap();
+
+        (mint, wallet)
+    }
+
+    // Tokens held by the token account at `key`, 0 when there is none
+    pub fn token_amount(&self, key: &Pubkey) -> u64 {
+        self.runtime
+            .token_account(key)
+            .map_or(0, |account| account.amount)
+    }
+
+    // The record of `signer` as the program wrote it before the version byte and the delegates,
+    // rent exempt for that length
+    pub fn add_unversioned_record(&mut self, signer: &Pubkey, record: &Record) -> Pubkey {
+        let (address, _) = Record::find_address(&self.program_id(), signer);
+        let data = &pack_state(record)[1..1 + RECORD_UNVERSIONED_LEN];
+        self.runtime.add_account(
+            TestAccount::new()
+                .key(address)
+                .owner(self.program_id())
+                .data(data)
+                .rent_exempt(),
+        );
+
+        address
+    }
+
+    // The record of `signer` as version 1 wrote it, before the mint and the vault
+    pub fn add_v1_record(&mut self, signer: &Pubkey, record: &Record) -> Pubkey {
+        let (address, _) = Record::find_address(&self.program_id(), signer);
+        let mut data = pack_state(record)[..RECORD_V1_LEN].to_vec();
+        data[0] = 1;
+        self.runtime.add_account(
+            TestAccount::new()
+                .key(address)
+                .owner(self.program_id())
+                .data(data)
+                .rent_exempt(),
+        );
+
+        address
+    }
+
+    // As getRecord of the TypeScript client, the record of `signer` decoded from its account
+    pub fn get_record(&self, signer: &Pubkey) -> Result<Record, ProgramError> {
+        let (address, _) = Record::find_address(&self.program_id(), signer);
+        let account = self
+            .runtime
+            .account(&address)
+            .ok_or(ProgramError::UninitializedAccount)?;
+
+        decode(&account.data)
+    }
+}
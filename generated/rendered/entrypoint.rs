@@ -0,0 +1,6 @@
+// Generated from the CIDL by `cidl generate`, changes to this file will be overwritten
+
+use crate::processor::process_instruction;
+use solana_program::entrypoint;
+
+entrypoint!(process_instruction);
@@ -0,0 +1,188 @@
+// Generated from the CIDL by `cidl generate`, changes to this file will be overwritten
+
+use codigolib::delegate_utils::*;
+use codigolib::rust_utils::*;
+use solana_program::program_error::ProgramError;
+use solana_program::pubkey::Pubkey;
+
+pub enum BudgetTrackerInstruction {
+    CreateUserRecord(CreateUserRecordArgs),
+    RegisterIncome(RegisterIncomeArgs),
+    RegisterOutcome(RegisterOutcomeArgs),
+    AddDelegate(AddDelegateArgs),
+    RemoveDelegate(RemoveDelegateArgs),
+    UpdateUserName(UpdateUserNameArgs),
+    MigrateRecord(MigrateRecordArgs),
+    CloseRecord(CloseRecordArgs),
+    BindMint(BindMintArgs),
+    RegisterTokenIncome(RegisterTokenIncomeArgs),
+    RegisterTokenOutcome(RegisterTokenOutcomeArgs),
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CreateUserRecordArgs {
+    pub user_name: String,
+    pub user_record_seed_signer: Pubkey,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RegisterIncomeArgs {
+    pub amount: u32,
+    pub user_record_seed_signer: Pubkey,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RegisterOutcomeArgs {
+    pub amount: u32,
+    pub user_record_seed_signer: Pubkey,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct AddDelegateArgs {
+    pub delegate: Delegate,
+    pub user_record_seed_signer: Pubkey,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RemoveDelegateArgs {
+    pub delegate: Pubkey,
+    pub user_record_seed_signer: Pubkey,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct UpdateUserNameArgs {
+    pub user_name: String,
+    pub user_record_seed_signer: Pubkey,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MigrateRecordArgs {
+    pub user_record_seed_signer: Pubkey,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CloseRecordArgs {
+    pub user_record_seed_signer: Pubkey,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct BindMintArgs {
+    pub user_record_seed_signer: Pubkey,
+    pub vault_seed_record: Pubkey,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RegisterTokenIncomeArgs {
+    pub amount: u32,
+    pub user_record_seed_signer: Pubkey,
+    pub vault_seed_record: Pubkey,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RegisterTokenOutcomeArgs {
+    pub amount: u32,
+    pub user_record_seed_signer: Pubkey,
+    pub vault_seed_record: Pubkey,
+}
+
+impl BudgetTrackerInstruction {
+    // The first byte is the position of the method in the CIDL, followed by the method inputs and
+    // the seeds of its accounts
+    pub fn unpack(data: &[u8]) -> Result<Self, ProgramError> {
+        let (tag, buf) = data
+            .split_first()
+            .ok_or(ProgramError::InvalidInstructionData)?;
+
+        match tag {
+            0 => {
+                let (buf, user_name) = try_instructions_unpack_string(buf)?;
+                let (_, user_record_seed_signer) = try_unpack_pubkey(buf)?;
+                Ok(Self::CreateUserRecord(CreateUserRecordArgs {
+                    user_name,
+                    user_record_seed_signer,
+                }))
+            }
+            1 => {
+                let (buf, amount) = try_unpack_u32(buf)?;
+                let (_, user_record_seed_signer) = try_unpack_pubkey(buf)?;
+                Ok(Self::RegisterIncome(RegisterIncomeArgs {
+                    amount,
+                    user_record_seed_signer,
+                }))
+            }
+            2 => {
+                let (buf, amount) = try_unpack_u32(buf)?;
+                let (_, user_record_seed_signer) = try_unpack_pubkey(buf)?;
+                Ok(Self::RegisterOutcome(RegisterOutcomeArgs {
+                    amount,
+                    user_record_seed_signer,
+                }))
+            }
+            3 => {
+                let (buf, delegate) = try_unpack_delegate(buf)?;
+                let (_, user_record_seed_signer) = try_unpack_pubkey(buf)?;
+                Ok(Self::AddDelegate(AddDelegateArgs {
+                    delegate,
+                    user_record_seed_signer,
+                }))
+            }
+            4 => {
+                let (buf, delegate) = try_unpack_pubkey(buf)?;
+                let (_, user_record_seed_signer) = try_unpack_pubkey(buf)?;
+                Ok(Self::RemoveDelegate(RemoveDelegateArgs {
+                    delegate,
+                    user_record_seed_signer,
+                }))
+            }
+            5 => {
+                let (buf, user_name) = try_instructions_unpack_string(buf)?;
+                let (_, user_record_seed_signer) = try_unpack_pubkey(buf)?;
+                Ok(Self::UpdateUserName(UpdateUserNameArgs {
+                    user_name,
+                    user_record_seed_signer,
+                }))
+            }
+            6 => {
+                let (_, user_record_seed_signer) = try_unpack_pubkey(buf)?;
+                Ok(Self::MigrateRecord(MigrateRecordArgs {
+                    user_record_seed_signer,
+                }))
+            }
+            7 => {
+                let (_, user_record_seed_signer) = try_unpack_pubkey(buf)?;
+                Ok(Self::CloseRecord(CloseRecordArgs {
+                    user_record_seed_signer,
+                }))
+            }
+            8 => {
+                let (buf, user_record_seed_signer) = try_unpack_pubkey(buf)?;
+                let (_, vault_seed_record) = try_unpack_pubkey(buf)?;
+                Ok(Self::BindMint(BindMintArgs {
+                    user_record_seed_signer,
+                    vault_seed_record,
+                }))
+            }
+            9 => {
+                let (buf, amount) = try_unpack_u32(buf)?;
+                let (buf, user_record_seed_signer) = try_unpack_pubkey(buf)?;
+                let (_, vault_seed_record) = try_unpack_pubkey(buf)?;
+                Ok(Self::RegisterTokenIncome(RegisterTokenIncomeArgs {
+                    amount,
+                    user_record_seed_signer,
+                    vault_seed_record,
+                }))
+            }
+            10 => {
+                let (buf, amount) = try_unpack_u32(buf)?;
+                let (buf, user_record_seed_signer) = try_unpack_pubkey(buf)?;
+                let (_, vault_seed_record) = try_unpack_pubkey(buf)?;
+                Ok(Self::RegisterTokenOutcome(RegisterTokenOutcomeArgs {
+                    amount,
+                    user_record_seed_signer,
+                    vault_seed_record,
+                }))
+            }
+            _ => Err(ProgramError::InvalidInstructionData),
+        }
+    }
+}
@@ -0,0 +1,8 @@
+// Generated from the CIDL by `cidl generate`, changes to this file will be overwritten
+
+#[cfg(not(feature = "no-entrypoint"))]
+mod entrypoint;
+pub mod instructions;
+pub mod processor;
+pub mod state;
+pub mod stubs;
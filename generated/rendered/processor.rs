@@ -0,0 +1,558 @@
+// Generated from the CIDL by `cidl generate`, changes to this file will be overwritten
+
+use crate::instructions::*;
+use crate::state::*;
+use crate::stubs;
+use codigolib::delegate_utils::DelegateRole;
+use codigolib::errors::{MathError, SecurityError};
+use codigolib::sec_utils::*;
+use codigolib::token_utils::*;
+use solana_program::account_info::{next_account_info, AccountInfo};
+use solana_program::entrypoint::ProgramResult;
+use solana_program::program::{invoke, invoke_signed};
+use solana_program::program_error::ProgramError;
+use solana_program::pubkey::Pubkey;
+use solana_program::rent::Rent;
+use solana_program::system_instruction;
+use solana_program::system_program;
+use solana_program::sysvar::Sysvar;
+
+pub fn process_instruction(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    match BudgetTrackerInstruction::unpack(data)? {
+        BudgetTrackerInstruction::CreateUserRecord(args) => {
+            process_create_user_record(program_id, accounts, args)
+        }
+        BudgetTrackerInstruction::RegisterIncome(args) => {
+            process_register_income(program_id, accounts, args)
+        }
+        BudgetTrackerInstruction::RegisterOutcome(args) => {
+            process_register_outcome(program_id, accounts, args)
+        }
+        BudgetTrackerInstruction::AddDelegate(args) => {
+            process_add_delegate(program_id, accounts, args)
+        }
+        BudgetTrackerInstruction::RemoveDelegate(args) => {
+            process_remove_delegate(program_id, accounts, args)
+        }
+        BudgetTrackerInstruction::UpdateUserName(args) => {
+            process_update_user_name(program_id, accounts, args)
+        }
+        BudgetTrackerInstruction::MigrateRecord(args) => {
+            process_migrate_record(program_id, accounts, args)
+        }
+        BudgetTrackerInstruction::CloseRecord(args) => {
+            process_close_record(program_id, accounts, args)
+        }
+        BudgetTrackerInstruction::BindMint(args) => process_bind_mint(program_id, accounts, args),
+        BudgetTrackerInstruction::RegisterTokenIncome(args) => {
+            process_register_token_income(program_id, accounts, args)
+        }
+        BudgetTrackerInstruction::RegisterTokenOutcome(args) => {
+            process_register_token_outcome(program_id, accounts, args)
+        }
+    }
+}
+
+fn process_create_user_record(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    args: CreateUserRecordArgs,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let user_record_info = next_account_info(account_info_iter)?;
+    let user_record_authority_info = next_account_info(account_info_iter)?;
+    let system_program_info = next_account_info(account_info_iter)?;
+    let fee_payer_info = next_account_info(account_info_iter)?;
+
+    verify_signers(&[user_record_authority_info, fee_payer_info])?;
+    if *system_program_info.key != system_program::id() {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    let user_record_seeds: &[&[u8]] = &[b"record", args.user_record_seed_signer.as_ref()];
+    verify_signer_address(
+        user_record_authority_info.key,
+        &args.user_record_seed_signer,
+    )?;
+    let (user_record_address, user_record_bump) =
+        Pubkey::find_program_address(user_record_seeds, program_id);
+    if *user_record_info.key != user_record_address {
+        return Err(SecurityError::NotExpectedAddress.into());
+    }
+    let user_record_signer_seeds: &[&[u8]] = &[
+        b"record",
+        args.user_record_seed_signer.as_ref(),
+        &[user_record_bump],
+    ];
+    let user_record_exists = !user_record_info.data_is_empty();
+    let mut user_record = if user_record_exists {
+        verify_ownership(program_id, &[user_record_info])?;
+        verify_len(Record::LEN, &[user_record_info])?;
+        AccountPDA::load(user_record_info)?
+    } else {
+        create_account(
+            program_id,
+            fee_payer_info,
+            user_record_info,
+            system_program_info,
+            Record::LEN,
+            &[user_record_signer_seeds],
+        )?;
+        AccountPDA::new(user_record_info, Record::default())
+    };
+
+    if user_record_exists {
+        return Ok(());
+    }
+
+    stubs::create_user_record::create_user_record(&mut user_record, args.user_name)?;
+
+    user_record.store();
+
+    Ok(())
+}
+
+fn process_register_income(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    args: RegisterIncomeArgs,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let user_record_info = next_account_info(account_info_iter)?;
+    let user_record_authority_info = next_account_info(account_info_iter)?;
+
+    verify_signers(&[user_record_authority_info])?;
+
+    let user_record_seeds: &[&[u8]] = &[b"record", args.user_record_seed_signer.as_ref()];
+    verify_pda(user_record_info, user_record_seeds, program_id)?;
+    verify_ownership(program_id, &[user_record_info])?;
+    verify_len(Record::LEN, &[user_record_info])?;
+    let mut user_record = AccountPDA::<Record>::load(user_record_info)?;
+    verify_delegate_role(
+        user_record_authority_info,
+        &args.user_record_seed_signer,
+        &user_record.data.delegates,
+        DelegateRole::Contributor,
+    )?;
+
+    stubs::register_income::register_income(&mut user_record, args.amount)?;
+
+    user_record.store();
+
+    Ok(())
+}
+
+fn process_register_outcome(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    args: RegisterOutcomeArgs,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let user_record_info = next_account_info(account_info_iter)?;
+    let user_record_authority_info = next_account_info(account_info_iter)?;
+
+    verify_signers(&[user_record_authority_info])?;
+
+    let user_record_seeds: &[&[u8]] = &[b"record", args.user_record_seed_signer.as_ref()];
+    verify_pda(user_record_info, user_record_seeds, program_id)?;
+    verify_ownership(program_id, &[user_record_info])?;
+    verify_len(Record::LEN, &[user_record_info])?;
+    let mut user_record = AccountPDA::<Record>::load(user_record_info)?;
+    verify_delegate_role(
+        user_record_authority_info,
+        &args.user_record_seed_signer,
+        &user_record.data.delegates,
+        DelegateRole::Contributor,
+    )?;
+
+    stubs::register_outcome::register_outcome(&mut user_record, args.amount)?;
+
+    user_record.store();
+
+    Ok(())
+}
+
+fn process_add_delegate(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    args: AddDelegateArgs,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let user_record_info = next_account_info(account_info_iter)?;
+    let user_record_authority_info = next_account_info(account_info_iter)?;
+
+    verify_signers(&[user_record_authority_info])?;
+
+    let user_record_seeds: &[&[u8]] = &[b"record", args.user_record_seed_signer.as_ref()];
+    verify_pda(user_record_info, user_record_seeds, program_id)?;
+    verify_ownership(program_id, &[user_record_info])?;
+    verify_len(Record::LEN, &[user_record_info])?;
+    let mut user_record = AccountPDA::<Record>::load(user_record_info)?;
+    verify_delegate_role(
+        user_record_authority_info,
+        &args.user_record_seed_signer,
+        &user_record.data.delegates,
+        DelegateRole::Admin,
+    )?;
+
+    stubs::add_delegate::add_delegate(&mut user_record, args.delegate)?;
+
+    user_record.store();
+
+    Ok(())
+}
+
+fn process_remove_delegate(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    args: RemoveDelegateArgs,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let user_record_info = next_account_info(account_info_iter)?;
+    let user_record_authority_info = next_account_info(account_info_iter)?;
+
+    verify_signers(&[user_record_authority_info])?;
+
+    let user_record_seeds: &[&[u8]] = &[b"record", args.user_record_seed_signer.as_ref()];
+    verify_pda(user_record_info, user_record_seeds, program_id)?;
+    verify_ownership(program_id, &[user_record_info])?;
+    verify_len(Record::LEN, &[user_record_info])?;
+    let mut user_record = AccountPDA::<Record>::load(user_record_info)?;
+    verify_delegate_role(
+        user_record_authority_info,
+        &args.user_record_seed_signer,
+        &user_record.data.delegates,
+        DelegateRole::Admin,
+    )?;
+
+    stubs::remove_delegate::remove_delegate(&mut user_record, args.delegate)?;
+
+    user_record.store();
+
+    Ok(())
+}
+
+fn process_update_user_name(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    args: UpdateUserNameArgs,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let user_record_info = next_account_info(account_info_iter)?;
+    let user_record_authority_info = next_account_info(account_info_iter)?;
+
+    verify_signers(&[user_record_authority_info])?;
+
+    let user_record_seeds: &[&[u8]] = &[b"record", args.user_record_seed_signer.as_ref()];
+    verify_pda(user_record_info, user_record_seeds, program_id)?;
+    verify_ownership(program_id, &[user_record_info])?;
+    verify_len(Record::LEN, &[user_record_info])?;
+    let mut user_record = AccountPDA::<Record>::load(user_record_info)?;
+    verify_delegate_role(
+        user_record_authority_info,
+        &args.user_record_seed_signer,
+        &user_record.data.delegates,
+        DelegateRole::Admin,
+    )?;
+
+    stubs::update_user_name::update_user_name(&mut user_record, args.user_name)?;
+
+    user_record.store();
+
+    Ok(())
+}
+
+fn process_migrate_record(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    args: MigrateRecordArgs,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let user_record_info = next_account_info(account_info_iter)?;
+    let user_record_authority_info = next_account_info(account_info_iter)?;
+    let system_program_info = next_account_info(account_info_iter)?;
+    let fee_payer_info = next_account_info(account_info_iter)?;
+
+    verify_signers(&[user_record_authority_info, fee_payer_info])?;
+    if *system_program_info.key != system_program::id() {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    let user_record_seeds: &[&[u8]] = &[b"record", args.user_record_seed_signer.as_ref()];
+    verify_signer_address(
+        user_record_authority_info.key,
+        &args.user_record_seed_signer,
+    )?;
+    verify_pda(user_record_info, user_record_seeds, program_id)?;
+    verify_ownership(program_id, &[user_record_info])?;
+
+    stubs::migrate_record::migrate_record(
+        program_id,
+        user_record_info,
+        fee_payer_info,
+        system_program_info,
+    )?;
+
+    Ok(())
+}
+
+fn process_close_record(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    args: CloseRecordArgs,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let user_record_info = next_account_info(account_info_iter)?;
+    let user_record_authority_info = next_account_info(account_info_iter)?;
+    let fee_payer_info = next_account_info(account_info_iter)?;
+
+    verify_signers(&[user_record_authority_info, fee_payer_info])?;
+
+    let user_record_seeds: &[&[u8]] = &[b"record", args.user_record_seed_signer.as_ref()];
+    verify_signer_address(
+        user_record_authority_info.key,
+        &args.user_record_seed_signer,
+    )?;
+    verify_pda(user_record_info, user_record_seeds, program_id)?;
+    verify_ownership(program_id, &[user_record_info])?;
+    verify_len(Record::LEN, &[user_record_info])?;
+    let user_record = AccountPDA::<Record>::load(user_record_info)?;
+
+    stubs::close_record::close_record(&user_record)?;
+
+    close_account(user_record_info, fee_payer_info)?;
+
+    Ok(())
+}
+
+fn process_bind_mint(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    args: BindMintArgs,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let user_record_info = next_account_info(account_info_iter)?;
+    let mint_info = next_account_info(account_info_iter)?;
+    let vault_info = next_account_info(account_info_iter)?;
+    let user_record_authority_info = next_account_info(account_info_iter)?;
+    let system_program_info = next_account_info(account_info_iter)?;
+    let token_program_info = next_account_info(account_info_iter)?;
+    let fee_payer_info = next_account_info(account_info_iter)?;
+
+    verify_signers(&[user_record_authority_info, fee_payer_info])?;
+    if *system_program_info.key != system_program::id() {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    verify_token_program(token_program_info)?;
+
+    let user_record_seeds: &[&[u8]] = &[b"record", args.user_record_seed_signer.as_ref()];
+    verify_signer_address(
+        user_record_authority_info.key,
+        &args.user_record_seed_signer,
+    )?;
+    verify_pda(user_record_info, user_record_seeds, program_id)?;
+    verify_ownership(program_id, &[user_record_info])?;
+    verify_len(Record::LEN, &[user_record_info])?;
+    let mut user_record = AccountPDA::<Record>::load(user_record_info)?;
+
+    verify_ownership(&spl_token_program::id(), &[mint_info])?;
+    verify_len(MINT_LEN, &[mint_info])?;
+
+    let vault_seeds: &[&[u8]] = &[b"vault", args.vault_seed_record.as_ref()];
+    let (vault_address, vault_bump) = Pubkey::find_program_address(vault_seeds, program_id);
+    if *vault_info.key != vault_address {
+        return Err(SecurityError::NotExpectedAddress.into());
+    }
+    let vault_signer_seeds: &[&[u8]] = &[b"vault", args.vault_seed_record.as_ref(), &[vault_bump]];
+    if !vault_info.data_is_empty() {
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+    create_account(
+        &spl_token_program::id(),
+        fee_payer_info,
+        vault_info,
+        system_program_info,
+        TOKEN_ACCOUNT_LEN,
+        &[vault_signer_seeds],
+    )?;
+    initialize_token_account(token_program_info, vault_info, mint_info, vault_info.key)?;
+    let vault = TokenAccountPDA::new(vault_info, vault_signer_seeds);
+
+    stubs::bind_mint::bind_mint(&mut user_record, mint_info, &vault)?;
+
+    user_record.store();
+
+    Ok(())
+}
+
+fn process_register_token_income(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    args: RegisterTokenIncomeArgs,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let user_record_info = next_account_info(account_info_iter)?;
+    let vault_info = next_account_info(account_info_iter)?;
+    let source_info = next_account_info(account_info_iter)?;
+    let user_record_authority_info = next_account_info(account_info_iter)?;
+    let token_program_info = next_account_info(account_info_iter)?;
+
+    verify_signers(&[user_record_authority_info])?;
+    verify_token_program(token_program_info)?;
+
+    let user_record_seeds: &[&[u8]] = &[b"record", args.user_record_seed_signer.as_ref()];
+    verify_pda(user_record_info, user_record_seeds, program_id)?;
+    verify_ownership(program_id, &[user_record_info])?;
+    verify_len(Record::LEN, &[user_record_info])?;
+    let mut user_record = AccountPDA::<Record>::load(user_record_info)?;
+    verify_delegate_role(
+        user_record_authority_info,
+        &args.user_record_seed_signer,
+        &user_record.data.delegates,
+        DelegateRole::Contributor,
+    )?;
+
+    let vault_seeds: &[&[u8]] = &[b"vault", args.vault_seed_record.as_ref()];
+    let (vault_address, vault_bump) = Pubkey::find_program_address(vault_seeds, program_id);
+    if *vault_info.key != vault_address {
+        return Err(SecurityError::NotExpectedAddress.into());
+    }
+    let vault_signer_seeds: &[&[u8]] = &[b"vault", args.vault_seed_record.as_ref(), &[vault_bump]];
+    verify_ownership(&spl_token_program::id(), &[vault_info])?;
+    verify_len(TOKEN_ACCOUNT_LEN, &[vault_info])?;
+    let vault = TokenAccountPDA::new(vault_info, vault_signer_seeds);
+
+    verify_ownership(&spl_token_program::id(), &[source_info])?;
+    verify_len(TOKEN_ACCOUNT_LEN, &[source_info])?;
+
+    stubs::register_token_income::register_token_income(
+        &mut user_record,
+        &vault,
+        source_info,
+        user_record_authority_info,
+        token_program_info,
+        args.amount,
+    )?;
+
+    user_record.store();
+
+    Ok(())
+}
+
+fn process_register_token_outcome(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    args: RegisterTokenOutcomeArgs,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let user_record_info = next_account_info(account_info_iter)?;
+    let vault_info = next_account_info(account_info_iter)?;
+    let destination_info = next_account_info(account_info_iter)?;
+    let user_record_authority_info = next_account_info(account_info_iter)?;
+    let token_program_info = next_account_info(account_info_iter)?;
+
+    verify_signers(&[user_record_authority_info])?;
+    verify_token_program(token_program_info)?;
+
+    let user_record_seeds: &[&[u8]] = &[b"record", args.user_record_seed_signer.as_ref()];
+    verify_signer_address(
+        user_record_authority_info.key,
+        &args.user_record_seed_signer,
+    )?;
+    verify_pda(user_record_info, user_record_seeds, program_id)?;
+    verify_ownership(program_id, &[user_record_info])?;
+    verify_len(Record::LEN, &[user_record_info])?;
+    let mut user_record = AccountPDA::<Record>::load(user_record_info)?;
+
+    let vault_seeds: &[&[u8]] = &[b"vault", args.vault_seed_record.as_ref()];
+    let (vault_address, vault_bump) = Pubkey::find_program_address(vault_seeds, program_id);
+    if *vault_info.key != vault_address {
+        return Err(SecurityError::NotExpectedAddress.into());
+    }
+    let vault_signer_seeds: &[&[u8]] = &[b"vault", args.vault_seed_record.as_ref(), &[vault_bump]];
+    verify_ownership(&spl_token_program::id(), &[vault_info])?;
+    verify_len(TOKEN_ACCOUNT_LEN, &[vault_info])?;
+    let vault = TokenAccountPDA::new(vault_info, vault_signer_seeds);
+
+    verify_ownership(&spl_token_program::id(), &[destination_info])?;
+    verify_len(TOKEN_ACCOUNT_LEN, &[destination_info])?;
+
+    stubs::register_token_outcome::register_token_outcome(
+        &mut user_record,
+        &vault,
+        destination_info,
+        user_record_authority_info,
+        token_program_info,
+        args.amount,
+    )?;
+
+    user_record.store();
+
+    Ok(())
+}
+
+// Creates `account` for `owner` with enough lamports to be rent exempt, PDAs sign with their
+// seeds. Anyone can send lamports to the address beforehand, which makes the system program refuse
+// to create it, so a funded account is topped up, allocated and assigned instead
+fn create_account<'a>(
+    owner: &Pubkey,
+    payer: &AccountInfo<'a>,
+    account: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>,
+    len: usize,
+    signer_seeds: &[&[&[u8]]],
+) -> ProgramResult {
+    let lamports = Rent::get()?.minimum_balance(len);
+
+    if account.lamports() == 0 {
+        return invoke_signed(
+            &system_instruction::create_account(
+                payer.key,
+                account.key,
+                lamports,
+                len as u64,
+                owner,
+            ),
+            &[payer.clone(), account.clone(), system_program.clone()],
+            signer_seeds,
+        );
+    }
+
+    let missing = lamports.saturating_sub(account.lamports());
+    if missing > 0 {
+        invoke(
+            &system_instruction::transfer(payer.key, account.key, missing),
+            &[payer.clone(), account.clone(), system_program.clone()],
+        )?;
+    }
+    invoke_signed(
+        &system_instruction::allocate(account.key, len as u64),
+        &[account.clone(), system_program.clone()],
+        signer_seeds,
+    )?;
+    invoke_signed(
+        &system_instruction::assign(account.key, owner),
+        &[account.clone(), system_program.clone()],
+        signer_seeds,
+    )
+}
+
+// Moves the lamports of `account` to `recipient` and gives the emptied account back to the
+// system program, the runtime deletes it at the end of the transaction
+fn close_account(account: &AccountInfo, recipient: &AccountInfo) -> ProgramResult {
+    let lamports = recipient
+        .lamports()
+        .checked_add(account.lamports())
+        .ok_or(MathError::Overflow)?;
+    **recipient.lamports.borrow_mut() = lamports;
+    **account.lamports.borrow_mut() = 0;
+
+    account.realloc(0, false)?;
+    account.assign(&system_program::id());
+
+    Ok(())
+}
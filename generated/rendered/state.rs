@@ -0,0 +1,143 @@
+// Generated from the CIDL by `cidl generate`, changes to this file will be overwritten
+
+use codigolib::delegate_utils::*;
+use codigolib::rust_utils::*;
+use solana_program::account_info::AccountInfo;
+use solana_program::program_error::ProgramError;
+use solana_program::pubkey::Pubkey;
+
+pub use codigolib::rust_utils::State;
+
+// An account owned by the program together with its unpacked data, stubs update `data` and the
+// processor stores it back once they return
+pub struct AccountPDA<'a, 'b, T: State> {
+    pub info: &'a AccountInfo<'b>,
+    pub data: T,
+}
+
+impl<'a, 'b, T: State> AccountPDA<'a, 'b, T> {
+    pub fn new(info: &'a AccountInfo<'b>, data: T) -> Self {
+        AccountPDA { info, data }
+    }
+
+    // The data was checked to have the length of T, the value of every field is checked here
+    pub fn load(info: &'a AccountInfo<'b>) -> Result<Self, ProgramError> {
+        let (_, data) = T::try_unpack(&info.data.borrow())?;
+        Ok(AccountPDA { info, data })
+    }
+
+    pub fn store(&self) {
+        self.data.pack(&mut self.info.data.borrow_mut());
+    }
+}
+
+// Through this data structure we will store the relevant information to track the income and outcome of a given user.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Record {
+    // Layout version of the record, records created before it have none and are migrated with migrate_record.
+    pub version: u8,
+    // The name of the user.
+    pub name: String,
+    // Number incomes/outcomes registered.
+    pub moves: u16,
+    // Sum of all outcomes.
+    pub outcome: u32,
+    // Sum of all incomes.
+    pub income: u32,
+    // The current balance of the user
+    pub total_balance: i64,
+    // Wallets allowed to act on the record besides its owner, with their role.
+    pub delegates: Vec<Delegate>,
+    // Mint of the tokens backing the balance, the default pubkey while the record only keeps counters.
+    pub mint: Pubkey,
+    // Token account of the program holding the tokens of the record, bound with the mint.
+    pub vault: Pubkey,
+}
+
+impl State for Record {
+    const LEN: usize = 306;
+    const FIELDS: &'static [(&'static str, usize)] = &[
+        ("version", 1),
+        ("name", 54),
+        ("moves", 2),
+        ("outcome", 4),
+        ("income", 4),
+        ("total_balance", 8),
+        ("delegates", 169),
+        ("mint", 32),
+        ("vault", 32),
+    ];
+
+    fn pack<'a>(&self, buf: &'a mut [u8]) -> &'a mut [u8] {
+        let buf = pack_u8(buf, self.version);
+        let buf = pack_string(buf, self.name.clone(), 54);
+        let buf = pack_u16(buf, self.moves);
+        let buf = pack_u32(buf, self.outcome);
+        let buf = pack_u32(buf, self.income);
+        let buf = pack_i64(buf, self.total_balance);
+        let buf = pack_vec(buf, 5, 33, pack_delegate, self.delegates.clone());
+        let buf = pack_pubkey(buf, self.mint);
+        pack_pubkey(buf, self.vault)
+    }
+
+    fn unpack(buf: &[u8]) -> (&[u8], Self) {
+        let (buf, version) = unpack_u8(buf);
+        let (buf, name) = state_unpack_string(buf, 54);
+        let (buf, moves) = unpack_u16(buf);
+        let (buf, outcome) = unpack_u32(buf);
+        let (buf, income) = unpack_u32(buf);
+        let (buf, total_balance) = unpack_i64(buf);
+        let (buf, delegates) = state_unpack_vec(buf, 5, 33, unpack_delegate);
+        let (buf, mint) = unpack_pubkey(buf);
+        let (buf, vault) = unpack_pubkey(buf);
+        (
+            buf,
+            Self {
+                version,
+                name,
+                moves,
+                outcome,
+                income,
+                total_balance,
+                delegates,
+                mint,
+                vault,
+            },
+        )
+    }
+
+    fn try_unpack(buf: &[u8]) -> Result<(&[u8], Self), ProgramError> {
+        let (buf, version) = try_unpack_u8(buf)?;
+        let (buf, name) = try_state_unpack_string(buf, 54)?;
+        let (buf, moves) = try_unpack_u16(buf)?;
+        let (buf, outcome) = try_unpack_u32(buf)?;
+        let (buf, income) = try_unpack_u32(buf)?;
+        let (buf, total_balance) = try_unpack_i64(buf)?;
+        let (buf, delegates) = try_state_unpack_vec(buf, 5, 33, try_unpack_delegate)?;
+        let (buf, mint) = try_unpack_pubkey(buf)?;
+        let (buf, vault) = try_unpack_pubkey(buf)?;
+        Ok((
+            buf,
+            Self {
+                version,
+                name,
+                moves,
+                outcome,
+                income,
+                total_balance,
+                delegates,
+                mint,
+                vault,
+            },
+        ))
+    }
+}
+
+// Token account holding the tokens of a record, owned by itself so only the program moves them.
+pub struct Vault;
+
+// Token account of a wallet, the tokens come from or go to it.
+pub struct TokenAccount;
+
+// Mint of the tokens backing a record.
+pub struct Mint;
@@ -0,0 +1,56 @@
+use crate::state::*;
+use codigolib::delegate_utils::*;
+use codigolib::event_utils::*;
+use codigolib::rust_utils::*;
+use solana_program::entrypoint::ProgramResult;
+use solana_program::program_error::ProgramError;
+use solana_program::pubkey::Pubkey;
+
+// `cap:5` of Record.delegates in the CIDL
+pub const DELEGATES_CAP: usize = 5;
+
+// Emitted when a wallet is added to the delegates or gets another role
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DelegateAdded {
+    pub record: Pubkey,
+    pub delegate: Delegate,
+}
+
+impl Event for DelegateAdded {
+    const DISCRIMINATOR: u8 = 3;
+    const LEN: usize = 32 + DELEGATE_SIZE;
+    const FIELDS: &'static [(&'static str, usize)] = &[("record", 32), ("delegate", DELEGATE_SIZE)];
+
+    fn pack<'a>(&self, buf: &'a mut [u8]) -> &'a mut [u8] {
+        let dst = pack_pubkey(buf, self.record);
+        pack_delegate(dst, self.delegate)
+    }
+
+    fn unpack(buf: &[u8]) -> (&[u8], Self) {
+        let (dst, record) = unpack_pubkey(buf);
+        let (dst, delegate) = unpack_delegate(dst);
+        (dst, DelegateAdded { record, delegate })
+    }
+
+    fn try_unpack(buf: &[u8]) -> Result<(&[u8], Self), ProgramError> {
+        let (dst, record) = try_unpack_pubkey(buf)?;
+        let (dst, delegate) = try_unpack_delegate(dst)?;
+        Ok((dst, DelegateAdded { record, delegate }))
+    }
+}
+
+// Allow another wallet to act on the record with the given role, or change the role it has. Only the owner and admin delegates can call it.
+pub fn add_delegate(user_record: &mut AccountPDA<Record>, delegate: Delegate) -> ProgramResult {
+    codigolib::delegate_utils::add_delegate(
+        &mut user_record.data.delegates,
+        DELEGATES_CAP,
+        delegate,
+    )?;
+
+    emit_event(&DelegateAdded {
+        record: *user_record.info.key,
+        delegate,
+    });
+
+    Ok(())
+}
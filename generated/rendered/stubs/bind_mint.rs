@@ -0,0 +1,88 @@
+use crate::state::*;
+use codigolib::errors::SecurityError;
+use codigolib::event_utils::*;
+use codigolib::rust_utils::*;
+use codigolib::token_utils::TokenAccountPDA;
+use solana_program::account_info::AccountInfo;
+use solana_program::entrypoint::ProgramResult;
+use solana_program::program_error::ProgramError;
+use solana_program::pubkey::Pubkey;
+
+// Records bound to a mint only move their balance with tokens
+pub fn is_token_backed(record: &Record) -> bool {
+    record.mint != Pubkey::default()
+}
+
+// Emitted once the record is backed by the tokens of its vault
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MintBound {
+    pub record: Pubkey,
+    pub mint: Pubkey,
+    pub vault: Pubkey,
+}
+
+impl Event for MintBound {
+    const DISCRIMINATOR: u8 = 8;
+    const LEN: usize = 32 + 32 + 32;
+    const FIELDS: &'static [(&'static str, usize)] = &[("record", 32), ("mint", 32), ("vault", 32)];
+
+    fn pack<'a>(&self, buf: &'a mut [u8]) -> &'a mut [u8] {
+        let dst = pack_pubkey(buf, self.record);
+        let dst = pack_pubkey(dst, self.mint);
+        pack_pubkey(dst, self.vault)
+    }
+
+    fn unpack(buf: &[u8]) -> (&[u8], Self) {
+        let (dst, record) = unpack_pubkey(buf);
+        let (dst, mint) = unpack_pubkey(dst);
+        let (dst, vault) = unpack_pubkey(dst);
+        (
+            dst,
+            MintBound {
+                record,
+                mint,
+                vault,
+            },
+        )
+    }
+
+    fn try_unpack(buf: &[u8]) -> Result<(&[u8], Self), ProgramError> {
+        let (dst, record) = try_unpack_pubkey(buf)?;
+        let (dst, mint) = try_unpack_pubkey(dst)?;
+        let (dst, vault) = try_unpack_pubkey(dst)?;
+        Ok((
+            dst,
+            MintBound {
+                record,
+                mint,
+                vault,
+            },
+        ))
+    }
+}
+
+// Back the balance of a record with tokens of the given mint, held in a vault created for the record. Only the owner can call it, before any income is registered.
+pub fn bind_mint<'a>(
+    user_record: &mut AccountPDA<Record>,
+    mint: &AccountInfo<'a>,
+    vault: &TokenAccountPDA<'_, 'a>,
+) -> ProgramResult {
+    let record = &mut user_record.data;
+    if is_token_backed(record) {
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+    // The balance registered so far has no tokens behind it
+    if record.total_balance != 0 {
+        return Err(SecurityError::TokenBalanceMismatch.into());
+    }
+    record.mint = *mint.key;
+    record.vault = *vault.info.key;
+
+    emit_event(&MintBound {
+        record: *user_record.info.key,
+        mint: record.mint,
+        vault: record.vault,
+    });
+
+    Ok(())
+}
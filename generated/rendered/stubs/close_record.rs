@@ -0,0 +1,65 @@
+use crate::state::*;
+use crate::stubs::bind_mint::is_token_backed;
+use codigolib::errors::SecurityError;
+use codigolib::event_utils::*;
+use codigolib::rust_utils::*;
+use solana_program::entrypoint::ProgramResult;
+use solana_program::program_error::ProgramError;
+use solana_program::pubkey::Pubkey;
+
+// Emitted before the record is deleted, with its last balance
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RecordClosed {
+    pub record: Pubkey,
+    pub total_balance: i64,
+}
+
+impl Event for RecordClosed {
+    const DISCRIMINATOR: u8 = 7;
+    const LEN: usize = 32 + 8;
+    const FIELDS: &'static [(&'static str, usize)] = &[("record", 32), ("total_balance", 8)];
+
+    fn pack<'a>(&self, buf: &'a mut [u8]) -> &'a mut [u8] {
+        let dst = pack_pubkey(buf, self.record);
+        pack_i64(dst, self.total_balance)
+    }
+
+    fn unpack(buf: &[u8]) -> (&[u8], Self) {
+        let (dst, record) = unpack_pubkey(buf);
+        let (dst, total_balance) = unpack_i64(dst);
+        (
+            dst,
+            RecordClosed {
+                record,
+                total_balance,
+            },
+        )
+    }
+
+    fn try_unpack(buf: &[u8]) -> Result<(&[u8], Self), ProgramError> {
+        let (dst, record) = try_unpack_pubkey(buf)?;
+        let (dst, total_balance) = try_unpack_i64(dst)?;
+        Ok((
+            dst,
+            RecordClosed {
+                record,
+                total_balance,
+            },
+        ))
+    }
+}
+
+// Delete the given record account and send its lamports to the fee payer. Only the owner can call it.
+pub fn close_record(user_record: &AccountPDA<Record>) -> ProgramResult {
+    // The tokens of the vault are only paid out while the record exists, the emptied vault stays
+    if is_token_backed(&user_record.data) && user_record.data.total_balance != 0 {
+        return Err(SecurityError::TokenTransferRequired.into());
+    }
+
+    emit_event(&RecordClosed {
+        record: *user_record.info.key,
+        total_balance: user_record.data.total_balance,
+    });
+
+    Ok(())
+}
@@ -0,0 +1,57 @@
+use crate::state::*;
+use crate::stubs::migrate_record::RECORD_VERSION;
+use codigolib::event_utils::*;
+use codigolib::rust_utils::*;
+use codigolib::string_utils::*;
+use solana_program::entrypoint::ProgramResult;
+use solana_program::program_error::ProgramError;
+use solana_program::pubkey::Pubkey;
+
+// `cap:50` of Record.name in the CIDL
+pub const USER_NAME_CAP: usize = 50;
+
+// Emitted once the record is initialized, with the name as stored
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RecordCreated {
+    pub record: Pubkey,
+    pub name: String,
+}
+
+impl Event for RecordCreated {
+    const DISCRIMINATOR: u8 = 0;
+    const LEN: usize = 32 + 4 + USER_NAME_CAP;
+    const FIELDS: &'static [(&'static str, usize)] = &[("record", 32), ("name", 4 + USER_NAME_CAP)];
+
+    fn pack<'a>(&self, buf: &'a mut [u8]) -> &'a mut [u8] {
+        let dst = pack_pubkey(buf, self.record);
+        pack_string(dst, self.name.clone(), 4 + USER_NAME_CAP)
+    }
+
+    fn unpack(buf: &[u8]) -> (&[u8], Self) {
+        let (dst, record) = unpack_pubkey(buf);
+        let (dst, name) = state_unpack_string(dst, 4 + USER_NAME_CAP);
+        (dst, RecordCreated { record, name })
+    }
+
+    fn try_unpack(buf: &[u8]) -> Result<(&[u8], Self), ProgramError> {
+        let (dst, record) = try_unpack_pubkey(buf)?;
+        let (dst, name) = try_state_unpack_string(dst, 4 + USER_NAME_CAP)?;
+        Ok((dst, RecordCreated { record, name }))
+    }
+}
+
+// To call once per account. Initialize a Record account. The total_balance of the account will be set to 0.
+pub fn create_user_record(
+    user_record: &mut AccountPDA<Record>,
+    user_name: String,
+) -> ProgramResult {
+    user_record.data.version = RECORD_VERSION;
+    user_record.data.name = validate_string(user_name, USER_NAME_CAP, StringPolicy::strict())?;
+
+    emit_event(&RecordCreated {
+        record: *user_record.info.key,
+        name: user_record.data.name.clone(),
+    });
+
+    Ok(())
+}
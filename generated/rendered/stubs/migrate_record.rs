@@ -0,0 +1,102 @@
+use crate::state::*;
+use codigolib::delegate_utils::DELEGATE_SIZE;
+use codigolib::event_utils::*;
+use codigolib::migration_utils::*;
+use codigolib::rust_utils::*;
+use solana_program::account_info::AccountInfo;
+use solana_program::entrypoint::ProgramResult;
+use solana_program::program_error::ProgramError;
+use solana_program::pubkey::Pubkey;
+use solana_program::rent::Rent;
+use solana_program::sysvar::Sysvar;
+
+// Version of the records this program writes
+pub const RECORD_VERSION: u8 = 2;
+
+// name (cap:50) + moves + outcome + income + total_balance, the layout deployed before the
+// version byte and the delegates
+pub const RECORD_UNVERSIONED_LEN: usize = 54 + 2 + 4 + 4 + 8;
+
+// The layout before the mint and the vault, with the version byte and the delegates (cap:5)
+pub const RECORD_V1_LEN: usize = VERSION_SIZE + RECORD_UNVERSIONED_LEN + 4 + 5 * DELEGATE_SIZE;
+
+// The fields keep their bytes after the version byte, the delegates stay an empty vector
+fn record_unversioned_to_v1(old: &[u8], new: &mut [u8]) {
+    new[VERSION_SIZE..VERSION_SIZE + RECORD_UNVERSIONED_LEN].copy_from_slice(old);
+}
+
+// The fields keep their bytes, the mint and the vault stay the default pubkey of records that
+// are not token-backed
+fn record_v1_to_v2(old: &[u8], new: &mut [u8]) {
+    new[..RECORD_V1_LEN].copy_from_slice(old);
+}
+
+// Emitted once the record is rewritten, with the version it has now
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RecordMigrated {
+    pub record: Pubkey,
+    pub version: u8,
+}
+
+impl Event for RecordMigrated {
+    const DISCRIMINATOR: u8 = 6;
+    const LEN: usize = 32 + 1;
+    const FIELDS: &'static [(&'static str, usize)] = &[("record", 32), ("version", 1)];
+
+    fn pack<'a>(&self, buf: &'a mut [u8]) -> &'a mut [u8] {
+        let dst = pack_pubkey(buf, self.record);
+        pack_u8(dst, self.version)
+    }
+
+    fn unpack(buf: &[u8]) -> (&[u8], Self) {
+        let (dst, record) = unpack_pubkey(buf);
+        let (dst, version) = unpack_u8(dst);
+        (dst, RecordMigrated { record, version })
+    }
+
+    fn try_unpack(buf: &[u8]) -> Result<(&[u8], Self), ProgramError> {
+        let (dst, record) = try_unpack_pubkey(buf)?;
+        let (dst, version) = try_unpack_u8(dst)?;
+        Ok((dst, RecordMigrated { record, version }))
+    }
+}
+
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        from_version: UNVERSIONED,
+        old_len: RECORD_UNVERSIONED_LEN,
+        new_len: RECORD_V1_LEN,
+        rewrite: record_unversioned_to_v1,
+    },
+    Migration {
+        from_version: 1,
+        old_len: RECORD_V1_LEN,
+        new_len: Record::LEN,
+        rewrite: record_v1_to_v2,
+    },
+];
+
+// Rewrite a record created before the version byte into the latest layout, the fee payer pays the rent of the added bytes. Only the owner can call it.
+pub fn migrate_record<'a>(
+    program_id: &Pubkey,
+    user_record: &AccountInfo<'a>,
+    fee_payer: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>,
+) -> ProgramResult {
+    migrate_account(
+        program_id,
+        user_record,
+        fee_payer,
+        system_program,
+        RECORD_VERSION,
+        MIGRATIONS,
+        &Rent::get()?,
+    )?;
+
+    emit_event(&RecordMigrated {
+        record: *user_record.key,
+        version: RECORD_VERSION,
+    });
+
+    Ok(())
+}
@@ -0,0 +1,13 @@
+// Generated from the CIDL by `cidl generate`, changes to this file will be overwritten
+
+pub mod add_delegate;
+pub mod bind_mint;
+pub mod close_record;
+pub mod create_user_record;
+pub mod migrate_record;
+pub mod register_income;
+pub mod register_outcome;
+pub mod register_token_income;
+pub mod register_token_outcome;
+pub mod remove_delegate;
+pub mod update_user_name;
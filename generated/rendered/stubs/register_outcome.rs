@@ -0,0 +1,88 @@
+use crate::state::*;
+use crate::stubs::bind_mint::is_token_backed;
+use codigolib::errors::{MathError, SecurityError};
+use codigolib::event_utils::*;
+use codigolib::rust_utils::*;
+use solana_program::entrypoint::ProgramResult;
+use solana_program::program_error::ProgramError;
+use solana_program::pubkey::Pubkey;
+
+// Emitted for every outcome, with the balance of the record after it
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct OutcomeRegistered {
+    pub record: Pubkey,
+    pub amount: u32,
+    pub total_balance: i64,
+}
+
+impl Event for OutcomeRegistered {
+    const DISCRIMINATOR: u8 = 2;
+    const LEN: usize = 32 + 4 + 8;
+    const FIELDS: &'static [(&'static str, usize)] =
+        &[("record", 32), ("amount", 4), ("total_balance", 8)];
+
+    fn pack<'a>(&self, buf: &'a mut [u8]) -> &'a mut [u8] {
+        let dst = pack_pubkey(buf, self.record);
+        let dst = pack_u32(dst, self.amount);
+        pack_i64(dst, self.total_balance)
+    }
+
+    fn unpack(buf: &[u8]) -> (&[u8], Self) {
+        let (dst, record) = unpack_pubkey(buf);
+        let (dst, amount) = unpack_u32(dst);
+        let (dst, total_balance) = unpack_i64(dst);
+        (
+            dst,
+            OutcomeRegistered {
+                record,
+                amount,
+                total_balance,
+            },
+        )
+    }
+
+    fn try_unpack(buf: &[u8]) -> Result<(&[u8], Self), ProgramError> {
+        let (dst, record) = try_unpack_pubkey(buf)?;
+        let (dst, amount) = try_unpack_u32(dst)?;
+        let (dst, total_balance) = try_unpack_i64(dst)?;
+        Ok((
+            dst,
+            OutcomeRegistered {
+                record,
+                amount,
+                total_balance,
+            },
+        ))
+    }
+}
+
+// Register the given amount as an outcome for the given record account. The total total_balance of the account will be decreased.
+pub fn register_outcome(user_record: &mut AccountPDA<Record>, amount: u32) -> ProgramResult {
+    if is_token_backed(&user_record.data) {
+        return Err(SecurityError::TokenTransferRequired.into());
+    }
+
+    add_outcome(user_record, amount)
+}
+
+// The counters and the event of an outcome, register_token_outcome adds them once the tokens moved
+pub fn add_outcome(user_record: &mut AccountPDA<Record>, amount: u32) -> ProgramResult {
+    let record = &mut user_record.data;
+    record.moves = record.moves.checked_add(1).ok_or(MathError::Overflow)?;
+    record.outcome = record
+        .outcome
+        .checked_add(amount)
+        .ok_or(MathError::Overflow)?;
+    record.total_balance = record
+        .total_balance
+        .checked_sub(amount as i64)
+        .ok_or(MathError::Underflow)?;
+
+    emit_event(&OutcomeRegistered {
+        record: *user_record.info.key,
+        amount,
+        total_balance: record.total_balance,
+    });
+
+    Ok(())
+}
@@ -0,0 +1,36 @@
+use crate::state::*;
+use crate::stubs::register_income::add_income;
+use codigolib::errors::SecurityError;
+use codigolib::sec_utils::verify_token_account;
+use codigolib::token_utils::TokenAccountPDA;
+use codigolib::token_utils::*;
+use solana_program::account_info::AccountInfo;
+use solana_program::entrypoint::ProgramResult;
+
+// Move the given amount of tokens into the vault of the record and register it as an income.
+pub fn register_token_income<'a>(
+    user_record: &mut AccountPDA<Record>,
+    vault: &TokenAccountPDA<'_, 'a>,
+    source: &AccountInfo<'a>,
+    user_record_authority: &AccountInfo<'a>,
+    token_program: &AccountInfo<'a>,
+    amount: u32,
+) -> ProgramResult {
+    let record = &user_record.data;
+    if *vault.info.key != record.vault {
+        return Err(SecurityError::NotExpectedAddress.into());
+    }
+    verify_token_account(vault.info, &record.mint, vault.info.key)?;
+    verify_token_account(source, &record.mint, user_record_authority.key)?;
+
+    transfer_tokens(
+        token_program,
+        source,
+        vault.info,
+        user_record_authority,
+        amount as u64,
+        &[],
+    )?;
+
+    add_income(user_record, amount)
+}
@@ -0,0 +1,37 @@
+use crate::state::*;
+use crate::stubs::register_outcome::add_outcome;
+use codigolib::errors::SecurityError;
+use codigolib::sec_utils::verify_token_account;
+use codigolib::token_utils::TokenAccountPDA;
+use codigolib::token_utils::*;
+use solana_program::account_info::AccountInfo;
+use solana_program::entrypoint::ProgramResult;
+
+// Move the given amount of tokens out of the vault of the record and register it as an outcome. Only the owner can call it.
+pub fn register_token_outcome<'a>(
+    user_record: &mut AccountPDA<Record>,
+    vault: &TokenAccountPDA<'_, 'a>,
+    destination: &AccountInfo<'a>,
+    user_record_authority: &AccountInfo<'a>,
+    token_program: &AccountInfo<'a>,
+    amount: u32,
+) -> ProgramResult {
+    let record = &user_record.data;
+    if *vault.info.key != record.vault {
+        return Err(SecurityError::NotExpectedAddress.into());
+    }
+    verify_token_account(vault.info, &record.mint, vault.info.key)?;
+    // Paid to the owner, a destination of someone else could also be the vault itself
+    verify_token_account(destination, &record.mint, user_record_authority.key)?;
+
+    transfer_tokens(
+        token_program,
+        vault.info,
+        destination,
+        vault.info,
+        amount as u64,
+        &[vault.signer_seeds],
+    )?;
+
+    add_outcome(user_record, amount)
+}
@@ -0,0 +1,48 @@
+use crate::state::*;
+use codigolib::event_utils::*;
+use codigolib::rust_utils::*;
+use solana_program::entrypoint::ProgramResult;
+use solana_program::program_error::ProgramError;
+use solana_program::pubkey::Pubkey;
+
+// Emitted when a wallet loses its access to the record
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DelegateRemoved {
+    pub record: Pubkey,
+    pub delegate: Pubkey,
+}
+
+impl Event for DelegateRemoved {
+    const DISCRIMINATOR: u8 = 4;
+    const LEN: usize = 32 + 32;
+    const FIELDS: &'static [(&'static str, usize)] = &[("record", 32), ("delegate", 32)];
+
+    fn pack<'a>(&self, buf: &'a mut [u8]) -> &'a mut [u8] {
+        let dst = pack_pubkey(buf, self.record);
+        pack_pubkey(dst, self.delegate)
+    }
+
+    fn unpack(buf: &[u8]) -> (&[u8], Self) {
+        let (dst, record) = unpack_pubkey(buf);
+        let (dst, delegate) = unpack_pubkey(dst);
+        (dst, DelegateRemoved { record, delegate })
+    }
+
+    fn try_unpack(buf: &[u8]) -> Result<(&[u8], Self), ProgramError> {
+        let (dst, record) = try_unpack_pubkey(buf)?;
+        let (dst, delegate) = try_unpack_pubkey(dst)?;
+        Ok((dst, DelegateRemoved { record, delegate }))
+    }
+}
+
+// Revoke the access of a delegate to the record. Only the owner and admin delegates can call it.
+pub fn remove_delegate(user_record: &mut AccountPDA<Record>, delegate: Pubkey) -> ProgramResult {
+    codigolib::delegate_utils::remove_delegate(&mut user_record.data.delegates, &delegate)?;
+
+    emit_event(&DelegateRemoved {
+        record: *user_record.info.key,
+        delegate,
+    });
+
+    Ok(())
+}
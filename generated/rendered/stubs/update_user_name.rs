@@ -0,0 +1,50 @@
+use crate::state::*;
+use crate::stubs::create_user_record::USER_NAME_CAP;
+use codigolib::event_utils::*;
+use codigolib::rust_utils::*;
+use codigolib::string_utils::*;
+use solana_program::entrypoint::ProgramResult;
+use solana_program::program_error::ProgramError;
+use solana_program::pubkey::Pubkey;
+
+// Emitted once the record is renamed, with the name as stored
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct UserNameUpdated {
+    pub record: Pubkey,
+    pub name: String,
+}
+
+impl Event for UserNameUpdated {
+    const DISCRIMINATOR: u8 = 5;
+    const LEN: usize = 32 + 4 + USER_NAME_CAP;
+    const FIELDS: &'static [(&'static str, usize)] = &[("record", 32), ("name", 4 + USER_NAME_CAP)];
+
+    fn pack<'a>(&self, buf: &'a mut [u8]) -> &'a mut [u8] {
+        let dst = pack_pubkey(buf, self.record);
+        pack_string(dst, self.name.clone(), 4 + USER_NAME_CAP)
+    }
+
+    fn unpack(buf: &[u8]) -> (&[u8], Self) {
+        let (dst, record) = unpack_pubkey(buf);
+        let (dst, name) = state_unpack_string(dst, 4 + USER_NAME_CAP);
+        (dst, UserNameUpdated { record, name })
+    }
+
+    fn try_unpack(buf: &[u8]) -> Result<(&[u8], Self), ProgramError> {
+        let (dst, record) = try_unpack_pubkey(buf)?;
+        let (dst, name) = try_state_unpack_string(dst, 4 + USER_NAME_CAP)?;
+        Ok((dst, UserNameUpdated { record, name }))
+    }
+}
+
+// Rename the user of the given record account. Only the owner and admin delegates can call it.
+pub fn update_user_name(user_record: &mut AccountPDA<Record>, user_name: String) -> ProgramResult {
+    user_record.data.name = validate_string(user_name, USER_NAME_CAP, StringPolicy::strict())?;
+
+    emit_event(&UserNameUpdated {
+        record: *user_record.info.key,
+        name: user_record.data.name.clone(),
+    });
+
+    Ok(())
+}
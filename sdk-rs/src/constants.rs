@@ -0,0 +1,29 @@
+// Generated from the CIDL by `cidl generate`, changes to this file will be overwritten
+
+// First byte of the instruction data, the position of the method in the CIDL
+pub const CREATE_USER_RECORD: u8 = 0;
+pub const REGISTER_INCOME: u8 = 1;
+pub const REGISTER_OUTCOME: u8 = 2;
+pub const ADD_DELEGATE: u8 = 3;
+pub const REMOVE_DELEGATE: u8 = 4;
+pub const UPDATE_USER_NAME: u8 = 5;
+pub const MIGRATE_RECORD: u8 = 6;
+pub const CLOSE_RECORD: u8 = 7;
+pub const BIND_MINT: u8 = 8;
+pub const REGISTER_TOKEN_INCOME: u8 = 9;
+pub const REGISTER_TOKEN_OUTCOME: u8 = 10;
+
+// Name and first byte of every instruction, in the order of the CIDL
+pub const INSTRUCTIONS: [(&str, u8); 11] = [
+    ("create_user_record", CREATE_USER_RECORD),
+    ("register_income", REGISTER_INCOME),
+    ("register_outcome", REGISTER_OUTCOME),
+    ("add_delegate", ADD_DELEGATE),
+    ("remove_delegate", REMOVE_DELEGATE),
+    ("update_user_name", UPDATE_USER_NAME),
+    ("migrate_record", MIGRATE_RECORD),
+    ("close_record", CLOSE_RECORD),
+    ("bind_mint", BIND_MINT),
+    ("register_token_income", REGISTER_TOKEN_INCOME),
+    ("register_token_outcome", REGISTER_TOKEN_OUTCOME),
+];
@@ -0,0 +1,300 @@
+// Generated from the CIDL by `cidl generate`, changes to this file will be overwritten
+
+use crate::constants::*;
+use crate::types::*;
+use codigolib::delegate_utils::*;
+use codigolib::rust_utils::*;
+use codigolib::token_utils::spl_token_program;
+use solana_program::instruction::{AccountMeta, Instruction};
+use solana_program::pubkey::Pubkey;
+use solana_program::system_program;
+
+// To call once per account. Initialize a Record account. The total_balance of the account will be set to 0.
+pub fn create_user_record(
+    program_id: &Pubkey,
+    user_name: String,
+    user_record_seed_signer: Pubkey,
+    user_record_authority: Pubkey,
+    fee_payer: Pubkey,
+) -> Instruction {
+    let (user_record, _) = Record::find_address(program_id, &user_record_seed_signer);
+
+    let mut data = vec![0; 1 + 4 + user_name.len() + 32];
+    let buf = pack_u8(&mut data, CREATE_USER_RECORD);
+    let buf = pack_string(buf, user_name.clone(), 4 + user_name.len());
+    pack_pubkey(buf, user_record_seed_signer);
+
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(user_record, false),
+            AccountMeta::new_readonly(user_record_authority, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new(fee_payer, true),
+        ],
+        data,
+    }
+}
+
+// Register the given amount as an income for the given record account. The total total_balance of the account will be increased.
+pub fn register_income(
+    program_id: &Pubkey,
+    amount: u32,
+    user_record_seed_signer: Pubkey,
+    user_record_authority: Pubkey,
+) -> Instruction {
+    let (user_record, _) = Record::find_address(program_id, &user_record_seed_signer);
+
+    let mut data = vec![0; 1 + 4 + 32];
+    let buf = pack_u8(&mut data, REGISTER_INCOME);
+    let buf = pack_u32(buf, amount);
+    pack_pubkey(buf, user_record_seed_signer);
+
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(user_record, false),
+            AccountMeta::new_readonly(user_record_authority, true),
+        ],
+        data,
+    }
+}
+
+// Register the given amount as an outcome for the given record account. The total total_balance of the account will be decreased.
+pub fn register_outcome(
+    program_id: &Pubkey,
+    amount: u32,
+    user_record_seed_signer: Pubkey,
+    user_record_authority: Pubkey,
+) -> Instruction {
+    let (user_record, _) = Record::find_address(program_id, &user_record_seed_signer);
+
+    let mut data = vec![0; 1 + 4 + 32];
+    let buf = pack_u8(&mut data, REGISTER_OUTCOME);
+    let buf = pack_u32(buf, amount);
+    pack_pubkey(buf, user_record_seed_signer);
+
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(user_record, false),
+            AccountMeta::new_readonly(user_record_authority, true),
+        ],
+        data,
+    }
+}
+
+// Allow another wallet to act on the record with the given role, or change the role it has. Only the owner and admin delegates can call it.
+pub fn add_delegate(
+    program_id: &Pubkey,
+    delegate: Delegate,
+    user_record_seed_signer: Pubkey,
+    user_record_authority: Pubkey,
+) -> Instruction {
+    let (user_record, _) = Record::find_address(program_id, &user_record_seed_signer);
+
+    let mut data = vec![0; 1 + 33 + 32];
+    let buf = pack_u8(&mut data, ADD_DELEGATE);
+    let buf = pack_delegate(buf, delegate);
+    pack_pubkey(buf, user_record_seed_signer);
+
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(user_record, false),
+            AccountMeta::new_readonly(user_record_authority, true),
+        ],
+        data,
+    }
+}
+
+// Revoke the access of a delegate to the record. Only the owner and admin delegates can call it.
+pub fn remove_delegate(
+    program_id: &Pubkey,
+    delegate: Pubkey,
+    user_record_seed_signer: Pubkey,
+    user_record_authority: Pubkey,
+) -> Instruction {
+    let (user_record, _) = Record::find_address(program_id, &user_record_seed_signer);
+
+    let mut data = vec![0; 1 + 32 + 32];
+    let buf = pack_u8(&mut data, REMOVE_DELEGATE);
+    let buf = pack_pubkey(buf, delegate);
+    pack_pubkey(buf, user_record_seed_signer);
+
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(user_record, false),
+            AccountMeta::new_readonly(user_record_authority, true),
+        ],
+        data,
+    }
+}
+
+// Rename the user of the given record account. Only the owner and admin delegates can call it.
+pub fn update_user_name(
+    program_id: &Pubkey,
+    user_name: String,
+    user_record_seed_signer: Pubkey,
+    user_record_authority: Pubkey,
+) -> Instruction {
+    let (user_record, _) = Record::find_address(program_id, &user_record_seed_signer);
+
+    let mut data = vec![0; 1 + 4 + user_name.len() + 32];
+    let buf = pack_u8(&mut data, UPDATE_USER_NAME);
+    let buf = pack_string(buf, user_name.clone(), 4 + user_name.len());
+    pack_pubkey(buf, user_record_seed_signer);
+
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(user_record, false),
+            AccountMeta::new_readonly(user_record_authority, true),
+        ],
+        data,
+    }
+}
+
+// Rewrite a record created before the version byte into the latest layout, the fee payer pays the rent of the added bytes. Only the owner can call it.
+pub fn migrate_record(
+    program_id: &Pubkey,
+    user_record_seed_signer: Pubkey,
+    user_record_authority: Pubkey,
+    fee_payer: Pubkey,
+) -> Instruction {
+    let (user_record, _) = Record::find_address(program_id, &user_record_seed_signer);
+
+    let mut data = vec![0; 1 + 32];
+    let buf = pack_u8(&mut data, MIGRATE_RECORD);
+    pack_pubkey(buf, user_record_seed_signer);
+
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(user_record, false),
+            AccountMeta::new_readonly(user_record_authority, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new(fee_payer, true),
+        ],
+        data,
+    }
+}
+
+// Delete the given record account and send its lamports to the fee payer. Only the owner can call it.
+pub fn close_record(
+    program_id: &Pubkey,
+    user_record_seed_signer: Pubkey,
+    user_record_authority: Pubkey,
+    fee_payer: Pubkey,
+) -> Instruction {
+    let (user_record, _) = Record::find_address(program_id, &user_record_seed_signer);
+
+    let mut data = vec![0; 1 + 32];
+    let buf = pack_u8(&mut data, CLOSE_RECORD);
+    pack_pubkey(buf, user_record_seed_signer);
+
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(user_record, false),
+            AccountMeta::new_readonly(user_record_authority, true),
+            AccountMeta::new(fee_payer, true),
+        ],
+        data,
+    }
+}
+
+// Back the balance of a record with tokens of the given mint, held in a vault created for the record. Only the owner can call it, before any income is registered.
+pub fn bind_mint(
+    program_id: &Pubkey,
+    mint: Pubkey,
+    user_record_seed_signer: Pubkey,
+    vault_seed_record: Pubkey,
+    user_record_authority: Pubkey,
+    fee_payer: Pubkey,
+) -> Instruction {
+    let (user_record, _) = Record::find_address(program_id, &user_record_seed_signer);
+    let (vault, _) = Vault::find_address(program_id, &vault_seed_record);
+
+    let mut data = vec![0; 1 + 32 + 32];
+    let buf = pack_u8(&mut data, BIND_MINT);
+    let buf = pack_pubkey(buf, user_record_seed_signer);
+    pack_pubkey(buf, vault_seed_record);
+
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(user_record, false),
+            AccountMeta::new_readonly(mint, false),
+            AccountMeta::new(vault, false),
+            AccountMeta::new_readonly(user_record_authority, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(spl_token_program::id(), false),
+            AccountMeta::new(fee_payer, true),
+        ],
+        data,
+    }
+}
+
+// Move the given amount of tokens into the vault of the record and register it as an income.
+pub fn register_token_income(
+    program_id: &Pubkey,
+    amount: u32,
+    source: Pubkey,
+    user_record_seed_signer: Pubkey,
+    vault_seed_record: Pubkey,
+    user_record_authority: Pubkey,
+) -> Instruction {
+    let (user_record, _) = Record::find_address(program_id, &user_record_seed_signer);
+    let (vault, _) = Vault::find_address(program_id, &vault_seed_record);
+
+    let mut data = vec![0; 1 + 4 + 32 + 32];
+    let buf = pack_u8(&mut data, REGISTER_TOKEN_INCOME);
+    let buf = pack_u32(buf, amount);
+    let buf = pack_pubkey(buf, user_record_seed_signer);
+    pack_pubkey(buf, vault_seed_record);
+
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(user_record, false),
+            AccountMeta::new(vault, false),
+            AccountMeta::new(source, false),
+            AccountMeta::new_readonly(user_record_authority, true),
+            AccountMeta::new_readonly(spl_token_program::id(), false),
+        ],
+        data,
+    }
+}
+
+// Move the given amount of tokens out of the vault of the record and register it as an outcome. Only the owner can call it.
+pub fn register_token_outcome(
+    program_id: &Pubkey,
+    amount: u32,
+    destination: Pubkey,
+    user_record_seed_signer: Pubkey,
+    vault_seed_record: Pubkey,
+    user_record_authority: Pubkey,
+) -> Instruction {
+    let (user_record, _) = Record::find_address(program_id, &user_record_seed_signer);
+    let (vault, _) = Vault::find_address(program_id, &vault_seed_record);
+
+    let mut data = vec![0; 1 + 4 + 32 + 32];
+    let buf = pack_u8(&mut data, REGISTER_TOKEN_OUTCOME);
+    let buf = pack_u32(buf, amount);
+    let buf = pack_pubkey(buf, user_record_seed_signer);
+    pack_pubkey(buf, vault_seed_record);
+
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(user_record, false),
+            AccountMeta::new(vault, false),
+            AccountMeta::new(destination, false),
+            AccountMeta::new_readonly(user_record_authority, true),
+            AccountMeta::new_readonly(spl_token_program::id(), false),
+        ],
+        data,
+    }
+}
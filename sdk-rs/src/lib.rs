@@ -0,0 +1,5 @@
+// Generated from the CIDL by `cidl generate`, changes to this file will be overwritten
+
+pub mod constants;
+pub mod instructions;
+pub mod types;
@@ -17,18 +17,19 @@ async function main(feePayer: Keypair) {
     // Instantiate a new Solana connection
     const connection = new Connection("http://127.0.0.1:8899");
 
-    // 1. Create a user record, logs the state of the account after creating it
-    await createUserRecordSendAndConfirm(connection, "John Doe", feePayer.publicKey, feePayer);
+    // 1. Create a user record, logs the state of the account after creating it. The fee payer
+    // also signs as the owner of the record, the wallet its address is derived from
+    await createUserRecordSendAndConfirm(connection, "John Doe", feePayer.publicKey, feePayer, feePayer);
     let record = await getRecord(connection, feePayer.publicKey);
     console.info(record);
 
     // 2. Registered a new income with a value of 100
-    await registerIncomeSendAndConfirm(connection, 100, feePayer.publicKey, feePayer);
+    await registerIncomeSendAndConfirm(connection, 100, feePayer.publicKey, feePayer, feePayer);
     record = await getRecord(connection, feePayer.publicKey);
     console.info(record);
 
     // 3. Registered a new outcome with a value of 50
-    await registerOutcomeSendAndConfirm(connection, 50, feePayer.publicKey, feePayer);
+    await registerOutcomeSendAndConfirm(connection, 50, feePayer.publicKey, feePayer, feePayer);
     record = await getRecord(connection, feePayer.publicKey);
     console.info(record);
 }
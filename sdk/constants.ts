@@ -10,8 +10,21 @@ export function GetProgramId(): PublicKey {
   return _PROGRAM_ID;
 }
 
+// The SPL token program, the owner of the vaults, the token accounts and the mints
+export const TOKEN_PROGRAM_ID = new PublicKey(
+  "TokenkegQfeZyiNwAJbNbGKPFXCWuBwf9Ss623VQ5DA"
+);
+
 export const Instructions = {
   createUserRecord: 0,
   registerIncome: 1,
   registerOutcome: 2,
+  addDelegate: 3,
+  removeDelegate: 4,
+  updateUserName: 5,
+  migrateRecord: 6,
+  closeRecord: 7,
+  bindMint: 8,
+  registerTokenIncome: 9,
+  registerTokenOutcome: 10,
 };
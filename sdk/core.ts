@@ -8,9 +8,9 @@ import {
   Transaction,
 } from "@solana/web3.js";
 import { Buffer } from "buffer";
-import { GetProgramId, Instructions } from "./constants";
+import { GetProgramId, Instructions, TOKEN_PROGRAM_ID } from "./constants";
 import * as utils from "./utils";
-import { Record } from "./types";
+import { Delegate, Record, Vault } from "./types";
 
 /**
  * (Instruction constructor)
@@ -18,11 +18,13 @@ import { Record } from "./types";
  *
  * @param userName - The username to be assigned to the Record.name property
  * @param userRecordSeedSigner
+ * @param userRecordAuthority - required signer, the owner of the record
  * @param feePayer - required signer
  */
 export function createUserRecord(
   userName: string,
   userRecordSeedSigner: PublicKey,
+  userRecordAuthority: PublicKey,
   feePayer: PublicKey
 ): TransactionInstruction {
   let dataBuffer = Buffer.from("");
@@ -47,6 +49,7 @@ export function createUserRecord(
     programId: GetProgramId(),
     keys: [
       { pubkey: userRecordAddress, isSigner: false, isWritable: true },
+      { pubkey: userRecordAuthority, isSigner: true, isWritable: false },
       { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
       { pubkey: feePayer, isSigner: true, isWritable: true },
     ],
@@ -62,17 +65,27 @@ export function createUserRecord(
  * @param userName - The username to be assigned to the Record.name property
  * @param userRecordSeedSigner
  * @param feePayer - required signer
+ * @param userRecordAuthority - required signer, the owner of the record. The fee payer when omitted
  */
 export async function createUserRecordSendAndConfirm(
   connection: Connection,
   userName: string,
   userRecordSeedSigner: PublicKey,
-  feePayer: Keypair
+  feePayer: Keypair,
+  userRecordAuthority: Keypair = feePayer
 ): Promise<string> {
   const tx = new Transaction().add(
-    createUserRecord(userName, userRecordSeedSigner, feePayer.publicKey)
+    createUserRecord(
+      userName,
+      userRecordSeedSigner,
+      userRecordAuthority.publicKey,
+      feePayer.publicKey
+    )
   );
-  return await sendAndConfirmTransaction(connection, tx, [feePayer]);
+  return await sendAndConfirmTransaction(connection, tx, [
+    feePayer,
+    userRecordAuthority,
+  ]);
 }
 
 /**
@@ -81,11 +94,12 @@ export async function createUserRecordSendAndConfirm(
  *
  * @param amount - The amount to be registered as the income.
  * @param userRecordSeedSigner
- * @param feePayer - required signer
+ * @param userRecordAuthority - required signer, the owner of the record or one of its contributor or admin delegates
  */
 export function registerIncome(
   amount: number,
-  userRecordSeedSigner: PublicKey
+  userRecordSeedSigner: PublicKey,
+  userRecordAuthority: PublicKey
 ): TransactionInstruction {
   let dataBuffer = Buffer.from("");
 
@@ -107,7 +121,10 @@ export function registerIncome(
 
   return new TransactionInstruction({
     programId: GetProgramId(),
-    keys: [{ pubkey: userRecordAddress, isSigner: false, isWritable: true }],
+    keys: [
+      { pubkey: userRecordAddress, isSigner: false, isWritable: true },
+      { pubkey: userRecordAuthority, isSigner: true, isWritable: false },
+    ],
     data: dataBuffer,
   });
 }
@@ -120,17 +137,22 @@ export function registerIncome(
  * @param amount - The amount to be registered as the income.
  * @param userRecordSeedSigner
  * @param feePayer - required signer
+ * @param userRecordAuthority - required signer, the owner of the record or one of its contributor or admin delegates. The fee payer when omitted
  */
 export async function registerIncomeSendAndConfirm(
   connection: Connection,
   amount: number,
   userRecordSeedSigner: PublicKey,
-  feePayer: Keypair
+  feePayer: Keypair,
+  userRecordAuthority: Keypair = feePayer
 ): Promise<string> {
   const tx = new Transaction().add(
-    registerIncome(amount, userRecordSeedSigner)
+    registerIncome(amount, userRecordSeedSigner, userRecordAuthority.publicKey)
   );
-  return await sendAndConfirmTransaction(connection, tx, [feePayer]);
+  return await sendAndConfirmTransaction(connection, tx, [
+    feePayer,
+    userRecordAuthority,
+  ]);
 }
 
 /**
@@ -139,11 +161,12 @@ export async function registerIncomeSendAndConfirm(
  *
  * @param amount - Number to be added to the outcome accumulator
  * @param userRecordSeedSigner
- * @param feePayer - required signer
+ * @param userRecordAuthority - required signer, the owner of the record or one of its contributor or admin delegates
  */
 export function registerOutcome(
   amount: number,
-  userRecordSeedSigner: PublicKey
+  userRecordSeedSigner: PublicKey,
+  userRecordAuthority: PublicKey
 ): TransactionInstruction {
   let dataBuffer = Buffer.from("");
 
@@ -165,7 +188,10 @@ export function registerOutcome(
 
   return new TransactionInstruction({
     programId: GetProgramId(),
-    keys: [{ pubkey: userRecordAddress, isSigner: false, isWritable: true }],
+    keys: [
+      { pubkey: userRecordAddress, isSigner: false, isWritable: true },
+      { pubkey: userRecordAuthority, isSigner: true, isWritable: false },
+    ],
     data: dataBuffer,
   });
 }
@@ -178,17 +204,657 @@ export function registerOutcome(
  * @param amount - Number to be added to the outcome accumulator
  * @param userRecordSeedSigner
  * @param feePayer - required signer
+ * @param userRecordAuthority - required signer, the owner of the record or one of its contributor or admin delegates. The fee payer when omitted
  */
 export async function registerOutcomeSendAndConfirm(
   connection: Connection,
   amount: number,
   userRecordSeedSigner: PublicKey,
-  feePayer: Keypair
+  feePayer: Keypair,
+  userRecordAuthority: Keypair = feePayer
+): Promise<string> {
+  const tx = new Transaction().add(
+    registerOutcome(amount, userRecordSeedSigner, userRecordAuthority.publicKey)
+  );
+  return await sendAndConfirmTransaction(connection, tx, [
+    feePayer,
+    userRecordAuthority,
+  ]);
+}
+
+/**
+ * (Instruction constructor)
+ * Allow another wallet to act on the record with the given role, or change the role it has. Only the owner and admin delegates can call it.
+ *
+ * @param delegate - The wallet and its role, 0 for viewer, 1 for contributor and 2 for admin.
+ * @param userRecordSeedSigner
+ * @param userRecordAuthority - required signer, the owner of the record or one of its admin delegates
+ */
+export function addDelegate(
+  delegate: Delegate,
+  userRecordSeedSigner: PublicKey,
+  userRecordAuthority: PublicKey
+): TransactionInstruction {
+  let dataBuffer = Buffer.from("");
+
+  dataBuffer = utils.packUInt8(dataBuffer, Instructions.addDelegate);
+  dataBuffer = utils.packDelegate(dataBuffer, delegate);
+
+  dataBuffer = utils.packPubkey(dataBuffer, userRecordSeedSigner);
+
+  // Check every seed length is smaller than 32 bytes
+  let userRecordSeedsBuffer = [
+    Buffer.from("record"),
+    utils.bufferFromPubkey(userRecordSeedSigner),
+  ];
+  utils.checkSeedsLength(userRecordSeedsBuffer);
+  const [userRecordAddress, _userRecordBump] = PublicKey.findProgramAddressSync(
+    userRecordSeedsBuffer,
+    Record.owner()
+  );
+
+  return new TransactionInstruction({
+    programId: GetProgramId(),
+    keys: [
+      { pubkey: userRecordAddress, isSigner: false, isWritable: true },
+      { pubkey: userRecordAuthority, isSigner: true, isWritable: false },
+    ],
+    data: dataBuffer,
+  });
+}
+
+/**
+ * (Transaction handler)
+ * Allow another wallet to act on the record with the given role, or change the role it has. Only the owner and admin delegates can call it.
+ *
+ * @param connection - A connection to a fullnode JSON RPC endpoint
+ * @param delegate - The wallet and its role, 0 for viewer, 1 for contributor and 2 for admin.
+ * @param userRecordSeedSigner
+ * @param feePayer - required signer
+ * @param userRecordAuthority - required signer, the owner of the record or one of its admin delegates. The fee payer when omitted
+ */
+export async function addDelegateSendAndConfirm(
+  connection: Connection,
+  delegate: Delegate,
+  userRecordSeedSigner: PublicKey,
+  feePayer: Keypair,
+  userRecordAuthority: Keypair = feePayer
+): Promise<string> {
+  const tx = new Transaction().add(
+    addDelegate(delegate, userRecordSeedSigner, userRecordAuthority.publicKey)
+  );
+  return await sendAndConfirmTransaction(connection, tx, [
+    feePayer,
+    userRecordAuthority,
+  ]);
+}
+
+/**
+ * (Instruction constructor)
+ * Revoke the access of a delegate to the record. Only the owner and admin delegates can call it.
+ *
+ * @param delegate - The wallet to remove from the delegates.
+ * @param userRecordSeedSigner
+ * @param userRecordAuthority - required signer, the owner of the record or one of its admin delegates
+ */
+export function removeDelegate(
+  delegate: PublicKey,
+  userRecordSeedSigner: PublicKey,
+  userRecordAuthority: PublicKey
+): TransactionInstruction {
+  let dataBuffer = Buffer.from("");
+
+  dataBuffer = utils.packUInt8(dataBuffer, Instructions.removeDelegate);
+  dataBuffer = utils.packPubkey(dataBuffer, delegate);
+
+  dataBuffer = utils.packPubkey(dataBuffer, userRecordSeedSigner);
+
+  // Check every seed length is smaller than 32 bytes
+  let userRecordSeedsBuffer = [
+    Buffer.from("record"),
+    utils.bufferFromPubkey(userRecordSeedSigner),
+  ];
+  utils.checkSeedsLength(userRecordSeedsBuffer);
+  const [userRecordAddress, _userRecordBump] = PublicKey.findProgramAddressSync(
+    userRecordSeedsBuffer,
+    Record.owner()
+  );
+
+  return new TransactionInstruction({
+    programId: GetProgramId(),
+    keys: [
+      { pubkey: userRecordAddress, isSigner: false, isWritable: true },
+      { pubkey: userRecordAuthority, isSigner: true, isWritable: false },
+    ],
+    data: dataBuffer,
+  });
+}
+
+/**
+ * (Transaction handler)
+ * Revoke the access of a delegate to the record. Only the owner and admin delegates can call it.
+ *
+ * @param connection - A connection to a fullnode JSON RPC endpoint
+ * @param delegate - The wallet to remove from the delegates.
+ * @param userRecordSeedSigner
+ * @param feePayer - required signer
+ * @param userRecordAuthority - required signer, the owner of the record or one of its admin delegates. The fee payer when omitted
+ */
+export async function removeDelegateSendAndConfirm(
+  connection: Connection,
+  delegate: PublicKey,
+  userRecordSeedSigner: PublicKey,
+  feePayer: Keypair,
+  userRecordAuthority: Keypair = feePayer
+): Promise<string> {
+  const tx = new Transaction().add(
+    removeDelegate(
+      delegate,
+      userRecordSeedSigner,
+      userRecordAuthority.publicKey
+    )
+  );
+  return await sendAndConfirmTransaction(connection, tx, [
+    feePayer,
+    userRecordAuthority,
+  ]);
+}
+
+/**
+ * (Instruction constructor)
+ * Rename the user of the given record account. Only the owner and admin delegates can call it.
+ *
+ * @param userName - The new username, at most 50 bytes once surrounding whitespace is trimmed.
+ * @param userRecordSeedSigner
+ * @param userRecordAuthority - required signer, the owner of the record or one of its admin delegates
+ */
+export function updateUserName(
+  userName: string,
+  userRecordSeedSigner: PublicKey,
+  userRecordAuthority: PublicKey
+): TransactionInstruction {
+  let dataBuffer = Buffer.from("");
+
+  dataBuffer = utils.packUInt8(dataBuffer, Instructions.updateUserName);
+  dataBuffer = utils.packString(dataBuffer, userName);
+
+  dataBuffer = utils.packPubkey(dataBuffer, userRecordSeedSigner);
+
+  // Check every seed length is smaller than 32 bytes
+  let userRecordSeedsBuffer = [
+    Buffer.from("record"),
+    utils.bufferFromPubkey(userRecordSeedSigner),
+  ];
+  utils.checkSeedsLength(userRecordSeedsBuffer);
+  const [userRecordAddress, _userRecordBump] = PublicKey.findProgramAddressSync(
+    userRecordSeedsBuffer,
+    Record.owner()
+  );
+
+  return new TransactionInstruction({
+    programId: GetProgramId(),
+    keys: [
+      { pubkey: userRecordAddress, isSigner: false, isWritable: true },
+      { pubkey: userRecordAuthority, isSigner: true, isWritable: false },
+    ],
+    data: dataBuffer,
+  });
+}
+
+/**
+ * (Transaction handler)
+ * Rename the user of the given record account. Only the owner and admin delegates can call it.
+ *
+ * @param connection - A connection to a fullnode JSON RPC endpoint
+ * @param userName - The new username, at most 50 bytes once surrounding whitespace is trimmed.
+ * @param userRecordSeedSigner
+ * @param feePayer - required signer
+ * @param userRecordAuthority - required signer, the owner of the record or one of its admin delegates. The fee payer when omitted
+ */
+export async function updateUserNameSendAndConfirm(
+  connection: Connection,
+  userName: string,
+  userRecordSeedSigner: PublicKey,
+  feePayer: Keypair,
+  userRecordAuthority: Keypair = feePayer
+): Promise<string> {
+  const tx = new Transaction().add(
+    updateUserName(
+      userName,
+      userRecordSeedSigner,
+      userRecordAuthority.publicKey
+    )
+  );
+  return await sendAndConfirmTransaction(connection, tx, [
+    feePayer,
+    userRecordAuthority,
+  ]);
+}
+
+/**
+ * (Instruction constructor)
+ * Rewrite a record created before the version byte into the latest layout, the fee payer pays the rent of the added bytes. Only the owner can call it.
+ *
+ * @param userRecordSeedSigner
+ * @param userRecordAuthority - required signer, the owner of the record
+ * @param feePayer - required signer
+ */
+export function migrateRecord(
+  userRecordSeedSigner: PublicKey,
+  userRecordAuthority: PublicKey,
+  feePayer: PublicKey
+): TransactionInstruction {
+  let dataBuffer = Buffer.from("");
+
+  dataBuffer = utils.packUInt8(dataBuffer, Instructions.migrateRecord);
+
+  dataBuffer = utils.packPubkey(dataBuffer, userRecordSeedSigner);
+
+  // Check every seed length is smaller than 32 bytes
+  let userRecordSeedsBuffer = [
+    Buffer.from("record"),
+    utils.bufferFromPubkey(userRecordSeedSigner),
+  ];
+  utils.checkSeedsLength(userRecordSeedsBuffer);
+  const [userRecordAddress, _userRecordBump] = PublicKey.findProgramAddressSync(
+    userRecordSeedsBuffer,
+    Record.owner()
+  );
+
+  return new TransactionInstruction({
+    programId: GetProgramId(),
+    keys: [
+      { pubkey: userRecordAddress, isSigner: false, isWritable: true },
+      { pubkey: userRecordAuthority, isSigner: true, isWritable: false },
+      { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+      { pubkey: feePayer, isSigner: true, isWritable: true },
+    ],
+    data: dataBuffer,
+  });
+}
+
+/**
+ * (Transaction handler)
+ * Rewrite a record created before the version byte into the latest layout, the fee payer pays the rent of the added bytes. Only the owner can call it.
+ *
+ * @param connection - A connection to a fullnode JSON RPC endpoint
+ * @param userRecordSeedSigner
+ * @param feePayer - required signer
+ * @param userRecordAuthority - required signer, the owner of the record. The fee payer when omitted
+ */
+export async function migrateRecordSendAndConfirm(
+  connection: Connection,
+  userRecordSeedSigner: PublicKey,
+  feePayer: Keypair,
+  userRecordAuthority: Keypair = feePayer
+): Promise<string> {
+  const tx = new Transaction().add(
+    migrateRecord(
+      userRecordSeedSigner,
+      userRecordAuthority.publicKey,
+      feePayer.publicKey
+    )
+  );
+  return await sendAndConfirmTransaction(connection, tx, [
+    feePayer,
+    userRecordAuthority,
+  ]);
+}
+
+/**
+ * (Instruction constructor)
+ * Delete the given record account and send its lamports to the fee payer. Only the owner can call it.
+ *
+ * @param userRecordSeedSigner
+ * @param userRecordAuthority - required signer, the owner of the record
+ * @param feePayer - required signer, receives the lamports of the record
+ */
+export function closeRecord(
+  userRecordSeedSigner: PublicKey,
+  userRecordAuthority: PublicKey,
+  feePayer: PublicKey
+): TransactionInstruction {
+  let dataBuffer = Buffer.from("");
+
+  dataBuffer = utils.packUInt8(dataBuffer, Instructions.closeRecord);
+
+  dataBuffer = utils.packPubkey(dataBuffer, userRecordSeedSigner);
+
+  // Check every seed length is smaller than 32 bytes
+  let userRecordSeedsBuffer = [
+    Buffer.from("record"),
+    utils.bufferFromPubkey(userRecordSeedSigner),
+  ];
+  utils.checkSeedsLength(userRecordSeedsBuffer);
+  const [userRecordAddress, _userRecordBump] = PublicKey.findProgramAddressSync(
+    userRecordSeedsBuffer,
+    Record.owner()
+  );
+
+  return new TransactionInstruction({
+    programId: GetProgramId(),
+    keys: [
+      { pubkey: userRecordAddress, isSigner: false, isWritable: true },
+      { pubkey: userRecordAuthority, isSigner: true, isWritable: false },
+      { pubkey: feePayer, isSigner: true, isWritable: true },
+    ],
+    data: dataBuffer,
+  });
+}
+
+/**
+ * (Transaction handler)
+ * Delete the given record account and send its lamports to the fee payer. Only the owner can call it.
+ *
+ * @param connection - A connection to a fullnode JSON RPC endpoint
+ * @param userRecordSeedSigner
+ * @param feePayer - required signer
+ * @param userRecordAuthority - required signer, the owner of the record. The fee payer when omitted
+ */
+export async function closeRecordSendAndConfirm(
+  connection: Connection,
+  userRecordSeedSigner: PublicKey,
+  feePayer: Keypair,
+  userRecordAuthority: Keypair = feePayer
+): Promise<string> {
+  const tx = new Transaction().add(
+    closeRecord(
+      userRecordSeedSigner,
+      userRecordAuthority.publicKey,
+      feePayer.publicKey
+    )
+  );
+  return await sendAndConfirmTransaction(connection, tx, [
+    feePayer,
+    userRecordAuthority,
+  ]);
+}
+
+/**
+ * (Instruction constructor)
+ * Back the balance of a record with tokens of the given mint, held in a vault created for the record. Only the owner can call it, before any income is registered.
+ *
+ * @param mint - The mint of the tokens.
+ * @param userRecordSeedSigner
+ * @param vaultSeedRecord
+ * @param userRecordAuthority - required signer, the owner of the record
+ * @param feePayer - required signer
+ */
+export function bindMint(
+  mint: PublicKey,
+  userRecordSeedSigner: PublicKey,
+  vaultSeedRecord: PublicKey,
+  userRecordAuthority: PublicKey,
+  feePayer: PublicKey
+): TransactionInstruction {
+  let dataBuffer = Buffer.from("");
+
+  dataBuffer = utils.packUInt8(dataBuffer, Instructions.bindMint);
+
+  dataBuffer = utils.packPubkey(dataBuffer, userRecordSeedSigner);
+  dataBuffer = utils.packPubkey(dataBuffer, vaultSeedRecord);
+
+  // Check every seed length is smaller than 32 bytes
+  let userRecordSeedsBuffer = [
+    Buffer.from("record"),
+    utils.bufferFromPubkey(userRecordSeedSigner),
+  ];
+  utils.checkSeedsLength(userRecordSeedsBuffer);
+  const [userRecordAddress, _userRecordBump] = PublicKey.findProgramAddressSync(
+    userRecordSeedsBuffer,
+    Record.owner()
+  );
+
+  // Check every seed length is smaller than 32 bytes
+  let vaultSeedsBuffer = [
+    Buffer.from("vault"),
+    utils.bufferFromPubkey(vaultSeedRecord),
+  ];
+  utils.checkSeedsLength(vaultSeedsBuffer);
+  const [vaultAddress, _vaultBump] = PublicKey.findProgramAddressSync(
+    vaultSeedsBuffer,
+    Vault.owner()
+  );
+
+  return new TransactionInstruction({
+    programId: GetProgramId(),
+    keys: [
+      { pubkey: userRecordAddress, isSigner: false, isWritable: true },
+      { pubkey: mint, isSigner: false, isWritable: false },
+      { pubkey: vaultAddress, isSigner: false, isWritable: true },
+      { pubkey: userRecordAuthority, isSigner: true, isWritable: false },
+      { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+      { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
+      { pubkey: feePayer, isSigner: true, isWritable: true },
+    ],
+    data: dataBuffer,
+  });
+}
+
+/**
+ * (Transaction handler)
+ * Back the balance of a record with tokens of the given mint, held in a vault created for the record. Only the owner can call it, before any income is registered.
+ *
+ * @param connection - A connection to a fullnode JSON RPC endpoint
+ * @param mint - The mint of the tokens.
+ * @param userRecordSeedSigner
+ * @param vaultSeedRecord
+ * @param feePayer - required signer
+ * @param userRecordAuthority - required signer, the owner of the record. The fee payer when omitted
+ */
+export async function bindMintSendAndConfirm(
+  connection: Connection,
+  mint: PublicKey,
+  userRecordSeedSigner: PublicKey,
+  vaultSeedRecord: PublicKey,
+  feePayer: Keypair,
+  userRecordAuthority: Keypair = feePayer
+): Promise<string> {
+  const tx = new Transaction().add(
+    bindMint(
+      mint,
+      userRecordSeedSigner,
+      vaultSeedRecord,
+      userRecordAuthority.publicKey,
+      feePayer.publicKey
+    )
+  );
+  return await sendAndConfirmTransaction(connection, tx, [
+    feePayer,
+    userRecordAuthority,
+  ]);
+}
+
+/**
+ * (Instruction constructor)
+ * Move the given amount of tokens into the vault of the record and register it as an income.
+ *
+ * @param amount - The amount of tokens to be registered as the income.
+ * @param source - Token account of the signer the tokens come from.
+ * @param userRecordSeedSigner
+ * @param vaultSeedRecord
+ * @param userRecordAuthority - required signer, the owner of the record or one of its contributor or admin delegates
+ */
+export function registerTokenIncome(
+  amount: number,
+  source: PublicKey,
+  userRecordSeedSigner: PublicKey,
+  vaultSeedRecord: PublicKey,
+  userRecordAuthority: PublicKey
+): TransactionInstruction {
+  let dataBuffer = Buffer.from("");
+
+  dataBuffer = utils.packUInt8(dataBuffer, Instructions.registerTokenIncome);
+  dataBuffer = utils.packUInt32(dataBuffer, amount);
+
+  dataBuffer = utils.packPubkey(dataBuffer, userRecordSeedSigner);
+  dataBuffer = utils.packPubkey(dataBuffer, vaultSeedRecord);
+
+  // Check every seed length is smaller than 32 bytes
+  let userRecordSeedsBuffer = [
+    Buffer.from("record"),
+    utils.bufferFromPubkey(userRecordSeedSigner),
+  ];
+  utils.checkSeedsLength(userRecordSeedsBuffer);
+  const [userRecordAddress, _userRecordBump] = PublicKey.findProgramAddressSync(
+    userRecordSeedsBuffer,
+    Record.owner()
+  );
+
+  // Check every seed length is smaller than 32 bytes
+  let vaultSeedsBuffer = [
+    Buffer.from("vault"),
+    utils.bufferFromPubkey(vaultSeedRecord),
+  ];
+  utils.checkSeedsLength(vaultSeedsBuffer);
+  const [vaultAddress, _vaultBump] = PublicKey.findProgramAddressSync(
+    vaultSeedsBuffer,
+    Vault.owner()
+  );
+
+  return new TransactionInstruction({
+    programId: GetProgramId(),
+    keys: [
+      { pubkey: userRecordAddress, isSigner: false, isWritable: true },
+      { pubkey: vaultAddress, isSigner: false, isWritable: true },
+      { pubkey: source, isSigner: false, isWritable: true },
+      { pubkey: userRecordAuthority, isSigner: true, isWritable: false },
+      { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
+    ],
+    data: dataBuffer,
+  });
+}
+
+/**
+ * (Transaction handler)
+ * Move the given amount of tokens into the vault of the record and register it as an income.
+ *
+ * @param connection - A connection to a fullnode JSON RPC endpoint
+ * @param amount - The amount of tokens to be registered as the income.
+ * @param source - Token account of the signer the tokens come from.
+ * @param userRecordSeedSigner
+ * @param vaultSeedRecord
+ * @param feePayer - required signer
+ * @param userRecordAuthority - required signer, the owner of the record or one of its contributor or admin delegates. The fee payer when omitted
+ */
+export async function registerTokenIncomeSendAndConfirm(
+  connection: Connection,
+  amount: number,
+  source: PublicKey,
+  userRecordSeedSigner: PublicKey,
+  vaultSeedRecord: PublicKey,
+  feePayer: Keypair,
+  userRecordAuthority: Keypair = feePayer
+): Promise<string> {
+  const tx = new Transaction().add(
+    registerTokenIncome(
+      amount,
+      source,
+      userRecordSeedSigner,
+      vaultSeedRecord,
+      userRecordAuthority.publicKey
+    )
+  );
+  return await sendAndConfirmTransaction(connection, tx, [
+    feePayer,
+    userRecordAuthority,
+  ]);
+}
+
+/**
+ * (Instruction constructor)
+ * Move the given amount of tokens out of the vault of the record and register it as an outcome. Only the owner can call it.
+ *
+ * @param amount - The amount of tokens to be registered as the outcome.
+ * @param destination - Token account of the owner the tokens go to.
+ * @param userRecordSeedSigner
+ * @param vaultSeedRecord
+ * @param userRecordAuthority - required signer, the owner of the record
+ */
+export function registerTokenOutcome(
+  amount: number,
+  destination: PublicKey,
+  userRecordSeedSigner: PublicKey,
+  vaultSeedRecord: PublicKey,
+  userRecordAuthority: PublicKey
+): TransactionInstruction {
+  let dataBuffer = Buffer.from("");
+
+  dataBuffer = utils.packUInt8(dataBuffer, Instructions.registerTokenOutcome);
+  dataBuffer = utils.packUInt32(dataBuffer, amount);
+
+  dataBuffer = utils.packPubkey(dataBuffer, userRecordSeedSigner);
+  dataBuffer = utils.packPubkey(dataBuffer, vaultSeedRecord);
+
+  // Check every seed length is smaller than 32 bytes
+  let userRecordSeedsBuffer = [
+    Buffer.from("record"),
+    utils.bufferFromPubkey(userRecordSeedSigner),
+  ];
+  utils.checkSeedsLength(userRecordSeedsBuffer);
+  const [userRecordAddress, _userRecordBump] = PublicKey.findProgramAddressSync(
+    userRecordSeedsBuffer,
+    Record.owner()
+  );
+
+  // Check every seed length is smaller than 32 bytes
+  let vaultSeedsBuffer = [
+    Buffer.from("vault"),
+    utils.bufferFromPubkey(vaultSeedRecord),
+  ];
+  utils.checkSeedsLength(vaultSeedsBuffer);
+  const [vaultAddress, _vaultBump] = PublicKey.findProgramAddressSync(
+    vaultSeedsBuffer,
+    Vault.owner()
+  );
+
+  return new TransactionInstruction({
+    programId: GetProgramId(),
+    keys: [
+      { pubkey: userRecordAddress, isSigner: false, isWritable: true },
+      { pubkey: vaultAddress, isSigner: false, isWritable: true },
+      { pubkey: destination, isSigner: false, isWritable: true },
+      { pubkey: userRecordAuthority, isSigner: true, isWritable: false },
+      { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
+    ],
+    data: dataBuffer,
+  });
+}
+
+/**
+ * (Transaction handler)
+ * Move the given amount of tokens out of the vault of the record and register it as an outcome. Only the owner can call it.
+ *
+ * @param connection - A connection to a fullnode JSON RPC endpoint
+ * @param amount - The amount of tokens to be registered as the outcome.
+ * @param destination - Token account of the owner the tokens go to.
+ * @param userRecordSeedSigner
+ * @param vaultSeedRecord
+ * @param feePayer - required signer
+ * @param userRecordAuthority - required signer, the owner of the record. The fee payer when omitted
+ */
+export async function registerTokenOutcomeSendAndConfirm(
+  connection: Connection,
+  amount: number,
+  destination: PublicKey,
+  userRecordSeedSigner: PublicKey,
+  vaultSeedRecord: PublicKey,
+  feePayer: Keypair,
+  userRecordAuthority: Keypair = feePayer
 ): Promise<string> {
   const tx = new Transaction().add(
-    registerOutcome(amount, userRecordSeedSigner)
+    registerTokenOutcome(
+      amount,
+      destination,
+      userRecordSeedSigner,
+      vaultSeedRecord,
+      userRecordAuthority.publicKey
+    )
   );
-  return await sendAndConfirmTransaction(connection, tx, [feePayer]);
+  return await sendAndConfirmTransaction(connection, tx, [
+    feePayer,
+    userRecordAuthority,
+  ]);
 }
 
 /******* GETTERS ********/
@@ -6,10 +6,29 @@ export {
   registerIncomeSendAndConfirm,
   registerOutcome,
   registerOutcomeSendAndConfirm,
+  addDelegate,
+  addDelegateSendAndConfirm,
+  removeDelegate,
+  removeDelegateSendAndConfirm,
+  updateUserName,
+  updateUserNameSendAndConfirm,
+  migrateRecord,
+  migrateRecordSendAndConfirm,
+  closeRecord,
+  closeRecordSendAndConfirm,
+  bindMint,
+  bindMintSendAndConfirm,
+  registerTokenIncome,
+  registerTokenIncomeSendAndConfirm,
+  registerTokenOutcome,
+  registerTokenOutcomeSendAndConfirm,
 } from "./core";
 
+// types
+export { Delegate, DelegateRole, Record, Vault } from "./types";
+
 // getters
 export { getRecord } from "./core";
 
 // sdk config
-export { GetProgramId, SetProgramId } from "./constants";
+export { GetProgramId, SetProgramId, TOKEN_PROGRAM_ID } from "./constants";
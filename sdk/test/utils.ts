@@ -0,0 +1,26 @@
+import { expect } from "chai";
+import * as utils from "../utils";
+
+// packOption only writes None for a missing value, the falsy values of a type are values too
+describe("packOption", () => {
+  const packUInt32Option = (data: any): string =>
+    utils.packOption(Buffer.alloc(0), data, utils.packUInt32).toString("hex");
+
+  it("packs 0 as Some", () => {
+    expect(packUInt32Option(0)).to.equal("0100000000");
+  });
+
+  it("packs false and an empty string as Some", () => {
+    expect(
+      utils.packOption(Buffer.alloc(0), false, utils.packBool).toString("hex")
+    ).to.equal("0100");
+    expect(
+      utils.packOption(Buffer.alloc(0), "", utils.packString).toString("hex")
+    ).to.equal("0100000000");
+  });
+
+  it("packs null and undefined as None", () => {
+    expect(packUInt32Option(null)).to.equal("00");
+    expect(packUInt32Option(undefined)).to.equal("00");
+  });
+});
@@ -4,6 +4,17 @@ import { PublicKey } from "@solana/web3.js";
 
 import { GetProgramId } from "./constants";
 
+export enum DelegateRole {
+  Viewer = 0,
+  Contributor = 1,
+  Admin = 2,
+}
+
+export interface Delegate {
+  key: PublicKey;
+  role: DelegateRole;
+}
+
 export class Record {
   /**
    * Create a new Record object
@@ -11,41 +22,82 @@ export class Record {
    * @remarks
    * Through this data structure we will store the relevant information to track the income and outcome of a given user.
    *
+   * @param version - Layout version of the record, records created before it have none and are migrated with migrate_record.
    * @param name - The name of the user.
    * @param moves - Number incomes/outcomes registered.
    * @param outcome - Sum of all outcomes.
    * @param income - Sum of all incomes.
    * @param totalBalance - The current balance of the user
+   * @param delegates - Wallets allowed to act on the record besides its owner, with their role.
+   * @param mint - Mint of the tokens backing the balance, the default pubkey while the record only keeps counters.
+   * @param vault - Token account of the program holding the tokens of the record, bound with the mint.
    * @param pubkey - A public key
    */
   constructor(
+    public version: number,
     public name: string,
     public moves: number,
     public outcome: number,
     public income: number,
     public totalBalance: bigint,
+    public delegates: Delegate[],
+    public mint: PublicKey,
+    public vault: PublicKey,
     public pubkey: PublicKey
   ) {}
 
   static decode(buffer: Buffer, pubkey: PublicKey): Record {
     let newBuffer = utils.copyBuffer(buffer);
 
+    let version;
     let name;
     let moves;
     let outcome;
     let income;
     let totalBalance;
+    let delegates;
+    let mint;
+    let vault;
 
+    [version, newBuffer] = utils.unpackUInt8(newBuffer);
     [name, newBuffer] = utils.unpackString(newBuffer, 54);
     [moves, newBuffer] = utils.unpackUInt16(newBuffer);
     [outcome, newBuffer] = utils.unpackUInt32(newBuffer);
     [income, newBuffer] = utils.unpackUInt32(newBuffer);
     [totalBalance, newBuffer] = utils.unpackInt64(newBuffer);
+    [delegates, newBuffer] = utils.unpackVec(
+      newBuffer,
+      utils.unpackDelegate,
+      169
+    );
+    [mint, newBuffer] = utils.unpackPubkey(newBuffer);
+    [vault, newBuffer] = utils.unpackPubkey(newBuffer);
 
-    return new Record(name, moves, outcome, income, totalBalance, pubkey);
+    return new Record(
+      version,
+      name,
+      moves,
+      outcome,
+      income,
+      totalBalance,
+      delegates,
+      mint,
+      vault,
+      pubkey
+    );
   }
 
   static owner(): PublicKey {
     return GetProgramId();
   }
 }
+
+export class Vault {
+  /**
+   * Token account holding the tokens of a record, owned by itself so only the program moves them.
+   * Its address is derived from the record, the token program stores its data.
+   */
+  static owner(): PublicKey {
+    return GetProgramId();
+  }
+}
@@ -8,7 +8,8 @@ export const packOption = (
   data: any,
   packF: (buf: Buffer, data: any) => Buffer
 ): Buffer => {
-  if (data) {
+  // 0, false and "" are values too, only null and undefined mean None
+  if (data !== null && data !== undefined) {
     buf = packBool(buf, true);
     buf = packF(buf, data);
     return buf;
@@ -396,6 +397,25 @@ export const unpackPubkey = (buf: Buffer): [PublicKey, Buffer] => {
   return [new PublicKey(pubkeyBuffer), trimBuffer(buf, 32)];
 };
 
+// A delegate is its public key followed by its role, 0 viewer, 1 contributor and 2 admin
+export const packDelegate = (
+  buf: Buffer,
+  data: { key: PublicKey; role: number }
+): Buffer => {
+  buf = packPubkey(buf, data.key);
+  return packUInt8(buf, data.role);
+};
+
+export const unpackDelegate = (
+  buf: Buffer
+): [{ key: PublicKey; role: number }, Buffer] => {
+  let key, role;
+  [key, buf] = unpackPubkey(buf);
+  [role, buf] = unpackUInt8(buf);
+  if (role > 2) throw new Error(`${role}: Invalid delegate role`);
+  return [{ key, role }, buf];
+};
+
 export const bufferFromString = (data: string) => {
   return Buffer.from(data);
 };